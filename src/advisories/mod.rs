@@ -0,0 +1,223 @@
+//! A small, bundled database of GitHub Security Advisories for actions.
+//!
+//! The bundled data is a snapshot of a subset of GitHub's advisory database
+//! for the `actions` ecosystem, in the same shape as GitHub's global
+//! advisories API. It lets `zizmor` detect known-vulnerable actions
+//! without network access; when online, results from GitHub's API
+//! are preferred since they're always up to date.
+//!
+//! The snapshot is regenerated with `make advisories`; see the
+//! development docs.
+//!
+//! See: <https://github.com/advisories?query=ecosystem%3Aactions>
+
+use std::{cmp::Ordering, sync::LazyLock};
+
+use crate::github_api::Advisory;
+
+/// The bundled advisory database, loaded lazily on first use.
+pub(crate) static BUNDLED: LazyLock<AdvisoryDatabase> = LazyLock::new(|| {
+    // NOTE: Safe unwrap, since the bundled data is checked by our tests.
+    AdvisoryDatabase::from_json(include_str!("advisories.json")).unwrap()
+});
+
+pub(crate) struct AdvisoryDatabase {
+    advisories: Vec<Advisory>,
+}
+
+impl AdvisoryDatabase {
+    pub(crate) fn from_json(json: &str) -> serde_json::Result<Self> {
+        Ok(Self {
+            advisories: serde_json::from_str(json)?,
+        })
+    }
+
+    /// Returns every advisory (and its corresponding vulnerability record)
+    /// affecting `owner/repo` at the given `version`.
+    ///
+    /// `version` is usually a tag like `v1.2.3`. Partial versions
+    /// like `v1` are treated as the newest version in their series,
+    /// since that's what they typically point to.
+    pub(crate) fn affecting<'a>(
+        &'a self,
+        owner: &str,
+        repo: &str,
+        version: &str,
+    ) -> Vec<(&'a Advisory, Option<&'a str>)> {
+        let Some(version) = Version::parse(version) else {
+            return vec![];
+        };
+
+        let name = format!("{owner}/{repo}");
+
+        let mut results = vec![];
+        for advisory in &self.advisories {
+            for vuln in &advisory.vulnerabilities {
+                if !vuln.package.name.eq_ignore_ascii_case(&name) {
+                    continue;
+                }
+
+                let Some(range) = &vuln.vulnerable_version_range else {
+                    continue;
+                };
+
+                if version_in_range(&version, range) {
+                    results.push((advisory, vuln.first_patched_version.as_deref()));
+                }
+            }
+        }
+
+        results
+    }
+}
+
+/// A (very) loose version, as commonly used to tag actions.
+///
+/// Missing components are `None`, e.g. `v1` has no minor or patch component.
+#[derive(Debug, PartialEq)]
+struct Version {
+    major: u64,
+    minor: Option<u64>,
+    patch: Option<u64>,
+}
+
+impl Version {
+    fn parse(version: &str) -> Option<Self> {
+        let version = version.strip_prefix('v').unwrap_or(version);
+
+        let mut components = version.splitn(3, '.');
+        let major = components.next()?.parse().ok()?;
+        let minor = components.next().map(|c| c.parse()).transpose().ok()?;
+        let patch = components.next().map(|c| c.parse()).transpose().ok()?;
+
+        Some(Self {
+            major,
+            minor,
+            patch,
+        })
+    }
+
+    /// Compares this version to `other`, treating any components missing
+    /// from `self` as being as large as possible and any missing from
+    /// `other` as zero.
+    fn cmp_newest(&self, other: &Version) -> Ordering {
+        let lhs = (
+            self.major,
+            self.minor.unwrap_or(u64::MAX),
+            self.patch.unwrap_or(u64::MAX),
+        );
+        let rhs = (
+            other.major,
+            other.minor.unwrap_or_default(),
+            other.patch.unwrap_or_default(),
+        );
+
+        lhs.cmp(&rhs)
+    }
+}
+
+/// Returns whether `version` is within the given GitHub-style
+/// version range, e.g. `>= 1.0.0, < 1.2.3`.
+///
+/// Invalid ranges never match.
+fn version_in_range(version: &Version, range: &str) -> bool {
+    range.split(',').map(str::trim).all(|constraint| {
+        let (op, bound) = match constraint.find(|c: char| c.is_ascii_alphanumeric()) {
+            Some(idx) => constraint.split_at(idx),
+            None => return false,
+        };
+
+        let Some(bound) = Version::parse(bound.trim()) else {
+            return false;
+        };
+
+        let ordering = version.cmp_newest(&bound);
+        match op.trim() {
+            "<" => ordering == Ordering::Less,
+            "<=" => ordering != Ordering::Greater,
+            ">" => ordering == Ordering::Greater,
+            ">=" => ordering != Ordering::Less,
+            "=" | "" => ordering == Ordering::Equal,
+            _ => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{version_in_range, Version, BUNDLED};
+
+    #[test]
+    fn test_version_parse() {
+        let cases = &[
+            (
+                "v1.2.3",
+                Some(Version {
+                    major: 1,
+                    minor: Some(2),
+                    patch: Some(3),
+                }),
+            ),
+            (
+                "1.2",
+                Some(Version {
+                    major: 1,
+                    minor: Some(2),
+                    patch: None,
+                }),
+            ),
+            (
+                "v4",
+                Some(Version {
+                    major: 4,
+                    minor: None,
+                    patch: None,
+                }),
+            ),
+            ("main", None),
+            ("release/v1", None),
+            ("v1.2.3-beta", None),
+        ];
+
+        for (version, expected) in cases {
+            assert_eq!(Version::parse(version), *expected);
+        }
+    }
+
+    #[test]
+    fn test_version_in_range() {
+        let cases = &[
+            ("v4.1.6", ">= 4.0.0, < 4.1.7", true),
+            ("v4.1.7", ">= 4.0.0, < 4.1.7", false),
+            ("v3.9.9", ">= 4.0.0, < 4.1.7", false),
+            // `v4` floats to the newest v4.x.y, which is patched.
+            ("v4", ">= 4.0.0, < 4.1.7", false),
+            // ...but every `v40` is vulnerable.
+            ("v40", "< 41", true),
+            ("v41", "< 41", false),
+            ("v45.0.7", "<= 45.0.7", true),
+            ("v45.0.8", "<= 45.0.7", false),
+            ("v1.0.0", "= 1.0.0", true),
+            ("v1.0.0", "garbage", false),
+        ];
+
+        for (version, range, expected) in cases {
+            let version = Version::parse(version).unwrap();
+            assert_eq!(version_in_range(&version, range), *expected, "{range}");
+        }
+    }
+
+    #[test]
+    fn test_bundled_affecting() {
+        let results = BUNDLED.affecting("tj-actions", "changed-files", "v40");
+        let ids = results
+            .iter()
+            .map(|(advisory, _)| advisory.ghsa_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["GHSA-mcph-m25j-8j63", "GHSA-mrrh-fwg8-r2c3"]);
+
+        assert!(BUNDLED
+            .affecting("actions", "download-artifact", "v4.1.7")
+            .is_empty());
+    }
+}
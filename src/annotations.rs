@@ -0,0 +1,109 @@
+//! GitHub Actions workflow command output (`--format github`).
+//!
+//! Each finding is printed as an `::error`, `::warning`, or `::notice`
+//! command, which GitHub Actions turns into an annotation on the finding's
+//! primary location, including in pull request diffs. Unlike SARIF, this
+//! doesn't need any permissions: the commands only have to be printed by a
+//! step of the job.
+//!
+//! See: <https://docs.github.com/en/actions/writing-workflows/choosing-what-your-workflow-does/workflow-commands-for-github-actions>
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::{
+    finding::{Finding, Severity},
+    registry::WorkflowRegistry,
+};
+
+/// Returns the workflow command for `severity`.
+///
+/// These mirror the levels of our SARIF output.
+fn command(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Informational => "notice",
+        Severity::Low | Severity::Medium => "warning",
+        Severity::High | Severity::Critical => "error",
+    }
+}
+
+/// Escapes `text` for use as a command's message.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes `text` for use as a command's property value.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Renders `findings` as workflow commands, one per line.
+///
+/// Suppressed findings (with `--show-suppressed`) aren't annotated.
+pub(crate) fn render_findings(registry: &WorkflowRegistry, findings: &[Finding]) -> Result<String> {
+    let mut commands = String::new();
+
+    for finding in findings.iter().filter(|f| f.suppression.is_none()) {
+        let Some(primary) = finding.locations.first() else {
+            continue;
+        };
+        let span = &primary.concrete.location;
+
+        let title = match &registry.remote {
+            Some(slug) => format!("zizmor: {ident} ({slug})", ident = finding.ident),
+            None => format!("zizmor: {ident}", ident = finding.ident),
+        };
+
+        // Annotations only have one location, so the message mentions the
+        // rest, much like the plain output does.
+        let mut message = format!(
+            "{desc}: {annotation}",
+            desc = finding.desc,
+            annotation = primary.symbolic.annotation
+        );
+        for location in &finding.locations[1..] {
+            write!(
+                message,
+                "\n{path}:{line}: {annotation}",
+                path = registry.get_relative_path(location.symbolic.name),
+                line = location.concrete.location.start_point.row + 1,
+                annotation = location.symbolic.annotation,
+            )?;
+        }
+        if let Some(suggestion) = &finding.suggestion {
+            write!(message, "\nhelp: {suggestion}")?;
+        }
+        write!(message, "\nsee: {url}", url = finding.url())?;
+
+        writeln!(
+            commands,
+            "::{command} file={file},line={line},endLine={end_line},col={col},title={title}::{message}",
+            command = command(finding.determinations.severity),
+            file = escape_property(registry.get_relative_path(primary.symbolic.name)),
+            line = span.start_point.row + 1,
+            end_line = span.end_point.row + 1,
+            col = span.start_point.column + 1,
+            title = escape_property(&title),
+            message = escape_data(&message),
+        )?;
+    }
+
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_data, escape_property};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape_data("plain"), "plain");
+        assert_eq!(escape_data("50%\r\nof it"), "50%25%0D%0Aof it");
+        assert_eq!(escape_data("a: b, c"), "a: b, c");
+        assert_eq!(escape_property("a: b, c"), "a%3A b%2C c");
+        assert_eq!(escape_property("100%:"), "100%25%3A");
+    }
+}
@@ -0,0 +1,171 @@
+//! Audits `if:` conditions that gate jobs or steps on the identity of
+//! `github.actor` or `github.triggering_actor`.
+//!
+//! Neither context identifies the author of the change being run:
+//! `github.actor` is whoever caused the *latest* event (e.g. Dependabot
+//! rebasing a PR that an attacker has since pushed to), and
+//! `github.triggering_actor` is whoever initiated the run, including
+//! re-runs. Either can be steered to a trusted identity by an attacker.
+//!
+//! See: <https://www.synacktiv.com/publications/github-actions-exploitation-dependabot>
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{event::BareEvent, job::StepBody, Job};
+
+use super::Audit;
+use crate::{
+    expr::Expr,
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::Workflow,
+    state::AuditState,
+};
+
+/// Contexts that identify an actor, but not the author of the code
+/// being run.
+const SPOOFABLE_ACTOR_CONTEXTS: &[&str] = &["github.actor", "github.triggering_actor"];
+
+pub(crate) struct ActorSpoofing {
+    pub(crate) state: AuditState,
+}
+
+impl ActorSpoofing {
+    /// Checks the given `if:` condition, returning a finding at `location`
+    /// if it gates on a spoofable actor context. `privileged` indicates
+    /// whether the gated job or step has access to credentials.
+    fn check_condition<'w>(
+        &self,
+        cond: &str,
+        location: SymbolicLocation<'w>,
+        privileged: bool,
+        workflow: &'w Workflow,
+    ) -> anyhow::Result<Option<Finding<'w>>> {
+        let Ok(expr) = Expr::parse_condition(cond) else {
+            log::warn!("couldn't parse condition: {cond}");
+            return Ok(None);
+        };
+
+        let Some((context, operand)) =
+            expr.context_equalities().into_iter().find(|(context, _)| {
+                SPOOFABLE_ACTOR_CONTEXTS
+                    .iter()
+                    .any(|spoofable| context.eq_ignore_ascii_case(spoofable))
+            })
+        else {
+            return Ok(None);
+        };
+
+        // These triggers run with elevated privileges in the context of the
+        // base repository, even when the triggering event came from a fork.
+        let privileged_trigger = [
+            BareEvent::IssueComment,
+            BareEvent::PullRequestReview,
+            BareEvent::PullRequestReviewComment,
+            BareEvent::PullRequestTarget,
+            BareEvent::WorkflowRun,
+        ]
+        .into_iter()
+        .any(|trigger| workflow.has_trigger(trigger));
+
+        let severity = if privileged_trigger {
+            Severity::High
+        } else if privileged {
+            Severity::Medium
+        } else if self.state.config.pedantic {
+            Severity::Low
+        } else {
+            return Ok(None);
+        };
+
+        // Bot identities (like `dependabot[bot]`) are the canonical
+        // spoofing target, since bots act on attacker-controlled PRs.
+        let confidence = match operand {
+            Expr::String(actor) if actor.ends_with("[bot]") => Confidence::High,
+            _ => Confidence::Medium,
+        };
+
+        Self::finding()
+            .severity(severity)
+            .confidence(confidence)
+            .rationale(Rationale::LiteralMatch)
+            .add_location(
+                location.annotated(format!("{context} may be spoofed by an attacker here")),
+            )
+            .suggestion(
+                "check github.event.pull_request.user.login instead, \
+                 or gate privileged operations behind a protected environment",
+            )
+            .build(workflow)
+            .map(Some)
+    }
+}
+
+impl Audit for ActorSpoofing {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "actor-spoofing"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "spoofable actor checks in conditions"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            let cond = match job.deref() {
+                Job::NormalJob(normal) => &normal.r#if,
+                Job::ReusableWorkflowCallJob(reusable) => &reusable.r#if,
+            };
+
+            if let Some(cond) = cond {
+                findings.extend(self.check_condition(
+                    cond,
+                    job.location().with_keys(&["if".into()]),
+                    job.uses_secrets(),
+                    workflow,
+                )?);
+            }
+
+            for step in job.steps() {
+                let Some(cond) = &step.r#if else {
+                    continue;
+                };
+
+                // `uses:` steps are privileged even without explicit
+                // secrets, since actions can read the ambient GITHUB_TOKEN.
+                let privileged =
+                    step.uses_secrets() || matches!(step.deref().body, StepBody::Uses { .. });
+
+                findings.extend(self.check_condition(
+                    cond,
+                    step.location().with_keys(&["if".into()]),
+                    privileged,
+                    workflow,
+                )?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,228 @@
+//! Audits `workflow_run` workflows that download artifacts from the
+//! triggering run and then execute their contents.
+//!
+//! `workflow_run` workflows run in the context of the base repository,
+//! with access to its secrets and a (potentially) writable `GITHUB_TOKEN`,
+//! even when the triggering run came from a fork. Artifacts produced by
+//! that triggering run are fully attacker-controlled, so executing (or
+//! `source`ing) anything from them is arbitrary code execution in a
+//! privileged context.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::EnvValue,
+    workflow::{event::BareEvent, job::StepBody, Job},
+};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::{Step, Workflow},
+    shell::{statements, Command},
+    state::AuditState,
+};
+
+/// Prefixes that refer to the workspace, which is where artifacts are
+/// extracted by default.
+const WORKSPACE_PREFIXES: &[&str] = &["${{ github.workspace }}/", "$GITHUB_WORKSPACE/", "./"];
+
+/// Interpreter flags that take inline code, rather than a file.
+const INLINE_CODE_FLAGS: &[&str] = &["-c", "-e", "-command", "/c", "/k"];
+
+/// Strips any workspace prefix (and trailing separators) from `path`.
+fn normalize(path: &str) -> &str {
+    let path = path.trim();
+    let path = WORKSPACE_PREFIXES
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix))
+        .unwrap_or(path);
+
+    path.trim_end_matches('/')
+}
+
+/// Returns the file that `command` executes, if any: either the command
+/// itself (when it's invoked by path), or the script passed to an
+/// interpreter like `bash` or `source`.
+fn executed_file<'s>(command: &Command<'s>) -> Option<&'s str> {
+    let mut words = command
+        .words
+        .iter()
+        .copied()
+        .skip_while(|word| word.contains('=') || *word == "sudo");
+
+    let first = words.next()?;
+
+    if command.is_interpreter() {
+        for word in words {
+            if INLINE_CODE_FLAGS.contains(&word.to_lowercase().as_str()) {
+                return None;
+            }
+            if !word.starts_with('-') {
+                return Some(word);
+            }
+        }
+        None
+    } else if first.contains('/') && !first.starts_with('/') {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+/// An artifact download step, and where it extracts to.
+struct Download<'w> {
+    step: Step<'w>,
+    /// The `path:` the artifact is extracted to, if not the workspace.
+    path: Option<&'w str>,
+}
+
+impl Download<'_> {
+    /// Returns our confidence that `file` comes from this artifact,
+    /// if it plausibly does.
+    fn contains(&self, file: &str) -> Option<Confidence> {
+        let file = normalize(file);
+
+        match self.path.map(normalize) {
+            Some(path) if !path.is_empty() => (file == path
+                || file
+                    .strip_prefix(path)
+                    .is_some_and(|rest| rest.starts_with('/')))
+            .then_some(Confidence::High),
+            // The artifact is extracted into the workspace, so any relative
+            // path might be from it (or from a checkout).
+            _ => (!file.starts_with(['/', '$', '~'])).then_some(Confidence::Low),
+        }
+    }
+}
+
+pub(crate) struct ArtifactExecution {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for ArtifactExecution {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "artifact-execution"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "execution of artifacts from a triggering workflow run"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        if !workflow.has_trigger(BareEvent::WorkflowRun) {
+            return Ok(findings);
+        }
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(_) = job.deref() else {
+                continue;
+            };
+
+            // Artifacts with secrets in scope can be exfiltrated outright;
+            // otherwise the attacker still gets the job's GITHUB_TOKEN.
+            let severity = if job.uses_secrets() {
+                Severity::High
+            } else {
+                Severity::Medium
+            };
+
+            let mut downloads = vec![];
+
+            for step in job.steps() {
+                let run = match &step.deref().body {
+                    StepBody::Uses { with, .. } => {
+                        if step.is_artifact_download() {
+                            let path = match with.get("path") {
+                                Some(EnvValue::String(path)) => Some(path.as_str()),
+                                _ => None,
+                            };
+                            downloads.push(Download { step, path });
+                        }
+                        continue;
+                    }
+                    StepBody::Run { run, .. } => run,
+                };
+
+                if downloads.is_empty() {
+                    continue;
+                }
+
+                // Prefer the most specific match, i.e. one against an
+                // explicit download path.
+                let executed = statements(run)
+                    .iter()
+                    .flat_map(|stmt| stmt.commands())
+                    .filter_map(|command| executed_file(&command))
+                    .flat_map(|file| {
+                        downloads.iter().filter_map(move |download| {
+                            download
+                                .contains(file)
+                                .map(|confidence| (file, download, confidence))
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                let Some(&(file, download, confidence)) = executed
+                    .iter()
+                    .find(|(_, _, confidence)| *confidence == Confidence::High)
+                    .or(executed.first())
+                else {
+                    continue;
+                };
+
+                findings.push(
+                    Self::finding()
+                        .severity(severity)
+                        .confidence(confidence)
+                        .rationale(if confidence == Confidence::High {
+                            Rationale::LiteralMatch
+                        } else {
+                            Rationale::Heuristic
+                        })
+                        .add_location(
+                            download
+                                .step
+                                .location()
+                                .with_keys(&["uses".into()])
+                                .annotated("artifact is downloaded from another workflow run"),
+                        )
+                        .add_location(
+                            step.location()
+                                .with_keys(&["run".into()])
+                                .annotated(format!("{file} may come from the artifact")),
+                        )
+                        .suggestion(
+                            "treat artifacts from workflow_run as untrusted data; \
+                             don't execute them in a privileged workflow",
+                        )
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -5,11 +5,16 @@ use github_actions_models::{
     common::{expr::ExplicitExpr, EnvValue},
     workflow::{job::StepBody, Job},
 };
-use itertools::Itertools;
 
-use super::WorkflowAudit;
+use serde_yaml::{Mapping, Value};
+
+use super::Audit;
 use crate::{
-    finding::{Confidence, Finding, Severity},
+    finding::{
+        fix::{Fix, Patch},
+        Confidence, Finding, Rationale, Severity,
+    },
+    models::Step,
     state::AuditState,
 };
 use crate::{models::Workflow, utils::split_patterns};
@@ -38,9 +43,44 @@ impl Artipacked {
 
         patterns
     }
+
+    /// Returns a fix that sets `persist-credentials: false` on `checkout`.
+    fn persist_credentials_fix<'w>(checkout: &Step<'w>) -> Fix<'w> {
+        let title = "set persist-credentials: false";
+        let location = checkout.location();
+
+        match &checkout.deref().body {
+            StepBody::Uses { with, .. } if with.contains_key("persist-credentials") => Fix::new(
+                title,
+                location.with_keys(&["with".into(), "persist-credentials".into()]),
+                Patch::Replace {
+                    value: Value::Bool(false),
+                },
+            ),
+            StepBody::Uses { with, .. } if !with.is_empty() => Fix::new(
+                title,
+                location.with_keys(&["with".into()]),
+                Patch::Add {
+                    key: "persist-credentials".into(),
+                    value: Value::Bool(false),
+                },
+            ),
+            _ => Fix::new(
+                title,
+                location,
+                Patch::Add {
+                    key: "with".into(),
+                    value: Value::Mapping(Mapping::from_iter([(
+                        "persist-credentials".into(),
+                        Value::Bool(false),
+                    )])),
+                },
+            ),
+        }
+    }
 }
 
-impl WorkflowAudit for Artipacked {
+impl Audit for Artipacked {
     fn ident() -> &'static str {
         "artipacked"
     }
@@ -52,11 +92,18 @@ impl WorkflowAudit for Artipacked {
         "credential persistence through GitHub Actions artifacts"
     }
 
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
     fn new(state: AuditState) -> Result<Self> {
         Ok(Self { state })
     }
 
-    fn audit<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
         let mut findings = vec![];
 
         for job in workflow.jobs() {
@@ -68,11 +115,18 @@ impl WorkflowAudit for Artipacked {
             }
 
             // First, collect all vulnerable checkouts and upload steps independently.
+            // We also collect every `run:` step, since these may execute
+            // untrusted code with access to the persisted credential.
             let mut vulnerable_checkouts = vec![];
             let mut vulnerable_uploads = vec![];
+            let mut run_steps = vec![];
             for step in job.steps() {
-                let StepBody::Uses { ref uses, ref with } = &step.deref().body else {
-                    continue;
+                let (uses, with) = match &step.deref().body {
+                    StepBody::Uses { uses, with } => (uses, with),
+                    StepBody::Run { .. } => {
+                        run_steps.push(step);
+                        continue;
+                    }
                 };
 
                 if uses.starts_with("actions/checkout") {
@@ -104,31 +158,15 @@ impl WorkflowAudit for Artipacked {
                 }
             }
 
-            if vulnerable_uploads.is_empty() {
-                // If we have no vulnerable uploads, then emit lower-confidence
-                // findings for just the checkout steps.
-                for checkout in vulnerable_checkouts {
-                    findings.push(
-                        Self::finding()
-                            .severity(Severity::Medium)
-                            .confidence(Confidence::Low)
-                            .add_location(
-                                checkout
-                                    .location()
-                                    .annotated("does not set persist-credentials: false"),
-                            )
-                            .build(workflow)?,
-                    );
-                }
-            } else {
-                // Select only pairs where the vulnerable checkout precedes the
-                // vulnerable upload. There are more efficient ways to do this than
-                // a cartesian product, but this way is simple.
-                for (checkout, upload) in vulnerable_checkouts
-                    .into_iter()
-                    .cartesian_product(vulnerable_uploads.into_iter())
-                {
-                    if checkout.index < upload.index {
+            for checkout in vulnerable_checkouts {
+                // Select only uploads that follow the vulnerable checkout.
+                let uploads = vulnerable_uploads
+                    .iter()
+                    .filter(|upload| checkout.index < upload.index)
+                    .collect::<Vec<_>>();
+
+                if !uploads.is_empty() {
+                    for upload in uploads {
                         findings.push(
                             Self::finding()
                                 .severity(Severity::High)
@@ -143,9 +181,49 @@ impl WorkflowAudit for Artipacked {
                                         .location()
                                         .annotated("may leak the credentials persisted above"),
                                 )
+                                .fix(Self::persist_credentials_fix(&checkout))
                                 .build(workflow)?,
                         );
                     }
+                } else if let Some(run) = run_steps
+                    .iter()
+                    .find(|run| checkout.is_untrusted_checkout() && checkout.index < run.index)
+                {
+                    // An untrusted checkout followed by a `run:` step means
+                    // that attacker-controlled code probably runs with
+                    // access to the persisted credential.
+                    findings.push(
+                        Self::finding()
+                            .severity(Severity::High)
+                            .confidence(Confidence::Medium)
+                            .rationale(Rationale::Heuristic)
+                            .add_location(
+                                checkout
+                                    .location()
+                                    .annotated("does not set persist-credentials: false"),
+                            )
+                            .add_location(run.location().annotated(
+                                "may run untrusted code with the credentials persisted above",
+                            ))
+                            .fix(Self::persist_credentials_fix(&checkout))
+                            .build(workflow)?,
+                    );
+                } else {
+                    // If we have no vulnerable uploads or untrusted code,
+                    // then emit a lower-confidence finding for just the checkout.
+                    findings.push(
+                        Self::finding()
+                            .severity(Severity::Medium)
+                            .confidence(Confidence::Low)
+                            .rationale(Rationale::Heuristic)
+                            .add_location(
+                                checkout
+                                    .location()
+                                    .annotated("does not set persist-credentials: false"),
+                            )
+                            .fix(Self::persist_credentials_fix(&checkout))
+                            .build(workflow)?,
+                    );
                 }
             }
         }
@@ -0,0 +1,194 @@
+//! Audits workflows that approve or merge pull requests automatically,
+//! when triggered by events that an attacker can cause.
+//!
+//! Under `pull_request_target`, `workflow_run` and similar triggers, the
+//! workflow runs with a privileged token on behalf of whoever opened the
+//! pull request. Approving or merging "their" pull request from such a
+//! workflow lets an attacker approve and merge their own changes.
+//!
+//! Both well-known approval/merge actions and `gh pr review --approve`
+//! and `gh pr merge` invocations in `run:` steps are detected.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{event::BareEvent, job::StepBody, Job};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::{Uses, Workflow},
+    shell::statements,
+    state::AuditState,
+};
+
+/// Actions (as `owner/repo`) that approve pull requests.
+const APPROVE_ACTIONS: &[&str] = &[
+    "hmarr/auto-approve-action",
+    "juliangruber/approve-pull-request-action",
+    "cognitedata/auto-approve-dependabot-action",
+];
+
+/// Actions (as `owner/repo`) that merge pull requests, or enable
+/// auto-merge on them.
+const MERGE_ACTIONS: &[&str] = &[
+    "pascalgn/automerge-action",
+    "peter-evans/enable-pull-request-automerge",
+    "reitermarkus/automerge",
+    "ahmadnassri/action-dependabot-auto-merge",
+    "fastify/github-action-merge-dependabot",
+];
+
+/// Returns what the given `run:` script does to pull requests, if it
+/// approves or merges them.
+fn script_approves(run: &str) -> Option<&'static str> {
+    statements(run).iter().find_map(|stmt| {
+        stmt.commands().iter().find_map(|command| {
+            let gh = command.gh()?;
+            match gh.subcommand.as_slice() {
+                ["pr", "review"] if gh.has_flag("--approve") || gh.has_flag("-a") => {
+                    Some("approves a pull request with gh pr review")
+                }
+                ["pr", "merge"] if gh.has_flag("--auto") => {
+                    Some("enables auto-merge on a pull request with gh pr merge")
+                }
+                ["pr", "merge"] => Some("merges a pull request with gh pr merge"),
+                _ => None,
+            }
+        })
+    })
+}
+
+/// Returns what the given action does to pull requests, if it
+/// approves or merges them.
+fn action_approves(uses: &str) -> Option<&'static str> {
+    let uses = Uses::from_step(uses)?;
+    let action = format!("{}/{}", uses.owner, uses.repo).to_lowercase();
+
+    if APPROVE_ACTIONS.contains(&action.as_str()) {
+        Some("approves a pull request")
+    } else if MERGE_ACTIONS.contains(&action.as_str()) {
+        Some("merges a pull request")
+    } else {
+        None
+    }
+}
+
+pub(crate) struct AutoApprove {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for AutoApprove {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "auto-approve"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "pull request approved or merged by an untrusted trigger"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let Some(trigger) = [
+            (BareEvent::PullRequestTarget, "pull_request_target"),
+            (BareEvent::WorkflowRun, "workflow_run"),
+            (BareEvent::IssueComment, "issue_comment"),
+            (BareEvent::PullRequestReview, "pull_request_review"),
+            (
+                BareEvent::PullRequestReviewComment,
+                "pull_request_review_comment",
+            ),
+        ]
+        .into_iter()
+        .find_map(|(event, name)| workflow.has_trigger(event).then_some(name)) else {
+            return Ok(findings);
+        };
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            for step in job.steps() {
+                let action = match &step.deref().body {
+                    StepBody::Uses { uses, .. } => action_approves(uses),
+                    StepBody::Run { run, .. } => script_approves(run),
+                };
+
+                let Some(action) = action else {
+                    continue;
+                };
+
+                // Approvals gated on the PR's author (typically Dependabot)
+                // are a common pattern. They're still risky, since many
+                // author checks are spoofable, but less likely to be a mistake.
+                let gated = [normal.r#if.as_deref(), step.r#if.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .any(|cond| {
+                        cond.contains("github.actor")
+                            || cond.contains("github.triggering_actor")
+                            || cond.contains(".user.login")
+                    });
+
+                let key = match &step.deref().body {
+                    StepBody::Uses { .. } => "uses",
+                    StepBody::Run { .. } => "run",
+                };
+
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::High)
+                        .confidence(if gated {
+                            Confidence::Low
+                        } else {
+                            Confidence::High
+                        })
+                        .rationale(if gated {
+                            Rationale::Heuristic
+                        } else {
+                            Rationale::LiteralMatch
+                        })
+                        .add_location(
+                            workflow
+                                .location()
+                                .with_keys(&["on".into()])
+                                .annotated(format!("{trigger} can be triggered by an attacker")),
+                        )
+                        .add_location(
+                            step.location()
+                                .with_keys(&[key.into()])
+                                .annotated(format!("this step {action}")),
+                        )
+                        .suggestion(
+                            "don't approve or merge pull requests from workflows that \
+                             their authors can trigger",
+                        )
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,163 @@
+//! Audits reusable workflow calls (`jobs.<id>.uses:`) into other
+//! repositories that are pinned to a branch, e.g.
+//! `org/repo/.github/workflows/ci.yml@main`.
+//!
+//! A branch moves with every push, so anybody who can push to it controls
+//! the called workflow, which runs with the caller's token and any secrets
+//! passed to it.
+//!
+//! When online, refs are checked against the repository's branches, and
+//! the branch's current commit is suggested as a pin. When offline, refs
+//! that don't look like versions are assumed to be branches.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::workflow::{job::Secrets, Job};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    github_api,
+    models::{Uses, Workflow},
+    refs::looks_like_version,
+    state::AuditState,
+};
+
+/// Branch names that are almost never used for tags.
+const COMMON_BRANCHES: &[&str] = &["main", "master", "develop", "dev", "trunk", "stable"];
+
+pub(crate) struct BranchPinnedReusable {
+    client: Option<github_api::Client>,
+}
+
+impl BranchPinnedReusable {
+    /// Returns our confidence (and its rationale) that `uses` is pinned to a
+    /// branch, along with the branch's current commit if we know it, or
+    /// `None` if it isn't pinned to a branch.
+    fn branch_pinned(
+        &self,
+        uses: &Uses<'_>,
+    ) -> Result<Option<(Confidence, Rationale, Option<String>)>> {
+        let Some(git_ref) = uses.symbolic_ref() else {
+            return Ok(None);
+        };
+
+        match &self.client {
+            Some(client) => Ok(client
+                .list_branches(uses.owner, uses.repo)?
+                .into_iter()
+                .find(|branch| branch.name == git_ref)
+                .map(|branch| {
+                    (
+                        Confidence::High,
+                        Rationale::ResolvedViaApi,
+                        Some(branch.commit.sha),
+                    )
+                })),
+            None if COMMON_BRANCHES.contains(&git_ref) => {
+                Ok(Some((Confidence::High, Rationale::LiteralMatch, None)))
+            }
+            None if looks_like_version(git_ref) => Ok(None),
+            None => Ok(Some((Confidence::Medium, Rationale::Heuristic, None))),
+        }
+    }
+}
+
+impl Audit for BranchPinnedReusable {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "branch-pinned-reusable"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "reusable workflow is pinned to a branch"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let client = state.github_client();
+
+        if client.is_none() {
+            log::info!("no GitHub API access; guessing at branch refs for reusable workflows");
+        }
+
+        Ok(Self { client })
+    }
+
+    fn uses_github_api(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            let Job::ReusableWorkflowCallJob(reusable) = job.deref() else {
+                continue;
+            };
+
+            // Local reusable workflows are part of the calling repository.
+            let Some(uses) = job.reusable_uses() else {
+                continue;
+            };
+
+            let Some((confidence, rationale, commit)) = self.branch_pinned(&uses)? else {
+                continue;
+            };
+
+            // NOTE: Unwrap is safe, since only symbolic refs are branch-pinned.
+            let branch = uses.symbolic_ref().unwrap();
+            let annotation = match confidence {
+                Confidence::High => format!("reusable workflow is pinned to branch {branch}"),
+                _ => format!("reusable workflow is pinned to {branch}, which looks like a branch"),
+            };
+
+            // The called workflow gets the caller's token, as well as any
+            // secrets that are passed to it.
+            let severity = match (&reusable.secrets, workflow.token_can_write(&job)) {
+                (Some(Secrets::Inherit), _) | (_, Some(true)) => Severity::High,
+                (Some(Secrets::Env(secrets)), _) if !secrets.is_empty() => Severity::High,
+                _ => Severity::Medium,
+            };
+
+            let suggestion = match commit {
+                Some(commit) => format!(
+                    "pin to the branch's current commit: {workflow}@{commit} # {branch}",
+                    workflow = reusable.uses.split_once('@').map_or("", |(base, _)| base),
+                ),
+                None => "pin the reusable workflow to a commit SHA".into(),
+            };
+
+            findings.push(
+                Self::finding()
+                    .severity(severity)
+                    .confidence(confidence)
+                    .rationale(rationale)
+                    .add_location(
+                        job.location()
+                            .with_keys(&["uses".into()])
+                            .annotated(annotation),
+                    )
+                    .suggestion(suggestion)
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
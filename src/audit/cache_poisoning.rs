@@ -0,0 +1,168 @@
+//! Audits privileged workflows for cache usage that an attacker
+//! could poison.
+//!
+//! Workflows triggered by `pull_request_target` or `workflow_run` run
+//! in the context of the base repository, meaning that they share a
+//! cache scope with the repository's default branch. Caches restored
+//! in these workflows may have been written by (or on behalf of) untrusted
+//! code, and caches saved by them are visible to other trusted workflows.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{
+    common::{Env, EnvValue},
+    workflow::{event::BareEvent, job::StepBody, Job},
+};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::Workflow,
+    state::AuditState,
+    utils::{extract_expressions, split_patterns},
+};
+
+/// Actions that restore (and usually save) caches unconditionally.
+const CACHE_ACTIONS: &[&str] = &["actions/cache"];
+
+/// Actions that restore caches when their `cache` input is set.
+const SETUP_ACTIONS_WITH_CACHE: &[&str] = &[
+    "actions/setup-go",
+    "actions/setup-java",
+    "actions/setup-node",
+    "actions/setup-python",
+];
+
+/// Contexts that are the same for every run on a given runner type, and
+/// therefore don't narrow a restore key down at all.
+const NON_NARROWING_CONTEXTS: &[&str] = &["runner.os", "runner.arch"];
+
+pub(crate) struct CachePoisoning {
+    pub(crate) _state: AuditState,
+}
+
+impl CachePoisoning {
+    fn uses_cache(&self, uses: &str, with: &Env) -> bool {
+        if CACHE_ACTIONS.iter().any(|action| uses.starts_with(action)) {
+            return true;
+        }
+
+        SETUP_ACTIONS_WITH_CACHE
+            .iter()
+            .any(|action| uses.starts_with(action))
+            && match with.get("cache") {
+                Some(EnvValue::Boolean(enabled)) => *enabled,
+                Some(EnvValue::String(cache)) => !cache.is_empty() && cache != "false",
+                _ => false,
+            }
+    }
+
+    /// Returns whether any of the given `restore-keys` is a prefix that
+    /// can only match caches by static text, i.e. doesn't include
+    /// anything tied to the contents of the repository or run.
+    fn broad_restore_keys(&self, restore_keys: &str) -> bool {
+        split_patterns(restore_keys).any(|key| {
+            extract_expressions(key).iter().all(|expr| {
+                NON_NARROWING_CONTEXTS
+                    .iter()
+                    .any(|ctx| expr.as_bare() == *ctx)
+            })
+        })
+    }
+}
+
+impl Audit for CachePoisoning {
+    fn ident() -> &'static str {
+        "cache-poisoning"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "runtime artifacts potentially vulnerable to cache poisoning"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> Result<Self> {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        if !(workflow.has_trigger(BareEvent::PullRequestTarget)
+            || workflow.has_trigger(BareEvent::WorkflowRun))
+        {
+            return Ok(findings);
+        }
+
+        for job in workflow.jobs() {
+            if !matches!(job.deref(), Job::NormalJob(_)) {
+                continue;
+            }
+
+            let steps = job.steps().collect::<Vec<_>>();
+
+            for (idx, step) in steps.iter().enumerate() {
+                let StepBody::Uses { uses, with } = &step.deref().body else {
+                    continue;
+                };
+
+                if !self.uses_cache(uses, with) {
+                    continue;
+                }
+
+                let mut finding = Self::finding().add_location(
+                    step.location()
+                        .with_keys(&["uses".into()])
+                        .annotated("cache is restored in a privileged workflow"),
+                );
+
+                // If the job has already checked out untrusted code by the
+                // time the cache is restored, that code can write to the
+                // cache directly.
+                let untrusted_checkout = steps[..idx]
+                    .iter()
+                    .find(|step| step.is_untrusted_checkout());
+                let (severity, confidence, rationale) = if let Some(checkout) = untrusted_checkout {
+                    finding = finding.add_location(
+                        checkout
+                            .location()
+                            .annotated("untrusted code is checked out here"),
+                    );
+                    (Severity::High, Confidence::High, Rationale::LiteralMatch)
+                } else {
+                    (Severity::Medium, Confidence::Low, Rationale::Heuristic)
+                };
+
+                if let Some(EnvValue::String(restore_keys)) = with.get("restore-keys") {
+                    if self.broad_restore_keys(restore_keys) {
+                        finding = finding.add_location(
+                            step.location()
+                                .with_keys(&["with".into(), "restore-keys".into()])
+                                .annotated("restore key prefix may match caches from other runs"),
+                        );
+                    }
+                }
+
+                findings.push(
+                    finding
+                        .severity(severity)
+                        .confidence(confidence)
+                        .rationale(rationale)
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
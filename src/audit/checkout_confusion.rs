@@ -0,0 +1,168 @@
+//! Audits jobs with multiple `actions/checkout` steps that use different
+//! credentials or check out different repositories.
+//!
+//! `actions/checkout` persists its credential (a token or SSH key) into
+//! the checkout's git configuration by default. When a job checks out
+//! several repositories, a credential persisted by one checkout remains
+//! on disk while code from the others (e.g. git hooks, build scripts or
+//! actions) runs, and may be scoped far more broadly than that code needs.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::EnvValue,
+    workflow::{job::StepBody, Job},
+};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::{Step, Workflow},
+    state::AuditState,
+};
+
+/// The inputs through which `actions/checkout` accepts a credential.
+const CREDENTIAL_INPUTS: &[&str] = &["token", "ssh-key"];
+
+/// A single `actions/checkout` step within a job.
+struct Checkout<'w> {
+    step: Step<'w>,
+    /// The `repository:` being checked out, or `None` for the
+    /// workflow's own repository.
+    repository: Option<&'w str>,
+    /// The explicit credential input and its value, or `None` for the
+    /// default `GITHUB_TOKEN`.
+    credential: Option<(&'static str, &'w str)>,
+}
+
+impl<'w> Checkout<'w> {
+    fn from_step(step: Step<'w>) -> Option<Self> {
+        let StepBody::Uses { uses, with } = &step.deref().body else {
+            return None;
+        };
+
+        if !uses.starts_with("actions/checkout") {
+            return None;
+        }
+
+        let string = |key: &str| match with.get(key) {
+            Some(EnvValue::String(value)) if !value.is_empty() => Some(value.as_str()),
+            _ => None,
+        };
+
+        let repository = string("repository");
+        let credential = CREDENTIAL_INPUTS
+            .iter()
+            .find_map(|input| string(input).map(|value| (*input, value)));
+
+        Some(Self {
+            step,
+            repository,
+            credential,
+        })
+    }
+
+    fn describe_repository(&self) -> &'w str {
+        self.repository.unwrap_or("this repository")
+    }
+
+    fn describe_credential(&self) -> String {
+        match self.credential {
+            Some((input, value)) => format!("{input}: {value}"),
+            None => "the default GITHUB_TOKEN".into(),
+        }
+    }
+}
+
+pub(crate) struct CheckoutConfusion {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for CheckoutConfusion {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "checkout-confusion"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "credentials persisted across multiple checkouts"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(_) = job.deref() else {
+                continue;
+            };
+
+            let checkouts = job
+                .steps()
+                .filter_map(Checkout::from_step)
+                .collect::<Vec<_>>();
+
+            for (idx, later) in checkouts.iter().enumerate() {
+                // Only the first earlier checkout that leaves a mismatched
+                // credential behind is reported, to keep findings focused.
+                let Some(earlier) = checkouts[..idx].iter().find(|earlier| {
+                    earlier.step.persists_credentials()
+                        && (earlier.repository != later.repository
+                            || earlier.credential != later.credential)
+                }) else {
+                    continue;
+                };
+
+                // An explicit credential is usually a PAT or deploy key,
+                // which is typically far more powerful than GITHUB_TOKEN.
+                let severity = if earlier.credential.is_some() {
+                    Severity::High
+                } else {
+                    Severity::Medium
+                };
+
+                findings.push(
+                    Self::finding()
+                        .severity(severity)
+                        .confidence(Confidence::Medium)
+                        .rationale(Rationale::Heuristic)
+                        .add_location(earlier.step.location().annotated(format!(
+                            "persists {credential} for {repository}",
+                            credential = earlier.describe_credential(),
+                            repository = earlier.describe_repository()
+                        )))
+                        .add_location(later.step.location().annotated(format!(
+                            "checks out {repository} with {credential}",
+                            credential = later.describe_credential(),
+                            repository = later.describe_repository()
+                        )))
+                        .suggestion(
+                            "set persist-credentials: false on checkouts whose \
+                             credentials aren't needed by later steps",
+                        )
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,227 @@
+//! Audits comment-triggered workflows (`issue_comment` and similar) that
+//! execute commands taken from the comment's body, i.e. "slash command"
+//! style ChatOps.
+//!
+//! Anybody who can comment on an issue or pull request (i.e. anybody,
+//! for public repositories) controls the comment's body. Workflows that
+//! run that body as a command, pass it to the `gh` CLI, or hand it to a
+//! ChatOps action let commenters choose what the workflow does, with
+//! whatever secrets and permissions the job has.
+//!
+//! Unlike `template-injection`, this follows the comment through `env:`
+//! variables and step outputs, since passing it through the environment
+//! prevents injection into the script but not its use as a command.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::EnvValue,
+    workflow::{event::BareEvent, job::StepBody, Job},
+};
+
+use super::Audit;
+use crate::{
+    expr::COMMENT_CONTEXTS,
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::{self, Uses, Workflow},
+    shell::{statements, variables},
+    state::AuditState,
+};
+
+/// Actions (as `owner/repo`) that dispatch commands from comments.
+const CHATOPS_ACTIONS: &[&str] = &[
+    "github/branch-deploy",
+    "github/command",
+    "peter-evans/slash-command-dispatch",
+    "xt0rted/slash-command-action",
+];
+
+pub(crate) struct CommentCommandExecution {
+    pub(crate) _state: AuditState,
+}
+
+impl CommentCommandExecution {
+    /// Returns the comment contexts whose values may flow into `text`
+    /// within `job`, including through step outputs.
+    fn comment_sources(workflow: &Workflow, text: &str, job: &models::Job) -> Vec<String> {
+        workflow
+            .untrusted_sources(text, Some(job))
+            .into_iter()
+            .filter(|source| COMMENT_CONTEXTS.contains(&source.as_str()))
+            .collect()
+    }
+}
+
+impl Audit for CommentCommandExecution {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "comment-command-execution"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "commands executed from comment bodies"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let Some(trigger) = [
+            (BareEvent::IssueComment, "issue_comment"),
+            (BareEvent::DiscussionComment, "discussion_comment"),
+            (
+                BareEvent::PullRequestReviewComment,
+                "pull_request_review_comment",
+            ),
+        ]
+        .into_iter()
+        .find_map(|(event, name)| workflow.has_trigger(event).then_some(name)) else {
+            return Ok(findings);
+        };
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            // Commands are always dangerous, but much more so when
+            // the job has secrets or a writable token to steal.
+            let severity = if job.uses_secrets() || workflow.token_can_write(&job) == Some(true) {
+                Severity::High
+            } else {
+                Severity::Medium
+            };
+
+            for step in job.steps() {
+                let (key, sinks) = match &step.deref().body {
+                    StepBody::Run { run, env, .. } => {
+                        // Variables resolve from the innermost `env:` outwards.
+                        let lookup = |var: &str| {
+                            env.get(var)
+                                .or_else(|| normal.env.get(var))
+                                .or_else(|| workflow.env.get(var))
+                        };
+
+                        let mut sinks = vec![];
+                        for stmt in statements(run) {
+                            let mut sources = Self::comment_sources(workflow, stmt.text, &job);
+                            for var in variables(stmt.text) {
+                                if let Some(EnvValue::String(value)) = lookup(var) {
+                                    sources.extend(
+                                        Self::comment_sources(workflow, value, &job)
+                                            .into_iter()
+                                            .map(|source| format!("{var} (from {source})")),
+                                    );
+                                }
+                            }
+
+                            let Some(source) = sources.first() else {
+                                continue;
+                            };
+
+                            for command in stmt.commands() {
+                                if command.is_dynamic() {
+                                    sinks.push((
+                                        format!("executes {source} as a command"),
+                                        Confidence::High,
+                                        Rationale::DataflowTraced,
+                                    ));
+                                } else if let Some(gh) = command.gh() {
+                                    sinks.push((
+                                        format!(
+                                            "passes {source} to gh {subcommand}",
+                                            subcommand = gh.subcommand.join(" ")
+                                        ),
+                                        Confidence::Medium,
+                                        Rationale::DataflowTraced,
+                                    ));
+                                }
+                            }
+                        }
+
+                        ("run", sinks)
+                    }
+                    StepBody::Uses { uses, with } => {
+                        let mut inputs = with.iter().collect::<Vec<_>>();
+                        inputs.sort_by_key(|(name, _)| *name);
+
+                        let mut sources = vec![];
+                        for (_, value) in inputs {
+                            if let EnvValue::String(value) = value {
+                                sources.extend(Self::comment_sources(workflow, value, &job));
+                            }
+                        }
+
+                        let chatops = Uses::from_step(uses).is_some_and(|uses| {
+                            let action = format!("{}/{}", uses.owner, uses.repo).to_lowercase();
+                            CHATOPS_ACTIONS.contains(&action.as_str())
+                        });
+
+                        let sinks = match sources.first() {
+                            Some(source) => {
+                                vec![(
+                                    format!("passes {source} to {uses}"),
+                                    Confidence::Medium,
+                                    Rationale::DataflowTraced,
+                                )]
+                            }
+                            None if chatops => vec![(
+                                format!("dispatches commands from comments with {uses}"),
+                                Confidence::Low,
+                                Rationale::Heuristic,
+                            )],
+                            None => vec![],
+                        };
+
+                        ("uses", sinks)
+                    }
+                };
+
+                for (annotation, confidence, rationale) in sinks {
+                    findings.push(
+                        Self::finding()
+                            .severity(severity)
+                            .confidence(confidence)
+                            .rationale(rationale)
+                            .add_location(
+                                workflow
+                                    .location()
+                                    .with_keys(&["on".into()])
+                                    .annotated(format!("{trigger} is triggered by any commenter")),
+                            )
+                            .add_location(
+                                step.location()
+                                    .with_keys(&[key.into()])
+                                    .annotated(annotation),
+                            )
+                            .suggestion(
+                                "match comments against a fixed set of commands, and check \
+                                 the commenter's permissions before acting on them",
+                            )
+                            .build(workflow)?,
+                    );
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
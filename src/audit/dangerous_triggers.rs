@@ -1,9 +1,11 @@
+use std::ops::Deref;
+
 use anyhow::Result;
-use github_actions_models::workflow::event::{BareEvent, OptionalBody};
-use github_actions_models::workflow::Trigger;
+use github_actions_models::workflow::event::BareEvent;
+use github_actions_models::workflow::Job;
 
-use super::WorkflowAudit;
-use crate::finding::{Confidence, Finding, Severity};
+use super::Audit;
+use crate::finding::{Confidence, Finding, Rationale, Severity};
 use crate::models::Workflow;
 use crate::state::AuditState;
 
@@ -11,7 +13,68 @@ pub(crate) struct DangerousTriggers {
     pub(crate) _state: AuditState,
 }
 
-impl WorkflowAudit for DangerousTriggers {
+impl DangerousTriggers {
+    fn trigger_finding<'w>(&self, workflow: &'w Workflow, annotation: &str) -> Result<Finding<'w>> {
+        let mut finding = Self::finding().add_location(
+            workflow
+                .location()
+                .with_keys(&["on".into()])
+                .annotated(annotation),
+        );
+
+        // The trigger itself is dangerous, but it's *especially* dangerous
+        // when a job actually does something with the triggering event's
+        // code or has access to secrets.
+        let mut escalated = false;
+        for job in workflow.jobs() {
+            if !matches!(job.deref(), Job::NormalJob(_)) {
+                if job.uses_secrets() {
+                    escalated = true;
+                    finding = finding.add_location(
+                        job.location()
+                            .with_keys(&["secrets".into()])
+                            .annotated("passes secrets to a reusable workflow"),
+                    );
+                }
+                continue;
+            }
+
+            for step in job.steps() {
+                if step.is_untrusted_checkout() {
+                    escalated = true;
+                    finding = finding.add_location(
+                        step.location()
+                            .with_keys(&["with".into(), "ref".into()])
+                            .annotated("checks out code from the triggering event's head"),
+                    );
+                }
+            }
+
+            if job.uses_secrets() {
+                escalated = true;
+                finding = finding.add_location(job.location().annotated("job uses secrets"));
+            }
+        }
+
+        let (severity, confidence, rationale) = if escalated {
+            (
+                Severity::Critical,
+                Confidence::High,
+                Rationale::LiteralMatch,
+            )
+        } else {
+            (Severity::High, Confidence::Medium, Rationale::Heuristic)
+        };
+
+        finding
+            .severity(severity)
+            .confidence(confidence)
+            .rationale(rationale)
+            .build(workflow)
+    }
+}
+
+impl Audit for DangerousTriggers {
     fn ident() -> &'static str {
         "dangerous-triggers"
     }
@@ -23,52 +86,28 @@ impl WorkflowAudit for DangerousTriggers {
         "use of fundamentally insecure workflow trigger"
     }
 
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
     fn new(state: AuditState) -> Result<Self> {
         Ok(Self { _state: state })
     }
 
-    fn audit<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
-        let trigger = &workflow.on;
-
-        let has_pull_request_target = match trigger {
-            Trigger::BareEvent(event) => *event == BareEvent::PullRequestTarget,
-            Trigger::BareEvents(events) => events.contains(&BareEvent::PullRequestTarget),
-            Trigger::Events(events) => !matches!(events.pull_request_target, OptionalBody::Missing),
-        };
-
-        let has_workflow_run = match trigger {
-            Trigger::BareEvent(event) => *event == BareEvent::WorkflowRun,
-            Trigger::BareEvents(events) => events.contains(&BareEvent::WorkflowRun),
-            Trigger::Events(events) => !matches!(events.workflow_run, OptionalBody::Missing),
-        };
-
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
         let mut findings = vec![];
-        if has_pull_request_target {
-            findings.push(
-                Self::finding()
-                    .confidence(Confidence::Medium)
-                    .severity(Severity::High)
-                    .add_location(
-                        workflow
-                            .location()
-                            .with_keys(&["on".into()])
-                            .annotated("pull_request_target is almost always used insecurely"),
-                    )
-                    .build(workflow)?,
-            );
+        if workflow.has_trigger(BareEvent::PullRequestTarget) {
+            findings.push(self.trigger_finding(
+                workflow,
+                "pull_request_target is almost always used insecurely",
+            )?);
         }
-        if has_workflow_run {
+        if workflow.has_trigger(BareEvent::WorkflowRun) {
             findings.push(
-                Self::finding()
-                    .confidence(Confidence::Medium)
-                    .severity(Severity::High)
-                    .add_location(
-                        workflow
-                            .location()
-                            .with_keys(&["on".into()])
-                            .annotated("workflow_run is almost always used insecurely"),
-                    )
-                    .build(workflow)?,
+                self.trigger_finding(workflow, "workflow_run is almost always used insecurely")?,
             );
         }
 
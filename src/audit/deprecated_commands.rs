@@ -0,0 +1,192 @@
+//! Audits `run:` steps (in workflows and composite actions) for deprecated
+//! or disabled workflow commands.
+//!
+//! Workflow commands like `::set-output::` are parsed out of a step's
+//! standard output, meaning that *any* process that can print to it (including
+//! one printing attacker-controlled data) can set outputs, state, or
+//! environment variables. GitHub replaced them with environment files.
+//!
+//! See: <https://github.blog/changelog/2022-10-11-github-actions-deprecating-save-state-and-set-output-commands/>
+
+use std::{ops::Deref, sync::LazyLock};
+
+use github_actions_models::workflow::job::StepBody;
+use regex::{Captures, Regex};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::{Action, Workflow},
+    shell::statements,
+    state::AuditState,
+};
+
+/// Matches a deprecated workflow command, capturing the command and its
+/// `name` parameter (if any).
+static COMMAND: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"::(set-output|save-state|set-env|add-path)(?:\s+name=([^:\s]+))?::").unwrap()
+});
+
+/// Returns the environment file that replaces the given command, and
+/// whether the command is disabled (rather than just deprecated).
+fn replacement(command: &str) -> (&'static str, bool) {
+    match command {
+        "set-output" => ("GITHUB_OUTPUT", false),
+        "save-state" => ("GITHUB_STATE", false),
+        // `set-env` and `add-path` were disabled outright, and are only
+        // honored when `ACTIONS_ALLOW_UNSECURE_COMMANDS` is set.
+        "set-env" => ("GITHUB_ENV", true),
+        "add-path" => ("GITHUB_PATH", true),
+        _ => unreachable!(),
+    }
+}
+
+/// Attempts to rewrite a simple `echo "::command name=foo::bar"` statement
+/// into its environment file equivalent.
+fn rewrite(stmt: &str, caps: &Captures, file: &str) -> Option<String> {
+    // We only handle the simple (and overwhelmingly common) case of a
+    // single `echo` that isn't already redirected somewhere.
+    if !stmt.starts_with("echo ") || stmt.contains('\n') || stmt.contains('>') {
+        return None;
+    }
+
+    // NOTE: Safe unwrap, since every capture has a group 0.
+    let command = caps.get(0).unwrap().as_str();
+    let prefix = match caps.get(2) {
+        Some(name) => format!("{name}=", name = name.as_str()),
+        None => String::new(),
+    };
+
+    Some(format!(
+        r#"{stmt} >> "${file}""#,
+        stmt = stmt.replacen(command, &prefix, 1)
+    ))
+}
+
+/// Returns the severity, annotation and suggestion for each deprecated
+/// or disabled command used in `script`.
+fn deprecated_commands(script: &str) -> Vec<(Severity, String, String)> {
+    let mut commands = vec![];
+
+    for stmt in statements(script) {
+        let stmt = stmt.text.trim();
+
+        for caps in COMMAND.captures_iter(stmt) {
+            let command = &caps[1];
+            let (file, disabled) = replacement(command);
+
+            let (severity, annotation) = if disabled {
+                (
+                    Severity::Medium,
+                    format!("disabled {command} command used here"),
+                )
+            } else {
+                (
+                    Severity::Low,
+                    format!("deprecated {command} command used here"),
+                )
+            };
+
+            let suggestion = match rewrite(stmt, &caps, file) {
+                Some(rewritten) => format!("use the {file} environment file instead: {rewritten}"),
+                None => format!("use the {file} environment file instead"),
+            };
+
+            commands.push((severity, annotation, suggestion));
+        }
+    }
+
+    commands
+}
+
+pub(crate) struct DeprecatedCommands {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for DeprecatedCommands {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "deprecated-commands"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "deprecated or disabled workflow commands"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            for step in job.steps() {
+                let StepBody::Run { run, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                for (severity, annotation, suggestion) in deprecated_commands(run) {
+                    findings.push(
+                        Self::finding()
+                            .severity(severity)
+                            .confidence(Confidence::High)
+                            .add_location(step.location_with_name())
+                            .add_location(
+                                step.location()
+                                    .with_keys(&["run".into()])
+                                    .annotated(annotation),
+                            )
+                            .suggestion(suggestion)
+                            .build(workflow)?,
+                    );
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+
+    fn audit_action<'a>(&self, action: &'a Action) -> anyhow::Result<Vec<Finding<'a>>> {
+        let mut findings = vec![];
+
+        for step in action.steps() {
+            let Some(run) = &step.run else {
+                continue;
+            };
+
+            for (severity, annotation, suggestion) in deprecated_commands(run) {
+                findings.push(
+                    Self::finding()
+                        .severity(severity)
+                        .confidence(Confidence::High)
+                        .add_location(step.location_with_name())
+                        .add_location(
+                            step.location()
+                                .with_keys(&["run".into()])
+                                .annotated(annotation),
+                        )
+                        .suggestion(suggestion)
+                        .build(action)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
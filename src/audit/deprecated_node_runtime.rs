@@ -0,0 +1,158 @@
+//! Audits actions for deprecated Node.js runtimes (`node12` and `node16`),
+//! which no longer receive security updates.
+//!
+//! This audit is online-only, since it needs the definition (`action.yml`)
+//! of each action at its pinned ref.
+
+use std::ops::Deref;
+
+use anyhow::{anyhow, Result};
+use github_actions_models::workflow::job::StepBody;
+use serde::Deserialize;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    github_api,
+    models::{Uses, Workflow},
+    state::AuditState,
+};
+
+/// Node.js runtimes that have reached end-of-life.
+const DEPRECATED_RUNTIMES: &[&str] = &["node12", "node16"];
+
+/// Just enough of an action definition to determine its runtime.
+///
+/// We use this instead of `github_actions_models::action::Action` since
+/// we only need `runs.using`, and don't want to reject definitions that are
+/// otherwise slightly malformed.
+#[derive(Deserialize)]
+struct ActionRuns {
+    runs: Runs,
+}
+
+#[derive(Deserialize)]
+struct Runs {
+    using: String,
+}
+
+pub(crate) struct DeprecatedNodeRuntime {
+    client: github_api::Client,
+}
+
+impl DeprecatedNodeRuntime {
+    /// Returns the runtime declared by the given action, if we can find
+    /// its definition.
+    fn runtime(&self, uses: &Uses<'_>) -> Result<Option<String>> {
+        let Some(definition) = self.client.fetch_action_metadata(uses)? else {
+            log::debug!(
+                "no action definition for {owner}/{repo}",
+                owner = uses.owner,
+                repo = uses.repo
+            );
+            return Ok(None);
+        };
+
+        match serde_yaml::from_str::<ActionRuns>(&definition) {
+            Ok(action) => Ok(Some(action.runs.using)),
+            Err(e) => {
+                log::warn!(
+                    "couldn't parse action definition for {owner}/{repo}: {e}",
+                    owner = uses.owner,
+                    repo = uses.repo
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Audit for DeprecatedNodeRuntime {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "deprecated-node-runtime"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "action runs on a deprecated Node.js runtime"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn requires_github_api() -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+
+    fn new(state: AuditState) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let Some(client) = state.github_client() else {
+            return Err(anyhow!("can't audit without a GitHub API token"));
+        };
+
+        Ok(Self { client })
+    }
+
+    fn uses_github_api(&self) -> bool {
+        true
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            for step in job.steps() {
+                let StepBody::Uses { uses, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                // Unpinned actions are handled by `unpinned-uses`.
+                let Some(uses) = Uses::from_step(uses).filter(|uses| uses.git_ref.is_some()) else {
+                    continue;
+                };
+
+                let Some(runtime) = self.runtime(&uses)? else {
+                    continue;
+                };
+
+                if !DEPRECATED_RUNTIMES.contains(&runtime.to_lowercase().as_str()) {
+                    continue;
+                }
+
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Medium)
+                        .confidence(Confidence::High)
+                        .add_location(step.location_with_name())
+                        .add_location(
+                            step.location()
+                                .with_keys(&["uses".into()])
+                                .annotated(format!("action runs on {runtime}")),
+                        )
+                        .suggestion(format!(
+                            "upgrade to a version of {owner}/{repo} that runs on a supported runtime",
+                            owner = uses.owner,
+                            repo = uses.repo
+                        ))
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,215 @@
+//! Audits `runs-on:` for GitHub-hosted runner images that have been
+//! retired, are in their brownout period, or are otherwise deprecated.
+//!
+//! Lifecycle data for each image is bundled as a standalone table
+//! (`runner-images.json`), and can be extended or overridden via the
+//! `images` setting without waiting for a new release. The bundled table
+//! only lists images whose retirement has already happened; deprecations
+//! and brownouts are announced on short notice, so they're expected to be
+//! configured until the table is next updated.
+//!
+//! See: <https://github.com/actions/runner-images>
+
+use anyhow::Context;
+use github_actions_models::common::expr::ExplicitExpr;
+use serde::Deserialize;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::{is_github_hosted, Workflow},
+    state::AuditState,
+};
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Status {
+    /// GitHub has announced the image's retirement.
+    Deprecated,
+    /// The image is scheduled for retirement, and jobs are being failed
+    /// during temporary brownouts.
+    Brownout,
+    /// The image has been removed; jobs that request it never start.
+    Retired,
+}
+
+/// The lifecycle state of a single runner image label.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ImageLifecycle {
+    label: String,
+    status: Status,
+    /// The date of the (next) status change, if known.
+    #[serde(default)]
+    date: Option<String>,
+    #[serde(default)]
+    replacement: Option<String>,
+}
+
+impl ImageLifecycle {
+    /// Returns whether this lifecycle applies to the given label, including
+    /// larger runner variants like `macos-13-xlarge`.
+    fn matches(&self, label: &str) -> bool {
+        label.eq_ignore_ascii_case(&self.label)
+            || label
+                .to_lowercase()
+                .strip_prefix(&self.label.to_lowercase())
+                .is_some_and(|rest| rest.starts_with('-'))
+    }
+
+    /// Returns the severity of using `label` (which this lifecycle matches),
+    /// and an annotation describing its status.
+    fn describe(&self, label: &str) -> (Severity, String) {
+        let (severity, status) = match self.status {
+            Status::Retired => (Severity::High, "has been retired"),
+            Status::Brownout => (Severity::Medium, "is scheduled for retirement"),
+            Status::Deprecated => (Severity::Low, "is deprecated"),
+        };
+        let since = match &self.date {
+            Some(date) if matches!(self.status, Status::Retired) => format!(" (since {date})"),
+            Some(date) => format!(" (retiring {date})"),
+            None => String::new(),
+        };
+
+        (severity, format!("runner image {label} {status}{since}"))
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct DeprecatedRunnerImagesConfig {
+    /// Additional lifecycle entries. Entries for labels that are also in
+    /// the bundled table take precedence over the bundled entries.
+    #[serde(default)]
+    images: Vec<ImageLifecycle>,
+}
+
+pub(crate) struct DeprecatedRunnerImages {
+    images: Vec<ImageLifecycle>,
+}
+
+impl Audit for DeprecatedRunnerImages {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "deprecated-runner-images"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "retired or deprecated runner image"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn config_keys() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &["images"]
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let bundled: Vec<ImageLifecycle> = serde_json::from_str(include_str!("runner-images.json"))
+            .context("invalid bundled runner image table")?;
+        let config: DeprecatedRunnerImagesConfig = state.config.file.rule_config(Self::ident())?;
+
+        // Configured entries come first, so that they shadow bundled ones.
+        let mut images = config.images;
+        images.extend(bundled);
+
+        Ok(Self { images })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            for label in job.runner_labels() {
+                if ExplicitExpr::from_curly(label).is_some() || !is_github_hosted(label) {
+                    continue;
+                }
+
+                let Some(image) = self.images.iter().find(|image| image.matches(label)) else {
+                    continue;
+                };
+
+                let (severity, annotation) = image.describe(label);
+                let mut finding = Self::finding()
+                    .severity(severity)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        job.location()
+                            .with_keys(&["runs-on".into()])
+                            .annotated(annotation),
+                    );
+                if let Some(replacement) = &image.replacement {
+                    finding = finding.suggestion(format!("use {replacement} instead"));
+                }
+
+                findings.push(finding.build(workflow)?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImageLifecycle, Status};
+    use crate::finding::Severity;
+
+    #[test]
+    fn test_describe() {
+        let image = |status, date: Option<&str>| ImageLifecycle {
+            label: "ubuntu-22.04".into(),
+            status,
+            date: date.map(Into::into),
+            replacement: None,
+        };
+
+        assert_eq!(
+            image(Status::Retired, Some("2027-04-01")).describe("ubuntu-22.04"),
+            (
+                Severity::High,
+                "runner image ubuntu-22.04 has been retired (since 2027-04-01)".into()
+            )
+        );
+        assert_eq!(
+            image(Status::Brownout, Some("2027-04-01")).describe("ubuntu-22.04-arm"),
+            (
+                Severity::Medium,
+                "runner image ubuntu-22.04-arm is scheduled for retirement (retiring 2027-04-01)"
+                    .into()
+            )
+        );
+        assert_eq!(
+            image(Status::Deprecated, None).describe("ubuntu-22.04"),
+            (
+                Severity::Low,
+                "runner image ubuntu-22.04 is deprecated".into()
+            )
+        );
+
+        // Configured entries are parsed like bundled ones.
+        let configured: ImageLifecycle = serde_json::from_str(
+            r#"{ "label": "macos-14", "status": "brownout", "date": "2027-01-01" }"#,
+        )
+        .unwrap();
+        assert!(matches!(configured.status, Status::Brownout));
+        assert!(configured.matches("macos-14-large"));
+        assert!(!configured.matches("macos-145"));
+    }
+}
@@ -2,13 +2,18 @@ use std::{collections::HashMap, ops::Deref, sync::LazyLock};
 
 use github_actions_models::{
     common::{BasePermission, Permission, Permissions},
-    workflow::Job,
+    workflow::{job::StepBody, Job},
 };
 
-use super::WorkflowAudit;
+use serde_yaml::Value;
+
+use super::Audit;
 use crate::{
-    finding::{Confidence, Severity},
-    AuditState,
+    finding::{
+        fix::{Fix, Patch},
+        Confidence, Rationale, Severity,
+    },
+    models, AuditState,
 };
 
 // Subjective mapping of permissions to severities, when given `write` access.
@@ -33,11 +38,96 @@ static KNOWN_PERMISSIONS: LazyLock<HashMap<&str, Severity>> = LazyLock::new(|| {
     .into()
 });
 
+/// A problem with a permissions block: its severity and confidence, the
+/// scope it's about (or `None`, for the whole block), an annotation, and
+/// optionally a fix for it (a title and a patch of the scope's node).
+type Problem<'w> = (
+    Severity,
+    Confidence,
+    Option<&'w str>,
+    String,
+    Option<(String, Patch)>,
+);
+
+/// Indicators that a job uses a permission: `uses:` prefixes, and
+/// fragments of `run:` scripts.
+type PermissionUsers = (&'static [&'static str], &'static [&'static str]);
+
+// Heuristic indicators that a job actually needs `write` access to a
+// given permission.
+static PERMISSION_USERS: LazyLock<HashMap<&str, PermissionUsers>> = LazyLock::new(|| {
+    [
+        (
+            "attestations",
+            (&["actions/attest"][..], &["gh attestation"][..]),
+        ),
+        (
+            "contents",
+            (
+                &[
+                    "softprops/action-gh-release",
+                    "ncipollo/release-action",
+                    "actions/create-release",
+                    "googleapis/release-please-action",
+                    "stefanzweifel/git-auto-commit-action",
+                ][..],
+                &["gh release", "git push", "git tag"][..],
+            ),
+        ),
+        (
+            "id-token",
+            (
+                &[
+                    "aws-actions/configure-aws-credentials",
+                    "azure/login",
+                    "google-github-actions/auth",
+                    "hashicorp/vault-action",
+                    "octo-sts/action",
+                    "pypa/gh-action-pypi-publish",
+                    "rubygems/release-gem",
+                    "rubygems/configure-rubygems-credentials",
+                    "sigstore/",
+                    "actions/attest",
+                ][..],
+                &[
+                    "ACTIONS_ID_TOKEN_REQUEST",
+                    "npm publish --provenance",
+                    "cosign sign",
+                ][..],
+            ),
+        ),
+        (
+            "issues",
+            (
+                &[
+                    "actions/github-script",
+                    "peter-evans/create-or-update-comment",
+                ][..],
+                &["gh issue"][..],
+            ),
+        ),
+        (
+            "packages",
+            (&["docker/login-action"][..], &["docker push"][..]),
+        ),
+        ("pages", (&["actions/deploy-pages"][..], &[][..])),
+        (
+            "pull-requests",
+            (
+                &["actions/github-script", "peter-evans/create-pull-request"][..],
+                &["gh pr"][..],
+            ),
+        ),
+        ("security-events", (&["github/codeql-action"][..], &[][..])),
+    ]
+    .into()
+});
+
 pub(crate) struct ExcessivePermissions {
-    pub(crate) _config: AuditState,
+    pub(crate) state: AuditState,
 }
 
-impl WorkflowAudit for ExcessivePermissions {
+impl Audit for ExcessivePermissions {
     fn ident() -> &'static str
     where
         Self: Sized,
@@ -52,53 +142,71 @@ impl WorkflowAudit for ExcessivePermissions {
         "overly broad workflow or job-level permissions"
     }
 
-    fn new(config: AuditState) -> anyhow::Result<Self>
+    fn default_severity() -> Severity
     where
         Self: Sized,
     {
-        Ok(Self { _config: config })
+        Severity::Medium
     }
 
-    fn audit<'w>(
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { state })
+    }
+
+    fn audit_workflow<'w>(
         &self,
         workflow: &'w crate::models::Workflow,
     ) -> anyhow::Result<Vec<crate::finding::Finding<'w>>> {
         let mut findings = vec![];
+        let jobs = workflow.jobs().collect::<Vec<_>>();
+
         // Top-level permissions.
-        for (severity, confidence, note) in self.check_permissions(&workflow.permissions, None) {
-            findings.push(
-                Self::finding()
-                    .severity(severity)
-                    .confidence(confidence)
-                    .add_location(
-                        workflow
-                            .location()
-                            .with_keys(&["permissions".into()])
-                            .annotated(note),
-                    )
-                    .build(workflow)?,
-            )
+        for (severity, confidence, scope, note, fix) in
+            self.check_permissions(&workflow.permissions, None, &jobs)
+        {
+            let mut location = workflow.location().with_keys(&["permissions".into()]);
+            if let Some(scope) = scope {
+                location = location.with_keys(&[scope.into()]);
+            }
+
+            let mut finding = Self::finding()
+                .severity(severity)
+                .confidence(confidence)
+                .rationale(Self::rationale(confidence))
+                .add_location(location.clone().annotated(note));
+            if let Some((title, patch)) = fix {
+                finding = finding.fix(Fix::new(title, location, patch));
+            }
+            findings.push(finding.build(workflow)?)
         }
 
-        for job in workflow.jobs() {
+        for job in &jobs {
             let Job::NormalJob(normal) = job.deref() else {
                 continue;
             };
 
-            for (severity, confidence, note) in
-                self.check_permissions(&normal.permissions, Some(&workflow.permissions))
-            {
-                findings.push(
-                    Self::finding()
-                        .severity(severity)
-                        .confidence(confidence)
-                        .add_location(
-                            job.location()
-                                .with_keys(&["permissions".into()])
-                                .annotated(note),
-                        )
-                        .build(workflow)?,
-                )
+            for (severity, confidence, scope, note, fix) in self.check_permissions(
+                &normal.permissions,
+                Some(&workflow.permissions),
+                std::slice::from_ref(job),
+            ) {
+                let mut location = job.location().with_keys(&["permissions".into()]);
+                if let Some(scope) = scope {
+                    location = location.with_keys(&[scope.into()]);
+                }
+
+                let mut finding = Self::finding()
+                    .severity(severity)
+                    .confidence(confidence)
+                    .rationale(Self::rationale(confidence))
+                    .add_location(location.clone().annotated(note));
+                if let Some((title, patch)) = fix {
+                    finding = finding.fix(Fix::new(title, location, patch));
+                }
+                findings.push(finding.build(workflow)?)
             }
         }
 
@@ -107,11 +215,65 @@ impl WorkflowAudit for ExcessivePermissions {
 }
 
 impl ExcessivePermissions {
-    fn check_permissions(
+    /// Returns whether the given job appears to need `write` access
+    /// to the given permission.
+    ///
+    /// This is a heuristic: jobs that need a permission typically either
+    /// use an action that's known to need it, or run a command that does.
+    fn job_needs_write(&self, job: &models::Job, name: &str) -> bool {
+        let Some((actions, commands)) = PERMISSION_USERS.get(name) else {
+            return false;
+        };
+
+        if !matches!(job.deref(), Job::NormalJob(_)) {
+            return false;
+        }
+
+        job.steps().any(|step| match &step.deref().body {
+            StepBody::Uses { uses, .. } => actions
+                .iter()
+                .any(|action| uses.to_lowercase().starts_with(action)),
+            StepBody::Run { run, .. } => commands.iter().any(|command| run.contains(command)),
+        })
+    }
+
+    /// Returns the rationale for a finding with the given confidence: only
+    /// unneeded job-level writes are reported with less than high
+    /// confidence, since our indicators of need are heuristic.
+    fn rationale(confidence: Confidence) -> Rationale {
+        match confidence {
+            Confidence::High => Rationale::LiteralMatch,
+            _ => Rationale::Heuristic,
+        }
+    }
+
+    fn check_permissions<'w>(
         &self,
-        permissions: &Permissions,
+        permissions: &'w Permissions,
         parent: Option<&Permissions>,
-    ) -> Vec<(Severity, Confidence, String)> {
+        jobs: &[models::Job],
+    ) -> Vec<Problem<'w>> {
+        // Broad base permissions are replaced with the minimal permissions
+        // that most jobs need, i.e. to check out the repository.
+        let minimal = || {
+            Some((
+                "replace with contents: read".to_string(),
+                Patch::Rewrite {
+                    text: "{ contents: read }".into(),
+                },
+            ))
+        };
+        // An unneeded `write` is downgraded as far as it goes.
+        let downgrade = |name: &str| {
+            let to = if name == "id-token" { "none" } else { "read" };
+            Some((
+                format!("set {name}: {to}"),
+                Patch::Replace {
+                    value: Value::String(to.into()),
+                },
+            ))
+        };
+
         match permissions {
             Permissions::Base(base) => match base {
                 // TODO: Think more about what to do here. Flagging default
@@ -122,49 +284,104 @@ impl ExcessivePermissions {
                 BasePermission::ReadAll => vec![(
                     Severity::Medium,
                     Confidence::High,
+                    None,
                     "uses read-all permissions".into(),
+                    minimal(),
                 )],
                 BasePermission::WriteAll => vec![(
                     Severity::High,
                     Confidence::High,
+                    None,
                     "uses write-all permissions".into(),
+                    minimal(),
                 )],
             },
-            Permissions::Explicit(perms) => match parent {
-                // In the general case, it's impossible to tell whether a
-                // job-level permission block is over-scoped.
-                Some(_) => vec![],
-                // Top-level permission-blocks should almost never contain
-                // write permissions.
-                None => {
-                    let mut results = vec![];
-
-                    for (name, perm) in perms {
-                        if *perm != Permission::Write {
-                            continue;
-                        }
+            Permissions::Explicit(perms) => {
+                let mut results = vec![];
 
-                        match KNOWN_PERMISSIONS.get(name.as_str()) {
-                            Some(sev) => results.push((
-                                *sev,
-                                Confidence::High,
-                                format!("{name}: write is overly broad at the workflow level"),
-                            )),
-                            None => {
-                                log::debug!("unknown permission: {name}");
-
-                                results.push((
-                                    Severity::Unknown,
-                                    Confidence::High,
-                                    format!("{name}: write is overly broad at the workflow level"),
-                                ))
-                            }
-                        }
+                for (name, perm) in perms {
+                    if *perm != Permission::Write {
+                        continue;
                     }
 
-                    results
+                    let severity = match KNOWN_PERMISSIONS.get(name.as_str()) {
+                        Some(sev) => *sev,
+                        None => {
+                            log::debug!("unknown permission: {name}");
+                            Self::default_severity()
+                        }
+                    };
+
+                    let needed =
+                        !jobs.is_empty() && jobs.iter().all(|job| self.job_needs_write(job, name));
+
+                    match (parent, needed) {
+                        // A job that needs a permission should have it
+                        // at the job level; that's the best practice.
+                        (Some(_), true) => continue,
+                        // OIDC token exchanges are performed by a small,
+                        // well-known set of actions and tools, so we're
+                        // more confident that an unused `id-token: write`
+                        // is truly unneeded.
+                        (Some(_), false) if name == "id-token" => results.push((
+                            severity,
+                            Confidence::Medium,
+                            Some(name.as_str()),
+                            "id-token: write is granted, but this job doesn't appear to \
+                             perform an OIDC token exchange"
+                                .into(),
+                            downgrade(name),
+                        )),
+                        // In the general case, it's impossible to tell whether
+                        // a job-level permission is over-scoped, since our
+                        // indicators of need are far from exhaustive. These
+                        // are only reported when pedantic, and with low
+                        // severity and confidence.
+                        (Some(_), false) if !self.state.config.pedantic => continue,
+                        (Some(_), false) => results.push((
+                            severity.min(Severity::Low),
+                            Confidence::Low,
+                            Some(name.as_str()),
+                            format!("{name}: write may not be needed by this job"),
+                            None,
+                        )),
+                        // Top-level permissions that every job needs are
+                        // still worth scoping down to the job level, but
+                        // aren't as severe.
+                        (None, true) => results.push((
+                            Severity::Low,
+                            Confidence::High,
+                            Some(name.as_str()),
+                            format!(
+                                "{name}: write is needed, but should be scoped to the job level"
+                            ),
+                            None,
+                        )),
+                        // A workflow-level `id-token: write` lets every job
+                        // mint OIDC tokens, extending the workflow's cloud
+                        // trust relationships to all of them.
+                        (None, false) if name == "id-token" => results.push((
+                            severity,
+                            Confidence::High,
+                            Some(name.as_str()),
+                            "id-token: write lets every job in this workflow request OIDC tokens"
+                                .into(),
+                            downgrade(name),
+                        )),
+                        // Top-level permission-blocks should almost never contain
+                        // write permissions.
+                        (None, false) => results.push((
+                            severity,
+                            Confidence::High,
+                            Some(name.as_str()),
+                            format!("{name}: write is overly broad at the workflow level"),
+                            downgrade(name),
+                        )),
+                    }
                 }
-            },
+
+                results
+            }
         }
     }
 }
@@ -0,0 +1,140 @@
+//! Audits `run:` steps that write attacker-influenced data to
+//! `$GITHUB_ENV` or `$GITHUB_PATH`.
+//!
+//! Both files are read by the runner after each step: `GITHUB_ENV` sets
+//! environment variables for all subsequent steps (including variables
+//! like `LD_PRELOAD` or `NODE_OPTIONS`), and `GITHUB_PATH` prepends to
+//! `PATH`. Either can be turned into code execution by an attacker
+//! who controls what gets written to them.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{event::BareEvent, job::StepBody};
+
+use super::Audit;
+use crate::{
+    dataflow::Dataflow,
+    expr::context_is_attacker_controlled,
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::Workflow,
+    shell::statements,
+    state::AuditState,
+};
+
+/// The environment files that the runner loads between steps.
+const ENV_FILES: &[&str] = &["GITHUB_ENV", "GITHUB_PATH"];
+
+/// Indicators that a statement reads (and potentially writes out)
+/// the contents of a file.
+const FILE_READERS: &[&str] = &["cat ", "$(<", "< ", "Get-Content", "jq "];
+
+pub(crate) struct GitHubEnv {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for GitHubEnv {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "github-env"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "dangerous use of GITHUB_ENV or GITHUB_PATH"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        // Artifacts in `workflow_run` workflows are typically produced by
+        // the triggering (potentially untrusted) workflow.
+        let untrusted_artifacts = workflow.has_trigger(BareEvent::WorkflowRun);
+        let dataflow = Dataflow::new(workflow, context_is_attacker_controlled);
+
+        for job in workflow.jobs() {
+            let mut downloaded_artifacts = false;
+
+            for step in job.steps() {
+                let run = match &step.deref().body {
+                    StepBody::Uses { .. } => {
+                        downloaded_artifacts |= step.is_artifact_download();
+                        continue;
+                    }
+                    StepBody::Run { run, .. } => run,
+                };
+
+                for stmt in statements(run) {
+                    for file in ENV_FILES {
+                        if !stmt.writes_to(file) {
+                            continue;
+                        }
+
+                        let sources = dataflow.script_sources(stmt.text, &job, &step);
+
+                        let (severity, confidence, rationale, annotation) = if !sources.is_empty() {
+                            (
+                                Severity::High,
+                                Confidence::High,
+                                Rationale::DataflowTraced,
+                                format!(
+                                    "writes attacker-controlled {sources} to {file}",
+                                    sources = sources.join(", ")
+                                ),
+                            )
+                        } else if downloaded_artifacts
+                            && FILE_READERS.iter().any(|reader| stmt.text.contains(reader))
+                        {
+                            (
+                                if untrusted_artifacts {
+                                    Severity::High
+                                } else {
+                                    Severity::Medium
+                                },
+                                Confidence::Medium,
+                                Rationale::Heuristic,
+                                format!("may write downloaded artifact contents to {file}"),
+                            )
+                        } else {
+                            continue;
+                        };
+
+                        findings.push(
+                            Self::finding()
+                                .severity(severity)
+                                .confidence(confidence)
+                                .rationale(rationale)
+                                .add_location(step.location_with_name())
+                                .add_location(
+                                    step.location()
+                                        .with_keys(&["run".into()])
+                                        .subspan(run, stmt.span.clone())
+                                        .annotated(annotation),
+                                )
+                                .build(workflow)?,
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,126 @@
+//! Detects template injection into `actions/github-script` scripts.
+//!
+//! `actions/github-script` runs its `script:` input as the body of an async
+//! JavaScript function. Template expressions in the script are expanded
+//! before it runs, meaning that an attacker-controlled expansion can
+//! break out of a string literal and execute arbitrary JavaScript, with
+//! access to the step's `github` client and token.
+
+use super::Audit;
+use crate::{
+    dataflow::{sinks, Dataflow, SinkKind},
+    expr::context_is_attacker_controlled,
+    finding::{Confidence, Rationale, Severity},
+    injection::{injectable_inputs, injectable_template_expressions},
+    state::AuditState,
+};
+
+pub(crate) struct GitHubScriptInjection {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for GitHubScriptInjection {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "github-script-injection"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "code injection via template expansion into actions/github-script"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(
+        &self,
+        workflow: &'w crate::models::Workflow,
+    ) -> anyhow::Result<Vec<crate::finding::Finding<'w>>> {
+        let mut findings = vec![];
+        let dataflow = Dataflow::new(workflow, context_is_attacker_controlled);
+
+        for job in workflow.jobs() {
+            for sink in sinks(&job) {
+                if sink.kind != SinkKind::JavaScript {
+                    continue;
+                }
+
+                for (expr, severity, confidence, rationale, span) in
+                    injectable_template_expressions(&sink, &job, &dataflow)
+                {
+                    findings.push(
+                        Self::finding()
+                            .severity(severity)
+                            .confidence(confidence)
+                            .rationale(rationale)
+                            .add_location(sink.step.location_with_name())
+                            .add_location(sink.location.clone().subspan(&sink.code, span).annotated(
+                                format!("{expr} may expand into attacker-controllable JavaScript"),
+                            ))
+                            .suggestion(format!(
+                                "pass {expr} through the step's env: and read it with process.env instead"
+                            ))
+                            .build(workflow)?,
+                    )
+                }
+            }
+        }
+
+        // Inputs that this workflow passes to the reusable workflows it
+        // calls, and that are expanded into their code.
+        for (caller, callee) in workflow.calls() {
+            let callee_dataflow = dataflow.callee(&caller, callee);
+
+            for job in callee.jobs() {
+                for sink in sinks(&job) {
+                    if sink.kind != SinkKind::JavaScript {
+                        continue;
+                    }
+
+                    for (input, sources) in
+                        injectable_inputs(&sink, &job, &callee_dataflow, &caller)
+                    {
+                        findings.push(
+                            Self::finding()
+                                .severity(Severity::High)
+                                .confidence(Confidence::High)
+                                .rationale(Rationale::DataflowTraced)
+                                .add_location(
+                                    caller
+                                        .location()
+                                        .with_keys(&["with".into(), input.into()])
+                                        .annotated(format!(
+                                            "passes attacker-controlled {sources} to {callee}",
+                                            sources = sources.join(", "),
+                                            callee = callee.filename(),
+                                        )),
+                                )
+                                .add_location(sink.location.clone().annotated(format!(
+                                    "inputs.{input} may expand into attacker-controllable JavaScript"
+                                )))
+                                .build(workflow)?,
+                        )
+                    }
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -8,7 +8,7 @@ use github_actions_models::{
     },
 };
 
-use super::WorkflowAudit;
+use super::Audit;
 use crate::{
     finding::{Confidence, Severity},
     state::AuditState,
@@ -16,7 +16,7 @@ use crate::{
 
 pub(crate) struct HardcodedContainerCredentials {}
 
-impl WorkflowAudit for HardcodedContainerCredentials {
+impl Audit for HardcodedContainerCredentials {
     fn ident() -> &'static str
     where
         Self: Sized,
@@ -31,6 +31,13 @@ impl WorkflowAudit for HardcodedContainerCredentials {
         "hardcoded credential in GitHub Actions container configurations"
     }
 
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
     fn new(_state: AuditState) -> anyhow::Result<Self>
     where
         Self: Sized,
@@ -38,7 +45,7 @@ impl WorkflowAudit for HardcodedContainerCredentials {
         Ok(Self {})
     }
 
-    fn audit<'w>(
+    fn audit_workflow<'w>(
         &self,
         workflow: &'w crate::models::Workflow,
     ) -> anyhow::Result<Vec<crate::finding::Finding<'w>>> {
@@ -0,0 +1,237 @@
+//! Detects hard-coded credentials in workflow `env:`, `with:`, and `run:`
+//! values.
+//!
+//! Container registry credentials are handled separately by the
+//! `hardcoded-container-credentials` audit.
+//!
+//! The bundled secret patterns can be disabled, and extended with
+//! additional patterns, in the audit's configuration.
+
+use std::ops::Deref;
+
+use anyhow::Context;
+use github_actions_models::{
+    common::{Env, EnvValue},
+    workflow::{job::StepBody, Job},
+};
+
+use serde::Deserialize;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::Workflow,
+    secrets::{SecretPattern, SecretScanner},
+    state::AuditState,
+};
+
+/// An additional secret pattern, from the configuration.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ConfiguredPattern {
+    /// A short identifier for the pattern, shown in findings.
+    name: String,
+    /// What the pattern matches, e.g. "internal API token".
+    description: String,
+    /// The regular expression to match.
+    regex: String,
+    /// How likely a match is to be an actual secret.
+    #[serde(default = "default_confidence")]
+    confidence: Confidence,
+}
+
+fn default_confidence() -> Confidence {
+    Confidence::Medium
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct HardcodedCredentialsConfig {
+    /// Additional patterns to match.
+    #[serde(default)]
+    patterns: Vec<ConfiguredPattern>,
+    /// The names of bundled patterns to disable.
+    #[serde(default)]
+    disabled: Vec<String>,
+}
+
+pub(crate) struct HardcodedCredentials {
+    scanner: SecretScanner,
+}
+
+impl HardcodedCredentials {
+    fn scan_text<'w>(
+        &self,
+        text: &str,
+        location: SymbolicLocation<'w>,
+        workflow: &'w Workflow,
+        findings: &mut Vec<Finding<'w>>,
+    ) -> anyhow::Result<()> {
+        for secret in self.scanner.scan(text) {
+            findings.push(
+                Self::finding()
+                    .severity(Severity::High)
+                    .confidence(secret.pattern.confidence)
+                    .rationale(Rationale::LiteralMatch)
+                    .add_location(location.clone().annotated(format!(
+                        "hard-coded {} ({}): {}",
+                        secret.pattern.description,
+                        secret.pattern.name,
+                        secret.redacted()
+                    )))
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Scans each value in `env`, reporting findings at `parent.<key>`.
+    fn scan_env<'w>(
+        &self,
+        env: &'w Env,
+        parent: SymbolicLocation<'w>,
+        workflow: &'w Workflow,
+        findings: &mut Vec<Finding<'w>>,
+    ) -> anyhow::Result<()> {
+        for (key, value) in env {
+            // Only strings can plausibly contain credentials.
+            let EnvValue::String(value) = value else {
+                continue;
+            };
+
+            self.scan_text(
+                value,
+                parent.with_keys(&[key.as_str().into()]),
+                workflow,
+                findings,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Audit for HardcodedCredentials {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "hardcoded-credentials"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "hard-coded credentials in workflow values"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn config_keys() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &["patterns", "disabled"]
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let config: HardcodedCredentialsConfig = state.config.file.rule_config(Self::ident())?;
+
+        let mut patterns = SecretScanner::builtin_patterns();
+        for name in &config.disabled {
+            if !patterns.iter().any(|pattern| &pattern.name == name) {
+                log::warn!(
+                    "{ident}: no bundled pattern named {name}",
+                    ident = Self::ident()
+                );
+            }
+        }
+        patterns.retain(|pattern| !config.disabled.contains(&pattern.name));
+
+        for pattern in config.patterns {
+            patterns.push(
+                SecretPattern::new(
+                    &pattern.name,
+                    pattern.description,
+                    pattern.confidence,
+                    &pattern.regex,
+                )
+                .with_context(|| format!("invalid regex for secret pattern {}", pattern.name))?,
+            );
+        }
+
+        Ok(Self {
+            scanner: SecretScanner::new(patterns),
+        })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        self.scan_env(
+            &workflow.env,
+            workflow.location().with_keys(&["env".into()]),
+            workflow,
+            &mut findings,
+        )?;
+
+        for job in workflow.jobs() {
+            match job.deref() {
+                Job::NormalJob(normal) => {
+                    self.scan_env(
+                        &normal.env,
+                        job.location().with_keys(&["env".into()]),
+                        workflow,
+                        &mut findings,
+                    )?;
+                }
+                Job::ReusableWorkflowCallJob(reusable) => {
+                    self.scan_env(
+                        &reusable.with,
+                        job.location().with_keys(&["with".into()]),
+                        workflow,
+                        &mut findings,
+                    )?;
+                    continue;
+                }
+            }
+
+            for step in job.steps() {
+                match &step.deref().body {
+                    StepBody::Uses { with, .. } => self.scan_env(
+                        with,
+                        step.location().with_keys(&["with".into()]),
+                        workflow,
+                        &mut findings,
+                    )?,
+                    StepBody::Run { run, env, .. } => {
+                        self.scan_text(
+                            run,
+                            step.location().with_keys(&["run".into()]),
+                            workflow,
+                            &mut findings,
+                        )?;
+                        self.scan_env(
+                            env,
+                            step.location().with_keys(&["env".into()]),
+                            workflow,
+                            &mut findings,
+                        )?;
+                    }
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
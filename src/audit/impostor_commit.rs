@@ -10,9 +10,9 @@ use std::ops::Deref;
 use anyhow::{anyhow, Result};
 use github_actions_models::workflow::{job::StepBody, Job};
 
-use super::WorkflowAudit;
+use super::Audit;
 use crate::{
-    finding::{Confidence, Finding, Severity},
+    finding::{Confidence, Finding, Rationale, Severity},
     github_api::{self, Branch, ComparisonStatus, Tag},
     models::{Uses, Workflow},
     state::AuditState,
@@ -58,13 +58,22 @@ impl ImpostorCommit {
     /// i.e. resolves due to presence in GitHub's fork network but is not actually
     /// present in any of the specified `owner/repo`'s tags or branches.
     fn impostor(&self, uses: Uses<'_>) -> Result<bool> {
-        let (branches, tags) = self.named_refs(uses)?;
-
         // If there's no ref or the ref is not a commit, there's nothing to impersonate.
         let Some(head_ref) = uses.commit_ref() else {
             return Ok(false);
         };
 
+        let (branches, tags) = self.named_refs(uses)?;
+
+        // Fast path: almost all commit-pinned actions are pinned to the
+        // current tip of some branch or tag, in which case we don't need
+        // to perform any (comparatively expensive) comparisons.
+        if branches.iter().any(|b| b.commit.sha == head_ref)
+            || tags.iter().any(|t| t.commit.sha == head_ref)
+        {
+            return Ok(false);
+        }
+
         for branch in branches {
             if self.named_ref_contains_commit(
                 &uses,
@@ -96,7 +105,7 @@ impl ImpostorCommit {
     }
 }
 
-impl WorkflowAudit for ImpostorCommit {
+impl Audit for ImpostorCommit {
     fn ident() -> &'static str {
         "impostor-commit"
     }
@@ -108,11 +117,21 @@ impl WorkflowAudit for ImpostorCommit {
         "commit with no history in referenced repository"
     }
 
-    fn new(state: AuditState) -> Result<Self> {
-        if state.config.offline {
-            return Err(anyhow!("offline audits only requested"));
-        }
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn requires_github_api() -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
 
+    fn new(state: AuditState) -> Result<Self> {
         let Some(client) = state.github_client() else {
             return Err(anyhow!("can't audit without a GitHub API token"));
         };
@@ -120,7 +139,11 @@ impl WorkflowAudit for ImpostorCommit {
         Ok(ImpostorCommit { client })
     }
 
-    fn audit<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+    fn uses_github_api(&self) -> bool {
+        true
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
         let mut findings = vec![];
 
         for job in workflow.jobs() {
@@ -140,6 +163,7 @@ impl WorkflowAudit for ImpostorCommit {
                                 Self::finding()
                                     .severity(Severity::High)
                                     .confidence(Confidence::High)
+                                    .rationale(Rationale::ResolvedViaApi)
                                     .add_location(step.location().annotated(IMPOSTOR_ANNOTATION))
                                     .build(workflow)?,
                             );
@@ -158,6 +182,7 @@ impl WorkflowAudit for ImpostorCommit {
                             Self::finding()
                                 .severity(Severity::High)
                                 .confidence(Confidence::High)
+                                .rationale(Rationale::ResolvedViaApi)
                                 .add_location(job.location().annotated(IMPOSTOR_ANNOTATION))
                                 .build(workflow)?,
                         );
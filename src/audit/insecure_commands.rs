@@ -0,0 +1,124 @@
+//! Audits workflows for `ACTIONS_ALLOW_UNSECURE_COMMANDS`, which
+//! re-enables the disabled `set-env` and `add-path` workflow commands.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::{expr::ExplicitExpr, Env, EnvValue},
+    workflow::{job::StepBody, Job},
+};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::Workflow,
+    state::AuditState,
+};
+
+const ALLOW_UNSECURE_COMMANDS: &str = "ACTIONS_ALLOW_UNSECURE_COMMANDS";
+
+pub(crate) struct InsecureCommands {
+    pub(crate) _state: AuditState,
+}
+
+impl InsecureCommands {
+    /// Checks `env` for `ACTIONS_ALLOW_UNSECURE_COMMANDS`, returning a
+    /// finding at `parent.ACTIONS_ALLOW_UNSECURE_COMMANDS` if it's enabled.
+    fn check_env<'w>(
+        &self,
+        env: &Env,
+        parent: SymbolicLocation<'w>,
+        workflow: &'w Workflow,
+    ) -> anyhow::Result<Option<Finding<'w>>> {
+        let (confidence, rationale) = match env.get(ALLOW_UNSECURE_COMMANDS) {
+            Some(EnvValue::Boolean(true)) => (Confidence::High, Rationale::LiteralMatch),
+            Some(EnvValue::String(value)) if value.eq_ignore_ascii_case("true") => {
+                (Confidence::High, Rationale::LiteralMatch)
+            }
+            // The variable's value is computed, so it might be `true`.
+            Some(EnvValue::String(value)) if ExplicitExpr::from_curly(value).is_some() => {
+                (Confidence::Low, Rationale::ExpressionUnevaluated)
+            }
+            _ => return Ok(None),
+        };
+
+        Self::finding()
+            .severity(Severity::High)
+            .confidence(confidence)
+            .rationale(rationale)
+            .add_location(
+                parent
+                    .with_keys(&[ALLOW_UNSECURE_COMMANDS.into()])
+                    .annotated("insecure workflow commands are enabled here"),
+            )
+            .build(workflow)
+            .map(Some)
+    }
+}
+
+impl Audit for InsecureCommands {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "insecure-commands"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "execution of insecure workflow commands is enabled"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        findings.extend(self.check_env(
+            &workflow.env,
+            workflow.location().with_keys(&["env".into()]),
+            workflow,
+        )?);
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            findings.extend(self.check_env(
+                &normal.env,
+                job.location().with_keys(&["env".into()]),
+                workflow,
+            )?);
+
+            for step in job.steps() {
+                // NOTE: Only `run:` steps have their `env:` modeled.
+                let StepBody::Run { env, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                findings.extend(self.check_env(
+                    env,
+                    step.location().with_keys(&["env".into()]),
+                    workflow,
+                )?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,248 @@
+//! Audits environment variables that weaken transport security during a
+//! build, e.g. `GIT_SSL_NO_VERIFY=1` or `NODE_TLS_REJECT_UNAUTHORIZED=0`.
+//!
+//! These disable TLS certificate or checksum verification for the tools
+//! that read them, letting anybody on the network path tamper with
+//! dependencies, source code, and anything else the job fetches.
+//!
+//! Variables are checked in `env:` blocks at every level, as well as in
+//! `export` statements and writes to `$GITHUB_ENV` in `run:` steps.
+//! Values computed by expressions are resolved where possible, e.g.
+//! `${{ vars.VERIFY_SSL }}` with a configured `VERIFY_SSL`. The
+//! known variables are bundled as a standalone table (`insecure-env.json`),
+//! which can be extended via the `variables` setting.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use anyhow::Context;
+use github_actions_models::{common::EnvValue, workflow::job::StepBody};
+use regex::Regex;
+use serde::Deserialize;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::{EnvVar, Job, Step, Workflow},
+    shell::statements,
+    state::AuditState,
+    symbols::SymbolTable,
+    utils::extract_expressions,
+};
+
+/// Matches shell-style variable assignments, e.g. `FOO=bar` or `FOO="bar"`,
+/// capturing the variable's name and (unquoted) value.
+static ASSIGNMENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\b([A-Za-z_]\w*)=["']?([^"'\s]*)"#).unwrap());
+
+/// A single environment variable that weakens verification.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct InsecureVariable {
+    /// The variable's name, matched case-insensitively.
+    name: String,
+    /// The values (matched case-insensitively) that weaken verification.
+    /// If empty, any non-empty value does.
+    #[serde(default)]
+    values: Vec<String>,
+    /// What setting the variable does, e.g. "disables TLS certificate
+    /// verification for git".
+    effect: String,
+}
+
+impl InsecureVariable {
+    /// Returns our confidence that setting this variable to the given value
+    /// weakens verification, if it might.
+    fn matches(&self, name: &str, value: &str) -> Option<Confidence> {
+        if !name.eq_ignore_ascii_case(&self.name) || value.is_empty() {
+            return None;
+        }
+
+        if self.values.is_empty()
+            || self
+                .values
+                .iter()
+                .any(|insecure| insecure.eq_ignore_ascii_case(value))
+        {
+            Some(Confidence::High)
+        } else if !extract_expressions(value).is_empty() {
+            // The value is only known at runtime.
+            Some(Confidence::Low)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct InsecureEnvConfig {
+    /// Additional variables to flag.
+    #[serde(default)]
+    variables: Vec<InsecureVariable>,
+}
+
+pub(crate) struct InsecureEnv {
+    variables: Vec<InsecureVariable>,
+    /// The configured `vars`, for resolving expressions in `env:` values.
+    vars: HashMap<String, String>,
+}
+
+impl InsecureEnv {
+    fn check<'w>(
+        &self,
+        name: &str,
+        value: &str,
+        location: SymbolicLocation<'w>,
+        workflow: &'w Workflow,
+    ) -> anyhow::Result<Option<Finding<'w>>> {
+        let Some((variable, confidence)) = self
+            .variables
+            .iter()
+            .find_map(|variable| Some((variable, variable.matches(name, value)?)))
+        else {
+            return Ok(None);
+        };
+
+        Self::finding()
+            .severity(Severity::Medium)
+            .confidence(confidence)
+            .rationale(if confidence == Confidence::High {
+                Rationale::LiteralMatch
+            } else {
+                Rationale::ExpressionUnevaluated
+            })
+            .add_location(
+                location.annotated(format!("{name}={value} {effect}", effect = variable.effect)),
+            )
+            .suggestion(format!(
+                "remove {name}, and fix the underlying certificate or proxy problem instead"
+            ))
+            .build(workflow)
+            .map(Some)
+    }
+
+    /// Checks `vars`, which are declared at the scope given by `job`
+    /// and `step`.
+    fn check_vars<'w>(
+        &self,
+        vars: Vec<EnvVar<'w>>,
+        symbols: &SymbolTable<'w>,
+        job: Option<&Job<'w>>,
+        step: Option<&Step<'w>>,
+        workflow: &'w Workflow,
+    ) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for var in vars {
+            let value = match var.value {
+                // Values computed by expressions are checked as what they
+                // evaluate to, when we know that.
+                EnvValue::String(value) if !extract_expressions(value).is_empty() => symbols
+                    .resolve(&format!("env.{name}", name = var.name), job, step)
+                    .map_or_else(|| value.clone(), |resolved| resolved.to_string()),
+                EnvValue::String(value) => value.clone(),
+                other => other.to_string(),
+            };
+
+            findings.extend(self.check(var.name, &value, var.location, workflow)?);
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for InsecureEnv {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "insecure-env"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "environment variable weakens transport security"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn config_keys() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &["variables"]
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let bundled: Vec<InsecureVariable> =
+            serde_json::from_str(include_str!("insecure-env.json"))
+                .context("invalid bundled insecure environment table")?;
+        let config: InsecureEnvConfig = state.config.file.rule_config(Self::ident())?;
+
+        let mut variables = config.variables;
+        variables.extend(bundled);
+
+        Ok(Self {
+            variables,
+            vars: state.config.file.vars().clone(),
+        })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let symbols = SymbolTable::new(workflow, &self.vars);
+        let mut findings = self.check_vars(workflow.env_vars(), &symbols, None, None, workflow)?;
+
+        for job in workflow.jobs() {
+            findings.extend(self.check_vars(
+                job.env_vars(),
+                &symbols,
+                Some(&job),
+                None,
+                workflow,
+            )?);
+
+            for step in job.steps() {
+                findings.extend(self.check_vars(
+                    step.env_vars(),
+                    &symbols,
+                    Some(&job),
+                    Some(&step),
+                    workflow,
+                )?);
+
+                let StepBody::Run { run, .. } = &step.body else {
+                    continue;
+                };
+
+                for stmt in statements(run) {
+                    if !(stmt.text.trim_start().starts_with("export ")
+                        || stmt.writes_to("GITHUB_ENV"))
+                    {
+                        continue;
+                    }
+
+                    for caps in ASSIGNMENT.captures_iter(stmt.text) {
+                        findings.extend(self.check(
+                            &caps[1],
+                            &caps[2],
+                            step.location().with_keys(&["run".into()]),
+                            workflow,
+                        )?);
+                    }
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
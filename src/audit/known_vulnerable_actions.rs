@@ -1,28 +1,80 @@
 //! Detects publicly disclosed action vulnerabilities.
 //!
-//! This audit uses GitHub's security advisories API as a source of
-//! ground truth.
+//! When online, this audit uses GitHub's security advisories API as a source
+//! of ground truth. When offline, it falls back on the (smaller)
+//! bundled advisory database in [`crate::advisories`], as it does when the
+//! GitHub instance doesn't have the advisories API (e.g. older versions of
+//! GitHub Enterprise Server).
 //!
 //! See: <https://docs.github.com/en/rest/security-advisories/global-advisories?apiVersion=2022-11-28>
 
-use anyhow::{anyhow, Context, Result};
+use std::sync::Once;
+
+use anyhow::{Context, Result};
 use github_actions_models::workflow::{job::StepBody, Job};
 
 use crate::{
+    advisories,
     finding::{Confidence, Severity},
     github_api,
     models::Uses,
     state::AuditState,
 };
 
-use super::WorkflowAudit;
+use super::Audit;
+
+/// A known vulnerability: its severity, GHSA ID, and first patched version
+/// (if there is one).
+type KnownVulnerability = (Severity, String, Option<String>);
 
 pub(crate) struct KnownVulnerableActions {
-    client: github_api::Client,
+    client: Option<github_api::Client>,
 }
 
 impl KnownVulnerableActions {
-    fn action_known_vulnerabilities(&self, uses: &Uses<'_>) -> Result<Vec<(Severity, String)>> {
+    fn severity(severity: &str) -> Severity {
+        match severity {
+            "low" => Severity::Low,
+            "medium" => Severity::Medium,
+            "high" => Severity::High,
+            "critical" => Severity::Critical,
+            // Advisories without a severity are still known vulnerabilities.
+            _ => Self::default_severity(),
+        }
+    }
+
+    fn action_known_vulnerabilities(&self, uses: &Uses<'_>) -> Result<Vec<KnownVulnerability>> {
+        match &self.client {
+            Some(client) => self.online_known_vulnerabilities(client, uses),
+            None => Ok(self.offline_known_vulnerabilities(uses)),
+        }
+    }
+
+    fn offline_known_vulnerabilities(&self, uses: &Uses<'_>) -> Vec<KnownVulnerability> {
+        // Without API access we can't resolve commit refs or branches
+        // to tags, so we can only match on refs that are already versions.
+        let Some(version) = uses.symbolic_ref() else {
+            return vec![];
+        };
+
+        advisories::BUNDLED
+            .affecting(uses.owner, uses.repo, version)
+            .into_iter()
+            .map(|(advisory, patched)| {
+                (
+                    Self::severity(&advisory.severity),
+                    advisory.ghsa_id.clone(),
+                    patched.map(Into::into),
+                )
+            })
+            .collect()
+    }
+
+    fn online_known_vulnerabilities(
+        &self,
+        client: &github_api::Client,
+        uses: &Uses<'_>,
+    ) -> Result<Vec<KnownVulnerability>> {
         let version = match uses.git_ref {
             // If `uses` is pinned to a symbolic ref, we need to perform
             // feats of heroism to figure out what's going on.
@@ -41,18 +93,14 @@ impl KnownVulnerableActions {
             // To handle all of the above, we convert the ref into a commit
             // and then find the longest tag for that commit.
             Some(version) if !uses.ref_is_commit() => {
-                let Some(commit_ref) =
-                    self.client.commit_for_ref(uses.owner, uses.repo, version)?
+                let Some(commit_ref) = client.commit_for_ref(uses.owner, uses.repo, version)?
                 else {
                     // No `ref -> commit` means that the action's version
                     // is probably just outright invalid.
                     return Ok(vec![]);
                 };
 
-                match self
-                    .client
-                    .longest_tag_for_commit(uses.owner, uses.repo, &commit_ref)?
-                {
+                match client.longest_tag_for_commit(uses.owner, uses.repo, &commit_ref)? {
                     Some(tag) => tag.name,
                     // Somehow we've round-tripped through a commit and ended
                     // up without a tag, which suggests we went
@@ -65,8 +113,7 @@ impl KnownVulnerableActions {
             // tag matching that ref. In theory the action's repo could do
             // something annoying like use branches for versions instead,
             // which we should also probably support.
-            Some(commit_ref) => match self
-                .client
+            Some(commit_ref) => match client
                 .longest_tag_for_commit(uses.owner, uses.repo, commit_ref)
                 .with_context(|| {
                     format!(
@@ -90,29 +137,40 @@ impl KnownVulnerableActions {
             None => return Ok(vec![]),
         };
 
-        let vulns = self
-            .client
-            .gha_advisories(uses.owner, uses.repo, &version)?;
+        let Some(advisories) = client.gha_advisories(uses.owner, uses.repo, &version)? else {
+            static UNAVAILABLE: Once = Once::new();
+            UNAVAILABLE.call_once(|| {
+                log::warn!(
+                    "the GitHub instance has no advisories API; using bundled advisory data only"
+                )
+            });
+            return Ok(self.offline_known_vulnerabilities(uses));
+        };
 
+        let name = format!("{owner}/{repo}", owner = uses.owner, repo = uses.repo);
         let mut results = vec![];
 
-        for vuln in vulns {
-            let severity = match vuln.severity.as_str() {
-                "low" => Severity::Unknown,
-                "medium" => Severity::Medium,
-                "high" => Severity::High,
-                "critical" => Severity::High,
-                _ => Severity::Unknown,
-            };
+        for advisory in advisories {
+            // An advisory can cover many actions; we only want the
+            // patched version for the one we're looking at.
+            let patched = advisory
+                .vulnerabilities
+                .iter()
+                .find(|v| v.package.name.eq_ignore_ascii_case(&name))
+                .and_then(|v| v.first_patched_version.clone());
 
-            results.push((severity, vuln.ghsa_id));
+            results.push((
+                Self::severity(&advisory.severity),
+                advisory.ghsa_id,
+                patched,
+            ));
         }
 
         Ok(results)
     }
 }
 
-impl WorkflowAudit for KnownVulnerableActions {
+impl Audit for KnownVulnerableActions {
     fn ident() -> &'static str
     where
         Self: Sized,
@@ -127,22 +185,31 @@ impl WorkflowAudit for KnownVulnerableActions {
         "action has a known vulnerability"
     }
 
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
     fn new(state: AuditState) -> anyhow::Result<Self>
     where
         Self: Sized,
     {
-        if state.config.offline {
-            return Err(anyhow!("offline audits only requested"));
-        }
+        let client = state.github_client();
 
-        let Some(client) = state.github_client() else {
-            return Err(anyhow!("can't audit without a GitHub API token"));
-        };
+        if client.is_none() {
+            log::info!("no GitHub API access; using bundled advisory data only");
+        }
 
         Ok(Self { client })
     }
 
-    fn audit<'w>(
+    fn uses_github_api(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn audit_workflow<'w>(
         &self,
         workflow: &'w crate::models::Workflow,
     ) -> anyhow::Result<Vec<crate::finding::Finding<'w>>> {
@@ -162,7 +229,12 @@ impl WorkflowAudit for KnownVulnerableActions {
                     continue;
                 };
 
-                for (severity, id) in self.action_known_vulnerabilities(&uses)? {
+                for (severity, id, patched) in self.action_known_vulnerabilities(&uses)? {
+                    let annotation = match patched {
+                        Some(patched) => format!("{id}: fixed in {patched}"),
+                        None => format!("{id}: no fixed version available"),
+                    };
+
                     findings.push(
                         Self::finding()
                             .confidence(Confidence::High)
@@ -170,7 +242,7 @@ impl WorkflowAudit for KnownVulnerableActions {
                             .add_location(
                                 step.location()
                                     .with_keys(&["uses".into()])
-                                    .annotated(&id)
+                                    .annotated(annotation)
                                     .with_url(format!("https://github.com/advisories/{id}")),
                             )
                             .build(workflow)?,
@@ -0,0 +1,162 @@
+//! Audits `pull_request_target` workflows that use pull request labels
+//! (e.g. `contains(github.event.pull_request.labels.*.name, 'safe to test')`)
+//! to decide whether to run untrusted code.
+//!
+//! Label gates are time-of-check to time-of-use (TOCTOU) races: a
+//! maintainer labels the pull request after reviewing it, but the PR's
+//! author can push new commits between the review and the workflow's
+//! checkout. Unless the workflow checks out the exact commit that was
+//! labeled (`github.event.pull_request.head.sha`), it runs whatever the
+//! branch points to at checkout time.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::EnvValue,
+    workflow::{event::BareEvent, job::StepBody, Job},
+};
+
+use super::Audit;
+use crate::{
+    expr::Expr,
+    finding::{Confidence, Rationale, Severity},
+    models::{Step, Workflow},
+    state::AuditState,
+};
+
+/// Contexts that expose a pull request's labels.
+const LABEL_CONTEXTS: &[&str] = &[
+    "github.event.pull_request.labels.*.name",
+    "github.event.label.name",
+];
+
+/// Returns whether the given `if:` condition checks pull request labels.
+fn checks_labels(cond: &str) -> bool {
+    match Expr::parse_condition(cond) {
+        Ok(expr) => expr.contexts().iter().any(|ctx| {
+            LABEL_CONTEXTS
+                .iter()
+                .any(|label| ctx.eq_ignore_ascii_case(label))
+        }),
+        Err(_) => {
+            log::warn!("couldn't parse condition: {cond}");
+            false
+        }
+    }
+}
+
+/// Returns the `ref:` of an untrusted checkout step.
+fn checkout_ref<'w>(step: &Step<'w>) -> Option<&'w str> {
+    if !step.is_untrusted_checkout() {
+        return None;
+    }
+
+    match &step.deref().body {
+        StepBody::Uses { with, .. } => match with.get("ref") {
+            Some(EnvValue::String(git_ref)) => Some(git_ref),
+            _ => None,
+        },
+        StepBody::Run { .. } => None,
+    }
+}
+
+pub(crate) struct LabelGating {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for LabelGating {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "label-gating"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "untrusted code gated on pull request labels"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(
+        &self,
+        workflow: &'w Workflow,
+    ) -> anyhow::Result<Vec<crate::finding::Finding<'w>>> {
+        let mut findings = vec![];
+
+        if !workflow.has_trigger(BareEvent::PullRequestTarget) {
+            return Ok(findings);
+        }
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            // The gate is either on the job itself, or on one of its steps.
+            let gate = match normal.r#if.as_deref().filter(|cond| checks_labels(cond)) {
+                Some(_) => Some(job.location().with_keys(&["if".into()])),
+                None => job
+                    .steps()
+                    .find(|step| step.r#if.as_deref().is_some_and(checks_labels))
+                    .map(|step| step.location().with_keys(&["if".into()])),
+            };
+
+            let Some(gate) = gate else {
+                continue;
+            };
+
+            let checkout = job
+                .steps()
+                .find_map(|step| checkout_ref(&step).map(|git_ref| (step, git_ref)));
+
+            let mut finding = Self::finding()
+                .add_location(gate.annotated("job is gated on pull request labels"))
+                .suggestion(
+                    "check out github.event.pull_request.head.sha, \
+                     so that only the labeled commit runs",
+                );
+
+            finding = match checkout {
+                // The labeled commit itself is checked out, so later pushes
+                // can't change what runs.
+                Some((_, git_ref)) if git_ref.contains("head.sha") => continue,
+                Some((step, git_ref)) => finding
+                    .severity(Severity::High)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        step.location()
+                            .with_keys(&["with".into(), "ref".into()])
+                            .annotated(format!(
+                                "{git_ref} may point to commits pushed after labeling"
+                            )),
+                    ),
+                // We can't see what the job runs, but a label gate only
+                // makes sense if it runs something from the pull request.
+                None => finding
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::Low)
+                    .rationale(Rationale::Heuristic),
+            };
+
+            findings.push(finding.build(workflow)?);
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,133 @@
+//! Audits deployment jobs that aren't guarded by a `concurrency` group.
+//!
+//! Without a concurrency group, overlapping runs (e.g. two quick pushes
+//! to the default branch) can deploy simultaneously and race each other,
+//! potentially leaving an older revision deployed.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::expr::BoE,
+    workflow::{Concurrency, Job},
+};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::{self, DeployIntent, Workflow},
+    state::AuditState,
+};
+
+pub(crate) struct MissingConcurrency {
+    pub(crate) state: AuditState,
+}
+
+impl MissingConcurrency {
+    /// Returns the locations for a finding on `job`, which is annotated
+    /// with `annotation`, along with our confidence that `intent` actually
+    /// indicates a deployment.
+    fn locations<'w>(
+        job: &models::Job<'w>,
+        intent: DeployIntent<'w>,
+        annotation: &str,
+    ) -> (Vec<SymbolicLocation<'w>>, Confidence) {
+        let (evidence, confidence) = intent.evidence(job);
+
+        let locations = match (evidence, intent) {
+            (Some(evidence), _) => vec![job.location().annotated(annotation), evidence],
+            // Name-based intent has no location more specific than
+            // the job itself.
+            (None, DeployIntent::Name(name)) => vec![job
+                .location()
+                .annotated(format!("{annotation} ({name} suggests a deployment)"))],
+            (None, _) => vec![job.location().annotated(annotation)],
+        };
+
+        (locations, confidence)
+    }
+}
+
+impl Audit for MissingConcurrency {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "missing-concurrency"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "deployment without a concurrency guard"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Low
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            let Some(intent) = job.deploy_intent() else {
+                continue;
+            };
+
+            // A job-level concurrency group takes precedence over
+            // the workflow's.
+            let (severity, annotation, suggestion) = match normal
+                .concurrency
+                .as_ref()
+                .or(workflow.concurrency.as_ref())
+            {
+                None => (
+                    Severity::Medium,
+                    "deployment has no concurrency group",
+                    "add a concurrency group, e.g. `concurrency: { group: deploy, cancel-in-progress: true }`",
+                ),
+                // Without `cancel-in-progress`, overlapping deployments queue
+                // rather than race, which is a reasonable (if slower) choice.
+                Some(Concurrency::Bare(_))
+                | Some(Concurrency::Rich {
+                    cancel_in_progress: BoE::Literal(false),
+                    ..
+                }) if self.state.config.pedantic => (
+                    Severity::Low,
+                    "deployment's concurrency group doesn't cancel in-progress runs",
+                    "set `cancel-in-progress: true` on the concurrency group",
+                ),
+                Some(_) => continue,
+            };
+
+            let (locations, confidence) = Self::locations(&job, intent, annotation);
+
+            let mut finding = Self::finding()
+                .severity(severity)
+                .confidence(confidence)
+                .rationale(Rationale::Heuristic)
+                .suggestion(suggestion);
+            for location in locations {
+                finding = finding.add_location(location);
+            }
+
+            findings.push(finding.build(workflow)?);
+        }
+
+        Ok(findings)
+    }
+}
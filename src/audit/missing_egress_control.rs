@@ -0,0 +1,147 @@
+//! Audits sensitive jobs (deployments, releases, and jobs that can mint
+//! OIDC tokens) that don't harden their runner with an egress control
+//! step, such as `step-security/harden-runner`.
+//!
+//! Egress control limits where a compromised dependency or action can
+//! send stolen credentials, and produces an audit trail of what the job
+//! actually connected to.
+//!
+//! This audit is "pedantic" only, since egress control is defense in depth
+//! rather than a fix for any particular weakness. Additional hardening
+//! actions can be configured with the `actions` setting.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{job::StepBody, Job};
+use serde::Deserialize;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::{Uses, Workflow},
+    state::AuditState,
+};
+
+/// Actions (as `owner/repo`) that harden a runner's network egress.
+const HARDENING_ACTIONS: &[&str] = &["step-security/harden-runner", "bullfrogsec/bullfrog"];
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct MissingEgressControlConfig {
+    /// Additional `owner/repo` actions to treat as hardening steps.
+    #[serde(default)]
+    actions: Vec<String>,
+}
+
+pub(crate) struct MissingEgressControl {
+    state: AuditState,
+    actions: Vec<String>,
+}
+
+impl Audit for MissingEgressControl {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "missing-egress-control"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "sensitive job without egress control"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Informational
+    }
+
+    fn pedantic() -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+
+    fn config_keys() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &["actions"]
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let config: MissingEgressControlConfig = state.config.file.rule_config(Self::ident())?;
+
+        let actions = HARDENING_ACTIONS
+            .iter()
+            .map(|action| action.to_string())
+            .chain(config.actions)
+            .map(|action| action.to_lowercase())
+            .collect();
+
+        Ok(Self { state, actions })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        if !self.state.config.pedantic {
+            log::info!("skipping egress control checks");
+            return Ok(findings);
+        }
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(_) = job.deref() else {
+                continue;
+            };
+
+            let reason = if workflow.token_can_write_to(&job, "id-token") == Some(true) {
+                "can request OIDC tokens"
+            } else if job.deploy_intent().is_some() {
+                "deploys or publishes"
+            } else {
+                continue;
+            };
+
+            let hardened = job.steps().any(|step| {
+                let StepBody::Uses { uses, .. } = &step.deref().body else {
+                    return false;
+                };
+
+                Uses::from_step(uses).is_some_and(|uses| {
+                    let action = format!("{}/{}", uses.owner, uses.repo).to_lowercase();
+                    self.actions.contains(&action)
+                })
+            });
+
+            if hardened {
+                continue;
+            }
+
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Informational)
+                    .confidence(Confidence::Medium)
+                    .rationale(Rationale::Heuristic)
+                    .add_location(job.location().annotated(format!(
+                        "this job {reason}, but doesn't restrict its network egress"
+                    )))
+                    .suggestion(
+                        "add step-security/harden-runner as the job's first step, \
+                         and tighten its egress-policy from audit to block",
+                    )
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
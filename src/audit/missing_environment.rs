@@ -0,0 +1,97 @@
+//! Audits jobs that deploy or publish something (via cloud CLIs, registry
+//! pushes, GitHub Pages, and so on) without an `environment:`.
+//!
+//! Deployment environments are what carry protection rules: required
+//! reviewers, wait timers, and restrictions on which branches can deploy.
+//! A deployment job without one has none of these, so any push that
+//! triggers the workflow deploys immediately.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::Job;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::{DeployIntent, Workflow},
+    state::AuditState,
+};
+
+pub(crate) struct MissingEnvironment {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for MissingEnvironment {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "missing-environment"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "deployment without an environment"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(_) = job.deref() else {
+                continue;
+            };
+
+            let mut finding =
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .add_location(job.location().annotated(
+                        "deployment job has no environment, so no protection rules apply",
+                    ))
+                    .suggestion(
+                        "bind this job to a deployment environment with protection rules, \
+                     e.g. `environment: production`",
+                    );
+
+            // `pages: write` is only useful for deploying to GitHub Pages,
+            // which the deploy-intent heuristics may not otherwise catch.
+            finding = match job.deploy_intent() {
+                Some(DeployIntent::Environment(_)) => continue,
+                Some(intent) => {
+                    let (evidence, confidence) = intent.evidence(&job);
+                    finding = finding
+                        .confidence(confidence)
+                        .rationale(Rationale::Heuristic);
+                    match evidence {
+                        Some(evidence) => finding.add_location(evidence),
+                        None => finding,
+                    }
+                }
+                None if workflow.token_can_write_to(&job, "pages") == Some(true) => finding
+                    .confidence(Confidence::Medium)
+                    .rationale(Rationale::Heuristic),
+                None => continue,
+            };
+
+            findings.push(finding.build(workflow)?);
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,77 @@
+//! Audits `run:` steps in composite actions that don't specify a `shell:`.
+//!
+//! Unlike workflow steps, composite action steps have no default shell:
+//! GitHub refuses to run a composite action with a `run:` step that lacks
+//! one, but only once a workflow actually uses it. Without an explicit
+//! shell, the interpreter for the step's script is also left up to the
+//! runner rather than the action's author.
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::Action,
+    state::AuditState,
+};
+
+pub(crate) struct MissingShell {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for MissingShell {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "missing-shell"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "composite action step without a shell"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_action<'a>(&self, action: &'a Action) -> anyhow::Result<Vec<Finding<'a>>> {
+        let mut findings = vec![];
+
+        if !action.is_composite() {
+            return Ok(findings);
+        }
+
+        for step in action.steps() {
+            if step.run.is_none() || step.shell.is_some() {
+                continue;
+            }
+
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        step.location()
+                            .with_keys(&["run".into()])
+                            .annotated("run: step has no shell"),
+                    )
+                    .suggestion("add an explicit shell to this step, e.g. shell: bash")
+                    .build(action)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
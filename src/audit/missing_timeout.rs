@@ -0,0 +1,190 @@
+//! Audits jobs (and, optionally, long `run:` steps) that don't set
+//! `timeout-minutes`, or that set it beyond a configured maximum.
+//!
+//! Without a timeout, a hung or runaway job occupies its runner for
+//! GitHub's default of 6 hours (or up to 5 days on self-hosted runners).
+//!
+//! This audit is "pedantic" only, since most jobs are short-lived in
+//! practice and a missing timeout isn't a vulnerability by itself.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::expr::LoE,
+    workflow::{job::StepBody, Job},
+};
+use serde::Deserialize;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::{is_github_hosted, Workflow},
+    state::AuditState,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct MissingTimeoutConfig {
+    /// Timeouts longer than this are flagged as well as missing ones.
+    #[serde(default = "default_max_minutes")]
+    max_minutes: u64,
+    /// If set, `run:` steps with at least this many lines are also
+    /// expected to set their own timeout.
+    #[serde(default)]
+    step_lines: Option<usize>,
+}
+
+/// GitHub's default (and maximum, for hosted runners) job timeout.
+fn default_max_minutes() -> u64 {
+    360
+}
+
+impl Default for MissingTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            max_minutes: default_max_minutes(),
+            step_lines: None,
+        }
+    }
+}
+
+pub(crate) struct MissingTimeout {
+    pub(crate) state: AuditState,
+    config: MissingTimeoutConfig,
+}
+
+impl Audit for MissingTimeout {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "missing-timeout"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "job or step has no timeout"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Low
+    }
+
+    fn pedantic() -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+
+    fn config_keys() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &["max-minutes", "step-lines"]
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let config = state.config.file.rule_config(Self::ident())?;
+
+        Ok(Self { state, config })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        if !self.state.config.pedantic {
+            log::info!("skipping missing timeout checks");
+            return Ok(findings);
+        }
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            // Runaway jobs are costlier on self-hosted runners, which
+            // are a finite fleet and allow much longer timeouts.
+            let severity = if job
+                .runner_labels()
+                .iter()
+                .all(|label| is_github_hosted(label))
+            {
+                Severity::Low
+            } else {
+                Severity::Medium
+            };
+
+            match &normal.timeout_minutes {
+                None => findings.push(
+                    Self::finding()
+                        .severity(severity)
+                        .confidence(Confidence::High)
+                        .add_location(
+                            job.location()
+                                .with_keys(&["runs-on".into()])
+                                .annotated("job has no timeout-minutes"),
+                        )
+                        .suggestion(format!(
+                            "set timeout-minutes to at most {max}",
+                            max = self.config.max_minutes
+                        ))
+                        .build(workflow)?,
+                ),
+                Some(LoE::Literal(minutes)) if *minutes > self.config.max_minutes => findings.push(
+                    Self::finding()
+                        .severity(severity)
+                        .confidence(Confidence::High)
+                        .add_location(
+                            job.location()
+                                .with_keys(&["timeout-minutes".into()])
+                                .annotated(format!(
+                                    "timeout exceeds {max} minutes",
+                                    max = self.config.max_minutes
+                                )),
+                        )
+                        .build(workflow)?,
+                ),
+                Some(_) => (),
+            }
+
+            let Some(step_lines) = self.config.step_lines else {
+                continue;
+            };
+
+            for step in job.steps() {
+                let StepBody::Run { run, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                if step.timeout_minutes.is_some() || run.lines().count() < step_lines {
+                    continue;
+                }
+
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Low)
+                        .confidence(Confidence::Medium)
+                        .rationale(Rationale::Heuristic)
+                        .add_location(step.location_with_name())
+                        .add_location(
+                            step.location()
+                                .with_keys(&["run".into()])
+                                .annotated("long script has no timeout-minutes"),
+                        )
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
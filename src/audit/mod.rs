@@ -3,23 +3,97 @@
 use anyhow::Result;
 
 use crate::{
-    finding::{Finding, FindingBuilder},
-    models::Workflow,
+    finding::{Confidence, Finding, FindingBuilder, Severity},
+    models::{Action, AuditInput, Dependabot, Workflow},
     state::AuditState,
 };
 
+pub(crate) mod actor_spoofing;
+pub(crate) mod artifact_execution;
 pub(crate) mod artipacked;
+pub(crate) mod auto_approve;
+pub(crate) mod branch_pinned_reusable;
+pub(crate) mod cache_poisoning;
+pub(crate) mod checkout_confusion;
+pub(crate) mod comment_command_execution;
 pub(crate) mod dangerous_triggers;
+pub(crate) mod deprecated_commands;
+pub(crate) mod deprecated_node_runtime;
+pub(crate) mod deprecated_runner_images;
 pub(crate) mod excessive_permissions;
+pub(crate) mod github_env;
+pub(crate) mod github_script_injection;
 pub(crate) mod hardcoded_container_credentials;
+pub(crate) mod hardcoded_credentials;
 pub(crate) mod impostor_commit;
+pub(crate) mod insecure_commands;
+pub(crate) mod insecure_env;
 pub(crate) mod known_vulnerable_actions;
+pub(crate) mod label_gating;
+pub(crate) mod missing_concurrency;
+pub(crate) mod missing_egress_control;
+pub(crate) mod missing_environment;
+pub(crate) mod missing_shell;
+pub(crate) mod missing_timeout;
+pub(crate) mod needs_cycle;
+pub(crate) mod pipe_to_shell;
+pub(crate) mod privileged_container;
+pub(crate) mod privileged_execution;
 pub(crate) mod ref_confusion;
+pub(crate) mod ref_type_confusion;
+pub(crate) mod secret_outputs;
+pub(crate) mod secrets_in_cache_key;
+pub(crate) mod secrets_in_env;
+pub(crate) mod secrets_inherit;
+pub(crate) mod security_gate_bypass;
 pub(crate) mod self_hosted_runner;
+pub(crate) mod sensitive_artifact;
 pub(crate) mod template_injection;
+pub(crate) mod third_party_token;
+pub(crate) mod typosquatting;
+pub(crate) mod unmaintained_actions;
+pub(crate) mod unpinned_images;
+pub(crate) mod unpinned_uses;
+pub(crate) mod unsound_condition;
+pub(crate) mod untrusted_checkout;
+pub(crate) mod untrusted_install;
+pub(crate) mod untrusted_matrix;
+pub(crate) mod untrusted_path;
+pub(crate) mod untrusted_trigger_chain;
 pub(crate) mod use_trusted_publishing;
+pub(crate) mod wildcard_branch_trigger;
 
-pub(crate) trait WorkflowAudit {
+/// A typed input to an [`Audit`].
+#[derive(Clone, Copy)]
+pub(crate) enum Input<'a> {
+    Workflow(&'a Workflow),
+    /// An action definition, composite or otherwise.
+    Action(&'a Action),
+    Dependabot(&'a Dependabot),
+    /// Any input's YAML document, for audits that don't depend on its kind.
+    Raw(&'a dyn AuditInput),
+}
+
+impl<'a> Input<'a> {
+    /// Returns the input as a raw YAML document, regardless of its kind.
+    pub(crate) fn raw(&self) -> &'a dyn AuditInput {
+        match self {
+            Input::Workflow(workflow) => *workflow,
+            Input::Action(action) => *action,
+            Input::Dependabot(dependabot) => *dependabot,
+            Input::Raw(raw) => *raw,
+        }
+    }
+}
+
+/// An audit over one or more kinds of input.
+///
+/// Audits implement `audit_*` for each kind of input that they apply to;
+/// the rest default to producing no findings. [`Audit::audit_raw`] runs
+/// on every input as an [`Input::Raw`], after the kind-specific method.
+///
+/// Audits run concurrently across inputs, so they must be thread-safe.
+pub(crate) trait Audit: Send + Sync {
     fn ident() -> &'static str
     where
         Self: Sized;
@@ -28,16 +102,102 @@ pub(crate) trait WorkflowAudit {
     where
         Self: Sized;
 
+    /// The severity of this audit's findings, unless a finding
+    /// specifies its own.
+    fn default_severity() -> Severity
+    where
+        Self: Sized;
+
+    /// The confidence of this audit's findings, unless a finding
+    /// specifies its own.
+    fn default_confidence() -> Confidence
+    where
+        Self: Sized,
+    {
+        Confidence::Unknown
+    }
+
+    /// Whether this audit only produces findings with `--pedantic`.
+    fn pedantic() -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+
+    /// The audit-specific settings that this audit accepts in the
+    /// configuration file, i.e. the keys of `rules.<ident>.config`.
+    fn config_keys() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &[]
+    }
+
+    /// The OAuth scopes that a (classic) GitHub API token needs for this
+    /// audit to see everything that it uses, beyond public data.
+    fn github_scopes() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &[]
+    }
+
+    /// Whether this audit can only run with access to GitHub's API. Such
+    /// audits are skipped when running offline, or without a token.
+    fn requires_github_api() -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+
     fn new(state: AuditState) -> Result<Self>
     where
         Self: Sized;
 
-    fn audit<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>>;
+    /// Whether this audit (as configured) uses GitHub's API, in which case
+    /// its findings depend on more than its inputs.
+    fn uses_github_api(&self) -> bool {
+        false
+    }
+
+    fn audit_workflow<'w>(&self, _workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        Ok(vec![])
+    }
+
+    fn audit_action<'a>(&self, _action: &'a Action) -> Result<Vec<Finding<'a>>> {
+        Ok(vec![])
+    }
+
+    fn audit_dependabot<'d>(&self, _dependabot: &'d Dependabot) -> Result<Vec<Finding<'d>>> {
+        Ok(vec![])
+    }
+
+    /// Audits `input`'s YAML document, whatever kind of input it is.
+    fn audit_raw<'r>(&self, _input: &'r dyn AuditInput) -> Result<Vec<Finding<'r>>> {
+        Ok(vec![])
+    }
+
+    /// Audits `input`, dispatching on its kind. Typed inputs are then
+    /// audited again as their raw documents.
+    fn audit<'a>(&self, input: Input<'a>) -> Result<Vec<Finding<'a>>> {
+        let mut findings = match input {
+            Input::Workflow(workflow) => self.audit_workflow(workflow)?,
+            Input::Action(action) => self.audit_action(action)?,
+            Input::Dependabot(dependabot) => self.audit_dependabot(dependabot)?,
+            Input::Raw(raw) => return self.audit_raw(raw),
+        };
+        findings.extend(self.audit(Input::Raw(input.raw()))?);
+
+        Ok(findings)
+    }
 
-    fn finding<'w>() -> FindingBuilder<'w>
+    fn finding<'a>() -> FindingBuilder<'a>
     where
         Self: Sized,
     {
-        FindingBuilder::new(Self::ident(), Self::desc())
+        FindingBuilder::new(Self::ident(), Self::desc(), Self::default_severity())
+            .confidence(Self::default_confidence())
     }
 }
@@ -0,0 +1,80 @@
+//! Detects jobs whose `needs:` form a cycle.
+//!
+//! GitHub rejects workflows with dependency cycles outright, so none of
+//! their jobs run, including any that gate or check the others. Cycles
+//! are easy to introduce when a large workflow's jobs are reorganized,
+//! and the failure only surfaces once the workflow is triggered.
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::Workflow,
+    state::AuditState,
+};
+
+pub(crate) struct NeedsCycle {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for NeedsCycle {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "needs-cycle"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "jobs with cyclic dependencies"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Low
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+        let graph = workflow.job_graph();
+
+        for cycle in graph.cycles() {
+            let mut finding = Self::finding()
+                .severity(Severity::Low)
+                .confidence(Confidence::High)
+                .suggestion("remove one of the dependencies so that the jobs can be ordered");
+
+            for id in &cycle {
+                let Some(job) = workflow.job(id) else {
+                    continue;
+                };
+
+                let needs = graph
+                    .dependencies(id)
+                    .filter(|need| cycle.contains(need))
+                    .collect::<Vec<_>>();
+
+                finding = finding.add_location(
+                    job.location()
+                        .with_keys(&["needs".into()])
+                        .annotated(format!("needs {}, forming a cycle", needs.join(", "))),
+                );
+            }
+
+            findings.push(finding.build(workflow)?);
+        }
+
+        Ok(findings)
+    }
+}
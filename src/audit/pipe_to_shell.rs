@@ -0,0 +1,156 @@
+//! Audits `run:` steps for remote code that's executed without
+//! verification, e.g. `curl ... | bash` or `iex (irm ...)`.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::job::StepBody;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::Workflow,
+    shell::{statements, Command, Statement},
+    state::AuditState,
+};
+
+/// Commands that fetch remote content.
+const DOWNLOADERS: &[&str] = &[
+    "curl",
+    "wget",
+    "iwr",
+    "irm",
+    "invoke-webrequest",
+    "invoke-restmethod",
+];
+
+fn is_one_of(command: &Command, names: &[&str]) -> bool {
+    command
+        .name()
+        .is_some_and(|name| names.contains(&name.as_str()))
+}
+
+/// Returns the downloader command whose output is executed by
+/// an interpreter in this statement, if any.
+fn executed_download<'a, 's>(commands: &'a [Command<'s>]) -> Option<&'a Command<'s>> {
+    for (idx, command) in commands.iter().enumerate() {
+        if !command.is_interpreter() {
+            continue;
+        }
+
+        // `curl ... | bash`, including with intermediate stages
+        // like `curl ... | tee script.sh | bash`.
+        let mut source = command.piped_from;
+        while let Some(src) = source {
+            if is_one_of(&commands[src], DOWNLOADERS) {
+                return Some(&commands[src]);
+            }
+            source = commands[src].piped_from;
+        }
+
+        // `bash <(curl ...)`, `sh -c "$(curl ...)"`, `iex (irm ...)`, etc.
+        if let Some(download) = commands
+            .iter()
+            .find(|inner| inner.enclosing == Some(idx) && is_one_of(inner, DOWNLOADERS))
+        {
+            return Some(download);
+        }
+    }
+
+    None
+}
+
+/// Returns whether this statement uses .NET's `WebClient` to download
+/// a script for `Invoke-Expression`, which doesn't fit the command model.
+fn executes_webclient_download(stmt: &Statement) -> bool {
+    let text = stmt.text.to_lowercase();
+    text.contains("downloadstring") && (text.contains("iex") || text.contains("invoke-expression"))
+}
+
+pub(crate) struct PipeToShell {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for PipeToShell {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "pipe-to-shell"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "remote code executed without verification"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            for step in job.steps() {
+                let StepBody::Run { run, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                for stmt in statements(run) {
+                    let commands = stmt.commands();
+
+                    let insecure_url = match executed_download(&commands) {
+                        Some(download) => download
+                            .words
+                            .iter()
+                            .any(|word| word.starts_with("http://")),
+                        None if executes_webclient_download(&stmt) => stmt.text.contains("http://"),
+                        None => continue,
+                    };
+
+                    // Fetching over plain HTTP means that anybody on the
+                    // network path can substitute their own code.
+                    let (severity, annotation) = if insecure_url {
+                        (
+                            Severity::High,
+                            "remote script is fetched over HTTP and executed",
+                        )
+                    } else {
+                        (
+                            Severity::Medium,
+                            "remote script is executed without verification",
+                        )
+                    };
+
+                    findings.push(
+                        Self::finding()
+                            .severity(severity)
+                            .confidence(Confidence::High)
+                            .add_location(step.location_with_name())
+                            .add_location(
+                                step.location()
+                                    .with_keys(&["run".into()])
+                                    .subspan(run, stmt.span.clone())
+                                    .annotated(annotation),
+                            )
+                            .build(workflow)?,
+                    );
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,166 @@
+//! Audits job containers and `services:` that break out of the job's
+//! isolation: privileged containers, containers in the host's network or
+//! process namespaces, and containers with the Docker socket mounted.
+//!
+//! Any of these gives the container (and whatever runs inside it, such as
+//! a compromised service image) control over the runner itself. This is
+//! much more severe on self-hosted runners, which persist across jobs.
+
+use github_actions_models::workflow::job::Container;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::{DockerOptions, Workflow},
+    state::AuditState,
+    symbols::SymbolTable,
+};
+
+/// Capabilities that are (nearly) equivalent to `--privileged`.
+const DANGEROUS_CAPABILITIES: &[&str] = &["ALL", "SYS_ADMIN", "SYS_PTRACE", "SYS_MODULE"];
+
+/// Returns every way in which the given `options:` break isolation.
+fn escapes(options: &DockerOptions) -> Vec<String> {
+    let mut escapes = vec![];
+
+    if options.enabled("--privileged") {
+        escapes.push("is privileged".into());
+    }
+
+    for (flags, namespace) in [
+        (&["--network", "--net"][..], "network"),
+        (&["--pid"][..], "process"),
+        (&["--ipc"][..], "IPC"),
+    ] {
+        if options.values(flags).any(|mode| mode == "host") {
+            escapes.push(format!("shares the host's {namespace} namespace"));
+        }
+    }
+
+    for capability in options.values(&["--cap-add"]) {
+        let capability = capability.to_uppercase();
+        let capability = capability.strip_prefix("CAP_").unwrap_or(&capability);
+        if DANGEROUS_CAPABILITIES.contains(&capability) {
+            escapes.push(format!("adds the {capability} capability"));
+        }
+    }
+
+    if options
+        .values(&["-v", "--volume", "--mount"])
+        .any(|volume| volume.contains("docker.sock"))
+    {
+        escapes.push("mounts the host's Docker socket".into());
+    }
+
+    escapes
+}
+
+pub(crate) struct PrivilegedContainer {
+    pub(crate) _state: AuditState,
+}
+
+impl PrivilegedContainer {
+    /// Returns the severity, confidence and rationale for an escape from a
+    /// job's containers, which depends on whether it runs on a self-hosted
+    /// runner.
+    fn determinations(self_hosted: Option<bool>) -> (Severity, Confidence, Rationale) {
+        match self_hosted {
+            Some(true) => (Severity::High, Confidence::High, Rationale::LiteralMatch),
+            Some(false) => (Severity::Medium, Confidence::Medium, Rationale::Heuristic),
+            None => (Severity::Low, Confidence::High, Rationale::LiteralMatch),
+        }
+    }
+}
+
+impl Audit for PrivilegedContainer {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "privileged-container"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "container breaks the job's isolation"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let symbols = SymbolTable::new(workflow, self._state.config.file.vars());
+        for job in workflow.jobs() {
+            let self_hosted = job.self_hosted(&symbols.contexts(Some(&job), None));
+            let (severity, confidence, rationale) = Self::determinations(self_hosted);
+
+            for (location, container) in job.containers() {
+                let Container::Container {
+                    volumes, options, ..
+                } = container
+                else {
+                    continue;
+                };
+
+                let mut escapes = options
+                    .as_deref()
+                    .map(|options| escapes(&DockerOptions::parse(options)))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|escape| (escape, "options"))
+                    .collect::<Vec<_>>();
+
+                if volumes.iter().any(|volume| volume.contains("docker.sock")) {
+                    escapes.push(("mounts the host's Docker socket".into(), "volumes"));
+                }
+
+                for (escape, key) in escapes {
+                    let mut finding = Self::finding()
+                        .severity(severity)
+                        .confidence(confidence)
+                        .rationale(rationale)
+                        .add_location(
+                            location
+                                .with_keys(&[key.into()])
+                                .annotated(format!("this container {escape}")),
+                        )
+                        .suggestion(
+                            "remove the flag or volume, or run this job on an ephemeral \
+                             GitHub-hosted runner",
+                        );
+
+                    if let Some(certain) = self_hosted {
+                        finding = finding.add_location(
+                            job.location()
+                                .with_keys(&["runs-on".into()])
+                                .annotated(if certain {
+                                    "job runs on a self-hosted runner"
+                                } else {
+                                    "job may run on a self-hosted runner"
+                                }),
+                        );
+                    }
+
+                    findings.push(finding.build(workflow)?);
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
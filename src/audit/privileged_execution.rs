@@ -0,0 +1,186 @@
+//! Audits `run:` steps that execute with elevated privileges (via `sudo`,
+//! `docker run --privileged`, or a mounted Docker socket) in jobs that
+//! build or execute code from a pull request.
+//!
+//! Pull request code that runs as root, or with access to the host's
+//! Docker daemon, controls the runner itself. On GitHub-hosted runners
+//! this is by design, since they're discarded after each job, so only
+//! jobs that may run on self-hosted runners are flagged. There, it lets an
+//! attacker persist on the runner and tamper with later jobs.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{event::BareEvent, job::StepBody};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::{Step, Workflow},
+    shell::{statements, Command},
+    state::AuditState,
+    symbols::SymbolTable,
+};
+
+/// Container runtimes whose `run` and `create` subcommands can grant
+/// containers access to the host.
+const CONTAINER_RUNTIMES: &[&str] = &["docker", "podman"];
+
+/// Returns how the given command elevates its privileges, if it does.
+fn elevation(command: &Command) -> Option<&'static str> {
+    if command
+        .words
+        .iter()
+        .any(|word| word.contains("docker.sock"))
+    {
+        return Some("exposes the host's Docker socket");
+    }
+
+    let name = command.name()?;
+    let args = command.args();
+    if CONTAINER_RUNTIMES.contains(&name.as_str())
+        && matches!(args.first(), Some(&"run" | &"create"))
+        && args.contains(&"--privileged")
+    {
+        return Some("runs a privileged container");
+    }
+
+    if command.words.first() == Some(&"sudo") {
+        return Some("runs a command as root with sudo");
+    }
+
+    None
+}
+
+/// Returns whether the given step checks out code.
+fn is_checkout(step: &Step) -> bool {
+    matches!(&step.deref().body, StepBody::Uses { uses, .. } if uses.starts_with("actions/checkout"))
+}
+
+pub(crate) struct PrivilegedExecution {
+    pub(crate) _state: AuditState,
+}
+
+impl PrivilegedExecution {
+    /// Returns the trigger through which the given job builds or executes
+    /// pull request code, if it does.
+    ///
+    /// Under `pull_request`, any checkout fetches the pull request's code.
+    /// Under the privileged triggers, only checkouts of the event's head do.
+    fn pr_code_trigger(workflow: &Workflow, step: &Step) -> Option<&'static str> {
+        if workflow.has_trigger(BareEvent::PullRequest) && is_checkout(step) {
+            return Some("pull_request");
+        }
+
+        if !step.is_untrusted_checkout() {
+            return None;
+        }
+
+        [
+            (BareEvent::PullRequestTarget, "pull_request_target"),
+            (BareEvent::WorkflowRun, "workflow_run"),
+            (BareEvent::IssueComment, "issue_comment"),
+        ]
+        .into_iter()
+        .find_map(|(event, name)| workflow.has_trigger(event).then_some(name))
+    }
+
+    /// Returns the severity, confidence and rationale for elevated
+    /// execution in a job that certainly (or only possibly) runs on a
+    /// self-hosted runner.
+    fn determinations(certain: bool) -> (Severity, Confidence, Rationale) {
+        if certain {
+            (Severity::High, Confidence::High, Rationale::LiteralMatch)
+        } else {
+            (Severity::Medium, Confidence::Medium, Rationale::Heuristic)
+        }
+    }
+}
+
+impl Audit for PrivilegedExecution {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "privileged-execution"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "pull request code runs with elevated privileges"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let symbols = SymbolTable::new(workflow, self._state.config.file.vars());
+        for job in workflow.jobs() {
+            // GitHub-hosted runners come with passwordless `sudo` and Docker
+            // by design, and are discarded after each job.
+            let Some(certain) = job.self_hosted(&symbols.contexts(Some(&job), None)) else {
+                continue;
+            };
+            let (severity, confidence, rationale) = Self::determinations(certain);
+
+            // Steps only run pull request code after it's been checked out.
+            let mut trigger = None;
+            for step in job.steps() {
+                let Some(trigger) = trigger else {
+                    trigger = Self::pr_code_trigger(workflow, &step);
+                    continue;
+                };
+
+                let StepBody::Run { run, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                let Some(elevation) = statements(run)
+                    .iter()
+                    .find_map(|stmt| stmt.commands().iter().find_map(elevation))
+                else {
+                    continue;
+                };
+
+                let finding = Self::finding()
+                    .severity(severity)
+                    .confidence(confidence)
+                    .rationale(rationale)
+                    .add_location(
+                        step.location()
+                            .with_keys(&["run".into()])
+                            .annotated(format!("{elevation}, after checking out {trigger} code")),
+                    )
+                    .add_location(job.location().with_keys(&["runs-on".into()]).annotated(
+                        if certain {
+                            "job runs on a self-hosted runner"
+                        } else {
+                            "job may run on a self-hosted runner"
+                        },
+                    ))
+                    .suggestion(
+                        "avoid elevated privileges in jobs that run pull request code, \
+                         or run them on ephemeral GitHub-hosted runners",
+                    );
+
+                findings.push(finding.build(workflow)?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
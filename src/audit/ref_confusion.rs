@@ -11,7 +11,7 @@ use std::ops::Deref;
 use anyhow::{anyhow, Result};
 use github_actions_models::workflow::{job::StepBody, Job};
 
-use super::WorkflowAudit;
+use super::Audit;
 use crate::{
     finding::{Confidence, Severity},
     github_api,
@@ -50,7 +50,7 @@ impl RefConfusion {
     }
 }
 
-impl WorkflowAudit for RefConfusion {
+impl Audit for RefConfusion {
     fn ident() -> &'static str
     where
         Self: Sized,
@@ -65,14 +65,24 @@ impl WorkflowAudit for RefConfusion {
         "git ref for action with ambiguous ref type"
     }
 
-    fn new(state: AuditState) -> anyhow::Result<Self>
+    fn default_severity() -> Severity
     where
         Self: Sized,
     {
-        if state.config.offline {
-            return Err(anyhow!("offline audits only requested"));
-        }
+        Severity::Medium
+    }
+
+    fn requires_github_api() -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
 
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
         let Some(client) = state.github_client() else {
             return Err(anyhow!("can't audit without a GitHub API token"));
         };
@@ -80,7 +90,11 @@ impl WorkflowAudit for RefConfusion {
         Ok(Self { client })
     }
 
-    fn audit<'w>(
+    fn uses_github_api(&self) -> bool {
+        true
+    }
+
+    fn audit_workflow<'w>(
         &self,
         workflow: &'w crate::models::Workflow,
     ) -> anyhow::Result<Vec<crate::finding::Finding<'w>>> {
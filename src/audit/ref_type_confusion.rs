@@ -0,0 +1,314 @@
+//! Audits workflows that can't tell branches and tags apart when deciding
+//! whether to run privileged operations.
+//!
+//! This happens in two ways:
+//!
+//! * `on.push.branches` filters with release-like patterns (e.g. `v*`),
+//!   which also match *branches* with tag-like names. Anybody who can push
+//!   a branch named `v9.9.9` can then run a workflow that was presumably
+//!   meant to run only for (often protected) release tags.
+//! * `if:` conditions that check short ref names (`github.ref_name`), or
+//!   match `github.ref` without its `refs/heads/` or `refs/tags/` prefix.
+//!   A tag named `main` satisfies `github.ref_name == 'main'` just as
+//!   well as the `main` branch does.
+//!
+//! Comparing `github.ref` for equality against (or checking it for a prefix
+//! of) an unqualified name is also flagged, since `github.ref` is always
+//! fully qualified and such checks never (or, when negated, always) match.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{
+    event::{BareEvent, BranchFilters},
+    Job,
+};
+
+use super::Audit;
+use crate::{
+    expr::{BinOp, Expr},
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::{self, Workflow},
+    refs::{filter_includes, RefKind, TAG_LIKE_NAMES},
+    state::AuditState,
+};
+
+/// Functions that match part of a string.
+const PARTIAL_MATCHES: &[&str] = &["startsWith", "endsWith", "contains"];
+
+/// A comparison of the triggering ref that doesn't do what it appears to.
+enum RefComparison<'e> {
+    /// `github.ref` compared for (in)equality against, or checked for a
+    /// prefix of, an unqualified name, e.g. `github.ref == 'main'`.
+    Unqualified { negated: bool, name: &'e str },
+    /// A check that accepts both a branch and a tag with the given name,
+    /// e.g. `github.ref_name == 'main'` or `endsWith(github.ref, '/main')`.
+    Ambiguous { context: &'e str, name: &'e str },
+}
+
+/// Returns all of the confusable ref comparisons in the given expression.
+fn ref_comparisons(expr: &Expr) -> Vec<RefComparison<'_>> {
+    expr.subexprs()
+        .into_iter()
+        .filter_map(|expr| match expr {
+            Expr::BinOp {
+                lhs,
+                op: op @ (BinOp::Eq | BinOp::Neq),
+                rhs,
+            } => {
+                let (context, name) = match (lhs.as_ref(), rhs.as_ref()) {
+                    (Expr::Context(context), Expr::String(name))
+                    | (Expr::String(name), Expr::Context(context)) => (context, name),
+                    _ => return None,
+                };
+
+                if context.eq_ignore_ascii_case("github.ref") && !name.starts_with("refs/") {
+                    Some(RefComparison::Unqualified {
+                        negated: *op == BinOp::Neq,
+                        name,
+                    })
+                } else if context.eq_ignore_ascii_case("github.ref_name") && *op == BinOp::Eq {
+                    Some(RefComparison::Ambiguous { context, name })
+                } else {
+                    None
+                }
+            }
+            Expr::Call { func, args }
+                if args.len() == 2
+                    && PARTIAL_MATCHES
+                        .iter()
+                        .any(|partial| func.eq_ignore_ascii_case(partial)) =>
+            {
+                let (Expr::Context(context), Expr::String(name)) = (&args[0], &args[1]) else {
+                    return None;
+                };
+
+                // `github.ref` always starts with `refs/`, so prefix checks
+                // against anything else always fail.
+                if context.eq_ignore_ascii_case("github.ref")
+                    && func.eq_ignore_ascii_case("startsWith")
+                    && !name.starts_with("refs/")
+                {
+                    return Some(RefComparison::Unqualified {
+                        negated: false,
+                        name,
+                    });
+                }
+
+                let qualified = name.contains("refs/heads/") || name.contains("refs/tags/");
+                if context.eq_ignore_ascii_case("github.ref_name")
+                    || (context.eq_ignore_ascii_case("github.ref") && !qualified)
+                {
+                    Some(RefComparison::Ambiguous {
+                        context,
+                        name: name.trim_start_matches('/'),
+                    })
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns whether the given workflow can run against refs of the given kind.
+///
+/// This is approximate: workflows without `push` or `release` triggers are
+/// assumed to run against branches only.
+fn runs_on(workflow: &Workflow, kind: RefKind) -> bool {
+    // Manually dispatched and `create` workflows can run against any ref.
+    if workflow.has_trigger(BareEvent::WorkflowDispatch) || workflow.has_trigger(BareEvent::Create)
+    {
+        return true;
+    }
+
+    let (branches, tags) = match workflow.push_trigger() {
+        None => (false, false),
+        Some(None) => (true, true),
+        // Filtering just one kind of ref excludes the other entirely.
+        Some(Some(push)) => match (push.branch_filters.is_some(), push.tag_filters.is_some()) {
+            (true, false) => (true, false),
+            (false, true) => (false, true),
+            _ => (true, true),
+        },
+    };
+
+    let release = workflow.has_trigger(BareEvent::Release);
+    match kind {
+        RefKind::Branch => branches || (workflow.push_trigger().is_none() && !release),
+        RefKind::Tag => tags || release,
+    }
+}
+
+/// Returns whether the given job does anything worth gating on its ref.
+fn is_privileged(workflow: &Workflow, job: &models::Job) -> bool {
+    job.deploy_intent().is_some()
+        || workflow.token_can_write(job) == Some(true)
+        || job.uses_secrets()
+}
+
+pub(crate) struct RefTypeConfusion {
+    pub(crate) _state: AuditState,
+}
+
+impl RefTypeConfusion {
+    fn check_condition<'w>(
+        workflow: &'w Workflow,
+        privileged: bool,
+        cond: &str,
+        location: SymbolicLocation<'w>,
+    ) -> anyhow::Result<Vec<Finding<'w>>> {
+        let Ok(expr) = Expr::parse_condition(cond) else {
+            return Ok(vec![]);
+        };
+
+        let mut findings = vec![];
+        for comparison in ref_comparisons(&expr) {
+            let (severity, confidence, rationale, annotation, suggestion) = match comparison {
+                RefComparison::Unqualified { negated, name } => {
+                    let prefix = RefKind::guess(name).prefix();
+                    let (severity, outcome) = if negated {
+                        (Severity::Medium, "always")
+                    } else {
+                        (Severity::Low, "never")
+                    };
+
+                    (
+                        severity,
+                        Confidence::High,
+                        Rationale::LiteralMatch,
+                        format!("github.ref is fully qualified, so this check against '{name}' {outcome} matches"),
+                        format!("compare github.ref against '{prefix}{name}' instead"),
+                    )
+                }
+                RefComparison::Ambiguous { context, name } => {
+                    let expected = RefKind::guess(name);
+                    let (other, other_name) = match expected {
+                        RefKind::Branch => (RefKind::Tag, "tag"),
+                        RefKind::Tag => (RefKind::Branch, "branch"),
+                    };
+
+                    if !privileged || !runs_on(workflow, other) {
+                        continue;
+                    }
+
+                    (
+                        Severity::Medium,
+                        Confidence::Medium,
+                        Rationale::Heuristic,
+                        format!("this check on {context} is also satisfied by a {other_name} named '{name}'"),
+                        format!(
+                            "compare github.ref against the fully qualified '{prefix}{name}' instead",
+                            prefix = expected.prefix()
+                        ),
+                    )
+                }
+            };
+
+            findings.push(
+                Self::finding()
+                    .severity(severity)
+                    .confidence(confidence)
+                    .rationale(rationale)
+                    .add_location(location.clone().annotated(annotation))
+                    .suggestion(suggestion)
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for RefTypeConfusion {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "ref-type-confusion"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "privileged operation can't tell branches and tags apart"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let any_privileged = workflow.jobs().any(|job| is_privileged(workflow, &job));
+
+        if let Some(Some(push)) = workflow.push_trigger() {
+            if let Some(BranchFilters::Branches(patterns)) = &push.branch_filters {
+                let tag_like = TAG_LIKE_NAMES
+                    .iter()
+                    .find(|name| filter_includes(patterns, name));
+
+                if let (Some(name), true) = (tag_like, any_privileged) {
+                    findings.push(
+                        Self::finding()
+                            .severity(Severity::Medium)
+                            .confidence(Confidence::Medium)
+                            .rationale(Rationale::Heuristic)
+                            .add_location(
+                                workflow
+                                    .location()
+                                    .with_keys(&["on".into(), "push".into(), "branches".into()])
+                                    .annotated(format!(
+                                        "this branch filter matches branches with tag-like names, e.g. {name}"
+                                    )),
+                            )
+                            .suggestion("filter release refs with `tags:` instead of `branches:`")
+                            .build(workflow)?,
+                    );
+                }
+            }
+        }
+
+        for job in workflow.jobs() {
+            let privileged = is_privileged(workflow, &job);
+
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            if let Some(cond) = &normal.r#if {
+                findings.extend(Self::check_condition(
+                    workflow,
+                    privileged,
+                    cond,
+                    job.location().with_keys(&["if".into()]),
+                )?);
+            }
+
+            for step in job.steps() {
+                if let Some(cond) = &step.r#if {
+                    findings.extend(Self::check_condition(
+                        workflow,
+                        privileged,
+                        cond,
+                        step.location().with_keys(&["if".into()]),
+                    )?);
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,108 @@
+//! Audits job `outputs:` whose values are derived from secrets, either
+//! directly (`${{ secrets.FOO }}`) or through the outputs of steps that
+//! have secrets in scope.
+//!
+//! Job outputs are stored by GitHub and can be read by any later job in
+//! the workflow, as well as through the API. The runner refuses to set
+//! outputs that contain a masked value verbatim, but any transformation
+//! (encoding, slicing, or a value derived from the secret) defeats
+//! masking entirely.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::Job;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::Workflow,
+    state::AuditState,
+};
+
+pub(crate) struct SecretOutputs {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for SecretOutputs {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "secret-outputs"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "secrets exposed through job outputs"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            let mut outputs = normal.outputs.iter().collect::<Vec<_>>();
+            outputs.sort_by_key(|(name, _)| *name);
+
+            for (name, value) in outputs {
+                // Secrets referenced by the output itself are certain;
+                // ones reached through step outputs are over-approximated.
+                let (sources, confidence, rationale) = match workflow.secret_sources(value, None) {
+                    direct if !direct.is_empty() => {
+                        (direct, Confidence::High, Rationale::LiteralMatch)
+                    }
+                    _ => (
+                        workflow.secret_sources(value, Some(&job)),
+                        Confidence::Medium,
+                        Rationale::DataflowTraced,
+                    ),
+                };
+
+                if sources.is_empty() {
+                    continue;
+                }
+
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Medium)
+                        .confidence(confidence)
+                        .rationale(rationale)
+                        .add_location(
+                            job.location()
+                                .with_keys(&["outputs".into(), name.as_str().into()])
+                                .annotated(format!(
+                                    "output may contain {sources}",
+                                    sources = sources.join(", ")
+                                )),
+                        )
+                        .suggestion(
+                            "pass secrets to the jobs that need them directly, \
+                             rather than through job outputs",
+                        )
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,190 @@
+//! Audits `actions/cache` keys (`key:` and `restore-keys:`) that are
+//! derived from secrets, either by interpolating `secrets.*` or by
+//! hashing files that typically contain credentials.
+//!
+//! Cache keys aren't secret: they're listed in the repository's cache
+//! management UI and API, and are shared with every workflow in the same
+//! cache scope. Secret values that end up in a key aren't masked there,
+//! and hashes of secret files can be used to confirm guesses at them.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::EnvValue,
+    workflow::{job::StepBody, Job},
+};
+
+use super::Audit;
+use crate::{
+    expr::Expr,
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::Workflow,
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+/// Actions whose `key` and `restore-keys` inputs are cache keys.
+const CACHE_ACTIONS: &[&str] = &["actions/cache"];
+
+/// The cache key inputs of [`CACHE_ACTIONS`].
+const KEY_INPUTS: &[&str] = &["key", "restore-keys"];
+
+/// File names that typically contain credentials.
+const SECRET_FILES: &[&str] = &[
+    ".env",
+    ".git-credentials",
+    ".netrc",
+    ".npmrc",
+    ".pypirc",
+    "credentials",
+    "credentials.json",
+    "id_ecdsa",
+    "id_ed25519",
+    "id_rsa",
+    "kubeconfig",
+];
+
+/// File extensions that typically contain credentials.
+const SECRET_EXTENSIONS: &[&str] = &[".key", ".p12", ".pem", ".pfx"];
+
+/// Returns whether the given `hashFiles` pattern refers to files that
+/// typically contain credentials.
+fn is_secret_file(pattern: &str) -> bool {
+    // Exclusions can only make a hash less sensitive.
+    if pattern.starts_with('!') {
+        return false;
+    }
+
+    let name = pattern.rsplit('/').next().unwrap_or(pattern);
+    SECRET_FILES.contains(&name)
+        || name.starts_with(".env.")
+        || SECRET_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+/// Returns the secret file patterns hashed (via `hashFiles`) anywhere in
+/// the given text.
+fn hashed_secret_files(text: &str) -> Vec<String> {
+    extract_expressions(text)
+        .iter()
+        .filter_map(|expr| Expr::parse(expr.as_bare()).ok())
+        .flat_map(|expr| {
+            expr.subexprs()
+                .into_iter()
+                .filter_map(|expr| match expr {
+                    Expr::Call { func, args } if func.eq_ignore_ascii_case("hashFiles") => {
+                        Some(args)
+                    }
+                    _ => None,
+                })
+                .flatten()
+                .filter_map(|arg| match arg {
+                    Expr::String(pattern) if is_secret_file(pattern) => Some(pattern.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+pub(crate) struct SecretsInCacheKey {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for SecretsInCacheKey {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "secrets-in-cache-key"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "cache key derived from secrets"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            if !matches!(job.deref(), Job::NormalJob(_)) {
+                continue;
+            }
+
+            for step in job.steps() {
+                let StepBody::Uses { uses, with } = &step.deref().body else {
+                    continue;
+                };
+
+                if !CACHE_ACTIONS.iter().any(|action| uses.starts_with(action)) {
+                    continue;
+                }
+
+                for input in KEY_INPUTS {
+                    let Some(EnvValue::String(key)) = with.get(*input) else {
+                        continue;
+                    };
+
+                    // Secrets referenced by the key itself are certain;
+                    // ones reached through step outputs are over-approximated.
+                    let (annotation, confidence, rationale) = match (
+                        workflow.secret_sources(key, None),
+                        workflow.secret_sources(key, Some(&job)),
+                        hashed_secret_files(key),
+                    ) {
+                        (direct, _, _) if !direct.is_empty() => (
+                            format!("cache key includes {}", direct.join(", ")),
+                            Confidence::High,
+                            Rationale::LiteralMatch,
+                        ),
+                        (_, _, files) if !files.is_empty() => (
+                            format!("cache key includes a hash of {}", files.join(", ")),
+                            Confidence::Medium,
+                            Rationale::Heuristic,
+                        ),
+                        (_, indirect, _) if !indirect.is_empty() => (
+                            format!("cache key may be derived from {}", indirect.join(", ")),
+                            Confidence::Medium,
+                            Rationale::DataflowTraced,
+                        ),
+                        _ => continue,
+                    };
+
+                    findings.push(
+                        Self::finding()
+                            .severity(Severity::Medium)
+                            .confidence(confidence)
+                            .rationale(rationale)
+                            .add_location(
+                                step.location()
+                                    .with_keys(&["with".into(), (*input).into()])
+                                    .annotated(annotation),
+                            )
+                            .suggestion(
+                                "derive cache keys from non-secret inputs, like lockfile hashes",
+                            )
+                            .build(workflow)?,
+                    );
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,121 @@
+//! Audits secrets that are expanded into workflow- or job-level `env:`
+//! blocks, rather than into the individual steps that need them.
+//!
+//! Variables in a shared `env:` block are visible to every step in their
+//! scope, including third-party actions that have no need for the secret.
+//! A single compromised (or merely careless) action can then leak it.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::job::StepBody;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::{EnvScope, EnvVar, Job, Step, Uses, Workflow},
+    state::AuditState,
+};
+
+pub(crate) struct SecretsInEnv {
+    pub(crate) state: AuditState,
+}
+
+impl SecretsInEnv {
+    /// Returns whether the given step runs a third-party action or container,
+    /// i.e. one not owned by GitHub or a configured `trusted-owners` entry.
+    fn is_third_party(&self, step: &Step) -> bool {
+        let StepBody::Uses { uses, .. } = &step.deref().body else {
+            return false;
+        };
+
+        // Local actions (`./...`) are part of the repository itself.
+        uses.starts_with("docker://")
+            || Uses::from_step(uses)
+                .is_some_and(|uses| !self.state.config.file.is_trusted_owner(uses.owner))
+    }
+
+    fn finding_for<'w>(
+        &self,
+        var: EnvVar<'w>,
+        mut jobs: impl Iterator<Item = Job<'w>>,
+        workflow: &'w Workflow,
+    ) -> anyhow::Result<Finding<'w>> {
+        let scope = match var.scope {
+            EnvScope::Workflow => "every job in this workflow",
+            EnvScope::Job => "every step in this job",
+            EnvScope::Step => "this step",
+        };
+
+        let third_party = jobs.find_map(|job| job.steps().find(|step| self.is_third_party(step)));
+
+        let mut finding = Self::finding()
+            .confidence(Confidence::High)
+            .add_location(var.location.annotated(format!(
+                "{name} exposes a secret to {scope}",
+                name = var.name
+            )))
+            .suggestion("move the secret into the env of the steps that need it");
+
+        finding = match third_party {
+            Some(step) => finding.severity(Severity::Medium).add_location(
+                step.location()
+                    .with_keys(&["uses".into()])
+                    .annotated("this third-party action can read the secret"),
+            ),
+            None => finding.severity(Severity::Low),
+        };
+
+        finding.build(workflow)
+    }
+}
+
+impl Audit for SecretsInEnv {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "secrets-in-env"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "secrets exposed through shared env blocks"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for var in workflow.env_vars() {
+            if var.uses_secrets() {
+                findings.push(self.finding_for(var, workflow.jobs(), workflow)?);
+            }
+        }
+
+        for job in workflow.jobs() {
+            for var in job.env_vars() {
+                if var.uses_secrets() {
+                    let scope = workflow.jobs().filter(|other| other.id == job.id);
+                    findings.push(self.finding_for(var, scope, workflow)?);
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,144 @@
+//! Audits reusable workflow calls that use `secrets: inherit`.
+//!
+//! `secrets: inherit` passes *every* secret available to the calling
+//! workflow to the called workflow, regardless of which secrets the
+//! called workflow actually needs.
+
+use std::{ops::Deref, path::Path};
+
+use anyhow::Result;
+use github_actions_models::workflow::{job::Secrets, Job};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::Workflow,
+    state::AuditState,
+    utils::github_repo_for_path,
+};
+
+pub(crate) struct SecretsInherit {
+    pub(crate) state: AuditState,
+}
+
+impl Audit for SecretsInherit {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "secrets-inherit"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "secrets unconditionally inherited by called workflow"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        // Calls to workflows under the same owner as the calling workflow
+        // are less risky than calls to third-party workflows.
+        let owner = github_repo_for_path(Path::new(&workflow.path)).map(|(owner, _)| owner);
+
+        for job in workflow.jobs() {
+            let Job::ReusableWorkflowCallJob(reusable) = job.deref() else {
+                continue;
+            };
+
+            if !matches!(reusable.secrets, Some(Secrets::Inherit)) {
+                continue;
+            }
+
+            let (severity, confidence, annotation) = match job.reusable_uses() {
+                // Local reusable workflows are part of the same repository,
+                // so inheriting secrets is only a least-privilege concern.
+                None => {
+                    if !self.state.config.pedantic {
+                        continue;
+                    }
+
+                    (
+                        Severity::Informational,
+                        Confidence::High,
+                        "local workflow is called here",
+                    )
+                }
+                Some(uses) => {
+                    let pinned = uses.commit_ref().is_some();
+
+                    match owner.as_deref() {
+                        Some(owner) if uses.owner.eq_ignore_ascii_case(owner) => (
+                            if pinned {
+                                Severity::Low
+                            } else {
+                                Severity::Medium
+                            },
+                            Confidence::High,
+                            "workflow from the same owner is called here",
+                        ),
+                        _ => (
+                            if pinned {
+                                Severity::Medium
+                            } else {
+                                Severity::High
+                            },
+                            // Without knowing the calling repository's owner,
+                            // we can't be sure that the workflow is third-party.
+                            if owner.is_some() {
+                                Confidence::High
+                            } else {
+                                Confidence::Medium
+                            },
+                            if pinned {
+                                "third-party workflow is called here"
+                            } else {
+                                "third-party workflow is called here, without a pinned commit"
+                            },
+                        ),
+                    }
+                }
+            };
+
+            findings.push(
+                Self::finding()
+                    .severity(severity)
+                    .confidence(confidence)
+                    .rationale(if confidence == Confidence::High {
+                        Rationale::LiteralMatch
+                    } else {
+                        Rationale::Heuristic
+                    })
+                    .add_location(
+                        job.location()
+                            .with_keys(&["uses".into()])
+                            .annotated(annotation),
+                    )
+                    .add_location(
+                        job.location()
+                            .with_keys(&["secrets".into()])
+                            .annotated("all of the caller's secrets are passed to it"),
+                    )
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
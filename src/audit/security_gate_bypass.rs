@@ -0,0 +1,317 @@
+//! Audits `continue-on-error: true` on steps and jobs that look like
+//! security gates, e.g. CodeQL analysis, dependency review, signature
+//! verification, or tests that gate a deployment.
+//!
+//! `continue-on-error` makes a failing step (or job) report success, which
+//! silently neutralizes any gate built on top of it.
+//!
+//! The classifier can be extended via the `actions`, `commands` and
+//! `names` settings.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::expr::BoE,
+    workflow::{job::StepBody, Job},
+};
+use serde::Deserialize;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::{self, Step, Uses, Workflow},
+    shell::statements,
+    state::AuditState,
+};
+
+/// Actions (as `owner/repo`) that perform security checks.
+const GATE_ACTIONS: &[&str] = &[
+    "actions/dependency-review-action",
+    "anchore/scan-action",
+    "aquasecurity/trivy-action",
+    "github/codeql-action",
+    "ossf/scorecard-action",
+    "returntocorp/semgrep-action",
+    "semgrep/semgrep-action",
+    "snyk/actions",
+    "trufflesecurity/trufflehog",
+    "gitleaks/gitleaks-action",
+    "slsa-framework/slsa-verifier",
+];
+
+/// Fragments of `run:` statements that perform security checks.
+const GATE_COMMANDS: &[&str] = &[
+    "cosign verify",
+    "gh attestation verify",
+    "slsa-verifier",
+    "cargo audit",
+    "cargo deny",
+    "npm audit",
+    "pip-audit",
+    "govulncheck",
+    "trivy ",
+    "grype ",
+    "semgrep ",
+    "gitleaks ",
+    "zizmor ",
+];
+
+/// Fragments of step and job names that indicate security checks.
+const GATE_NAMES: &[&str] = &[
+    "codeql",
+    "security",
+    "vulnerab",
+    "audit",
+    "verify",
+    "signature",
+    "scan",
+];
+
+/// Words in `run:` statements that indicate tests, which act as a gate
+/// when they precede a deployment.
+const TEST_WORDS: &[&str] = &[
+    "test", "tests", "check", "pytest", "nextest", "jest", "vitest", "rspec", "tox", "ctest",
+];
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct SecurityGateBypassConfig {
+    /// Additional `owner/repo` actions to treat as security gates.
+    #[serde(default)]
+    actions: Vec<String>,
+    /// Additional `run:` fragments to treat as security gates.
+    #[serde(default)]
+    commands: Vec<String>,
+    /// Additional step or job name fragments to treat as security gates.
+    #[serde(default)]
+    names: Vec<String>,
+}
+
+/// Why a step or job was classified as a gate.
+struct Gate {
+    reason: String,
+    severity: Severity,
+    confidence: Confidence,
+    rationale: Rationale,
+}
+
+pub(crate) struct SecurityGateBypass {
+    actions: Vec<String>,
+    commands: Vec<String>,
+    names: Vec<String>,
+}
+
+impl SecurityGateBypass {
+    fn name_gate(&self, name: &str) -> Option<Gate> {
+        let name = name.to_lowercase();
+        self.names
+            .iter()
+            .find(|fragment| name.contains(fragment.as_str()))
+            .map(|fragment| Gate {
+                reason: format!("is named like a security check ({fragment})"),
+                severity: Severity::Medium,
+                confidence: Confidence::Medium,
+                rationale: Rationale::Heuristic,
+            })
+    }
+
+    /// Classifies the given step, if it's a security gate. Tests are only
+    /// treated as gates if `deploys` is set.
+    fn step_gate(&self, step: &Step, deploys: bool) -> Option<Gate> {
+        let gate = match &step.deref().body {
+            StepBody::Uses { uses, .. } => Uses::from_step(uses).and_then(|uses| {
+                let action = format!("{}/{}", uses.owner, uses.repo).to_lowercase();
+                self.actions.contains(&action).then(|| Gate {
+                    reason: format!("runs {action}"),
+                    severity: Severity::High,
+                    confidence: Confidence::High,
+                    rationale: Rationale::LiteralMatch,
+                })
+            }),
+            StepBody::Run { run, .. } => statements(run).iter().find_map(|stmt| {
+                let text = stmt.text.to_lowercase();
+                if let Some(command) = self
+                    .commands
+                    .iter()
+                    .find(|command| text.contains(command.as_str()))
+                {
+                    Some(Gate {
+                        reason: format!("runs {command}", command = command.trim()),
+                        severity: Severity::High,
+                        confidence: Confidence::High,
+                        rationale: Rationale::LiteralMatch,
+                    })
+                } else if deploys
+                    && stmt.commands().iter().any(|command| {
+                        command
+                            .words
+                            .iter()
+                            .any(|word| TEST_WORDS.contains(&word.to_lowercase().as_str()))
+                    })
+                {
+                    Some(Gate {
+                        reason: "runs tests that gate a deployment".into(),
+                        severity: Severity::Medium,
+                        confidence: Confidence::Low,
+                        rationale: Rationale::Heuristic,
+                    })
+                } else {
+                    None
+                }
+            }),
+        };
+
+        gate.or_else(|| step.name.as_deref().and_then(|name| self.name_gate(name)))
+    }
+
+    /// Classifies the given job, if it contains a security gate
+    /// or is named like one.
+    fn job_gate(&self, job: &models::Job, deploys: bool) -> Option<Gate> {
+        job.steps()
+            .find_map(|step| self.step_gate(&step, deploys))
+            .or_else(|| {
+                let Job::NormalJob(normal) = job.deref() else {
+                    return None;
+                };
+                [Some(job.id), normal.name.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|name| self.name_gate(name))
+            })
+    }
+
+    fn finding_for<'w>(
+        location: SymbolicLocation<'w>,
+        continue_on_error: &BoE,
+        gate: Gate,
+        workflow: &'w Workflow,
+    ) -> anyhow::Result<Finding<'w>> {
+        // `continue-on-error: ${{ ... }}` is typically only set for
+        // experimental matrix entries, so it's less likely to be a bypass.
+        let (confidence, rationale) = match continue_on_error {
+            BoE::Expr(_) => (Confidence::Low, Rationale::ExpressionUnevaluated),
+            BoE::Literal(_) => (gate.confidence, gate.rationale),
+        };
+
+        Self::finding()
+            .severity(gate.severity)
+            .confidence(confidence)
+            .rationale(rationale)
+            .add_location(
+                location
+                    .with_keys(&["continue-on-error".into()])
+                    .annotated(format!(
+                        "failures are ignored, but this {reason}",
+                        reason = gate.reason
+                    )),
+            )
+            .suggestion("remove continue-on-error, so that failures block the workflow")
+            .build(workflow)
+    }
+}
+
+/// Returns whether the given `continue-on-error` setting may be true.
+fn may_continue(continue_on_error: &BoE) -> bool {
+    !matches!(continue_on_error, BoE::Literal(false))
+}
+
+impl Audit for SecurityGateBypass {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "security-gate-bypass"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "security check allowed to fail"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn config_keys() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &["actions", "commands", "names"]
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let config: SecurityGateBypassConfig = state.config.file.rule_config(Self::ident())?;
+
+        let extend = |defaults: &[&str], extra: Vec<String>| {
+            defaults
+                .iter()
+                .map(|value| value.to_string())
+                .chain(extra)
+                .map(|value| value.to_lowercase())
+                .collect()
+        };
+
+        Ok(Self {
+            actions: extend(GATE_ACTIONS, config.actions),
+            commands: extend(GATE_COMMANDS, config.commands),
+            names: extend(GATE_NAMES, config.names),
+        })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+        let graph = workflow.job_graph();
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            // Tests gate a deployment if they run within a deploying job,
+            // or within a job that a deploying job needs.
+            let gates_deploy = graph.dependents(job.id).into_iter().any(|id| {
+                workflow
+                    .job(id)
+                    .is_some_and(|other| other.deploy_intent().is_some())
+            });
+
+            if may_continue(&normal.continue_on_error) {
+                if let Some(gate) = self.job_gate(&job, gates_deploy) {
+                    findings.push(Self::finding_for(
+                        job.location(),
+                        &normal.continue_on_error,
+                        gate,
+                        workflow,
+                    )?);
+                    continue;
+                }
+            }
+
+            let deploys = job.deploy_intent().is_some();
+            for step in job.steps() {
+                if !may_continue(&step.continue_on_error) {
+                    continue;
+                }
+
+                if let Some(gate) = self.step_gate(&step, deploys) {
+                    findings.push(Self::finding_for(
+                        step.location(),
+                        &step.continue_on_error,
+                        gate,
+                        workflow,
+                    )?);
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -4,25 +4,156 @@
 //!
 //! This audit is "pedantic" only, since zizmor can't detect
 //! whether self-hosted runners are ephemeral or not.
+//!
+//! When a GitHub API token is available, custom runner labels and
+//! runner groups are resolved against the repository's (and its
+//! organization's) actual self-hosted runners.
+//!
+//! `runs-on:` expressions are evaluated against each of the job's matrix
+//! combinations when the matrix is static, so that e.g.
+//! `runs-on: ${{ matrix.os }}` is only flagged if some `os` is self-hosted.
+//! `vars.*` are evaluated too, when their values are configured.
+
+use std::path::Path;
 
 use crate::{
-    finding::{Confidence, Severity},
+    finding::{Confidence, Rationale, Severity},
+    github_api::{self, Runner},
+    models::is_github_hosted,
+    symbols::SymbolTable,
+    utils::{extract_expressions, github_repo_for_path},
     AuditState,
 };
 
 use anyhow::Result;
-use github_actions_models::{
-    common::expr::ExplicitExpr,
-    workflow::{job::RunsOn, Job},
-};
+use github_actions_models::workflow::{job::RunsOn, Job};
+
+use super::Audit;
+
+/// The result of resolving a job's runner against the runners that
+/// GitHub knows about.
+enum Resolution {
+    /// The runner is definitely self-hosted; contains the runner's name.
+    SelfHosted(String),
+    /// We could see the available self-hosted runners, and none matched.
+    NotSelfHosted,
+    /// We couldn't resolve the runner, e.g. due to being offline.
+    Unknown,
+}
 
-use super::WorkflowAudit;
+/// Returns the name of the first runner with all of the given labels, if any.
+fn matching_runner(runners: &[Runner], labels: &[String]) -> Option<String> {
+    runners
+        .iter()
+        .find(|runner| runner.matches(labels))
+        .map(|runner| runner.name.clone())
+}
 
 pub(crate) struct SelfHostedRunner {
     pub(crate) _state: AuditState,
+    client: Option<github_api::Client>,
+}
+
+impl SelfHostedRunner {
+    fn resolve_labels(
+        &self,
+        repo: Option<&(String, String)>,
+        labels: &[String],
+    ) -> Result<Resolution> {
+        let (Some(client), Some((owner, repo))) = (&self.client, repo) else {
+            return Ok(Resolution::Unknown);
+        };
+
+        Ok(match client.list_self_hosted_runners(owner, repo)? {
+            Some(runners) => match matching_runner(&runners, labels) {
+                Some(name) => Resolution::SelfHosted(name),
+                None => Resolution::NotSelfHosted,
+            },
+            None => Resolution::Unknown,
+        })
+    }
+
+    fn resolve_group(
+        &self,
+        repo: Option<&(String, String)>,
+        group: &str,
+        labels: &[String],
+    ) -> Result<Resolution> {
+        let (Some(client), Some((owner, _))) = (&self.client, repo) else {
+            return Ok(Resolution::Unknown);
+        };
+
+        Ok(match client.list_runner_group_runners(owner, group)? {
+            Some(runners) => match matching_runner(&runners, labels) {
+                Some(name) => Resolution::SelfHosted(name),
+                None => Resolution::NotSelfHosted,
+            },
+            None => Resolution::Unknown,
+        })
+    }
+    /// Classifies a concrete set of `runs-on:` labels, returning the
+    /// confidence, its rationale, and the annotation for a finding if they
+    /// may select a self-hosted runner. `expanded` indicates that the labels were
+    /// produced by evaluating an expression.
+    fn classify_labels(
+        &self,
+        repo: Option<&(String, String)>,
+        labels: &[String],
+        expanded: bool,
+    ) -> Result<Option<(Confidence, Rationale, String)>> {
+        let Some(label) = labels.first() else {
+            return Ok(None);
+        };
+
+        if labels
+            .iter()
+            .any(|label| self._state.config.file.is_trusted_runner(label))
+        {
+            return Ok(None);
+        }
+
+        if label == "self-hosted" {
+            // All self-hosted runners start with the 'self-hosted'
+            // label followed by any specifiers.
+            return Ok(Some(if expanded {
+                (
+                    Confidence::High,
+                    Rationale::ExpressionEvaluated,
+                    format!(
+                        "expression expands into a self-hosted runner ({labels})",
+                        labels = labels.join(", ")
+                    ),
+                )
+            } else {
+                (
+                    Confidence::High,
+                    Rationale::LiteralMatch,
+                    "self-hosted runner used here".into(),
+                )
+            }));
+        }
+
+        if labels.iter().any(|label| is_github_hosted(label)) {
+            return Ok(None);
+        }
+
+        // Self-hosted runners don't need to be selected with
+        // the 'self-hosted' label; any of their custom labels
+        // will do. We only flag custom labels that we can resolve to a
+        // self-hosted runner, since they're just as likely to refer to
+        // e.g. GitHub's larger hosted runners.
+        Ok(match self.resolve_labels(repo, labels)? {
+            Resolution::SelfHosted(runner) => Some((
+                Confidence::High,
+                Rationale::ResolvedViaApi,
+                format!("label resolves to self-hosted runner {runner}"),
+            )),
+            Resolution::NotSelfHosted | Resolution::Unknown => None,
+        })
+    }
 }
 
-impl WorkflowAudit for SelfHostedRunner {
+impl Audit for SelfHostedRunner {
     fn ident() -> &'static str
     where
         Self: Sized,
@@ -37,14 +168,46 @@ impl WorkflowAudit for SelfHostedRunner {
         "runs on a self-hosted runner"
     }
 
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Low
+    }
+
+    fn pedantic() -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+
+    fn github_scopes() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        // Listing runners requires admin access to the repository, and
+        // to the organization for its runners and runner groups.
+        &["repo", "admin:org"]
+    }
+
     fn new(state: AuditState) -> anyhow::Result<Self>
     where
         Self: Sized,
     {
-        Ok(Self { _state: state })
+        let client = state.github_client();
+
+        Ok(Self {
+            _state: state,
+            client,
+        })
     }
 
-    fn audit<'w>(
+    fn uses_github_api(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn audit_workflow<'w>(
         &self,
         workflow: &'w crate::models::Workflow,
     ) -> Result<Vec<crate::finding::Finding<'w>>> {
@@ -55,6 +218,18 @@ impl WorkflowAudit for SelfHostedRunner {
             return Ok(results);
         }
 
+        let repo = match self.client {
+            Some(_) => github_repo_for_path(Path::new(&workflow.path)),
+            None => None,
+        };
+        if self.client.is_some() && repo.is_none() {
+            log::debug!(
+                "couldn't determine a GitHub repository for {workflow}; not resolving runners",
+                workflow = workflow.filename()
+            );
+        }
+
+        let symbols = SymbolTable::new(workflow, self._state.config.file.vars());
         for job in workflow.jobs() {
             let Job::NormalJob(normal) = *job else {
                 continue;
@@ -62,61 +237,100 @@ impl WorkflowAudit for SelfHostedRunner {
 
             match &normal.runs_on {
                 RunsOn::Target(labels) => {
-                    let Some(label) = labels.first() else {
-                        continue;
+                    let dynamic = labels
+                        .iter()
+                        .any(|label| !extract_expressions(label).is_empty());
+
+                    let candidates = if dynamic {
+                        // The job's runner is expanded via an expression. We
+                        // evaluate it for each of the job's matrix combinations
+                        // when we can, and otherwise flag it as potentially
+                        // expanding to self-hosted.
+                        let contexts = symbols.contexts(Some(&job), None);
+                        let Some(candidates) = job.expand_runner_labels(&contexts) else {
+                            results.push(
+                                Self::finding()
+                                    .confidence(Confidence::Low)
+                                    .rationale(Rationale::ExpressionUnevaluated)
+                                    .add_location(
+                                        job.location().with_keys(&["runs-on".into()]).annotated(
+                                            "expression may expand into a self-hosted runner",
+                                        ),
+                                    )
+                                    .build(workflow)?,
+                            );
+                            continue;
+                        };
+
+                        candidates
+                    } else {
+                        vec![labels.clone()]
                     };
 
-                    if label == "self-hosted" {
-                        // All self-hosted runners start with the 'self-hosted'
-                        // label followed by any specifiers.
-                        results.push(
-                            Self::finding()
-                                .confidence(Confidence::High)
-                                .severity(Severity::Unknown)
-                                .add_location(
-                                    job.location()
-                                        .with_keys(&["runs-on".into()])
-                                        .annotated("self-hosted runner used here"),
-                                )
-                                .build(workflow)?,
-                        );
-                    } else if ExplicitExpr::from_curly(label).is_some() {
-                        // The job might also have its runner expanded via an
-                        // expression. Long-term we should perform this evaluation
-                        // to increase our confidence, but for now we flag it as
-                        // potentially expanding to self-hosted.
-                        results.push(
-                            Self::finding()
-                                .confidence(Confidence::Low)
-                                .severity(Severity::Unknown)
-                                .add_location(
-                                    job.location().with_keys(&["runs-on".into()]).annotated(
-                                        "expression may expand into a self-hosted runner",
-                                    ),
-                                )
-                                .build(workflow)?,
-                        );
+                    let mut best: Option<(Confidence, Rationale, String)> = None;
+                    for candidate in &candidates {
+                        if let Some(classified) =
+                            self.classify_labels(repo.as_ref(), candidate, dynamic)?
+                        {
+                            if best
+                                .as_ref()
+                                .map_or(true, |(best, ..)| classified.0 > *best)
+                            {
+                                best = Some(classified);
+                            }
+                        }
                     }
+
+                    let Some((confidence, rationale, annotation)) = best else {
+                        continue;
+                    };
+
+                    results.push(
+                        Self::finding()
+                            .confidence(confidence)
+                            .rationale(rationale)
+                            .add_location(
+                                job.location()
+                                    .with_keys(&["runs-on".into()])
+                                    .annotated(annotation),
+                            )
+                            .build(workflow)?,
+                    );
                 }
                 // NOTE: GHA docs are unclear on whether runner groups always
                 // imply self-hosted runners or not. All examples suggest that they
-                // do, but I'm not sure.
+                // do, but GitHub's larger hosted runners can also be grouped.
+                // When we can, we resolve the group to disambiguate.
                 // See: https://docs.github.com/en/actions/hosting-your-own-runners/managing-self-hosted-runners/managing-access-to-self-hosted-runners-using-groups
                 // See: https://docs.github.com/en/actions/writing-workflows/choosing-where-your-workflow-runs/choosing-the-runner-for-a-job
-                RunsOn::Group {
-                    group: _,
-                    labels: _,
-                } => results.push(
-                    Self::finding()
-                        .confidence(Confidence::Low)
-                        .severity(Severity::Unknown)
-                        .add_location(
-                            job.location()
-                                .with_keys(&["runs-on".into()])
-                                .annotated("runner group implies self-hosted runner"),
-                        )
-                        .build(workflow)?,
-                ),
+                RunsOn::Group { group, labels } => {
+                    let (confidence, rationale, annotation) =
+                        match self.resolve_group(repo.as_ref(), group, labels)? {
+                            Resolution::SelfHosted(runner) => (
+                                Confidence::High,
+                                Rationale::ResolvedViaApi,
+                                format!("runner group contains self-hosted runner {runner}"),
+                            ),
+                            Resolution::NotSelfHosted => continue,
+                            Resolution::Unknown => (
+                                Confidence::Low,
+                                Rationale::Heuristic,
+                                "runner group implies self-hosted runner".into(),
+                            ),
+                        };
+
+                    results.push(
+                        Self::finding()
+                            .confidence(confidence)
+                            .rationale(rationale)
+                            .add_location(
+                                job.location()
+                                    .with_keys(&["runs-on".into()])
+                                    .annotated(annotation),
+                            )
+                            .build(workflow)?,
+                    )
+                }
             }
         }
 
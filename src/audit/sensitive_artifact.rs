@@ -0,0 +1,216 @@
+//! Audits `actions/upload-artifact` steps whose `path:` includes files
+//! that commonly contain credentials, like `.git`, `.env` or `~/.ssh`.
+//!
+//! Artifacts are downloadable by anyone with read access to the repository
+//! (i.e. everyone, for public repositories), so anything credential-like
+//! that ends up in one is effectively published.
+//!
+//! Uploads of the entire workspace are also flagged, except after a
+//! credential-persisting checkout: those are reported by `artipacked`.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::EnvValue,
+    workflow::{job::StepBody, Job},
+};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::Workflow,
+    state::AuditState,
+    utils::split_patterns,
+};
+
+/// Prefixes that refer to the workspace, which is the default
+/// root for relative artifact paths.
+const WORKSPACE_PREFIXES: &[&str] = &["${{ github.workspace }}", "$GITHUB_WORKSPACE", "."];
+
+/// A kind of sensitive path within an artifact.
+#[derive(Clone, Copy, PartialEq)]
+enum Sensitive {
+    /// The entire workspace, which may contain any of the below.
+    Workspace,
+    /// A `.git` directory, which contains credentials persisted
+    /// by `actions/checkout`.
+    Git,
+    /// A dotenv file, e.g. `.env` or `.env.production`.
+    Env,
+    /// An SSH directory, e.g. `~/.ssh`.
+    Ssh,
+    /// A package manager cache, which may contain registry tokens.
+    Cache,
+}
+
+impl Sensitive {
+    /// Classifies the given upload pattern, if it's sensitive.
+    fn classify(pattern: &str) -> Option<Self> {
+        // Exclusions can only make an upload less sensitive.
+        if pattern.starts_with('!') {
+            return None;
+        }
+
+        let mut path = pattern.trim_end_matches('/');
+        for prefix in WORKSPACE_PREFIXES {
+            if let Some(rest) = path.strip_prefix(prefix) {
+                if rest.is_empty() || rest.starts_with('/') {
+                    path = rest.trim_start_matches('/');
+                    break;
+                }
+            }
+        }
+
+        if matches!(path, "" | "." | ".." | "*" | "**" | "**/*") {
+            return Some(Self::Workspace);
+        }
+
+        let components = path.split('/').collect::<Vec<_>>();
+
+        if components.contains(&".git") {
+            Some(Self::Git)
+        } else if components.contains(&".ssh") {
+            Some(Self::Ssh)
+        } else if components
+            .iter()
+            .any(|component| *component == ".env" || component.starts_with(".env."))
+        {
+            Some(Self::Env)
+        } else if path.contains("node_modules/.cache") {
+            Some(Self::Cache)
+        } else {
+            None
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Workspace => "uploads the entire workspace",
+            Self::Git => "uploads a .git directory",
+            Self::Env => "uploads a dotenv file",
+            Self::Ssh => "uploads an SSH directory",
+            Self::Cache => "uploads a package cache",
+        }
+    }
+
+    fn severity(self, persisted_credentials: bool) -> Severity {
+        match self {
+            Self::Ssh => Severity::High,
+            // `actions/checkout` writes its token into `.git/config`.
+            Self::Git if persisted_credentials => Severity::High,
+            Self::Git | Self::Env => Severity::Medium,
+            Self::Workspace | Self::Cache => Severity::Low,
+        }
+    }
+}
+
+pub(crate) struct SensitiveArtifact {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for SensitiveArtifact {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "sensitive-artifact"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "sensitive files uploaded as artifacts"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(_) = job.deref() else {
+                continue;
+            };
+
+            let mut credential_checkout = None;
+
+            for step in job.steps() {
+                let StepBody::Uses { uses, with } = &step.deref().body else {
+                    continue;
+                };
+
+                if step.persists_credentials() {
+                    credential_checkout.get_or_insert(step);
+                    continue;
+                }
+
+                if !uses.starts_with("actions/upload-artifact") {
+                    continue;
+                }
+
+                let Some(EnvValue::String(path)) = with.get("path") else {
+                    continue;
+                };
+
+                // Report only the most severe pattern in each upload.
+                let Some(sensitive) = split_patterns(path)
+                    .filter_map(Sensitive::classify)
+                    .max_by_key(|sensitive| sensitive.severity(credential_checkout.is_some()))
+                else {
+                    continue;
+                };
+
+                // `artipacked` already covers this case.
+                if sensitive == Sensitive::Workspace && credential_checkout.is_some() {
+                    continue;
+                }
+
+                // Since v4.4.0, upload-artifact skips hidden files (including
+                // all of the above) unless explicitly told otherwise.
+                let (confidence, rationale) = match with.get("include-hidden-files") {
+                    Some(EnvValue::Boolean(true)) => (Confidence::High, Rationale::LiteralMatch),
+                    _ if sensitive == Sensitive::Workspace => {
+                        (Confidence::Low, Rationale::Heuristic)
+                    }
+                    _ => (Confidence::Medium, Rationale::Heuristic),
+                };
+
+                let mut finding = Self::finding()
+                    .severity(sensitive.severity(credential_checkout.is_some()))
+                    .confidence(confidence)
+                    .rationale(rationale)
+                    .add_location(
+                        step.location()
+                            .with_keys(&["with".into(), "path".into()])
+                            .annotated(sensitive.describe()),
+                    )
+                    .suggestion("narrow the artifact's path to just the files that are needed");
+
+                if let (Sensitive::Git, Some(checkout)) = (sensitive, &credential_checkout) {
+                    finding = finding.add_location(
+                        checkout
+                            .location()
+                            .annotated("this checkout persists credentials into .git"),
+                    );
+                }
+
+                findings.push(finding.build(workflow)?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,137 @@
+//! Audits `GITHUB_TOKEN`s (via `secrets.GITHUB_TOKEN` or `github.token`)
+//! that are passed as inputs to third-party actions.
+//!
+//! Actions don't receive the job's `GITHUB_TOKEN` unless it's passed to
+//! them explicitly. Passing it to a third-party action grants that action
+//! (and anyone who can compromise it) every permission the token has.
+//!
+//! Actions owned by GitHub, or by a configured `trusted-owners` entry,
+//! are not flagged.
+
+use std::ops::Deref;
+
+use github_actions_models::{common::EnvValue, workflow::job::StepBody};
+
+use super::Audit;
+use crate::{
+    expr::Expr,
+    finding::{Confidence, Finding, Severity},
+    models::{Uses, Workflow},
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+/// Returns whether the given input value contains the `GITHUB_TOKEN`.
+fn passes_token(value: &str) -> bool {
+    extract_expressions(value).iter().any(|expr| {
+        Expr::parse(expr.as_bare()).is_ok_and(|expr| {
+            expr.contexts().iter().any(|ctx| {
+                ctx.eq_ignore_ascii_case("secrets.GITHUB_TOKEN")
+                    || ctx.eq_ignore_ascii_case("github.token")
+            })
+        })
+    })
+}
+
+pub(crate) struct ThirdPartyToken {
+    pub(crate) state: AuditState,
+}
+
+impl Audit for ThirdPartyToken {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "third-party-token"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "GITHUB_TOKEN passed to a third-party action"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            // The token is only as dangerous as its permissions.
+            let (severity, permissions) = match workflow.token_can_write(&job) {
+                Some(true) => (Severity::High, "token has write permissions"),
+                None => (
+                    Severity::Medium,
+                    "token has the repository's default permissions",
+                ),
+                Some(false) => (Severity::Low, "token is read-only"),
+            };
+
+            for step in job.steps() {
+                let StepBody::Uses { uses, with } = &step.deref().body else {
+                    continue;
+                };
+
+                // Local actions are part of the repository, and `docker://`
+                // actions can't take inputs.
+                let Some(action) = Uses::from_step(uses) else {
+                    continue;
+                };
+
+                if self.state.config.file.is_trusted_owner(action.owner) {
+                    continue;
+                }
+
+                let mut inputs = with
+                    .iter()
+                    .filter(|(_, value)| {
+                        matches!(value, EnvValue::String(value) if passes_token(value))
+                    })
+                    .map(|(input, _)| input)
+                    .collect::<Vec<_>>();
+                inputs.sort();
+
+                for input in inputs {
+                    findings.push(
+                        Self::finding()
+                            .severity(severity)
+                            .confidence(Confidence::High)
+                            .add_location(step.location().with_keys(&["uses".into()]).annotated(
+                                format!(
+                                    "third-party action owned by {owner}",
+                                    owner = action.owner
+                                ),
+                            ))
+                            .add_location(
+                                step.location()
+                                    .with_keys(&["with".into(), input.as_str().into()])
+                                    .annotated(format!(
+                                        "GITHUB_TOKEN is passed here; {permissions}"
+                                    )),
+                            )
+                            .suggestion(
+                                "limit the job's permissions to what the action needs, \
+                                 or add its owner to trusted-owners",
+                            )
+                            .build(workflow)?,
+                    );
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,266 @@
+//! Audits `uses:` clauses for likely typosquats of popular actions,
+//! e.g. `actions/chekout` or `acti0ns/checkout`.
+//!
+//! Names are compared against a bundled corpus of popular actions (which
+//! can be extended via the `trusted-actions` setting), both by edit distance
+//! and after normalizing visually confusable characters.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{job::StepBody, Job};
+use serde::Deserialize;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::{Uses, Workflow},
+    state::AuditState,
+};
+
+/// Popular actions and reusable workflows, as `owner/repo`.
+const POPULAR_ACTIONS: &[&str] = &[
+    "actions/attest-build-provenance",
+    "actions/cache",
+    "actions/checkout",
+    "actions/configure-pages",
+    "actions/create-github-app-token",
+    "actions/deploy-pages",
+    "actions/dependency-review-action",
+    "actions/download-artifact",
+    "actions/github-script",
+    "actions/labeler",
+    "actions/setup-dotnet",
+    "actions/setup-go",
+    "actions/setup-java",
+    "actions/setup-node",
+    "actions/setup-python",
+    "actions/stale",
+    "actions/upload-artifact",
+    "actions/upload-pages-artifact",
+    "aws-actions/configure-aws-credentials",
+    "azure/login",
+    "codecov/codecov-action",
+    "dawidd6/action-download-artifact",
+    "docker/build-push-action",
+    "docker/login-action",
+    "docker/metadata-action",
+    "docker/setup-buildx-action",
+    "docker/setup-qemu-action",
+    "dtolnay/rust-toolchain",
+    "github/codeql-action",
+    "golangci/golangci-lint-action",
+    "google-github-actions/auth",
+    "google-github-actions/setup-gcloud",
+    "goreleaser/goreleaser-action",
+    "hashicorp/setup-terraform",
+    "peaceiris/actions-gh-pages",
+    "peter-evans/create-pull-request",
+    "pypa/gh-action-pypi-publish",
+    "ruby/setup-ruby",
+    "shivammathur/setup-php",
+    "softprops/action-gh-release",
+    "Swatinem/rust-cache",
+    "tj-actions/changed-files",
+];
+
+/// Characters that are commonly substituted for visually similar ones,
+/// either within ASCII or from other scripts.
+const CONFUSABLES: &[(char, char)] = &[
+    ('0', 'o'),
+    ('1', 'l'),
+    ('3', 'e'),
+    ('5', 's'),
+    ('_', '-'),
+    ('.', '-'),
+    // Cyrillic.
+    ('а', 'a'),
+    ('с', 'c'),
+    ('е', 'e'),
+    ('һ', 'h'),
+    ('і', 'i'),
+    ('ј', 'j'),
+    ('о', 'o'),
+    ('р', 'p'),
+    ('ѕ', 's'),
+    ('х', 'x'),
+    ('у', 'y'),
+    // Greek.
+    ('α', 'a'),
+    ('ο', 'o'),
+    ('ρ', 'p'),
+    ('ν', 'v'),
+];
+
+/// Multi-character sequences that render similarly to a single character.
+const CONFUSABLE_SEQUENCES: &[(&str, &str)] = &[("rn", "m"), ("vv", "w")];
+
+/// Folds `name` into a canonical "skeleton", such that names that look alike
+/// have the same skeleton.
+fn skeleton(name: &str) -> String {
+    let mut skeleton: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            CONFUSABLES
+                .iter()
+                .find_map(|(from, to)| (*from == c).then_some(*to))
+                .unwrap_or(c)
+        })
+        .collect();
+
+    for (from, to) in CONFUSABLE_SEQUENCES {
+        skeleton = skeleton.replace(from, to);
+    }
+
+    skeleton
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct TyposquattingConfig {
+    /// Additional `owner/repo` names to treat as trusted, both as
+    /// typosquatting targets and as names that are never flagged.
+    #[serde(default)]
+    trusted_actions: Vec<String>,
+}
+
+pub(crate) struct Typosquatting {
+    trusted: Vec<String>,
+}
+
+impl Typosquatting {
+    /// Returns the trusted name that `name` likely squats on, along with
+    /// our confidence in that, if any.
+    fn squatted(&self, name: &str) -> Option<(&str, Confidence)> {
+        let lowered = name.to_lowercase();
+        if self.trusted.iter().any(|trusted| trusted == &lowered) {
+            return None;
+        }
+
+        let folded = skeleton(&lowered);
+        if let Some(trusted) = self
+            .trusted
+            .iter()
+            .find(|trusted| skeleton(trusted) == folded)
+        {
+            return Some((trusted, Confidence::High));
+        }
+
+        // Longer names can tolerate more typos before they stop being
+        // a plausible misspelling of a trusted name.
+        let threshold = if lowered.len() >= 20 { 2 } else { 1 };
+
+        self.trusted
+            .iter()
+            .map(|trusted| (trusted, strsim::damerau_levenshtein(&lowered, trusted)))
+            .filter(|(_, distance)| *distance <= threshold)
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(trusted, distance)| {
+                let confidence = if distance == 1 {
+                    Confidence::Medium
+                } else {
+                    Confidence::Low
+                };
+                (trusted.as_str(), confidence)
+            })
+    }
+
+    fn check_uses<'w>(
+        &self,
+        uses: Option<Uses<'_>>,
+        location: SymbolicLocation<'w>,
+        workflow: &'w Workflow,
+    ) -> anyhow::Result<Option<Finding<'w>>> {
+        let Some(uses) = uses else {
+            return Ok(None);
+        };
+
+        let name = format!("{owner}/{repo}", owner = uses.owner, repo = uses.repo);
+        let Some((trusted, confidence)) = self.squatted(&name) else {
+            return Ok(None);
+        };
+
+        Self::finding()
+            .severity(Severity::High)
+            .confidence(confidence)
+            .rationale(Rationale::Heuristic)
+            .add_location(location.annotated(format!("{name} looks like a typosquat of {trusted}")))
+            .build(workflow)
+            .map(Some)
+    }
+}
+
+impl Audit for Typosquatting {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "typosquatting"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "action name resembles a popular action"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn config_keys() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &["trusted-actions"]
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let config: TyposquattingConfig = state.config.file.rule_config(Self::ident())?;
+
+        let trusted = POPULAR_ACTIONS
+            .iter()
+            .map(|name| name.to_string())
+            .chain(config.trusted_actions)
+            .map(|name| name.to_lowercase())
+            .collect();
+
+        Ok(Self { trusted })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            if let Job::ReusableWorkflowCallJob(reusable) = job.deref() {
+                findings.extend(self.check_uses(
+                    Uses::from_reusable(&reusable.uses),
+                    job.location().with_keys(&["uses".into()]),
+                    workflow,
+                )?);
+                continue;
+            }
+
+            for step in job.steps() {
+                let StepBody::Uses { uses, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                findings.extend(self.check_uses(
+                    Uses::from_step(uses),
+                    step.location().with_keys(&["uses".into()]),
+                    workflow,
+                )?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,212 @@
+//! Audits actions whose repositories are archived, haven't been pushed to
+//! in a long time, or no longer contain the action on their default branch.
+//!
+//! None of these are vulnerabilities in and of themselves, but they mean
+//! that nobody is going to fix the action when one is found.
+//!
+//! This is an online-only audit. Repository metadata is cached on disk
+//! (keyed by `owner/repo`) between runs.
+
+use std::{
+    ops::Deref,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, Result};
+use github_actions_models::workflow::job::StepBody;
+use serde::Deserialize;
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    github_api,
+    models::{Uses, Workflow},
+    state::AuditState,
+};
+
+/// The default number of years without a push before a repository is
+/// considered unmaintained.
+const DEFAULT_INACTIVE_YEARS: u32 = 2;
+
+/// A (non-leap) year.
+const YEAR: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+struct UnmaintainedActionsConfig {
+    /// The number of years without a push after which a repository is
+    /// considered unmaintained.
+    inactive_years: u32,
+}
+
+impl Default for UnmaintainedActionsConfig {
+    fn default() -> Self {
+        Self {
+            inactive_years: DEFAULT_INACTIVE_YEARS,
+        }
+    }
+}
+
+/// Why an action is considered unmaintained.
+struct Unmaintained {
+    annotation: String,
+    severity: Severity,
+    confidence: Confidence,
+    rationale: Rationale,
+}
+
+pub(crate) struct UnmaintainedActions {
+    client: github_api::Client,
+    inactive_after: Duration,
+}
+
+impl UnmaintainedActions {
+    fn unmaintained(&self, uses: &Uses<'_>) -> Result<Option<Unmaintained>> {
+        let (owner, repo) = (uses.owner, uses.repo);
+
+        // Missing repositories are either private or gone entirely; the
+        // latter will fail the workflow outright, so there's nothing to add.
+        let Some(repository) = self.client.get_repository(owner, repo)? else {
+            return Ok(None);
+        };
+
+        if repository.archived {
+            return Ok(Some(Unmaintained {
+                annotation: format!("{owner}/{repo} is archived"),
+                severity: Severity::Medium,
+                confidence: Confidence::High,
+                rationale: Rationale::ResolvedViaApi,
+            }));
+        }
+
+        let latest = Uses {
+            git_ref: Some(&repository.default_branch),
+            ..*uses
+        };
+        if self.client.fetch_action_metadata(&latest)?.is_none() {
+            return Ok(Some(Unmaintained {
+                annotation: format!(
+                    "action no longer exists on {owner}/{repo}'s default branch ({branch})",
+                    branch = repository.default_branch
+                ),
+                severity: Severity::Medium,
+                confidence: Confidence::Medium,
+                rationale: Rationale::ResolvedViaApi,
+            }));
+        }
+
+        let inactive = repository
+            .pushed_at
+            .as_deref()
+            .and_then(|pushed_at| humantime::parse_rfc3339(pushed_at).ok())
+            .and_then(|pushed_at| SystemTime::now().duration_since(pushed_at).ok())
+            .is_some_and(|age| age > self.inactive_after);
+
+        if inactive {
+            return Ok(Some(Unmaintained {
+                annotation: format!(
+                    "{owner}/{repo} hasn't been pushed to in over {years} years",
+                    years = self.inactive_after.as_secs() / YEAR.as_secs()
+                ),
+                severity: Severity::Low,
+                confidence: Confidence::Medium,
+                rationale: Rationale::Heuristic,
+            }));
+        }
+
+        Ok(None)
+    }
+}
+
+impl Audit for UnmaintainedActions {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "unmaintained-actions"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "action is archived or unmaintained"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn config_keys() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &["inactive-years"]
+    }
+
+    fn requires_github_api() -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+
+    fn new(state: AuditState) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let config: UnmaintainedActionsConfig = state.config.file.rule_config(Self::ident())?;
+
+        let Some(client) = state.github_client() else {
+            return Err(anyhow!("can't audit without a GitHub API token"));
+        };
+
+        Ok(Self {
+            client,
+            inactive_after: YEAR.saturating_mul(config.inactive_years),
+        })
+    }
+
+    fn uses_github_api(&self) -> bool {
+        true
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            for step in job.steps() {
+                let StepBody::Uses { uses, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                let Some(uses) = Uses::from_step(uses) else {
+                    continue;
+                };
+
+                let Some(unmaintained) = self.unmaintained(&uses)? else {
+                    continue;
+                };
+
+                findings.push(
+                    Self::finding()
+                        .severity(unmaintained.severity)
+                        .confidence(unmaintained.confidence)
+                        .rationale(unmaintained.rationale)
+                        .add_location(
+                            step.location()
+                                .with_keys(&["uses".into()])
+                                .annotated(unmaintained.annotation),
+                        )
+                        .suggestion("replace this action with a maintained alternative")
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
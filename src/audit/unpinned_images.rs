@@ -0,0 +1,224 @@
+//! Audits job containers, service containers, and `docker://` steps for
+//! images that aren't pinned by digest, or that come from unrecognized
+//! registries.
+//!
+//! Images computed by expressions are checked when the expressions can be
+//! resolved statically, e.g. `${{ vars.REGISTRY }}/app:latest` with a
+//! configured `REGISTRY`.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::{
+    common::expr::ExplicitExpr,
+    workflow::job::{Container, StepBody},
+};
+
+use super::Audit;
+use crate::{
+    expr::{Contexts, Value},
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::{DockerImage, Workflow},
+    state::AuditState,
+    symbols::SymbolTable,
+    utils::extract_expressions,
+};
+
+/// Well-known public registries, and suffixes for well-known private
+/// registry hostnames (e.g. `123456789012.dkr.ecr.us-east-1.amazonaws.com`).
+const KNOWN_REGISTRIES: &[&str] = &[
+    "docker.io",
+    "ghcr.io",
+    "mcr.microsoft.com",
+    "gcr.io",
+    ".gcr.io",
+    ".pkg.dev",
+    "public.ecr.aws",
+    ".amazonaws.com",
+    ".azurecr.io",
+    "quay.io",
+    "registry.k8s.io",
+    "localhost",
+];
+
+/// Returns the image of the given job or service container, along with
+/// its location.
+fn container_image<'w>(
+    container: &'w Container,
+    location: SymbolicLocation<'w>,
+) -> (&'w str, SymbolicLocation<'w>) {
+    match container {
+        Container::Name(image) => (image.as_str(), location),
+        Container::Container { image, .. } => {
+            (image.as_str(), location.with_keys(&["image".into()]))
+        }
+    }
+}
+
+pub(crate) struct UnpinnedImages {
+    pub(crate) _state: AuditState,
+}
+
+impl UnpinnedImages {
+    fn check_image<'w>(
+        &self,
+        image: &str,
+        location: SymbolicLocation<'w>,
+        contexts: &Contexts,
+        workflow: &'w Workflow,
+    ) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        // Images that are computed by an expression can only be checked
+        // if we know what the expression evaluates to.
+        let expanded = if ExplicitExpr::from_curly(image).is_some() || image.contains("${{") {
+            match contexts.expand(image) {
+                Some(Value::String(expanded)) => Some(expanded),
+                _ => return Ok(findings),
+            }
+        } else {
+            None
+        };
+        let rationale = match expanded {
+            Some(_) => Rationale::ExpressionEvaluated,
+            None => Rationale::LiteralMatch,
+        };
+        let annotate = |annotation: String| match &expanded {
+            Some(expanded) => format!("{annotation} (expands to {expanded})"),
+            None => annotation,
+        };
+
+        let Some(image) = DockerImage::parse(expanded.as_deref().unwrap_or(image)) else {
+            log::debug!("couldn't parse docker image: {image}");
+            return Ok(findings);
+        };
+
+        if image.digest.is_none() {
+            let (severity, annotation) = match image.tag {
+                None => (
+                    Severity::High,
+                    "image has no tag or digest, so is implicitly latest".into(),
+                ),
+                Some("latest") => (Severity::High, "image is pinned to latest".into()),
+                Some(tag) => (
+                    Severity::Medium,
+                    format!("image is pinned to a mutable tag ({tag})"),
+                ),
+            };
+
+            findings.push(
+                Self::finding()
+                    .severity(severity)
+                    .confidence(Confidence::High)
+                    .rationale(rationale)
+                    .add_location(location.clone().annotated(annotate(annotation)))
+                    .build(workflow)?,
+            );
+        }
+
+        let registry = image.registry();
+        let host = registry.split(':').next().unwrap_or(registry);
+        if !KNOWN_REGISTRIES.iter().any(|known| {
+            if known.starts_with('.') {
+                host.ends_with(known)
+            } else {
+                host == *known
+            }
+        }) {
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Low)
+                    .confidence(Confidence::Medium)
+                    .rationale(Rationale::Heuristic)
+                    .add_location(location.annotated(annotate(format!(
+                        "image is pulled from unrecognized registry {registry}"
+                    ))))
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for UnpinnedImages {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "unpinned-images"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "unpinned or unrecognized container images"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let symbols = SymbolTable::new(workflow, self._state.config.file.vars());
+        for job in workflow.jobs() {
+            // Containers are evaluated at job scope, i.e. without `env`.
+            let contexts = symbols.contexts(Some(&job), None);
+
+            for (location, container) in job.containers() {
+                let (image, location) = container_image(container, location);
+                findings.extend(self.check_image(image, location, &contexts, workflow)?);
+            }
+
+            for step in job.steps() {
+                let StepBody::Uses { uses, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                if uses.starts_with("docker://") {
+                    findings.extend(self.check_image(
+                        uses,
+                        step.location().with_keys(&["uses".into()]),
+                        &contexts,
+                        workflow,
+                    )?);
+                }
+            }
+        }
+
+        // Images in the reusable workflows that this workflow calls may
+        // depend on the inputs that it passes to them, so we check the
+        // ones that are computed by expressions here.
+        for (caller, callee) in workflow.calls() {
+            let symbols = symbols.callee(&caller, callee);
+
+            for job in callee.jobs() {
+                let contexts = symbols.contexts(Some(&job), None);
+
+                for (location, container) in job.containers() {
+                    let (image, location) = container_image(container, location);
+                    if extract_expressions(image).is_empty() {
+                        continue;
+                    }
+
+                    findings.extend(self.check_image(image, location, &contexts, workflow)?);
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,209 @@
+//! Audits action usage for references that aren't pinned to
+//! a fully qualified commit SHA.
+//!
+//! Tags and branches are mutable, meaning that the action's maintainer
+//! (or anybody who compromises the action's repository) can change
+//! the code that a symbolic `uses:` ref resolves to at any time.
+//!
+//! This applies to both workflow steps and composite action steps.
+//!
+//! When online, symbolic refs are resolved to their current commit, and
+//! a fix that pins to it is attached.
+//!
+//! The severities of first-party and third-party findings are configurable.
+
+use std::ops::Deref;
+
+use anyhow::Result;
+use github_actions_models::workflow::{job::StepBody, Job};
+use serde::Deserialize;
+
+use super::Audit;
+use crate::{
+    finding::{
+        fix::{Fix, Patch},
+        Confidence, Finding, Severity, SymbolicLocation,
+    },
+    github_api,
+    models::{Action, Uses, Workflow},
+    state::AuditState,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+struct UnpinnedUsesConfig {
+    /// The severity of first-party (`actions/*`) actions pinned to a
+    /// mutable ref.
+    first_party_severity: Severity,
+    /// The severity of third-party actions pinned to a mutable ref.
+    third_party_severity: Severity,
+}
+
+impl Default for UnpinnedUsesConfig {
+    fn default() -> Self {
+        Self {
+            first_party_severity: Severity::Low,
+            third_party_severity: Severity::Medium,
+        }
+    }
+}
+
+pub(crate) struct UnpinnedUses {
+    client: Option<github_api::Client>,
+    config: UnpinnedUsesConfig,
+}
+
+impl UnpinnedUses {
+    /// Returns a fix that pins `raw` (a `uses:` at `location`) to the commit
+    /// that its symbolic ref currently points to, if we can resolve it.
+    fn pin<'w>(
+        &self,
+        raw: &str,
+        uses: &Uses<'_>,
+        location: SymbolicLocation<'w>,
+    ) -> Result<Option<Fix<'w>>> {
+        let (Some(client), Some(git_ref)) = (&self.client, uses.symbolic_ref()) else {
+            return Ok(None);
+        };
+        let Some(commit) = client.commit_for_ref(uses.owner, uses.repo, git_ref)? else {
+            return Ok(None);
+        };
+
+        let path = raw.rsplit_once('@').map_or(raw, |(path, _)| path);
+        Ok(Some(Fix::new(
+            format!("pin to {commit}"),
+            location,
+            Patch::Rewrite {
+                text: format!("{path}@{commit} # {git_ref}"),
+            },
+        )))
+    }
+
+    /// Returns the severity and annotation for the given `uses:`, or `None`
+    /// if it's pinned to a commit.
+    fn unpinned(&self, uses: &Uses<'_>) -> Option<(Severity, String)> {
+        match uses.symbolic_ref() {
+            // First-party actions are maintained by GitHub, who already
+            // controls the runner itself. These are still worth pinning,
+            // but are less likely to be compromised.
+            Some(git_ref) if uses.is_first_party() => Some((
+                self.config.first_party_severity,
+                format!("action is pinned to a mutable ref ({git_ref})"),
+            )),
+            Some(git_ref) => Some((
+                self.config.third_party_severity,
+                format!("third-party action is pinned to a mutable ref ({git_ref})"),
+            )),
+            None if uses.git_ref.is_none() => {
+                Some((Severity::High, "action is not pinned to a ref".into()))
+            }
+            None => None,
+        }
+    }
+}
+
+impl Audit for UnpinnedUses {
+    fn ident() -> &'static str {
+        "unpinned-uses"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "action is not pinned to a commit SHA"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn config_keys() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &["first-party-severity", "third-party-severity"]
+    }
+
+    fn new(state: AuditState) -> Result<Self> {
+        let config = state.config.file.rule_config(Self::ident())?;
+
+        Ok(Self {
+            client: state.github_client(),
+            config,
+        })
+    }
+
+    fn uses_github_api(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            if !matches!(job.deref(), Job::NormalJob(_)) {
+                continue;
+            }
+
+            for step in job.steps() {
+                let StepBody::Uses { uses, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                let raw = uses;
+                let Some(uses) = Uses::from_step(raw) else {
+                    continue;
+                };
+
+                if let Some((severity, annotation)) = self.unpinned(&uses) {
+                    let location = step.location().with_keys(&["uses".into()]);
+                    let mut finding = Self::finding()
+                        .severity(severity)
+                        .confidence(Confidence::High)
+                        .add_location(location.clone().annotated(annotation));
+
+                    if let Some(fix) = self.pin(raw, &uses, location)? {
+                        finding = finding.fix(fix);
+                    }
+
+                    findings.push(finding.build(workflow)?);
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+
+    fn audit_action<'a>(&self, action: &'a Action) -> Result<Vec<Finding<'a>>> {
+        let mut findings = vec![];
+
+        for step in action.steps() {
+            let Some(raw) = step.uses.as_deref() else {
+                continue;
+            };
+            let Some(uses) = Uses::from_step(raw) else {
+                continue;
+            };
+
+            if let Some((severity, annotation)) = self.unpinned(&uses) {
+                let location = step.location().with_keys(&["uses".into()]);
+                let mut finding = Self::finding()
+                    .severity(severity)
+                    .confidence(Confidence::High)
+                    .add_location(location.clone().annotated(annotation));
+
+                if let Some(fix) = self.pin(raw, &uses, location)? {
+                    finding = finding.fix(fix);
+                }
+
+                findings.push(finding.build(action)?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,150 @@
+//! Audits `if:` conditions that always evaluate to true, regardless of
+//! the values they appear to check.
+//!
+//! The most common cause is a condition with text outside of its `${{ }}`
+//! fence, e.g. `if: ${{ a }} && ${{ b }}`, or a fenced expression in a
+//! multi-line YAML scalar (which adds a trailing newline). GitHub treats
+//! these as string interpolations, and the resulting non-empty string is
+//! always truthy. Expressions containing truthy literals at the top level
+//! (e.g. `... || 'true'`) are also flagged.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::Job;
+
+use super::Audit;
+use crate::{
+    expr::Expr,
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::Workflow,
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+/// Returns whether the given expression is constant and always true.
+///
+/// A bare `true` is clearly intentional, so it isn't flagged.
+fn constant_truthy(expr: &str) -> bool {
+    Expr::parse(expr).is_ok_and(|expr| !matches!(expr, Expr::Boolean(_)) && expr.is_always_truthy())
+}
+
+/// Suggestion for conditions that are accidentally interpolated strings.
+const INTERPOLATION_SUGGESTION: &str =
+    "wrap the entire condition in a single ${{ }} on a single line, or remove the ${{ }}";
+
+/// Suggestion for conditions with constant truthy operands.
+const CONSTANT_SUGGESTION: &str = "remove the constant operand from the condition";
+
+/// Returns why the given condition is always true, a suggestion for
+/// fixing it, and our confidence, if it is.
+fn unsound(cond: &str) -> Option<(&'static str, &'static str, Confidence)> {
+    let exprs = extract_expressions(cond);
+
+    let mut rest = cond.to_string();
+    for expr in &exprs {
+        rest = rest.replacen(expr.as_raw(), "", 1);
+    }
+
+    match exprs.as_slice() {
+        // No fences at all: the whole condition is a bare expression.
+        [] => constant_truthy(cond.trim()).then_some((
+            "condition is constant and always true",
+            CONSTANT_SUGGESTION,
+            Confidence::High,
+        )),
+        [expr] if rest.is_empty() => constant_truthy(expr.as_bare()).then_some((
+            "condition is constant and always true",
+            CONSTANT_SUGGESTION,
+            Confidence::High,
+        )),
+        // Leftover whitespace typically comes from a block scalar (`|`),
+        // whose trailing newline makes the condition an interpolation.
+        [_] if rest.trim().is_empty() => Some((
+            "whitespace outside of ${{ }} makes this an always-true string",
+            INTERPOLATION_SUGGESTION,
+            Confidence::Medium,
+        )),
+        _ => Some((
+            "text outside of ${{ }} makes this an always-true string",
+            INTERPOLATION_SUGGESTION,
+            Confidence::High,
+        )),
+    }
+}
+
+pub(crate) struct UnsoundCondition {
+    pub(crate) _state: AuditState,
+}
+
+impl UnsoundCondition {
+    fn check<'w>(
+        cond: Option<&str>,
+        location: SymbolicLocation<'w>,
+        workflow: &'w Workflow,
+    ) -> anyhow::Result<Option<Finding<'w>>> {
+        let Some((annotation, suggestion, confidence)) = cond.and_then(unsound) else {
+            return Ok(None);
+        };
+
+        Self::finding()
+            .severity(Severity::Medium)
+            .confidence(confidence)
+            .rationale(Rationale::LiteralMatch)
+            .add_location(location.with_keys(&["if".into()]).annotated(annotation))
+            .suggestion(suggestion)
+            .build(workflow)
+            .map(Some)
+    }
+}
+
+impl Audit for UnsoundCondition {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "unsound-condition"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "if: condition is always true"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Medium
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            let cond = match job.deref() {
+                Job::NormalJob(normal) => normal.r#if.as_deref(),
+                Job::ReusableWorkflowCallJob(reusable) => reusable.r#if.as_deref(),
+            };
+            findings.extend(Self::check(cond, job.location(), workflow)?);
+
+            for step in job.steps() {
+                findings.extend(Self::check(
+                    step.r#if.as_deref(),
+                    step.location(),
+                    workflow,
+                )?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,109 @@
+//! Audits `pull_request_target` and `issue_comment` workflows that check
+//! out the pull request's head, i.e. the classic "pwn request".
+//!
+//! Both triggers run in the context of the base repository, with its
+//! secrets and a potentially writable `GITHUB_TOKEN`. Checking out the
+//! pull request's code in such a workflow means that any subsequent build,
+//! test or install step runs attacker-controlled code with those privileges.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{event::BareEvent, Job};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::Workflow,
+    state::AuditState,
+};
+
+pub(crate) struct UntrustedCheckout {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for UntrustedCheckout {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "untrusted-checkout"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "untrusted code checked out in a privileged workflow"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let Some(trigger) = [
+            (BareEvent::PullRequestTarget, "pull_request_target"),
+            (BareEvent::IssueComment, "issue_comment"),
+        ]
+        .into_iter()
+        .find_map(|(event, name)| workflow.has_trigger(event).then_some(name)) else {
+            return Ok(findings);
+        };
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(_) = job.deref() else {
+                continue;
+            };
+
+            let Some(checkout) = job.steps().find(|step| step.is_untrusted_checkout()) else {
+                continue;
+            };
+
+            // Attacker-controlled code can always poison caches and the like,
+            // but secrets and a writable token are what make it critical.
+            let (severity, privilege) = match (job.uses_secrets(), workflow.token_can_write(&job)) {
+                (true, _) => (Severity::Critical, "job has access to secrets"),
+                (false, Some(true)) => (Severity::Critical, "job's GITHUB_TOKEN can write"),
+                (false, None) => (
+                    Severity::Medium,
+                    "job's GITHUB_TOKEN has the repository's default permissions",
+                ),
+                (false, Some(false)) => (Severity::Low, "job's GITHUB_TOKEN is read-only"),
+            };
+
+            findings.push(
+                Self::finding()
+                    .severity(severity)
+                    .confidence(Confidence::High)
+                    .add_location(job.location().annotated(privilege))
+                    .add_location(
+                        checkout
+                            .location()
+                            .with_keys(&["with".into(), "ref".into()])
+                            .annotated(format!(
+                                "checks out the pull request's code under {trigger}"
+                            )),
+                    )
+                    .suggestion(
+                        "use the pull_request trigger for untrusted code, \
+                         and pass its results to a separate privileged workflow",
+                    )
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
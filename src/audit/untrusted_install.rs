@@ -0,0 +1,145 @@
+//! Audits privileged workflows that check out untrusted code, and then
+//! install dependencies from its manifests, e.g. `npm ci` or
+//! `pip install -r requirements.txt`.
+//!
+//! Package managers run install scripts (npm's `preinstall` and friends,
+//! `setup.py`, native extension builds) as part of installing, so a pull
+//! request that controls the manifest controls code that runs with the
+//! job's secrets and token. Checkouts are matched by their ref's
+//! provenance, so refs computed from the pull request's head via step
+//! or job outputs are included.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::EnvValue,
+    workflow::{event::BareEvent, job::StepBody, Job},
+};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::Workflow,
+    shell::statements,
+    state::AuditState,
+};
+
+pub(crate) struct UntrustedInstall {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for UntrustedInstall {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "untrusted-install"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "dependencies installed from untrusted manifests"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let Some(trigger) = [
+            (BareEvent::PullRequestTarget, "pull_request_target"),
+            (BareEvent::WorkflowRun, "workflow_run"),
+        ]
+        .into_iter()
+        .find_map(|(event, name)| workflow.has_trigger(event).then_some(name)) else {
+            return Ok(findings);
+        };
+
+        for job in workflow.jobs() {
+            if !matches!(job.deref(), Job::NormalJob(_)) {
+                continue;
+            }
+
+            let severity = if job.uses_secrets() || workflow.token_can_write(&job) == Some(true) {
+                Severity::High
+            } else {
+                Severity::Medium
+            };
+
+            let steps = job.steps().collect::<Vec<_>>();
+            let Some(checkout) = steps
+                .iter()
+                .position(|step| workflow.is_untrusted_checkout(step, &job))
+            else {
+                continue;
+            };
+
+            // A checkout into a subdirectory may leave the base repository's
+            // manifests where installs (by default) look for them.
+            let subdirectory = match &steps[checkout].deref().body {
+                StepBody::Uses { with, .. } => matches!(
+                    with.get("path"),
+                    Some(EnvValue::String(path)) if !path.is_empty() && path != "."
+                ),
+                StepBody::Run { .. } => false,
+            };
+            let (confidence, rationale) = if subdirectory {
+                (Confidence::Medium, Rationale::Heuristic)
+            } else {
+                (Confidence::High, Rationale::LiteralMatch)
+            };
+
+            for step in &steps[checkout + 1..] {
+                let StepBody::Run { run, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                let Some((command, manifest)) = statements(run).iter().find_map(|stmt| {
+                    stmt.commands().iter().find_map(|command| {
+                        command
+                            .package_install()
+                            .map(|manifest| (command.words.join(" "), manifest))
+                    })
+                }) else {
+                    continue;
+                };
+
+                findings.push(
+                    Self::finding()
+                        .severity(severity)
+                        .confidence(confidence)
+                        .rationale(rationale)
+                        .add_location(
+                            steps[checkout]
+                                .location()
+                                .annotated(format!("{trigger} code is checked out here")),
+                        )
+                        .add_location(step.location().with_keys(&["run".into()]).annotated(
+                            format!("{command} runs install scripts from the untrusted {manifest}"),
+                        ))
+                        .suggestion(
+                            "install untrusted dependencies in a separate job without secrets, \
+                             or disable install scripts (e.g. npm's --ignore-scripts)",
+                        )
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,163 @@
+//! Audits `strategy.matrix` definitions that are built from
+//! attacker-controllable data, e.g. `${{ fromJSON(needs.setup.outputs.matrix) }}`
+//! where the `setup` job derives its output from the triggering event.
+//!
+//! Matrix values are commonly interpolated into `runs-on:`, `env:` and
+//! `run:` scripts, so an attacker who controls the matrix controls where
+//! and what each matrixed job runs.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::expr::LoE,
+    workflow::{
+        job::{StepBody, Strategy},
+        Job,
+    },
+};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::{self, Workflow},
+    state::AuditState,
+};
+
+/// Returns each expression within the given strategy's matrix,
+/// along with its location.
+fn matrix_expressions<'w>(
+    job: &models::Job<'w>,
+    strategy: &'w Strategy,
+) -> Vec<(SymbolicLocation<'w>, &'w str)> {
+    let location = job
+        .location()
+        .with_keys(&["strategy".into(), "matrix".into()]);
+
+    let matrix = match &strategy.matrix {
+        Some(LoE::Expr(expr)) => return vec![(location, expr.as_raw())],
+        Some(LoE::Literal(matrix)) => matrix,
+        None => return vec![],
+    };
+
+    let mut exprs = vec![];
+
+    for (key, list) in [("include", &matrix.include), ("exclude", &matrix.exclude)] {
+        if let LoE::Expr(expr) = list {
+            exprs.push((location.with_keys(&[key.into()]), expr.as_raw()));
+        }
+    }
+
+    match &matrix.dimensions {
+        LoE::Expr(expr) => exprs.push((location.clone(), expr.as_raw())),
+        LoE::Literal(dimensions) => {
+            for (name, values) in dimensions {
+                if let LoE::Expr(expr) = values {
+                    exprs.push((location.with_keys(&[name.as_str().into()]), expr.as_raw()));
+                }
+            }
+        }
+    }
+
+    exprs
+}
+
+/// Returns the first place where the given job uses its matrix in a way
+/// that an attacker could abuse, i.e. in `runs-on:` or a `run:` script.
+fn dangerous_use<'w>(job: &models::Job<'w>) -> Option<SymbolicLocation<'w>> {
+    if job
+        .runner_labels()
+        .iter()
+        .any(|label| label.contains("matrix."))
+    {
+        return Some(
+            job.location()
+                .with_keys(&["runs-on".into()])
+                .annotated("matrix selects the runner"),
+        );
+    }
+
+    job.steps().find_map(|step| match &step.deref().body {
+        StepBody::Run { run, .. } if run.contains("matrix.") => Some(
+            step.location()
+                .with_keys(&["run".into()])
+                .annotated("matrix is expanded into this script"),
+        ),
+        _ => None,
+    })
+}
+
+pub(crate) struct UntrustedMatrix {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for UntrustedMatrix {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "untrusted-matrix"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "matrix built from attacker-controllable data"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        for job in workflow.jobs() {
+            let Job::NormalJob(normal) = job.deref() else {
+                continue;
+            };
+
+            let Some(strategy) = &normal.strategy else {
+                continue;
+            };
+
+            for (location, expr) in matrix_expressions(&job, strategy) {
+                let sources = workflow.untrusted_sources(expr, Some(&job));
+                if sources.is_empty() {
+                    continue;
+                }
+
+                let mut finding = Self::finding()
+                    .confidence(Confidence::Medium)
+                    .rationale(Rationale::DataflowTraced)
+                    .add_location(location.annotated(format!(
+                        "matrix may be controlled by {sources}",
+                        sources = sources.join(", ")
+                    )))
+                    .suggestion(
+                        "build the matrix from trusted data, or validate it \
+                         against an allowlist before use",
+                    );
+
+                finding = match dangerous_use(&job) {
+                    Some(usage) => finding.severity(Severity::High).add_location(usage),
+                    None => finding.severity(Severity::Medium),
+                };
+
+                findings.push(finding.build(workflow)?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
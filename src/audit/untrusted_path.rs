@@ -0,0 +1,149 @@
+//! Audits privileged workflows that check out untrusted code, and then add
+//! directories within the checkout (e.g. `./bin` or `node_modules/.bin`)
+//! to `$GITHUB_PATH`.
+//!
+//! Every later step in the job resolves commands through the prepended
+//! directories first. If pull request code controls their contents, it
+//! can plant binaries named like the tools later steps run (`git`, `node`,
+//! `gh`, and so on), and thereby run with the job's secrets and token.
+//!
+//! Writes of attacker-controlled *values* to `$GITHUB_PATH` are covered
+//! by `github-env`; this audit covers fixed paths with attacker-controlled
+//! contents.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{event::BareEvent, job::StepBody, Job};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Rationale, Severity},
+    models::Workflow,
+    shell::statements,
+    state::AuditState,
+};
+
+/// Fragments of paths that point into the workspace, where
+/// `actions/checkout` puts the checked-out code by default.
+const WORKSPACE_PATHS: &[&str] = &[
+    "$GITHUB_WORKSPACE",
+    "${GITHUB_WORKSPACE}",
+    "$env:GITHUB_WORKSPACE",
+    "github.workspace",
+    "$(pwd)",
+    "$PWD",
+    "${PWD}",
+    "./",
+    "node_modules/.bin",
+    "vendor/bin",
+];
+
+pub(crate) struct UntrustedPath {
+    pub(crate) _state: AuditState,
+}
+
+impl Audit for UntrustedPath {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "untrusted-path"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "untrusted directory added to PATH"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let Some(trigger) = [
+            (BareEvent::PullRequestTarget, "pull_request_target"),
+            (BareEvent::WorkflowRun, "workflow_run"),
+            (BareEvent::IssueComment, "issue_comment"),
+        ]
+        .into_iter()
+        .find_map(|(event, name)| workflow.has_trigger(event).then_some(name)) else {
+            return Ok(findings);
+        };
+
+        for job in workflow.jobs() {
+            if !matches!(job.deref(), Job::NormalJob(_)) {
+                continue;
+            }
+
+            let steps = job.steps().collect::<Vec<_>>();
+
+            // Only directories prepended after an untrusted checkout
+            // can contain untrusted code.
+            let Some(checkout) = steps.iter().position(|step| step.is_untrusted_checkout()) else {
+                continue;
+            };
+
+            for (index, step) in steps.iter().enumerate().skip(checkout + 1) {
+                let StepBody::Run { run, .. } = &step.deref().body else {
+                    continue;
+                };
+
+                let prepends = statements(run).iter().any(|stmt| {
+                    stmt.writes_to("GITHUB_PATH")
+                        && WORKSPACE_PATHS.iter().any(|path| stmt.text.contains(path))
+                });
+
+                if !prepends {
+                    continue;
+                }
+
+                // The runner only applies `GITHUB_PATH` to later steps.
+                let Some(next) = steps.get(index + 1) else {
+                    continue;
+                };
+
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::High)
+                        .confidence(Confidence::Medium)
+                        .rationale(Rationale::Heuristic)
+                        .add_location(
+                            steps[checkout]
+                                .location()
+                                .annotated(format!("{trigger} code is checked out here")),
+                        )
+                        .add_location(
+                            step.location()
+                                .with_keys(&["run".into()])
+                                .annotated("adds a directory from the checkout to PATH"),
+                        )
+                        .add_location(
+                            next.location()
+                                .annotated("this and later steps may run planted binaries"),
+                        )
+                        .suggestion(
+                            "invoke tools from the checkout by their full path, \
+                             instead of adding their directory to PATH",
+                        )
+                        .build(workflow)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
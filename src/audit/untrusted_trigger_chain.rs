@@ -0,0 +1,234 @@
+//! Audits chains of workflows in which a workflow that untrusted users can
+//! trigger (e.g. via `pull_request` from a fork) in turn triggers a
+//! privileged workflow, via `workflow_run` or `repository_dispatch`, which
+//! then consumes the untrusted run's code or artifacts.
+//!
+//! The downstream workflow runs in the context of the base repository,
+//! with its secrets and a (potentially) writable `GITHUB_TOKEN`, no matter
+//! who caused the upstream run. Checking out the upstream run's head, or
+//! downloading its artifacts, hands that privilege to whoever controls it.
+//!
+//! Unlike `artifact-execution` and `dangerous-triggers`, which consider each
+//! workflow in isolation, this audit only flags chains that it can see end
+//! to end, i.e. when both workflows are scanned together.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{event::BareEvent, job::StepBody, Job};
+
+use super::Audit;
+use crate::{
+    dataflow::Dataflow,
+    finding::{Confidence, Finding, Rationale, Severity, SymbolicLocation},
+    models::{Chain, Workflow},
+    state::AuditState,
+};
+
+/// Returns whether `context` is part of a `repository_dispatch` event's
+/// payload, which the dispatching workflow chooses.
+fn is_dispatch_payload(context: &str) -> bool {
+    context.starts_with("github.event.client_payload")
+}
+
+pub(crate) struct UntrustedTriggerChain {
+    pub(crate) _state: AuditState,
+}
+
+impl UntrustedTriggerChain {
+    /// Returns the locations in `upstream` that show it passing untrusted
+    /// data down the chain to `workflow`, along with our confidence that
+    /// it does.
+    fn upstream_evidence<'w>(
+        chain: &Chain,
+        upstream: &'w Workflow,
+        workflow: &Workflow,
+    ) -> (Vec<SymbolicLocation<'w>>, Confidence) {
+        let mut evidence = vec![];
+
+        for job in upstream.jobs() {
+            for step in job.steps() {
+                let StepBody::Uses { uses, .. } = &step.deref().body else {
+                    if let Chain::RepositoryDispatch(event) = chain {
+                        if step.dispatched_events().contains(event) {
+                            evidence.push(
+                                step.location()
+                                    .with_keys(&["run".into()])
+                                    .annotated(format!("sends {event} to {}", workflow.filename())),
+                            );
+                        }
+                    }
+                    continue;
+                };
+
+                match chain {
+                    Chain::WorkflowRun if uses.starts_with("actions/upload-artifact") => evidence
+                        .push(
+                            step.location()
+                                .with_keys(&["uses".into()])
+                                .annotated("uploads an artifact from the untrusted run"),
+                        ),
+                    Chain::RepositoryDispatch(event)
+                        if step.dispatched_events().contains(event) =>
+                    {
+                        evidence.push(
+                            step.location()
+                                .with_keys(&["uses".into()])
+                                .annotated(format!("sends {event} to {}", workflow.filename())),
+                        )
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        let confidence = match chain {
+            Chain::WorkflowRun if !evidence.is_empty() => Confidence::High,
+            _ => Confidence::Medium,
+        };
+
+        (evidence, confidence)
+    }
+}
+
+impl Audit for UntrustedTriggerChain {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "untrusted-trigger-chain"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "privileged workflow consumes an untrusted workflow's run"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::High
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+        let dataflow = Dataflow::new(workflow, is_dispatch_payload);
+
+        for (chain, upstream) in workflow.upstreams() {
+            // Triggers that let untrusted users cause the upstream run.
+            let Some(event) = [
+                (BareEvent::PullRequest, "pull_request"),
+                (BareEvent::PullRequestReview, "pull_request_review"),
+                (
+                    BareEvent::PullRequestReviewComment,
+                    "pull_request_review_comment",
+                ),
+                (BareEvent::IssueComment, "issue_comment"),
+                (BareEvent::Issues, "issues"),
+            ]
+            .into_iter()
+            .find_map(|(event, name)| upstream.has_trigger(event).then_some(name)) else {
+                continue;
+            };
+
+            let (trigger, annotation) = match chain {
+                Chain::WorkflowRun => (
+                    "workflow_run",
+                    format!("runs whenever {} completes", upstream.filename()),
+                ),
+                Chain::RepositoryDispatch(event) => (
+                    "repository_dispatch",
+                    format!("runs when {} sends {event}", upstream.filename()),
+                ),
+            };
+            let (upstream_evidence, confidence) =
+                Self::upstream_evidence(chain, upstream, workflow);
+
+            for job in workflow.jobs() {
+                let Job::NormalJob(_) = job.deref() else {
+                    continue;
+                };
+
+                let mut consumed = vec![];
+                for step in job.steps() {
+                    let consumes = match chain {
+                        Chain::WorkflowRun if workflow.is_untrusted_checkout(&step, &job) => {
+                            Some("checks out the untrusted run's head")
+                        }
+                        Chain::WorkflowRun if step.is_artifact_download() => {
+                            Some("downloads artifacts from the untrusted run")
+                        }
+                        Chain::RepositoryDispatch(_)
+                            if matches!(step.deref().body, StepBody::Uses { .. })
+                                && step.texts().iter().any(|text| {
+                                    !dataflow.sources(text, Some(&job), Some(&step)).is_empty()
+                                }) =>
+                        {
+                            Some("uses inputs chosen by the dispatching workflow")
+                        }
+                        _ => None,
+                    };
+
+                    if let Some(consumes) = consumes {
+                        consumed.push(
+                            step.location()
+                                .with_keys(&["uses".into()])
+                                .annotated(consumes),
+                        );
+                    }
+                }
+
+                if consumed.is_empty() {
+                    continue;
+                }
+
+                // Secrets and write access are what make the chain worth
+                // exploiting.
+                let severity =
+                    if job.uses_secrets() || workflow.token_can_write(&job) != Some(false) {
+                        Severity::High
+                    } else {
+                        Severity::Medium
+                    };
+
+                let mut finding = Self::finding()
+                    .severity(severity)
+                    .confidence(confidence)
+                    .rationale(Rationale::Heuristic)
+                    .add_location(
+                        workflow
+                            .location()
+                            .with_keys(&["on".into(), trigger.into()])
+                            .annotated(annotation.clone()),
+                    )
+                    .add_location(
+                        upstream
+                            .location()
+                            .with_keys(&["on".into()])
+                            .annotated(format!("untrusted users can trigger this via {event}")),
+                    )
+                    .suggestion(
+                        "treat the upstream run's code and artifacts as untrusted data, \
+                         or move privileged steps into a separate job that doesn't consume them",
+                    );
+
+                for location in upstream_evidence.iter().chain(&consumed) {
+                    finding = finding.add_location(location.clone());
+                }
+
+                findings.push(finding.build(workflow)?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
@@ -5,7 +5,7 @@ use github_actions_models::{
     workflow::{job::StepBody, Job},
 };
 
-use super::WorkflowAudit;
+use super::Audit;
 use crate::{
     finding::{Confidence, Severity},
     state::AuditState,
@@ -60,7 +60,7 @@ impl UseTrustedPublishing {
     }
 }
 
-impl WorkflowAudit for UseTrustedPublishing {
+impl Audit for UseTrustedPublishing {
     fn ident() -> &'static str {
         "use-trusted-publishing"
     }
@@ -72,11 +72,18 @@ impl WorkflowAudit for UseTrustedPublishing {
         "prefer trusted publishing for authentication"
     }
 
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Informational
+    }
+
     fn new(state: AuditState) -> anyhow::Result<Self> {
         Ok(Self { _state: state })
     }
 
-    fn audit<'w>(
+    fn audit_workflow<'w>(
         &self,
         workflow: &'w crate::models::Workflow,
     ) -> anyhow::Result<Vec<crate::finding::Finding<'w>>> {
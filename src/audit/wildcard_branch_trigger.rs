@@ -0,0 +1,128 @@
+//! Audits workflows whose jobs have secrets or write permissions, and that
+//! run on pushes to any branch: either with no branch filter at all, or
+//! with a filter that matches arbitrary names (like `**`).
+//!
+//! In organizations with broad push rights, anybody who can push a branch
+//! then gets privileged execution. Since a pushed branch can also change
+//! the workflow itself, branch filters are only a first line of defense:
+//! secrets that should only be used from specific branches belong in a
+//! deployment environment with deployment branch rules, so jobs bound to
+//! an environment aren't flagged.
+
+use std::ops::Deref;
+
+use github_actions_models::workflow::{event::BranchFilters, Job};
+
+use super::Audit;
+use crate::{
+    finding::{Confidence, Finding, Severity},
+    models::{DeployIntent, Workflow},
+    refs::filter_includes,
+    state::AuditState,
+};
+
+/// Branch names that nobody would filter for deliberately,
+/// with and without a `/` (which `*` doesn't match).
+const ARBITRARY_BRANCHES: &[&str] = &["arbitrary", "arbitrary/branch"];
+
+pub(crate) struct WildcardBranchTrigger {
+    pub(crate) _state: AuditState,
+}
+
+impl WildcardBranchTrigger {
+    /// Returns why the given workflow runs on pushes to any branch, if it does.
+    fn any_branch(workflow: &Workflow) -> Option<&'static str> {
+        match workflow.push_trigger()? {
+            None => Some("runs on pushes to any branch"),
+            Some(push) => match &push.branch_filters {
+                Some(BranchFilters::Branches(patterns)) => ARBITRARY_BRANCHES
+                    .iter()
+                    .any(|name| filter_includes(patterns, name))
+                    .then_some("branch filter matches any branch"),
+                Some(BranchFilters::BranchesIgnore(patterns)) => ARBITRARY_BRANCHES
+                    .iter()
+                    .any(|name| !filter_includes(patterns, name))
+                    .then_some("branch filter only excludes specific branches"),
+                // Filtering only tags excludes branches entirely.
+                None if push.tag_filters.is_some() => None,
+                None => Some("runs on pushes to any branch"),
+            },
+        }
+    }
+}
+
+impl Audit for WildcardBranchTrigger {
+    fn ident() -> &'static str
+    where
+        Self: Sized,
+    {
+        "wildcard-branch-trigger"
+    }
+
+    fn desc() -> &'static str
+    where
+        Self: Sized,
+    {
+        "privileged workflow runs on pushes to any branch"
+    }
+
+    fn default_severity() -> Severity
+    where
+        Self: Sized,
+    {
+        Severity::Low
+    }
+
+    fn new(state: AuditState) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self { _state: state })
+    }
+
+    fn audit_workflow<'w>(&self, workflow: &'w Workflow) -> anyhow::Result<Vec<Finding<'w>>> {
+        let mut findings = vec![];
+
+        let Some(reason) = Self::any_branch(workflow) else {
+            return Ok(findings);
+        };
+
+        for job in workflow.jobs() {
+            if !matches!(job.deref(), Job::NormalJob(_))
+                || matches!(job.deploy_intent(), Some(DeployIntent::Environment(_)))
+            {
+                continue;
+            }
+
+            let privilege = if job.uses_secrets() {
+                "uses secrets"
+            } else if workflow.token_can_write(&job) == Some(true) {
+                "has write permissions"
+            } else {
+                continue;
+            };
+
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Low)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        workflow
+                            .location()
+                            .with_keys(&["on".into()])
+                            .annotated(reason),
+                    )
+                    .add_location(job.location().annotated(format!(
+                        "this job {privilege}, but isn't restricted to specific branches"
+                    )))
+                    .suggestion(
+                        "restrict the push trigger to specific branches, or bind this job \
+                         to an environment with deployment branch rules",
+                    )
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,165 @@
+//! Discovering the GitHub API token to use for online audits.
+//!
+//! In order of precedence, the token comes from `--gh-token`, the `GH_TOKEN`
+//! or `GITHUB_TOKEN` environment variables, or the GitHub CLI's login for the
+//! GitHub instance (i.e. `gh auth token`). Which one was used is logged, since
+//! a stale token from an unexpected source is otherwise hard to diagnose.
+
+use std::{fmt::Display, process::Command};
+
+use crate::github_api::Client;
+
+/// The environment variables that a token is read from, in order of
+/// precedence.
+const TOKEN_VARIABLES: &[&str] = &["GH_TOKEN", "GITHUB_TOKEN"];
+
+/// Where a token came from.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Source {
+    Flag,
+    Env(&'static str),
+    GhCli,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Flag => write!(f, "--gh-token"),
+            Source::Env(variable) => write!(f, "${variable}"),
+            Source::GhCli => write!(f, "`gh auth token`"),
+        }
+    }
+}
+
+/// A GitHub API token, and where it came from.
+#[derive(Clone)]
+pub(crate) struct Token {
+    pub(crate) secret: String,
+    pub(crate) source: Source,
+}
+
+/// Discovers a token for the GitHub instance at `hostname`: the `explicit`
+/// one (from `--gh-token`) if given, and otherwise one from the environment
+/// or the GitHub CLI.
+pub(crate) fn discover(explicit: Option<&str>, hostname: &str) -> Option<Token> {
+    discover_with(
+        explicit,
+        |variable| std::env::var(variable).ok(),
+        || gh_auth_token(hostname),
+    )
+}
+
+/// [`discover`], with the environment and the GitHub CLI abstracted out
+/// (for testing).
+fn discover_with(
+    explicit: Option<&str>,
+    env: impl Fn(&str) -> Option<String>,
+    gh: impl FnOnce() -> Option<String>,
+) -> Option<Token> {
+    let token = |secret: String, source| {
+        let secret = secret.trim().to_string();
+        (!secret.is_empty()).then_some(Token { secret, source })
+    };
+
+    let token = explicit
+        .and_then(|secret| token(secret.into(), Source::Flag))
+        .or_else(|| {
+            TOKEN_VARIABLES
+                .iter()
+                .find_map(|&variable| token(env(variable)?, Source::Env(variable)))
+        })
+        .or_else(|| token(gh()?, Source::GhCli));
+
+    match &token {
+        Some(token) => log::info!("using the GitHub API token from {}", token.source),
+        None => log::info!("no GitHub API token found"),
+    }
+    token
+}
+
+/// Returns the GitHub CLI's token for `hostname`, if it's installed and
+/// logged in there.
+fn gh_auth_token(hostname: &str) -> Option<String> {
+    let output = Command::new("gh")
+        .args(["auth", "token", "--hostname", hostname])
+        .output()
+        .inspect_err(|e| log::debug!("couldn't run `gh auth token`: {e}"))
+        .ok()?;
+
+    if !output.status.success() {
+        log::debug!("`gh auth token` failed: {}", output.status);
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Warns about each of the `audits` (with the OAuth scopes that it needs)
+/// that the token from `source` lacks scopes for, since such audits quietly
+/// see less (e.g. no runners) rather than failing.
+///
+/// Only classic personal access tokens have scopes; the permissions of other
+/// kinds of tokens can't be checked up front.
+pub(crate) fn check_scopes(client: &Client, source: &Source, audits: &[(&str, &[&str])]) {
+    let scopes = match client.token_scopes() {
+        Ok(Some(scopes)) => scopes,
+        Ok(None) => return,
+        Err(e) => {
+            log::debug!("couldn't check the GitHub API token's scopes: {e}");
+            return;
+        }
+    };
+
+    for (audit, needed) in audits {
+        let missing = needed
+            .iter()
+            .filter(|scope| !scopes.iter().any(|s| s == **scope))
+            .copied()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            log::warn!(
+                "{audit} may be incomplete: the GitHub API token from {source} lacks the \
+                 {missing} scope(s)",
+                missing = missing.join(", ")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{discover_with, Source};
+
+    #[test]
+    fn test_discover() {
+        let env = |vars: &'static [(&str, &str)]| {
+            move |variable: &str| {
+                vars.iter()
+                    .find(|(name, _)| *name == variable)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let gh = || Some("gho_cli\n".to_string());
+
+        for (explicit, vars, expected) in [
+            (Some("flag"), &[("GH_TOKEN", "env")][..], Some(Source::Flag)),
+            (
+                None,
+                &[("GH_TOKEN", "env")][..],
+                Some(Source::Env("GH_TOKEN")),
+            ),
+            (
+                None,
+                &[("GITHUB_TOKEN", "env"), ("GH_TOKEN", "")][..],
+                Some(Source::Env("GITHUB_TOKEN")),
+            ),
+            (Some(""), &[][..], Some(Source::GhCli)),
+        ] {
+            let token = discover_with(explicit, env(vars), gh);
+            assert_eq!(token.map(|token| token.source), expected);
+        }
+
+        let token = discover_with(None, env(&[]), gh).unwrap();
+        assert_eq!(token.secret, "gho_cli");
+        assert!(discover_with(None, env(&[]), || None).is_none());
+    }
+}
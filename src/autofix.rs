@@ -0,0 +1,330 @@
+//! `--fix`, which applies the fixes attached to findings to their inputs.
+//!
+//! Fixes are concretized into textual edits of their inputs' source when
+//! their findings are built (see [`crate::finding::fix`]), so applying them
+//! only changes the nodes that they fix: comments, key ordering, and quoting
+//! everywhere else are left as they were.
+//!
+//! With `--dry-run` or `--patch-out`, the fixes are rendered as a unified
+//! diff (which `git apply` accepts) instead of being written.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+
+use crate::{
+    finding::{
+        fix::{self, Edit},
+        Finding,
+    },
+    registry::WorkflowRegistry,
+};
+
+/// The number of unchanged lines to show around each change in a diff.
+const CONTEXT_LINES: usize = 3;
+
+/// The edits to make to each input, by input name.
+type Plan<'a> = BTreeMap<&'a str, Vec<&'a Edit>>;
+
+/// Adds a finding's `edits` (each with the name of the input it edits) to
+/// `plan`, unless any of them overlaps an edit that's already planned.
+/// Returns whether they were added.
+///
+/// A finding's edits are all planned or none are, so that a finding is
+/// never half-fixed.
+fn accept<'a>(plan: &mut Plan<'a>, edits: &[(&'a str, &'a Edit)]) -> bool {
+    // Insertions at the same offset also "overlap," since the order they'd
+    // be applied in is arbitrary.
+    let overlaps = |a: &Edit, b: &Edit| {
+        let (a, b) = (&a.location, &b.location);
+        a.start_offset == b.start_offset
+            || (a.start_offset < b.end_offset && b.start_offset < a.end_offset)
+    };
+
+    let conflict = edits.iter().enumerate().any(|(i, (name, edit))| {
+        plan.get(name)
+            .into_iter()
+            .flatten()
+            .chain(edits[..i].iter().filter(|(n, _)| n == name).map(|(_, e)| e))
+            .any(|planned| overlaps(planned, edit))
+    });
+    if conflict {
+        return false;
+    }
+
+    for &(name, edit) in edits {
+        plan.entry(name).or_default().push(edit);
+    }
+    true
+}
+
+/// Applies the fixes of the (unsuppressed) `findings` to their inputs in
+/// `registry`, rewriting them on disk or, given a `patch`, appending a diff
+/// of each input to it instead. Returns the number of findings that were
+/// fixed.
+///
+/// When two findings' fixes overlap (e.g. because two audits fix the same
+/// node), only the first finding is fixed; the other is left for another
+/// run, against the fixed input.
+pub(crate) fn apply(
+    registry: &WorkflowRegistry,
+    findings: &[Finding<'_>],
+    mut patch: Option<&mut String>,
+) -> Result<usize> {
+    let mut plan = Plan::new();
+    let mut fixed = 0;
+
+    for finding in findings
+        .iter()
+        .filter(|f| f.suppression.is_none() && !f.fixes.is_empty())
+    {
+        let edits = finding
+            .fixes
+            .iter()
+            .map(|fix| (fix.fix.location.name, &fix.edit))
+            .collect::<Vec<_>>();
+
+        if accept(&mut plan, &edits) {
+            fixed += 1;
+        } else {
+            log::warn!(
+                "not fixing {ident}: its fix overlaps another finding's",
+                ident = finding.ident
+            );
+        }
+    }
+
+    for (name, edits) in plan {
+        let input = registry.get_input(name);
+        if let Some(patch) = &mut patch {
+            let path = registry.get_relative_path(name);
+            patch.push_str(&diff(path, input.document().source(), &edits));
+            continue;
+        }
+
+        let patched = fix::apply(input.document().source(), &edits)?;
+
+        std::fs::write(input.path(), patched)
+            .with_context(|| format!("couldn't write {path}", path = input.path()))?;
+        log::info!(
+            "applied {n} fixes to {path}",
+            n = edits.len(),
+            path = input.path()
+        );
+    }
+
+    Ok(fixed)
+}
+
+/// Renders `edits` of `source` (the input at `path`) as a unified diff.
+/// Edits must not overlap.
+fn diff(path: &str, source: &str, edits: &[&Edit]) -> String {
+    let line_start = |offset: usize| source[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+    let line_end = |offset: usize| {
+        source[offset..]
+            .find('\n')
+            .map_or(source.len(), |idx| offset + idx + 1)
+    };
+
+    let mut edits = edits.to_vec();
+    edits.sort_by_key(|edit| edit.location.start_offset);
+
+    // Each change replaces a run of whole lines; edits that touch the
+    // same lines are part of the same change.
+    let mut runs: Vec<(usize, usize, Vec<&Edit>)> = vec![];
+    for edit in edits {
+        let (start, end) = (edit.location.start_offset, edit.location.end_offset);
+        let start = line_start(start);
+        let end = if line_start(end) == end {
+            end
+        } else {
+            line_end(end)
+        };
+
+        match runs.last_mut() {
+            Some((_, run_end, run)) if start < *run_end => {
+                *run_end = (*run_end).max(end);
+                run.push(edit);
+            }
+            _ => runs.push((start, end, vec![edit])),
+        }
+    }
+
+    // Each change is its first line (counting from 0), the lines that it
+    // removes, and the lines that it adds.
+    let changes = runs
+        .into_iter()
+        .map(|(start, end, run)| {
+            let mut text = String::new();
+            let mut offset = start;
+            for edit in run {
+                text.push_str(&source[offset..edit.location.start_offset]);
+                text.push_str(&edit.replacement);
+                offset = edit.location.end_offset;
+            }
+            text.push_str(&source[offset..end]);
+
+            let first = source[..start].matches('\n').count();
+            let removed = source[start..end].split_inclusive('\n').count();
+            (first, removed, text)
+        })
+        .collect::<Vec<_>>();
+
+    let lines = source.split_inclusive('\n').collect::<Vec<_>>();
+    let push_line = |out: &mut String, prefix: char, line: &str| {
+        out.push(prefix);
+        out.push_str(line);
+        if !line.ends_with('\n') {
+            out.push_str("\n\\ No newline at end of file\n");
+        }
+    };
+
+    let mut out = format!("diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n");
+    // The difference in line counts so far, between the old and new input.
+    let mut delta = 0isize;
+    let mut changes = changes.as_slice();
+    while !changes.is_empty() {
+        // Changes whose context would overlap share a hunk.
+        let mut n = 1;
+        while n < changes.len()
+            && changes[n].0 <= changes[n - 1].0 + changes[n - 1].1 + 2 * CONTEXT_LINES
+        {
+            n += 1;
+        }
+        let (hunk, rest) = changes.split_at(n);
+        changes = rest;
+
+        let (last, last_removed, _) = &hunk[n - 1];
+        let start = hunk[0].0.saturating_sub(CONTEXT_LINES);
+        let end = (last + last_removed + CONTEXT_LINES).min(lines.len());
+
+        let mut body = String::new();
+        let mut line = start;
+        let mut added = 0;
+        for (first, removed, text) in hunk {
+            for context in &lines[line..*first] {
+                push_line(&mut body, ' ', context);
+            }
+            for old in &lines[*first..first + removed] {
+                push_line(&mut body, '-', old);
+            }
+            for new in text.split_inclusive('\n') {
+                push_line(&mut body, '+', new);
+                added += 1;
+            }
+            line = first + removed;
+        }
+        for context in &lines[line..end] {
+            push_line(&mut body, ' ', context);
+        }
+
+        let old_count = end - start;
+        let removed = hunk.iter().map(|(_, removed, _)| removed).sum::<usize>();
+        let new_count = old_count - removed + added;
+        // An empty range is numbered by the line before it.
+        let number = |start: usize, count: usize| if count == 0 { start } else { start + 1 };
+        let new_start = start.checked_add_signed(delta).unwrap_or(0);
+        out.push_str(&format!(
+            "@@ -{old},{old_count} +{new},{new_count} @@\n",
+            old = number(start, old_count),
+            new = number(new_start, new_count),
+        ));
+        out.push_str(&body);
+
+        delta += new_count as isize - old_count as isize;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{accept, diff, Plan};
+    use crate::finding::{fix::Edit, ConcreteLocation};
+
+    #[test]
+    fn test_accept() {
+        let source = "on: push\njobs: {}\n";
+        let edit = |start, end| Edit {
+            location: ConcreteLocation::from_offsets(source, start, end),
+            replacement: "x".into(),
+        };
+        let (push, jobs, insertion) = (edit(4, 8), edit(15, 17), edit(18, 18));
+        let (push_again, overlapping) = (edit(4, 8), edit(6, 16));
+
+        let mut plan = Plan::new();
+        assert!(accept(
+            &mut plan,
+            &[("ci.yml", &push), ("ci.yml", &insertion)]
+        ));
+        // The same node in another input doesn't overlap.
+        assert!(accept(&mut plan, &[("other.yml", &push_again)]));
+
+        // A finding whose edits overlap is rejected as a whole.
+        assert!(!accept(
+            &mut plan,
+            &[("ci.yml", &jobs), ("ci.yml", &overlapping)]
+        ));
+        assert!(!accept(&mut plan, &[("ci.yml", &insertion)]));
+        assert!(!accept(&mut plan, &[("ci.yml", &jobs), ("ci.yml", &jobs)]));
+        assert_eq!(plan["ci.yml"].len(), 2);
+
+        assert!(accept(&mut plan, &[("ci.yml", &jobs)]));
+        assert_eq!(plan["ci.yml"].len(), 3);
+    }
+
+    #[test]
+    fn test_diff() {
+        let source = "on: push\n\
+                      permissions: write-all\n\
+                      jobs:\n\
+                      \x20 build:\n\
+                      \x20   runs-on: ubuntu-latest\n\
+                      \x20   steps:\n\
+                      \x20     - uses: actions/checkout@v4\n";
+        let edit = |start, end, replacement: &str| Edit {
+            location: ConcreteLocation::from_offsets(source, start, end),
+            replacement: replacement.into(),
+        };
+
+        let start = source.find("write-all").unwrap();
+        let permissions = edit(start, start + "write-all".len(), "{ contents: read }");
+        let with = edit(
+            source.len(),
+            source.len(),
+            "        with:\n          persist-credentials: false\n",
+        );
+
+        assert_eq!(
+            diff("ci.yml", source, &[&with, &permissions]),
+            "diff --git a/ci.yml b/ci.yml\n\
+             --- a/ci.yml\n\
+             +++ b/ci.yml\n\
+             @@ -1,7 +1,9 @@\n\
+             \x20on: push\n\
+             -permissions: write-all\n\
+             +permissions: { contents: read }\n\
+             \x20jobs:\n\
+             \x20  build:\n\
+             \x20    runs-on: ubuntu-latest\n\
+             \x20    steps:\n\
+             \x20      - uses: actions/checkout@v4\n\
+             +        with:\n\
+             +          persist-credentials: false\n"
+        );
+
+        // Distant changes get their own hunks.
+        let source = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let edit = |start: usize, replacement: &str| Edit {
+            location: ConcreteLocation::from_offsets(source, start, start + 1),
+            replacement: replacement.into(),
+        };
+        let (first, last) = (edit(0, "x"), edit(18, "y\nz"));
+        assert_eq!(
+            diff("f", source, &[&first, &last]),
+            "diff --git a/f b/f\n--- a/f\n+++ b/f\n\
+             @@ -1,4 +1,4 @@\n-a\n+x\n b\n c\n d\n\
+             @@ -7,4 +7,5 @@\n g\n h\n i\n-j\n+y\n+z\n"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! A minimal on-disk cache, for remote data that never changes once
+//! fetched (e.g. a file's contents at a specific commit), or that
+//! changes rarely enough to be reused for a while (e.g. a repository's
+//! metadata).
+//!
+//! The cache lives under `$ZIZMOR_CACHE_DIR` if set, and otherwise
+//! under `$XDG_CACHE_HOME/zizmor` or `~/.cache/zizmor`. Caching is
+//! best-effort: failures to read or write the cache are logged and
+//! otherwise ignored.
+//!
+//! `zizmor cache clear` removes everything in it; see [`clear`].
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+
+/// Every namespace that's cached in; see [`DiskCache::new`].
+const NAMESPACES: &[&str] = &[
+    "action-metadata",
+    "api-data",
+    "api-responses",
+    "repository-metadata",
+    "results",
+];
+
+/// Returns the cache directory, or `None` if there isn't one (i.e. no
+/// `$HOME` to put it in).
+pub(crate) fn dir() -> Option<PathBuf> {
+    std::env::var_os("ZIZMOR_CACHE_DIR")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("XDG_CACHE_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+                .map(|cache| cache.join("zizmor"))
+        })
+}
+
+/// Removes everything that's cached, returning the cache directory that it
+/// was removed from (if there is one).
+pub(crate) fn clear() -> Result<Option<PathBuf>> {
+    let Some(dir) = dir() else {
+        return Ok(None);
+    };
+
+    clear_in(&dir)?;
+    Ok(Some(dir))
+}
+
+/// Removes each namespace in `dir`. Anything else is left alone, in case
+/// `$ZIZMOR_CACHE_DIR` points at a directory that's shared with other
+/// tools.
+fn clear_in(dir: &Path) -> Result<()> {
+    for namespace in NAMESPACES {
+        let path = dir.join(namespace);
+        match fs::remove_dir_all(&path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("couldn't remove {path:?}"))
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub(crate) struct DiskCache {
+    root: Option<PathBuf>,
+}
+
+impl DiskCache {
+    /// Creates a cache for the given namespace (e.g. `action-metadata`),
+    /// within the default cache directory.
+    pub(crate) fn new(namespace: &str) -> Self {
+        debug_assert!(
+            NAMESPACES.contains(&namespace),
+            "unknown namespace: {namespace}"
+        );
+
+        let base = dir();
+
+        if base.is_none() {
+            log::debug!("no cache directory available; disabling on-disk cache");
+        }
+
+        Self {
+            root: base.map(|base| base.join(namespace)),
+        }
+    }
+
+    /// Returns the path for `key`, which is a relative `/`-separated path.
+    ///
+    /// Returns `None` if there's no cache directory, or if `key` tries to
+    /// escape it.
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        let root = self.root.as_ref()?;
+        let key = Path::new(key);
+
+        if !key
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            log::warn!("refusing to use suspicious cache key: {key:?}");
+            return None;
+        }
+
+        Some(root.join(key))
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<String> {
+        let path = self.path(key)?;
+        fs::read_to_string(path).ok()
+    }
+
+    /// Like [`DiskCache::get`], but ignores entries older than `max_age`.
+    pub(crate) fn get_fresh(&self, key: &str, max_age: Duration) -> Option<String> {
+        let path = self.path(key)?;
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+
+        if age > max_age {
+            return None;
+        }
+
+        fs::read_to_string(path).ok()
+    }
+
+    pub(crate) fn put(&self, key: &str, value: &str) {
+        let Some(path) = self.path(key) else {
+            return;
+        };
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, value));
+
+        if let Err(e) = result {
+            log::debug!("couldn't write cache entry {path:?}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{clear_in, DiskCache};
+
+    #[test]
+    fn test_disk_cache() {
+        let root = std::env::temp_dir().join(format!("zizmor-cache-test-{}", std::process::id()));
+        let cache = DiskCache {
+            root: Some(root.clone()),
+        };
+
+        assert_eq!(cache.get("a/b/c"), None);
+        cache.put("a/b/c", "contents");
+        assert_eq!(cache.get("a/b/c").as_deref(), Some("contents"));
+
+        // Fresh entries are returned, stale ones aren't.
+        assert_eq!(
+            cache
+                .get_fresh("a/b/c", Duration::from_secs(3600))
+                .as_deref(),
+            Some("contents")
+        );
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(cache.get_fresh("a/b/c", Duration::from_millis(10)), None);
+
+        // Keys can't escape the cache directory.
+        cache.put("../escaped", "contents");
+        assert_eq!(cache.get("../escaped"), None);
+        assert!(!root.parent().unwrap().join("escaped").exists());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_clear() {
+        let dir = std::env::temp_dir().join(format!("zizmor-clear-test-{}", std::process::id()));
+        let cache = DiskCache {
+            root: Some(dir.join("results")),
+        };
+        cache.put("a/b", "contents");
+        std::fs::write(dir.join("unrelated"), "contents").unwrap();
+
+        clear_in(&dir).unwrap();
+        assert_eq!(cache.get("a/b"), None);
+        assert!(!dir.join("results").exists());
+        // Only our own namespaces are removed.
+        assert!(dir.join("unrelated").exists());
+        // Clearing an already-empty cache is fine.
+        clear_in(&dir).unwrap();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
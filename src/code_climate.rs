@@ -0,0 +1,102 @@
+//! Code Climate issue output (`--format code-climate`), which GitLab
+//! displays as Code Quality findings in merge requests.
+//!
+//! See: <https://github.com/codeclimate/platform/blob/master/spec/analyzers/SPEC.md#issues>
+//! and <https://docs.gitlab.com/ee/ci/testing/code_quality.html#code-quality-report-format>
+
+use serde::Serialize;
+
+use crate::{
+    finding::{Finding, Location, Severity},
+    registry::WorkflowRegistry,
+    rules,
+};
+
+/// A single issue, for a single finding.
+#[derive(Serialize)]
+pub(crate) struct Issue {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    check_name: &'static str,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<Content>,
+    categories: [&'static str; 1],
+    location: IssueLocation,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    other_locations: Vec<IssueLocation>,
+    severity: &'static str,
+    fingerprint: String,
+}
+
+#[derive(Serialize)]
+struct Content {
+    body: &'static str,
+}
+
+#[derive(Serialize)]
+struct IssueLocation {
+    path: String,
+    lines: Lines,
+}
+
+#[derive(Serialize)]
+struct Lines {
+    begin: usize,
+    end: usize,
+}
+
+/// Returns the Code Climate severity for `severity`.
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Informational => "info",
+        Severity::Low => "minor",
+        Severity::Medium => "major",
+        Severity::High => "critical",
+        Severity::Critical => "blocker",
+    }
+}
+
+fn build_location(registry: &WorkflowRegistry, location: &Location) -> IssueLocation {
+    let span = &location.concrete.location;
+    IssueLocation {
+        path: registry.get_relative_path(location.symbolic.name).into(),
+        lines: Lines {
+            begin: span.start_point.row + 1,
+            end: span.end_point.row + 1,
+        },
+    }
+}
+
+/// Builds an issue for each of `findings`.
+///
+/// Code Climate has no notion of suppression, so suppressed findings (with
+/// `--show-suppressed`) are left out.
+pub(crate) fn build_issues(registry: &WorkflowRegistry, findings: &[Finding]) -> Vec<Issue> {
+    findings
+        .iter()
+        .filter(|finding| finding.suppression.is_none())
+        .filter_map(|finding| {
+            let (primary, others) = finding.locations.split_first()?;
+            Some(Issue {
+                kind: "issue",
+                check_name: finding.ident,
+                description: format!(
+                    "{desc}: {annotation}",
+                    desc = finding.desc,
+                    annotation = primary.symbolic.annotation
+                ),
+                content: rules::remediation(finding.ident).map(|body| Content { body }),
+                // Every audit is about security.
+                categories: ["Security"],
+                location: build_location(registry, primary),
+                other_locations: others
+                    .iter()
+                    .map(|location| build_location(registry, location))
+                    .collect(),
+                severity: severity(finding.determinations.severity),
+                fingerprint: finding.fingerprint.clone(),
+            })
+        })
+        .collect()
+}
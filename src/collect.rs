@@ -0,0 +1,490 @@
+//! Collecting inputs from the files, directories, and glob patterns that
+//! `zizmor` is given.
+//!
+//! Directories are searched recursively for workflows (in
+//! `.github/workflows`), action definitions, and Dependabot configurations,
+//! skipping anything that a `.gitignore` or `.zizmorignore` ignores.
+//!
+//! Inputs are grouped by the repository that they're in, i.e. the nearest
+//! directory with a `.github` directory, so that nested checkouts (e.g.
+//! of submodules, or of several repositories side by side) are audited
+//! separately, with their inputs named relative to their own roots.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::OsStr,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+
+use crate::{config::glob, registry};
+
+/// The gitignore-style files that are honored in each directory. Rules in
+/// later files take precedence.
+const IGNORE_FILES: &[&str] = &[".gitignore", ".zizmorignore"];
+
+/// An input that's been collected, but not yet loaded.
+pub(crate) enum Pending {
+    Workflow(PathBuf),
+    /// An action definition, with its name.
+    Action(PathBuf, String),
+    /// A Dependabot configuration, with its name.
+    Dependabot(PathBuf, String),
+    /// An input read from stdin, with the path that it's labeled with
+    /// (which determines its kind, as for any other path).
+    Stdin(PathBuf, String),
+}
+
+impl Pending {
+    /// Returns this input's path on disk (or, for stdin, its label).
+    pub(crate) fn path(&self) -> &Path {
+        match self {
+            Pending::Workflow(path)
+            | Pending::Action(path, _)
+            | Pending::Dependabot(path, _)
+            | Pending::Stdin(path, _) => path,
+        }
+    }
+
+    /// Returns this input's path, relative to the repository root.
+    pub(crate) fn relative_path(&self) -> &str {
+        match self {
+            Pending::Stdin(path, _) if is_action_definition(path) || is_dependabot_config(path) => {
+                path.to_str().unwrap_or_default()
+            }
+            // NOTE: Workflow paths are checked for UTF-8 when registered.
+            Pending::Workflow(path) | Pending::Stdin(path, _) => {
+                registry::workflow_relative_path(path.to_str().unwrap_or_default())
+            }
+            Pending::Action(_, name) | Pending::Dependabot(_, name) => name,
+        }
+    }
+}
+
+/// The inputs collected from a single repository: workflows, then actions,
+/// then Dependabot configurations.
+pub(crate) struct Repository {
+    pub(crate) root: PathBuf,
+    pub(crate) inputs: Vec<Pending>,
+    /// The `owner/repo` slug of the GitHub repository that this was
+    /// fetched from, if it wasn't audited in place. See [`crate::remote`].
+    pub(crate) remote: Option<String>,
+}
+
+/// Returns whether the given path looks like an action definition.
+pub(crate) fn is_action_definition(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == "action.yml" || name == "action.yaml")
+}
+
+/// Returns whether the given path looks like a Dependabot configuration.
+pub(crate) fn is_dependabot_config(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == "dependabot.yml" || name == "dependabot.yaml")
+}
+
+/// Returns whether the given path has a YAML extension.
+fn is_yaml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "yml" || ext == "yaml")
+}
+
+/// Returns whether the given path is one that we collect from directories.
+pub(crate) fn is_discoverable(path: &Path) -> bool {
+    let parent = path.parent().unwrap_or(Path::new(""));
+
+    is_action_definition(path)
+        || (is_dependabot_config(path) && parent.ends_with(".github"))
+        || (is_yaml(path) && parent.ends_with(".github/workflows"))
+}
+
+/// Returns the root of the repository that the file at `path` is in: the
+/// parent of the `.github` directory that it's in, or the nearest directory
+/// with a `.github` directory. Directories above `base` are only considered
+/// if `base` is itself within a `.github` directory.
+fn repository_root(path: &Path, base: &Path) -> PathBuf {
+    let within_github = base
+        .components()
+        .any(|component| component.as_os_str() == ".github");
+
+    for ancestor in path.ancestors().skip(1) {
+        if ancestor.file_name() == Some(OsStr::new(".github")) {
+            if let Some(parent) = ancestor.parent() {
+                return parent.to_path_buf();
+            }
+        }
+        if ancestor.join(".github").is_dir() || (ancestor == base && !within_github) {
+            return ancestor.to_path_buf();
+        }
+    }
+
+    base.to_path_buf()
+}
+
+/// Gitignore-style rules from the ignore files of the directories that are
+/// being walked, each with the directory it's relative to, and whether it's
+/// negated (i.e. re-includes paths).
+#[derive(Default)]
+struct Ignores(Vec<(PathBuf, Regex, bool)>);
+
+impl Ignores {
+    /// Loads the rules from `dir`'s ignore files, returning how many
+    /// were loaded.
+    fn load(&mut self, dir: &Path) -> Result<usize> {
+        let before = self.0.len();
+
+        for name in IGNORE_FILES {
+            let path = dir.join(name);
+            if !path.is_file() {
+                continue;
+            }
+
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("couldn't read {path:?}"))?;
+            for line in contents.lines().map(str::trim_end) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let (pattern, negated) = match line.strip_prefix('!') {
+                    Some(pattern) => (pattern, true),
+                    None => (line.strip_prefix('\\').unwrap_or(line), false),
+                };
+                let pattern = glob(pattern).with_context(|| format!("invalid rule in {path:?}"))?;
+                self.0.push((dir.to_path_buf(), pattern, negated));
+            }
+        }
+
+        Ok(self.0.len() - before)
+    }
+
+    /// Returns whether `path` is ignored, i.e. whether the last rule that
+    /// matches it isn't negated.
+    fn is_ignored(&self, path: &Path) -> bool {
+        self.0
+            .iter()
+            .rev()
+            .find_map(|(dir, pattern, negated)| {
+                let relative = path.strip_prefix(dir).ok()?.to_str()?;
+                pattern.is_match(relative).then_some(!negated)
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// Walks `dir` recursively, skipping `.git` directories and anything that's
+/// ignored, and calls `visit` on each file and directory. Directories are
+/// only descended into if `visit` returns `true` for them.
+///
+/// Symbolic links to directories aren't followed, to avoid cycles.
+fn walk(
+    dir: &Path,
+    ignores: &mut Ignores,
+    visit: &mut impl FnMut(&Path, bool) -> Result<bool>,
+) -> Result<()> {
+    let loaded = ignores.load(dir)?;
+
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("couldn't read {dir:?}"))?
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_name() == ".git" || ignores.is_ignored(&path) {
+            continue;
+        }
+
+        if entry.file_type()?.is_dir() {
+            if visit(&path, true)? {
+                walk(&path, ignores, visit)?;
+            }
+        } else if path.is_file() {
+            visit(&path, false)?;
+        }
+    }
+
+    ignores.0.truncate(ignores.0.len() - loaded);
+    Ok(())
+}
+
+/// Returns whether `input` is a glob pattern, rather than a path.
+fn is_pattern(input: &str) -> bool {
+    input.contains(['*', '?'])
+}
+
+/// Returns the files and directories that match the glob pattern `pattern`
+/// (as in the configuration file's `exclude`, but relative to the current
+/// directory), skipping anything that's ignored.
+fn expand(pattern: &str) -> Result<Vec<PathBuf>> {
+    // Only the part of the pattern after its last literal directory is
+    // matched, against paths relative to that directory.
+    let wildcard = pattern.find(['*', '?']).unwrap_or(pattern.len());
+    let (base, rest) = match pattern[..wildcard].rfind('/') {
+        Some(idx) => (Path::new(&pattern[..=idx]), &pattern[idx + 1..]),
+        None => (Path::new("."), pattern),
+    };
+    let regex = glob(&format!("/{rest}"))?;
+
+    let mut matches = vec![];
+    if base.is_dir() {
+        walk(base, &mut Ignores::default(), &mut |path, _| {
+            let relative = path.strip_prefix(base)?.to_str().unwrap_or_default();
+            if regex.is_match(relative) {
+                matches.push(path.to_path_buf());
+                Ok(false)
+            } else {
+                Ok(true)
+            }
+        })?;
+    }
+
+    Ok(matches)
+}
+
+/// The inputs collected so far, by repository root.
+#[derive(Default)]
+struct Collector(BTreeMap<PathBuf, Collected>);
+
+#[derive(Default)]
+struct Collected {
+    workflows: BTreeSet<PathBuf>,
+    actions: BTreeMap<String, PathBuf>,
+    dependabots: BTreeMap<String, PathBuf>,
+}
+
+impl Collector {
+    /// Collects the file at `path`, which must be canonical, within the
+    /// directory `base`. Files that aren't action definitions or Dependabot
+    /// configurations are collected as workflows.
+    fn add_file(&mut self, path: &Path, base: &Path) -> Result<()> {
+        let root = repository_root(path, base);
+        let name = path
+            .strip_prefix(&root)?
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid input: {path:?} is not UTF-8"))?
+            .to_string();
+
+        let collected = self.0.entry(root).or_default();
+        if is_action_definition(path) {
+            collected.actions.insert(name, path.to_path_buf());
+        } else if is_dependabot_config(path) {
+            collected.dependabots.insert(name, path.to_path_buf());
+        } else {
+            collected.workflows.insert(path.to_path_buf());
+        }
+
+        Ok(())
+    }
+
+    /// Collects every input within the directory at `dir`, which must be
+    /// canonical.
+    fn add_dir(&mut self, dir: &Path) -> Result<()> {
+        log::debug!("collecting inputs from {dir:?}");
+
+        let mut found = vec![];
+        walk(dir, &mut Ignores::default(), &mut |path, is_dir| {
+            if !is_dir && is_discoverable(path) {
+                found.push(path.to_path_buf());
+            }
+            Ok(true)
+        })?;
+
+        // A directory without any of the usual layout is treated as
+        // a directory of workflows.
+        if found.is_empty() {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_file() && is_yaml(&path) {
+                    found.push(path);
+                }
+            }
+        }
+
+        if found.is_empty() {
+            log::warn!("no inputs collected from {dir:?}");
+        }
+        for path in found {
+            self.add_file(&path, dir)?;
+        }
+
+        Ok(())
+    }
+
+    fn add(&mut self, path: &Path) -> Result<()> {
+        let path = std::fs::canonicalize(path)?;
+        if path.is_dir() {
+            self.add_dir(&path)
+        } else {
+            // NOTE: Unwrap is safe, since canonical paths to files
+            // always have a parent.
+            self.add_file(&path, path.parent().unwrap())
+        }
+    }
+}
+
+/// Collects the inputs from each of `inputs`, i.e. workflow files, action
+/// definitions, Dependabot configurations, directories, or glob patterns
+/// matching any of them, and groups them by repository.
+pub(crate) fn collect(inputs: &[PathBuf]) -> Result<Vec<Repository>> {
+    let mut collector = Collector::default();
+
+    for input in inputs {
+        if input == Path::new("-") {
+            return Err(anyhow!(
+                "stdin (-) can't be audited along with other inputs"
+            ));
+        }
+
+        if input.exists() {
+            collector.add(input)?;
+            continue;
+        }
+
+        let pattern = input.to_str().unwrap_or_default();
+        if !is_pattern(pattern) {
+            return Err(anyhow!(
+                "{input:?} isn't a workflow file, action definition, \
+                 Dependabot configuration, directory, or glob pattern"
+            ));
+        }
+
+        let matches = expand(pattern)?;
+        if matches.is_empty() {
+            return Err(anyhow!("no files or directories match {pattern}"));
+        }
+        for path in matches {
+            collector.add(&path)?;
+        }
+    }
+
+    let repositories = collector
+        .0
+        .into_iter()
+        .map(|(root, collected)| {
+            let mut workflows = collected.workflows.into_iter().collect::<Vec<_>>();
+            workflows.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+
+            let inputs = workflows
+                .into_iter()
+                .map(Pending::Workflow)
+                .chain(
+                    collected
+                        .actions
+                        .into_iter()
+                        .map(|(name, path)| Pending::Action(path, name)),
+                )
+                .chain(
+                    collected
+                        .dependabots
+                        .into_iter()
+                        .map(|(name, path)| Pending::Dependabot(path, name)),
+                )
+                .collect();
+
+            Repository {
+                root,
+                inputs,
+                remote: None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if repositories.is_empty() {
+        return Err(anyhow!(
+            "no workflow, action, or Dependabot files collected; empty or wrong directory?"
+        ));
+    }
+
+    Ok(repositories)
+}
+
+/// Reads a single input from stdin, labeled with the path `label`.
+pub(crate) fn stdin(label: &Path) -> Result<Repository> {
+    let mut contents = String::new();
+    std::io::stdin()
+        .read_to_string(&mut contents)
+        .context("couldn't read from stdin")?;
+
+    Ok(Repository {
+        root: PathBuf::from("."),
+        inputs: vec![Pending::Stdin(label.to_path_buf(), contents)],
+        remote: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{collect, Pending, Repository};
+
+    fn names(repository: &Repository) -> Vec<&str> {
+        repository
+            .inputs
+            .iter()
+            .map(Pending::relative_path)
+            .collect()
+    }
+
+    #[test]
+    fn test_collect() {
+        let root = std::env::temp_dir().join(format!("zizmor-collect-test-{}", std::process::id()));
+        let write = |path: &str, contents: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+
+        write(".github/workflows/ci.yml", "");
+        write(".github/workflows/README.md", "");
+        write(".github/dependabot.yml", "");
+        write(".github/actions/setup/action.yml", "");
+        write("action.yml", "");
+        write("tools/lint/action.yaml", "");
+        write("node_modules/example/action.yml", "");
+        write("vendor/ignored/action.yml", "");
+        write("vendor/kept/action.yml", "");
+        write(".gitignore", "node_modules/\n");
+        write(".zizmorignore", "# comment\nvendor/*\n!vendor/kept\n");
+        write("checkouts/other/.github/workflows/ci.yml", "");
+        write("checkouts/other/action.yml", "");
+
+        let repositories = collect(std::slice::from_ref(&root)).unwrap();
+        assert_eq!(repositories.len(), 2);
+        assert_eq!(
+            names(&repositories[0]),
+            [
+                ".github/workflows/ci.yml",
+                ".github/actions/setup/action.yml",
+                "action.yml",
+                "tools/lint/action.yaml",
+                "vendor/kept/action.yml",
+                ".github/dependabot.yml",
+            ]
+        );
+        assert!(repositories[1].root.ends_with("checkouts/other"));
+        assert_eq!(
+            names(&repositories[1]),
+            [".github/workflows/ci.yml", "action.yml"]
+        );
+
+        // Scanning within a repository still names inputs relative to it.
+        let repositories = collect(&[root.join(".github/workflows")]).unwrap();
+        assert_eq!(names(&repositories[0]), [".github/workflows/ci.yml"]);
+
+        // Patterns are expanded, and their matches collected as usual.
+        let pattern = format!("{}/*/other", root.display());
+        let repositories = collect(&[Path::new(&pattern).into()]).unwrap();
+        assert_eq!(repositories.len(), 1);
+        assert!(repositories[0].root.ends_with("checkouts/other"));
+
+        let pattern = format!("{}/*.yml", root.join("nonexistent").display());
+        assert!(collect(&[Path::new(&pattern).into()]).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,645 @@
+//! `zizmor`'s configuration file.
+//!
+//! The configuration file is a YAML (or, with a `.toml` extension, TOML)
+//! document with per-audit settings under `rules`, keyed by audit identifier:
+//!
+//! ```yaml
+//! rules:
+//!   typosquatting:
+//!     severity: high
+//!     config:
+//!       trusted-actions:
+//!         - example/checkout
+//! ```
+//!
+//! Settings shared by multiple audits live at the top level, e.g.
+//! `trusted-owners` and `vars`, as do settings for `zizmor` itself, e.g.
+//! `exclude`, `offline`, and `online-audits`.
+//!
+//! Unless one is passed explicitly, the configuration file is discovered
+//! from the audited repository; see [`Config::discover`].
+
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::finding::{Confidence, Severity};
+
+/// The places that a repository's configuration file is discovered from,
+/// relative to its root, in order of precedence.
+pub(crate) const DISCOVERABLE: &[&str] = &[
+    ".github/zizmor.yml",
+    ".github/zizmor.yaml",
+    ".github/zizmor.toml",
+    "zizmor.yml",
+    "zizmor.yaml",
+    "zizmor.toml",
+];
+
+/// Action owners that are always trusted, since they're GitHub itself.
+const FIRST_PARTY_OWNERS: &[&str] = &["actions", "github"];
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Config {
+    #[serde(default)]
+    rules: HashMap<String, RuleConfig>,
+    /// Additional action owners (users or organizations) whose actions
+    /// are trusted, beyond GitHub's own.
+    #[serde(default)]
+    trusted_owners: Vec<String>,
+    /// Known values for the repository's configuration variables,
+    /// i.e. the `vars` context.
+    #[serde(default)]
+    vars: HashMap<String, String>,
+    /// Runner labels whose runners are trusted, e.g. ephemeral self-hosted
+    /// runners, or GitHub's larger hosted runners.
+    #[serde(default)]
+    trusted_runners: Vec<String>,
+    /// Inputs that aren't audited at all.
+    #[serde(default)]
+    exclude: Patterns,
+    /// The minimum severity of findings to report, as with `--min-severity`.
+    #[serde(default)]
+    min_severity: Option<Severity>,
+    /// The minimum confidence of findings to report, as with
+    /// `--min-confidence`.
+    #[serde(default)]
+    min_confidence: Option<Confidence>,
+    /// The locations of findings to ignore, by audit.
+    #[serde(default)]
+    ignore: HashMap<String, Vec<Ignore>>,
+    /// Whether to only perform audits that don't require network access,
+    /// as with `--offline`.
+    #[serde(default)]
+    offline: bool,
+    /// The only audits that may use GitHub's API (when online), as with
+    /// `--online-audits`. By default, every audit may.
+    #[serde(default)]
+    online_audits: Option<Vec<String>>,
+    /// The hostname of the GitHub instance to use, as with `--gh-hostname`.
+    #[serde(default)]
+    gh_hostname: Option<String>,
+    /// The configuration file's contents.
+    #[serde(skip)]
+    source: String,
+}
+
+/// The settings for a single audit.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    /// Whether the audit is disabled entirely.
+    #[serde(default)]
+    disable: bool,
+    /// The severity of all of the audit's findings, overriding the
+    /// severities that the audit assigns them.
+    #[serde(default)]
+    severity: Option<Severity>,
+    /// The confidence of all of the audit's findings, overriding the
+    /// confidences that the audit assigns them.
+    #[serde(default)]
+    confidence: Option<Confidence>,
+    /// Inputs that the audit doesn't run on.
+    #[serde(default)]
+    exclude: Patterns,
+    /// Audit-specific settings, whose shape is defined by each audit.
+    #[serde(default)]
+    config: Option<serde_yaml::Value>,
+}
+
+/// Glob-style patterns for inputs' paths, relative to the repository root
+/// (e.g. `.github/workflows/vendored-*.yml`).
+///
+/// `*` and `?` match within a single path component, while `**` matches
+/// across components. A pattern that matches a directory matches every
+/// path beneath it.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(try_from = "Vec<String>")]
+struct Patterns(Vec<Regex>);
+
+/// Translates a glob-style pattern (as in [`Patterns`]) into a regex.
+pub(crate) fn glob(pattern: &str) -> Result<Regex> {
+    let pattern = pattern.trim_end_matches('/');
+
+    // Like `.gitignore`, patterns without a `/` match at any depth, and
+    // a leading `/` only anchors the pattern.
+    let mut regex = String::from(if pattern.contains('/') {
+        "^"
+    } else {
+        "^(?:.*/)?"
+    });
+    let mut rest = pattern.strip_prefix('/').unwrap_or(pattern);
+    while let Some(c) = rest.chars().next() {
+        let len = if rest.starts_with("**/") {
+            regex.push_str("(?:.*/)?");
+            3
+        } else if rest.starts_with("**") {
+            regex.push_str(".*");
+            2
+        } else if c == '*' {
+            regex.push_str("[^/]*");
+            1
+        } else if c == '?' {
+            regex.push_str("[^/]");
+            1
+        } else {
+            regex.push_str(&regex::escape(&rest[..c.len_utf8()]));
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    // Patterns that match a directory match everything beneath it.
+    regex.push_str("(?:/.*)?$");
+
+    Regex::new(&regex).with_context(|| format!("invalid pattern: {pattern}"))
+}
+
+impl TryFrom<Vec<String>> for Patterns {
+    type Error = anyhow::Error;
+
+    fn try_from(patterns: Vec<String>) -> Result<Self> {
+        Ok(Self(
+            patterns
+                .iter()
+                .map(|pattern| glob(pattern))
+                .collect::<Result<_>>()?,
+        ))
+    }
+}
+
+/// The location of findings to ignore: a path pattern (as in [`Patterns`]),
+/// optionally followed by `:<line>`, e.g. `deploy.yml:23`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+struct Ignore {
+    path: Regex,
+    line: Option<usize>,
+}
+
+impl TryFrom<String> for Ignore {
+    type Error = anyhow::Error;
+
+    fn try_from(ignore: String) -> Result<Self> {
+        match ignore.rsplit_once(':') {
+            Some((path, line)) if !line.is_empty() && line.bytes().all(|b| b.is_ascii_digit()) => {
+                Ok(Self {
+                    path: glob(path)?,
+                    line: Some(line.parse()?),
+                })
+            }
+            _ => Ok(Self {
+                path: glob(&ignore)?,
+                line: None,
+            }),
+        }
+    }
+}
+
+impl Patterns {
+    fn matches(&self, path: &str) -> bool {
+        self.0.iter().any(|pattern| pattern.is_match(path))
+    }
+}
+
+impl Config {
+    /// Discovers and loads the configuration file for the repository that
+    /// `input` is in, or returns an empty configuration if there is none.
+    ///
+    /// The repository's root is the parent of the `.github` directory that
+    /// `input` is in, or `input` itself if it's a directory outside of one.
+    pub(crate) fn discover(input: &Path) -> Result<Self> {
+        let Some(root) = repo_root(input) else {
+            return Ok(Self::default());
+        };
+
+        match DISCOVERABLE
+            .iter()
+            .map(|candidate| root.join(candidate))
+            .find(|candidate| candidate.is_file())
+        {
+            Some(path) => {
+                log::debug!("discovered config at {path:?}");
+                Self::load(&path)
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Loads the configuration file at `path`, which is parsed as TOML if
+    /// it has a `.toml` extension, and as YAML otherwise.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("couldn't read config from {path:?}"))?;
+
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            Self::from_toml_str(&contents)
+        } else {
+            Self::from_str(&contents)
+        }
+        .with_context(|| format!("invalid config in {path:?}"))
+    }
+
+    fn from_str(contents: &str) -> Result<Self> {
+        // An empty file is a valid (empty) config.
+        if contents.trim().is_empty() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self {
+            source: contents.into(),
+            ..serde_yaml::from_str(contents)?
+        })
+    }
+
+    fn from_toml_str(contents: &str) -> Result<Self> {
+        Ok(Self {
+            source: contents.into(),
+            ..toml::from_str(contents)?
+        })
+    }
+
+    /// Returns the configuration file's contents, or an empty string
+    /// if there's no configuration file.
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns whether actions owned by the given user or organization
+    /// are trusted, i.e. are either first-party or in `trusted-owners`.
+    pub(crate) fn is_trusted_owner(&self, owner: &str) -> bool {
+        FIRST_PARTY_OWNERS
+            .iter()
+            .copied()
+            .chain(self.trusted_owners.iter().map(String::as_str))
+            .any(|trusted| trusted.eq_ignore_ascii_case(owner))
+    }
+
+    /// Returns the configured values of the `vars` context.
+    pub(crate) fn vars(&self) -> &HashMap<String, String> {
+        &self.vars
+    }
+
+    /// Returns whether jobs that run on runners with the given label
+    /// are trusted, i.e. the label is in `trusted-runners`.
+    pub(crate) fn is_trusted_runner(&self, label: &str) -> bool {
+        self.trusted_runners
+            .iter()
+            .any(|trusted| trusted.eq_ignore_ascii_case(label))
+    }
+
+    /// Returns whether `offline` is set.
+    pub(crate) fn offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Returns the audits in `online-audits`, if it's set.
+    pub(crate) fn online_audits(&self) -> Option<&[String]> {
+        self.online_audits.as_deref()
+    }
+
+    /// Returns the configured GitHub hostname, if any.
+    pub(crate) fn gh_hostname(&self) -> Option<&str> {
+        self.gh_hostname.as_deref()
+    }
+
+    /// Returns the configured minimum severity of findings to report, if any.
+    pub(crate) fn min_severity(&self) -> Option<Severity> {
+        self.min_severity
+    }
+
+    /// Returns the configured minimum confidence of findings to report,
+    /// if any.
+    pub(crate) fn min_confidence(&self) -> Option<Confidence> {
+        self.min_confidence
+    }
+
+    /// Returns whether the input at the given path (relative to the
+    /// repository root) is excluded from every audit.
+    pub(crate) fn is_excluded(&self, path: &str) -> bool {
+        self.exclude.matches(path)
+    }
+
+    /// Returns whether the input at the given path (relative to the
+    /// repository root) is excluded from the audit with the given
+    /// identifier, either specifically or because it's excluded entirely.
+    pub(crate) fn is_excluded_from(&self, ident: &str, path: &str) -> bool {
+        self.is_excluded(path)
+            || self
+                .rules
+                .get(ident)
+                .is_some_and(|rule| rule.exclude.matches(path))
+    }
+
+    /// Returns whether findings of the audit with the given identifier are
+    /// ignored at the given path (relative to the repository root), on
+    /// any of the given (1-based) lines.
+    pub(crate) fn is_ignored(&self, ident: &str, path: &str, lines: RangeInclusive<usize>) -> bool {
+        self.ignore.get(ident).is_some_and(|ignores| {
+            ignores.iter().any(|ignore| {
+                ignore.path.is_match(path) && ignore.line.map_or(true, |line| lines.contains(&line))
+            })
+        })
+    }
+
+    /// Returns whether the audit with the given identifier is disabled.
+    pub(crate) fn is_disabled(&self, ident: &str) -> bool {
+        self.rules.get(ident).is_some_and(|rule| rule.disable)
+    }
+
+    /// Returns the configured severity for the audit with the given
+    /// identifier, if its findings' severities are overridden.
+    pub(crate) fn severity(&self, ident: &str) -> Option<Severity> {
+        self.rules.get(ident).and_then(|rule| rule.severity)
+    }
+
+    /// Returns the configured confidence for the audit with the given
+    /// identifier, if its findings' confidences are overridden.
+    pub(crate) fn confidence(&self, ident: &str) -> Option<Confidence> {
+        self.rules.get(ident).and_then(|rule| rule.confidence)
+    }
+
+    /// Returns the audit-specific settings for the audit with the given
+    /// identifier, or the default settings if none are configured.
+    pub(crate) fn rule_config<T: DeserializeOwned + Default>(&self, ident: &str) -> Result<T> {
+        match self.rules.get(ident).and_then(|rule| rule.config.as_ref()) {
+            Some(config) => serde_yaml::from_value(config.clone())
+                .with_context(|| format!("invalid config for {ident}")),
+            None => Ok(T::default()),
+        }
+    }
+}
+
+/// Returns the root of the repository that `input` is in, if any.
+/// See [`Config::discover`].
+fn repo_root(input: &Path) -> Option<PathBuf> {
+    let input = std::fs::canonicalize(input).ok()?;
+
+    match input
+        .ancestors()
+        .find(|ancestor| ancestor.file_name().is_some_and(|name| name == ".github"))
+    {
+        Some(github) => github.parent().map(Path::to_path_buf),
+        None => input.is_dir().then_some(input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::Config;
+    use crate::finding::{Confidence, Severity};
+
+    #[derive(Debug, Default, Deserialize, PartialEq)]
+    #[serde(rename_all = "kebab-case")]
+    struct ExampleConfig {
+        names: Vec<String>,
+    }
+
+    #[test]
+    fn test_rule_config() {
+        let config = Config::from_str(
+            r#"
+rules:
+  example:
+    config:
+      names: [foo, bar]
+  unconfigured: {}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.rule_config::<ExampleConfig>("example").unwrap(),
+            ExampleConfig {
+                names: vec!["foo".into(), "bar".into()]
+            }
+        );
+        assert_eq!(
+            config.rule_config::<ExampleConfig>("unconfigured").unwrap(),
+            ExampleConfig::default()
+        );
+        assert_eq!(
+            config.rule_config::<ExampleConfig>("missing").unwrap(),
+            ExampleConfig::default()
+        );
+
+        assert!(Config::from_str("").is_ok());
+        assert!(Config::from_str("unknown: true").is_err());
+        assert!(
+            Config::from_str("rules: { example: { config: { names: 1 } } }")
+                .unwrap()
+                .rule_config::<ExampleConfig>("example")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_toml() {
+        let config = Config::from_toml_str(
+            r#"
+offline = true
+
+[rules.example]
+severity = "critical"
+
+[rules.example.config]
+names = ["foo", "bar"]
+"#,
+        )
+        .unwrap();
+
+        assert!(config.offline());
+        assert_eq!(config.severity("example"), Some(Severity::Critical));
+        assert_eq!(
+            config.rule_config::<ExampleConfig>("example").unwrap(),
+            ExampleConfig {
+                names: vec!["foo".into(), "bar".into()]
+            }
+        );
+
+        assert!(Config::from_toml_str("").is_ok());
+        assert!(Config::from_toml_str("unknown = true").is_err());
+    }
+
+    #[test]
+    fn test_severity() {
+        let config = Config::from_str(
+            r#"
+rules:
+  example:
+    severity: critical
+  unconfigured: {}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.severity("example"), Some(Severity::Critical));
+        assert_eq!(config.severity("unconfigured"), None);
+        assert_eq!(config.severity("missing"), None);
+
+        assert!(Config::from_str("rules: { example: { severity: unknown } }").is_err());
+
+        let config = Config::from_str("{ min-severity: medium, min-confidence: high }").unwrap();
+        assert_eq!(config.min_severity(), Some(Severity::Medium));
+        assert_eq!(config.min_confidence(), Some(Confidence::High));
+        assert_eq!(Config::default().min_severity(), None);
+    }
+
+    #[test]
+    fn test_rule_overrides() {
+        let config = Config::from_str(
+            r#"
+rules:
+  disabled:
+    disable: true
+  example:
+    confidence: low
+    exclude: [.github/workflows/release.yml]
+"#,
+        )
+        .unwrap();
+
+        assert!(config.is_disabled("disabled"));
+        assert!(!config.is_disabled("example"));
+        assert!(!config.is_disabled("missing"));
+        assert_eq!(config.confidence("example"), Some(Confidence::Low));
+        assert_eq!(config.confidence("missing"), None);
+
+        assert!(config.is_excluded_from("example", ".github/workflows/release.yml"));
+        assert!(!config.is_excluded_from("example", ".github/workflows/ci.yml"));
+        assert!(!config.is_excluded_from("missing", ".github/workflows/release.yml"));
+    }
+
+    #[test]
+    fn test_exclude() {
+        let config = Config::from_str(
+            r#"
+exclude:
+  - vendor/
+  - .github/workflows/generated-*.yml
+  - "**/test/action.yml"
+  - /build
+"#,
+        )
+        .unwrap();
+
+        assert!(config.is_excluded("vendor/action.yml"));
+        assert!(config.is_excluded("vendor/nested/action.yml"));
+        assert!(config.is_excluded(".github/workflows/generated-ci.yml"));
+        assert!(config.is_excluded("test/action.yml"));
+        assert!(config.is_excluded(".github/actions/test/action.yml"));
+        assert!(config.is_excluded_from("example", "vendor/action.yml"));
+
+        assert!(config.is_excluded("third-party/vendor/action.yml"));
+        assert!(config.is_excluded("build/action.yml"));
+        assert!(!config.is_excluded("nested/build/action.yml"));
+
+        assert!(!config.is_excluded("vendored/action.yml"));
+        assert!(!config.is_excluded(".github/workflows/ci.yml"));
+        assert!(!config.is_excluded(".github/workflows/nested/generated-ci.yml"));
+        assert!(!Config::default().is_excluded("vendor/action.yml"));
+    }
+
+    #[test]
+    fn test_ignore() {
+        let config = Config::from_str(
+            r#"
+ignore:
+  example:
+    - .github/workflows/ci/*.yml
+    - deploy.yml:23
+"#,
+        )
+        .unwrap();
+
+        assert!(config.is_ignored("example", ".github/workflows/ci/test.yml", 1..=1));
+        assert!(config.is_ignored("example", ".github/workflows/deploy.yml", 23..=23));
+        assert!(config.is_ignored("example", ".github/workflows/deploy.yml", 20..=25));
+
+        assert!(!config.is_ignored("example", ".github/workflows/deploy.yml", 24..=30));
+        assert!(!config.is_ignored("example", ".github/workflows/test.yml", 1..=1));
+        assert!(!config.is_ignored("other", ".github/workflows/ci/test.yml", 1..=1));
+    }
+
+    #[test]
+    fn test_discover() {
+        let root = std::env::temp_dir().join(format!("zizmor-config-test-{}", std::process::id()));
+        let workflows = root.join(".github/workflows");
+        std::fs::create_dir_all(&workflows).unwrap();
+        std::fs::write(workflows.join("ci.yml"), "").unwrap();
+
+        assert!(!Config::discover(&root).unwrap().offline());
+
+        std::fs::write(root.join("zizmor.yml"), "offline: true").unwrap();
+        for input in [&root, &workflows, &workflows.join("ci.yml")] {
+            assert!(Config::discover(input).unwrap().offline());
+        }
+
+        // `.github/zizmor.yml` takes precedence.
+        std::fs::write(root.join(".github/zizmor.yml"), "gh-hostname: example.com").unwrap();
+        let config = Config::discover(&root).unwrap();
+        assert!(!config.offline());
+        assert_eq!(config.gh_hostname(), Some("example.com"));
+        assert_eq!(config.online_audits(), None);
+
+        // TOML files are discovered too.
+        std::fs::remove_file(root.join(".github/zizmor.yml")).unwrap();
+        std::fs::remove_file(root.join("zizmor.yml")).unwrap();
+        std::fs::write(root.join("zizmor.toml"), "offline = true").unwrap();
+        assert!(Config::discover(&root).unwrap().offline());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_online_audits() {
+        let config = Config::from_str("online-audits: [impostor-commit]").unwrap();
+        assert_eq!(
+            config.online_audits(),
+            Some(&["impostor-commit".to_string()][..])
+        );
+
+        // An empty list means that no audits may use the API.
+        let config = Config::from_str("online-audits: []").unwrap();
+        assert_eq!(config.online_audits(), Some(&[][..]));
+    }
+
+    #[test]
+    fn test_trusted_owners() {
+        let config = Config::from_str("trusted-owners: [Example]").unwrap();
+
+        assert!(config.is_trusted_owner("actions"));
+        assert!(config.is_trusted_owner("GitHub"));
+        assert!(config.is_trusted_owner("example"));
+        assert!(!config.is_trusted_owner("other"));
+        assert!(!Config::default().is_trusted_owner("example"));
+    }
+
+    #[test]
+    fn test_vars() {
+        let config = Config::from_str("vars: { RUNNER: linux-large }").unwrap();
+
+        assert_eq!(
+            config.vars().get("RUNNER").map(String::as_str),
+            Some("linux-large")
+        );
+        assert!(Config::default().vars().is_empty());
+    }
+
+    #[test]
+    fn test_trusted_runners() {
+        let config = Config::from_str("trusted-runners: [Ephemeral-Linux]").unwrap();
+
+        assert!(config.is_trusted_runner("ephemeral-linux"));
+        assert!(!config.is_trusted_runner("self-hosted"));
+        assert!(!Config::default().is_trusted_runner("ephemeral-linux"));
+    }
+}
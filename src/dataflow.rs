@@ -0,0 +1,554 @@
+//! Tracking of values through a workflow, from sources to sinks.
+//!
+//! A *source* is an expression context of interest, e.g. an
+//! attacker-controllable field of the triggering event's payload. Values
+//! from sources flow through `env:` blocks, `$GITHUB_ENV` and `$GITHUB_OUTPUT`
+//! writes, step outputs (`steps.<id>.outputs.<name>`) and job outputs
+//! (`needs.<job>.outputs.<name>`), and eventually into *sinks*: code that
+//! gets executed, like `run:` scripts and the script inputs of actions
+//! like `actions/github-script`.
+//!
+//! Like the rest of zizmor's script analysis, this is an approximation:
+//! when we can't tell which part of a step produces a value, we assume
+//! that all of it does.
+
+use std::ops::Deref;
+
+use github_actions_models::{
+    common::EnvValue,
+    workflow::{
+        self,
+        job::{Secrets, StepBody},
+    },
+};
+
+use crate::{
+    expr::Expr,
+    finding::SymbolicLocation,
+    models::{Job, Step, Uses, Workflow},
+    shell::{statements, variables},
+    utils::extract_expressions,
+};
+
+/// The maximum number of hops (env variables, step outputs and job outputs)
+/// followed when determining where a value comes from.
+const MAX_DEPTH: usize = 8;
+
+/// Default environment variables, along with the contexts that they mirror.
+const DEFAULT_ENV: &[(&str, &str)] = &[
+    ("GITHUB_HEAD_REF", "github.head_ref"),
+    ("GITHUB_REF_NAME", "github.ref_name"),
+];
+
+/// Actions that execute one of their inputs as code, along with that input
+/// and the kind of code it contains.
+const SCRIPT_ACTIONS: &[(&str, &str, SinkKind)] = &[
+    ("actions/github-script", "script", SinkKind::JavaScript),
+    ("appleboy/ssh-action", "script", SinkKind::Shell),
+    ("azure/cli", "inlineScript", SinkKind::Shell),
+    ("azure/powershell", "inlineScript", SinkKind::Shell),
+];
+
+/// The kind of code that a [`Sink`] executes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SinkKind {
+    /// A shell script, e.g. a `run:` block.
+    Shell,
+    /// A JavaScript script, e.g. `actions/github-script`'s `script:`.
+    JavaScript,
+}
+
+/// A step's code, which may have values expanded into it.
+pub(crate) struct Sink<'w> {
+    pub(crate) step: Step<'w>,
+    pub(crate) kind: SinkKind,
+    /// The code, prior to any template expansion.
+    pub(crate) code: String,
+    /// The location of the code within the step.
+    pub(crate) location: SymbolicLocation<'w>,
+}
+
+/// Returns every sink in the given job, in step order.
+pub(crate) fn sinks<'w>(job: &Job<'w>) -> Vec<Sink<'w>> {
+    let mut sinks = vec![];
+
+    for step in job.steps() {
+        let inner: &'w workflow::job::Step = *step;
+        match &inner.body {
+            StepBody::Run { run, .. } => {
+                let location = step.location().with_keys(&["run".into()]);
+                sinks.push(Sink {
+                    step,
+                    kind: SinkKind::Shell,
+                    code: run.clone(),
+                    location,
+                });
+            }
+            StepBody::Uses { uses, with } => {
+                let Some(uses) = Uses::from_step(uses) else {
+                    continue;
+                };
+                let action = format!("{}/{}", uses.owner, uses.repo);
+
+                let Some((_, input, kind)) = SCRIPT_ACTIONS
+                    .iter()
+                    .find(|(candidate, _, _)| candidate.eq_ignore_ascii_case(&action))
+                else {
+                    continue;
+                };
+
+                let Some(code) = with.get(*input) else {
+                    continue;
+                };
+
+                let location = step.location().with_keys(&["with".into(), (*input).into()]);
+                sinks.push(Sink {
+                    step,
+                    kind: *kind,
+                    code: code.to_string(),
+                    location,
+                });
+            }
+        }
+    }
+
+    sinks
+}
+
+/// Resolves the sources whose values may flow into expressions and
+/// scripts within a workflow.
+#[derive(Clone)]
+pub(crate) struct Dataflow<'w> {
+    workflow: &'w Workflow,
+    is_source: fn(&str) -> bool,
+    /// For reusable workflows, the caller's [`Dataflow`] and the ID of
+    /// the calling job, through which `inputs` and `secrets` resolve.
+    caller: Option<(Box<Dataflow<'w>>, &'w str)>,
+}
+
+impl<'w> Dataflow<'w> {
+    /// Creates a new [`Dataflow`] for `workflow`, with sources being
+    /// the contexts that match `is_source`.
+    pub(crate) fn new(workflow: &'w Workflow, is_source: fn(&str) -> bool) -> Self {
+        Self {
+            workflow,
+            is_source,
+            caller: None,
+        }
+    }
+
+    /// Returns a [`Dataflow`] for `callee`, a reusable workflow called by
+    /// `job`, in which the callee's `inputs` and `secrets` resolve to
+    /// the values that `job` passes to it.
+    pub(crate) fn callee(&self, job: &Job<'w>, callee: &'w Workflow) -> Self {
+        Self {
+            workflow: callee,
+            is_source: self.is_source,
+            caller: Some((Box::new(self.clone()), job.id)),
+        }
+    }
+
+    /// Returns the sources whose values may flow into the expressions
+    /// in `text`.
+    ///
+    /// `job` and `step` give the position at which `text` is evaluated,
+    /// which determines the step outputs and `env` variables that are
+    /// visible to it. Without a `step`, every `$GITHUB_ENV` write in
+    /// the job is assumed to be visible.
+    pub(crate) fn sources(
+        &self,
+        text: &str,
+        job: Option<&Job<'w>>,
+        step: Option<&Step<'w>>,
+    ) -> Vec<String> {
+        let mut sources = vec![];
+        self.collect(
+            text,
+            job,
+            step.map(|step| step.index),
+            MAX_DEPTH,
+            &mut sources,
+        );
+        sources
+    }
+
+    /// Like [`Dataflow::sources`], but for a single context,
+    /// e.g. `steps.foo.outputs.bar`.
+    pub(crate) fn context_sources(
+        &self,
+        context: &str,
+        job: Option<&Job<'w>>,
+        step: Option<&Step<'w>>,
+    ) -> Vec<String> {
+        let mut sources = vec![];
+        self.collect_context(
+            context,
+            job,
+            step.map(|step| step.index),
+            MAX_DEPTH,
+            &mut sources,
+        );
+        sources
+    }
+
+    /// Returns the sources whose values may flow into `script`, a part
+    /// of the given `run:` step, either through its expressions or through
+    /// the environment variables that it references.
+    pub(crate) fn script_sources(
+        &self,
+        script: &str,
+        job: &Job<'w>,
+        step: &Step<'w>,
+    ) -> Vec<String> {
+        let mut sources = vec![];
+        self.collect_script(script, job, step.index, MAX_DEPTH, &mut sources);
+        sources
+    }
+
+    fn push_source(sources: &mut Vec<String>, source: &str) {
+        if !sources.iter().any(|existing| existing == source) {
+            sources.push(source.into());
+        }
+    }
+
+    fn collect(
+        &self,
+        text: &str,
+        job: Option<&Job<'w>>,
+        step: Option<usize>,
+        depth: usize,
+        sources: &mut Vec<String>,
+    ) {
+        for expr in extract_expressions(text) {
+            let Ok(expr) = Expr::parse(expr.as_bare()) else {
+                continue;
+            };
+
+            for context in expr.contexts() {
+                self.collect_context(context, job, step, depth, sources);
+            }
+        }
+    }
+
+    fn collect_context(
+        &self,
+        context: &str,
+        job: Option<&Job<'w>>,
+        step: Option<usize>,
+        depth: usize,
+        sources: &mut Vec<String>,
+    ) {
+        if (self.is_source)(context) {
+            Self::push_source(sources, context);
+            return;
+        }
+
+        if depth == 0 {
+            return;
+        }
+
+        match context.split('.').collect::<Vec<_>>()[..] {
+            ["needs", id, "outputs", name, ..] => {
+                let Some(producer) = self.workflow.job(id) else {
+                    return;
+                };
+                if let Some(output) = producer.output(name) {
+                    self.collect(output, Some(&producer), None, depth - 1, sources);
+                }
+            }
+            ["steps", id, "outputs", name, ..] => {
+                let Some(job) = job else {
+                    return;
+                };
+                if let Some(producer) = job.step(id) {
+                    self.collect_step_output(job, &producer, name, depth - 1, sources);
+                }
+            }
+            ["env", name, ..] => self.collect_env(name, job, step, depth - 1, sources),
+            ["inputs" | "secrets", ..] => self.collect_binding(context, depth - 1, sources),
+            _ => (),
+        }
+    }
+
+    /// Collects the sources of an `inputs.*` or `secrets.*` context,
+    /// through the value that the caller binds it to (if any).
+    fn collect_binding(&self, context: &str, depth: usize, sources: &mut Vec<String>) {
+        let Some((caller, job_id)) = &self.caller else {
+            return;
+        };
+        let Some(job) = caller.workflow.job(job_id) else {
+            return;
+        };
+        let workflow::Job::ReusableWorkflowCallJob(reusable) = job.deref() else {
+            return;
+        };
+
+        let value = match context.split('.').collect::<Vec<_>>()[..] {
+            ["inputs", name, ..] => reusable.with.get(name),
+            ["secrets", name, ..] => match &reusable.secrets {
+                // Inherited secrets resolve to the caller's own.
+                Some(Secrets::Inherit) => {
+                    return caller.collect_context(context, Some(&job), None, depth, sources)
+                }
+                Some(Secrets::Env(secrets)) => secrets.get(name),
+                None => None,
+            },
+            _ => None,
+        };
+
+        if let Some(EnvValue::String(value)) = value {
+            caller.collect(value, Some(&job), None, depth, sources);
+        }
+    }
+
+    /// Collects the sources of `script`, which runs as part of the step
+    /// at index `step`, including those of the variables it references.
+    fn collect_script(
+        &self,
+        script: &str,
+        job: &Job<'w>,
+        step: usize,
+        depth: usize,
+        sources: &mut Vec<String>,
+    ) {
+        self.collect(script, Some(job), Some(step), depth, sources);
+
+        if depth == 0 {
+            return;
+        }
+
+        for var in variables(script) {
+            if let Some((_, context)) = DEFAULT_ENV.iter().find(|(name, _)| *name == var) {
+                self.collect_context(context, Some(job), Some(step), depth, sources);
+            } else if !var.starts_with("GITHUB_") && !var.starts_with("RUNNER_") {
+                // Default variables can't be overridden, so only
+                // the others can come from `env:` or `$GITHUB_ENV`.
+                self.collect_env(var, Some(job), Some(step), depth - 1, sources);
+            }
+        }
+    }
+
+    /// Collects the sources of the `name` environment variable, as seen by
+    /// the step at index `step` (or by the job as a whole, if `None`).
+    ///
+    /// Variables resolve from the innermost `env:` outwards: the step's,
+    /// then the job's, then the workflow's. `$GITHUB_ENV` writes by earlier
+    /// steps may also set the variable, but since they can be conditional,
+    /// they're collected alongside the job's and workflow's `env:`.
+    fn collect_env(
+        &self,
+        name: &str,
+        job: Option<&Job<'w>>,
+        step: Option<usize>,
+        depth: usize,
+        sources: &mut Vec<String>,
+    ) {
+        if let Some(job) = job {
+            let steps = job.steps().collect::<Vec<_>>();
+
+            if let Some(current) = step.and_then(|step| steps.get(step)) {
+                if let StepBody::Run { env, .. } = &current.body {
+                    if let Some(EnvValue::String(value)) = env.get(name) {
+                        self.collect(value, Some(job), step, depth, sources);
+                        return;
+                    }
+                }
+            }
+
+            for earlier in steps.iter().take(step.unwrap_or(steps.len())) {
+                let StepBody::Run { run, .. } = &earlier.body else {
+                    continue;
+                };
+
+                for stmt in statements(run) {
+                    if stmt.writes_to("GITHUB_ENV") && stmt.may_assign(name) {
+                        self.collect_script(stmt.text, job, earlier.index, depth, sources);
+                    }
+                }
+            }
+
+            if let workflow::Job::NormalJob(normal) = job.deref() {
+                if let Some(EnvValue::String(value)) = normal.env.get(name) {
+                    self.collect(value, Some(job), None, depth, sources);
+                    return;
+                }
+            }
+        }
+
+        if let Some(EnvValue::String(value)) = self.workflow.env.get(name) {
+            self.collect(value, None, None, depth, sources);
+        }
+    }
+
+    /// Collects the sources of the `name` output of the `producer` step.
+    ///
+    /// For `run:` steps, this is the data written to `$GITHUB_OUTPUT`
+    /// under `name`. Steps that use actions (or whose outputs we can't
+    /// find) are over-approximated: every expression in the step, including
+    /// its `env:` and `with:`, is assumed to contribute to each output.
+    fn collect_step_output(
+        &self,
+        job: &Job<'w>,
+        producer: &Step<'w>,
+        name: &str,
+        depth: usize,
+        sources: &mut Vec<String>,
+    ) {
+        if let StepBody::Run { run, .. } = &producer.body {
+            let writers = statements(run)
+                .into_iter()
+                .filter(|stmt| stmt.writes_to("GITHUB_OUTPUT") || stmt.sets_output())
+                .collect::<Vec<_>>();
+
+            if !writers.is_empty() {
+                for stmt in writers.iter().filter(|stmt| stmt.may_assign(name)) {
+                    self.collect_script(stmt.text, job, producer.index, depth, sources);
+                }
+                return;
+            }
+        }
+
+        for text in producer.texts() {
+            self.collect_script(text, job, producer.index, depth, sources);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sinks, Dataflow, SinkKind};
+    use crate::{
+        expr::context_is_attacker_controlled,
+        models::{test_workflow, Workflow},
+    };
+
+    #[test]
+    fn dataflow_sources() {
+        let workflow = test_workflow(
+            r#"
+on: pull_request_target
+env:
+  TITLE: ${{ github.event.pull_request.title }}
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    outputs:
+      branch: ${{ steps.meta.outputs.branch }}
+      version: ${{ steps.meta.outputs.version }}
+    steps:
+      - id: meta
+        run: |
+          echo "version=1.2.3" >> $GITHUB_OUTPUT
+          echo "branch=$GITHUB_HEAD_REF" >> $GITHUB_OUTPUT
+      - run: echo "LABEL=$TITLE" >> $GITHUB_ENV
+      - uses: actions/github-script@v7
+        with:
+          script: console.log("${{ env.LABEL }}")
+      - run: echo "${{ needs.build.outputs.version }}"
+  notify:
+    needs: build
+    runs-on: ubuntu-latest
+    steps:
+      - uses: appleboy/ssh-action@v1
+        with:
+          script: echo ${{ needs.build.outputs.branch }}
+"#,
+        );
+
+        let dataflow = Dataflow::new(&workflow, context_is_attacker_controlled);
+        let build = workflow.job("build").unwrap();
+        let notify = workflow.job("notify").unwrap();
+
+        let build_sinks = sinks(&build);
+        assert_eq!(
+            build_sinks
+                .iter()
+                .map(|sink| (sink.step.index, sink.kind))
+                .collect::<Vec<_>>(),
+            [
+                (0, SinkKind::Shell),
+                (1, SinkKind::Shell),
+                (2, SinkKind::JavaScript),
+                (3, SinkKind::Shell),
+            ]
+        );
+
+        // `env.LABEL` comes from a `$GITHUB_ENV` write in an earlier step.
+        let script = &build_sinks[2];
+        assert_eq!(
+            dataflow.sources(&script.code, Some(&build), Some(&script.step)),
+            ["github.event.pull_request.title"]
+        );
+        assert!(dataflow
+            .context_sources("env.LABEL", Some(&build), build.steps().next().as_ref())
+            .is_empty());
+
+        // Only the `branch` output is written from an untrusted source.
+        let notify_sinks = sinks(&notify);
+        assert_eq!(
+            dataflow.sources(&notify_sinks[0].code, Some(&notify), None),
+            ["github.head_ref"]
+        );
+        assert!(dataflow
+            .context_sources("needs.build.outputs.version", Some(&build), None)
+            .is_empty());
+    }
+
+    #[test]
+    fn dataflow_callee_sources() {
+        let dir = std::env::temp_dir().join(format!("zizmor-callee-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("caller.yml"),
+            r#"
+on: issues
+jobs:
+  call:
+    uses: ./.github/workflows/callee.yml
+    with:
+      title: ${{ github.event.issue.title }}
+      number: ${{ github.event.issue.number }}
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("callee.yml"),
+            r#"
+on:
+  workflow_call:
+    inputs:
+      title:
+        type: string
+      number:
+        type: number
+jobs:
+  greet:
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo "${{ inputs.title }} ${{ inputs.number }}"
+"#,
+        )
+        .unwrap();
+
+        let mut workflow = Workflow::from_file(dir.join("caller.yml")).unwrap();
+        workflow.link();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let dataflow = Dataflow::new(&workflow, context_is_attacker_controlled);
+        let (caller, callee) = workflow.calls().next().unwrap();
+        assert_eq!(caller.id, "call");
+        assert_eq!(callee.filename(), "callee.yml");
+
+        let callee_dataflow = dataflow.callee(&caller, callee);
+        let job = callee.job("greet").unwrap();
+        assert_eq!(
+            callee_dataflow.context_sources("inputs.title", Some(&job), None),
+            ["github.event.issue.title"]
+        );
+
+        // Without a caller, inputs have no known sources.
+        assert!(Dataflow::new(callee, context_is_attacker_controlled)
+            .context_sources("inputs.title", Some(&job), None)
+            .is_empty());
+    }
+}
@@ -0,0 +1,646 @@
+//! Evaluation of expressions against known context values.
+//!
+//! Evaluation is *partial*: only the contexts we've been given are known,
+//! and anything that depends on an unknown context (or on runtime state,
+//! like `success()` or `hashFiles(...)`) evaluates to `None` rather than
+//! a guess. Short-circuiting operators still evaluate when their known
+//! side decides the result, e.g. `false && github.actor == 'x'`.
+//!
+//! Semantics follow GitHub's documented rules, including loose equality
+//! with numeric coercion and case-insensitive string comparisons.
+//!
+//! See: <https://docs.github.com/en/actions/writing-workflows/choosing-what-your-workflow-does/evaluate-expressions-in-workflows-and-actions>
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use super::{BinOp, Expr, UnOp};
+use crate::utils::extract_expression;
+
+/// A value produced by evaluating an expression.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// An object, with its keys in their original order.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns whether this value is truthy, per GitHub's coercion rules.
+    pub(crate) fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Boolean(b) => *b,
+            Value::Number(n) => *n != 0.0 && !n.is_nan(),
+            Value::String(s) => !s.is_empty(),
+            Value::Array(_) | Value::Object(_) => true,
+        }
+    }
+
+    /// Coerces this value to a number, per GitHub's coercion rules.
+    fn to_number(&self) -> f64 {
+        match self {
+            Value::Null => 0.0,
+            Value::Boolean(b) => f64::from(u8::from(*b)),
+            Value::Number(n) => *n,
+            Value::String(s) if s.trim().is_empty() => 0.0,
+            Value::String(s) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .unwrap_or(f64::NAN),
+            Value::Array(_) | Value::Object(_) => f64::NAN,
+        }
+    }
+
+    /// Looks up the given key in this object, case-insensitively.
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Returns this value's members (array elements or object values),
+    /// as selected by a `*` filter.
+    fn members(&self) -> Vec<Value> {
+        match self {
+            Value::Array(elements) => elements.clone(),
+            Value::Object(members) => members.iter().map(|(_, value)| value.clone()).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Indexes into this value, e.g. `value['key']` or `value[0]`.
+    fn index(&self, index: &Value) -> Value {
+        match (self, index) {
+            (Value::Array(elements), Value::Number(n)) if *n >= 0.0 && n.fract() == 0.0 => {
+                elements.get(*n as usize).cloned().unwrap_or(Value::Null)
+            }
+            (Value::Object(_), Value::String(key)) => self.get(key).cloned().unwrap_or(Value::Null),
+            _ => Value::Null,
+        }
+    }
+
+    /// Compares two values with GitHub's loose equality: values of
+    /// different types are compared as numbers, and strings are
+    /// compared case-insensitively.
+    fn loosely_equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Boolean(lhs), Value::Boolean(rhs)) => lhs == rhs,
+            (Value::Number(lhs), Value::Number(rhs)) => lhs == rhs,
+            (Value::String(lhs), Value::String(rhs)) => lhs.eq_ignore_ascii_case(rhs),
+            // Arrays and objects are only equal to themselves, which
+            // we can't observe (every evaluation produces a new copy).
+            (Value::Array(_) | Value::Object(_), _) | (_, Value::Array(_) | Value::Object(_)) => {
+                false
+            }
+            _ => self.to_number() == other.to_number(),
+        }
+    }
+
+    /// Orders two values with the same coercions as [`Value::loosely_equals`].
+    /// Returns `None` if the values are unordered (e.g. `NaN`).
+    fn loosely_compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::String(lhs), Value::String(rhs)) => {
+                Some(lhs.to_lowercase().cmp(&rhs.to_lowercase()))
+            }
+            _ => self.to_number().partial_cmp(&other.to_number()),
+        }
+    }
+
+    /// Returns this value as a JSON value, for `toJSON`.
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Null => serde_json::Value::Null,
+            Value::Boolean(b) => (*b).into(),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => (*n as i64).into(),
+            Value::Number(n) => serde_json::Number::from_f64(*n)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Value::String(s) => s.as_str().into(),
+            Value::Array(elements) => elements.iter().map(Value::to_json).collect(),
+            Value::Object(members) => serde_json::Value::Object(
+                members
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.to_json()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    /// Formats this value the way it's interpolated into a template.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Boolean(b) => write!(f, "{b}"),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Value::Number(n) => write!(f, "{n}"),
+            Value::String(s) => f.write_str(s),
+            Value::Array(_) => f.write_str("Array"),
+            Value::Object(_) => f.write_str("Object"),
+        }
+    }
+}
+
+impl From<&serde_json::Value> for Value {
+    fn from(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Boolean(*b),
+            serde_json::Value::Number(n) => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+            serde_json::Value::String(s) => Value::String(s.clone()),
+            serde_json::Value::Array(elements) => {
+                Value::Array(elements.iter().map(Value::from).collect())
+            }
+            serde_json::Value::Object(members) => Value::Object(
+                members
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<&serde_yaml::Value> for Value {
+    fn from(value: &serde_yaml::Value) -> Self {
+        match value {
+            serde_yaml::Value::Null => Value::Null,
+            serde_yaml::Value::Bool(b) => Value::Boolean(*b),
+            serde_yaml::Value::Number(n) => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+            serde_yaml::Value::String(s) => Value::String(s.clone()),
+            serde_yaml::Value::Sequence(elements) => {
+                Value::Array(elements.iter().map(Value::from).collect())
+            }
+            serde_yaml::Value::Mapping(members) => Value::Object(
+                members
+                    .iter()
+                    .map(|(name, value)| {
+                        let name = match name {
+                            serde_yaml::Value::String(name) => name.clone(),
+                            other => Value::from(other).to_string(),
+                        };
+                        (name, value.into())
+                    })
+                    .collect(),
+            ),
+            serde_yaml::Value::Tagged(tagged) => (&tagged.value).into(),
+        }
+    }
+}
+
+/// The set of contexts (e.g. `matrix`) whose values are known.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Contexts {
+    contexts: HashMap<String, Value>,
+    /// The contexts whose values are only partially known, i.e. whose
+    /// missing members are unknown rather than `null`.
+    partial: HashSet<String>,
+}
+
+impl Contexts {
+    /// Adds a known context, e.g. `matrix`.
+    pub(crate) fn with(mut self, name: &str, value: Value) -> Self {
+        self.contexts.insert(name.to_lowercase(), value);
+        self
+    }
+
+    /// Adds a partially known context, e.g. `env` when some of its
+    /// variables are computed at runtime.
+    pub(crate) fn with_partial(mut self, name: &str, value: Value) -> Self {
+        self.partial.insert(name.to_lowercase());
+        self.with(name, value)
+    }
+
+    /// Looks up a context reference like `matrix.os` or
+    /// `github.event.issue.labels.*.name`.
+    ///
+    /// Returns `None` if the top-level context isn't known. Missing
+    /// members of known contexts are `null`, as they are on GitHub,
+    /// except in partially known contexts.
+    fn lookup(&self, reference: &str) -> Option<Value> {
+        let mut parts = reference.split('.');
+        let name = parts.next()?.to_lowercase();
+        let context = self.contexts.get(&name)?;
+
+        // Neither the whole of a partial context nor its missing
+        // members are known.
+        if self.partial.contains(&name) && context.get(parts.clone().next()?).is_none() {
+            return None;
+        }
+
+        Some(
+            parts
+                .fold(Selection::One(context.clone()), Selection::select)
+                .into(),
+        )
+    }
+
+    /// Expands a template like `ubuntu-${{ matrix.version }}`, returning
+    /// `None` if any of its expressions can't be evaluated.
+    ///
+    /// As on GitHub, a template that's a single expression evaluates to the
+    /// expression's value, which may be an array or object; anything else
+    /// evaluates to a string.
+    pub(crate) fn expand(&self, template: &str) -> Option<Value> {
+        let mut expanded = String::new();
+        let mut view = template;
+
+        while let Some(start) = view.find("${{") {
+            let (expr, next) = extract_expression(view)?;
+            let value = Expr::parse(expr.as_bare()).ok()?.evaluate(self)?;
+
+            if start == 0 && next == view.len() && view.len() == template.len() {
+                return Some(value);
+            }
+
+            expanded.push_str(&view[..start]);
+            expanded.push_str(&value.to_string());
+            view = &view[next..];
+        }
+
+        expanded.push_str(view);
+        Some(Value::String(expanded))
+    }
+}
+
+/// The result of dereferencing a context, which is a list of values
+/// once a `*` filter has been applied.
+enum Selection {
+    One(Value),
+    Filtered(Vec<Value>),
+}
+
+impl Selection {
+    fn select(self, key: &str) -> Self {
+        match (self, key) {
+            (Selection::One(value), "*") => Selection::Filtered(value.members()),
+            (Selection::One(value), key) => {
+                Selection::One(value.get(key).cloned().unwrap_or(Value::Null))
+            }
+            (Selection::Filtered(values), "*") => {
+                Selection::Filtered(values.iter().flat_map(Value::members).collect())
+            }
+            // Filters drop members that don't have the selected key.
+            (Selection::Filtered(values), key) => Selection::Filtered(
+                values
+                    .iter()
+                    .filter_map(|value| value.get(key).cloned())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<Selection> for Value {
+    fn from(selection: Selection) -> Self {
+        match selection {
+            Selection::One(value) => value,
+            Selection::Filtered(values) => Value::Array(values),
+        }
+    }
+}
+
+/// Implements `format(...)`, which replaces `{N}` with its `N`th argument
+/// and unescapes `{{` and `}}`.
+fn format(template: &str, args: &[Value]) -> Option<String> {
+    let mut formatted = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                formatted.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                formatted.push('}');
+            }
+            '{' => {
+                let mut index = String::new();
+                loop {
+                    match chars.next()? {
+                        '}' => break,
+                        c => index.push(c),
+                    }
+                }
+                formatted.push_str(&args.get(index.parse::<usize>().ok()?)?.to_string());
+            }
+            // A lone `}` is an error on GitHub.
+            '}' => return None,
+            c => formatted.push(c),
+        }
+    }
+
+    Some(formatted)
+}
+
+impl Expr {
+    /// Evaluates this expression against the given known contexts,
+    /// returning `None` if its value can't be determined statically.
+    pub(crate) fn evaluate(&self, contexts: &Contexts) -> Option<Value> {
+        match self {
+            Expr::Number(n) => Some(Value::Number(*n)),
+            Expr::String(s) => Some(Value::String(s.clone())),
+            Expr::Boolean(b) => Some(Value::Boolean(*b)),
+            Expr::Null => Some(Value::Null),
+            // Only valid within an index, which handles it below.
+            Expr::Star => None,
+            Expr::Context(reference) => contexts.lookup(reference),
+            Expr::Index { parent, indices } => {
+                let mut selection = Selection::One(parent.evaluate(contexts)?);
+
+                for index in indices {
+                    selection = match (selection, index) {
+                        (selection, Expr::Star) => selection.select("*"),
+                        (Selection::One(value), index) => {
+                            Selection::One(value.index(&index.evaluate(contexts)?))
+                        }
+                        (Selection::Filtered(values), index) => {
+                            let index = index.evaluate(contexts)?;
+                            Selection::Filtered(
+                                values
+                                    .iter()
+                                    .map(|value| value.index(&index))
+                                    .filter(|value| *value != Value::Null)
+                                    .collect(),
+                            )
+                        }
+                    };
+                }
+
+                Some(selection.into())
+            }
+            Expr::Call { func, args } => Self::call(func, args, contexts),
+            Expr::BinOp {
+                lhs,
+                op: BinOp::And,
+                rhs,
+            } => match lhs.evaluate(contexts) {
+                Some(lhs) if !lhs.is_truthy() => Some(lhs),
+                Some(_) => rhs.evaluate(contexts),
+                None => None,
+            },
+            Expr::BinOp {
+                lhs,
+                op: BinOp::Or,
+                rhs,
+            } => match lhs.evaluate(contexts) {
+                Some(lhs) if lhs.is_truthy() => Some(lhs),
+                Some(_) => rhs.evaluate(contexts),
+                None => None,
+            },
+            Expr::BinOp { lhs, op, rhs } => {
+                let lhs = lhs.evaluate(contexts)?;
+                let rhs = rhs.evaluate(contexts)?;
+
+                let result = match op {
+                    BinOp::Eq => lhs.loosely_equals(&rhs),
+                    BinOp::Neq => !lhs.loosely_equals(&rhs),
+                    BinOp::Gt => lhs.loosely_compare(&rhs) == Some(Ordering::Greater),
+                    BinOp::Ge => matches!(
+                        lhs.loosely_compare(&rhs),
+                        Some(Ordering::Greater | Ordering::Equal)
+                    ),
+                    BinOp::Lt => lhs.loosely_compare(&rhs) == Some(Ordering::Less),
+                    BinOp::Le => matches!(
+                        lhs.loosely_compare(&rhs),
+                        Some(Ordering::Less | Ordering::Equal)
+                    ),
+                    BinOp::And | BinOp::Or => unreachable!(),
+                };
+
+                Some(Value::Boolean(result))
+            }
+            Expr::UnOp {
+                op: UnOp::Not,
+                expr,
+            } => Some(Value::Boolean(!expr.evaluate(contexts)?.is_truthy())),
+        }
+    }
+
+    fn call(func: &str, args: &[Expr], contexts: &Contexts) -> Option<Value> {
+        let args = args
+            .iter()
+            .map(|arg| arg.evaluate(contexts))
+            .collect::<Option<Vec<_>>>()?;
+
+        match (func.to_lowercase().as_str(), args.as_slice()) {
+            ("contains", [Value::Array(elements), item]) => Some(Value::Boolean(
+                elements.iter().any(|element| element.loosely_equals(item)),
+            )),
+            ("contains", [search, item]) => Some(Value::Boolean(
+                search
+                    .to_string()
+                    .to_lowercase()
+                    .contains(&item.to_string().to_lowercase()),
+            )),
+            ("startswith", [search, prefix]) => Some(Value::Boolean(
+                search
+                    .to_string()
+                    .to_lowercase()
+                    .starts_with(&prefix.to_string().to_lowercase()),
+            )),
+            ("endswith", [search, suffix]) => Some(Value::Boolean(
+                search
+                    .to_string()
+                    .to_lowercase()
+                    .ends_with(&suffix.to_string().to_lowercase()),
+            )),
+            ("format", [template, args @ ..]) => {
+                format(&template.to_string(), args).map(Value::String)
+            }
+            ("join", [Value::Array(elements), rest @ ..]) if rest.len() <= 1 => {
+                let separator = rest.first().map_or(",".into(), Value::to_string);
+                Some(Value::String(
+                    elements
+                        .iter()
+                        .map(Value::to_string)
+                        .collect::<Vec<_>>()
+                        .join(&separator),
+                ))
+            }
+            ("join", [value, rest @ ..]) if rest.len() <= 1 => {
+                Some(Value::String(value.to_string()))
+            }
+            ("tojson", [value]) => serde_json::to_string_pretty(&value.to_json())
+                .ok()
+                .map(Value::String),
+            ("fromjson", [json]) => serde_json::from_str::<serde_json::Value>(&json.to_string())
+                .ok()
+                .map(|json| (&json).into()),
+            ("always", []) => Some(Value::Boolean(true)),
+            // Everything else (`success()`, `hashFiles(...)`, etc.) depends
+            // on the state of the run.
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Contexts, Value};
+    use crate::expr::Expr;
+
+    fn eval(expr: &str, contexts: &Contexts) -> Option<Value> {
+        Expr::parse(expr).unwrap().evaluate(contexts)
+    }
+
+    #[test]
+    fn test_evaluate_literals_and_operators() {
+        let contexts = Contexts::default();
+
+        for (expr, expected) in [
+            ("1 == 1", Value::Boolean(true)),
+            ("'abc' == 'ABC'", Value::Boolean(true)),
+            ("'1' == 1", Value::Boolean(true)),
+            ("null == 0", Value::Boolean(true)),
+            ("true == 1", Value::Boolean(true)),
+            ("'' == 0", Value::Boolean(true)),
+            ("'abc' == 0", Value::Boolean(false)),
+            ("'b' > 'A'", Value::Boolean(true)),
+            ("2 <= '3'", Value::Boolean(true)),
+            ("!''", Value::Boolean(true)),
+            ("'a' && 'b'", Value::String("b".into())),
+            ("'' || 'fallback'", Value::String("fallback".into())),
+            ("false && unknown.context", Value::Boolean(false)),
+            ("true || unknown.context", Value::Boolean(true)),
+        ] {
+            assert_eq!(eval(expr, &contexts), Some(expected), "{expr}");
+        }
+
+        for unknown in [
+            "unknown.context",
+            "unknown.context || true",
+            "true && unknown.context",
+            "unknown.context && false",
+            "success()",
+            "hashFiles('**/*.lock')",
+        ] {
+            assert_eq!(eval(unknown, &contexts), None, "{unknown}");
+        }
+    }
+
+    #[test]
+    fn test_evaluate_functions() {
+        let contexts = Contexts::default();
+
+        for (expr, expected) in [
+            ("contains('Hello world', 'WORLD')", Value::Boolean(true)),
+            (
+                "contains(fromJSON('[\"push\", \"pull_request\"]'), 'push')",
+                Value::Boolean(true),
+            ),
+            (
+                "startsWith('refs/heads/main', 'refs/HEADS/')",
+                Value::Boolean(true),
+            ),
+            ("endsWith('v1.2.3', '.4')", Value::Boolean(false)),
+            (
+                "format('{0}-{1} {{literal}}', 'ubuntu', 22.04)",
+                Value::String("ubuntu-22.04 {literal}".into()),
+            ),
+            (
+                "join(fromJSON('[\"a\", \"b\"]'), ', ')",
+                Value::String("a, b".into()),
+            ),
+            ("join('abc')", Value::String("abc".into())),
+            ("toJSON(1)", Value::String("1".into())),
+            ("fromJSON('true')", Value::Boolean(true)),
+            ("(fromJSON('[1, 2]'))[1]", Value::Number(2.0)),
+        ] {
+            assert_eq!(eval(expr, &contexts), Some(expected), "{expr}");
+        }
+
+        assert_eq!(eval("format('{0', 'a')", &contexts), None);
+        assert_eq!(eval("fromJSON('not json')", &contexts), None);
+    }
+
+    #[test]
+    fn test_evaluate_contexts() {
+        let matrix = serde_json::json!({
+            "os": "ubuntu-latest",
+            "targets": [{"name": "a"}, {"name": "b"}, {"other": "c"}],
+        });
+        let contexts = Contexts::default().with("matrix", (&matrix).into());
+
+        assert_eq!(
+            eval("matrix.os", &contexts),
+            Some(Value::String("ubuntu-latest".into()))
+        );
+        assert_eq!(
+            eval("MATRIX.OS", &contexts),
+            Some(Value::String("ubuntu-latest".into()))
+        );
+        assert_eq!(eval("matrix.missing", &contexts), Some(Value::Null));
+        assert_eq!(
+            eval("matrix['os']", &contexts),
+            Some(Value::String("ubuntu-latest".into()))
+        );
+        assert_eq!(
+            eval("matrix.targets.*.name", &contexts),
+            Some(Value::Array(vec![
+                Value::String("a".into()),
+                Value::String("b".into())
+            ]))
+        );
+        assert_eq!(
+            eval("contains(matrix.targets.*.name, 'B')", &contexts),
+            Some(Value::Boolean(true))
+        );
+        assert_eq!(eval("github.ref", &contexts), None);
+    }
+
+    #[test]
+    fn test_evaluate_partial_contexts() {
+        let env = serde_json::json!({"REGISTRY": "ghcr.io"});
+        let contexts = Contexts::default().with_partial("env", (&env).into());
+
+        assert_eq!(
+            eval("env.registry", &contexts),
+            Some(Value::String("ghcr.io".into()))
+        );
+        assert_eq!(eval("env.MISSING", &contexts), None);
+        assert_eq!(eval("env", &contexts), None);
+        assert_eq!(eval("env.MISSING || 'fallback'", &contexts), None);
+    }
+
+    #[test]
+    fn test_contexts_expand() {
+        let matrix = serde_json::json!({"os": "ubuntu", "version": 22.04, "labels": ["a", "b"]});
+        let contexts = Contexts::default().with("matrix", (&matrix).into());
+
+        assert_eq!(
+            contexts.expand("${{ matrix.os }}-${{ matrix.version }}"),
+            Some(Value::String("ubuntu-22.04".into()))
+        );
+        assert_eq!(
+            contexts.expand("${{ matrix.labels }}"),
+            Some(Value::Array(vec![
+                Value::String("a".into()),
+                Value::String("b".into())
+            ]))
+        );
+        assert_eq!(
+            contexts.expand("no expressions"),
+            Some(Value::String("no expressions".into()))
+        );
+        assert_eq!(contexts.expand("${{ github.ref }}"), None);
+    }
+}
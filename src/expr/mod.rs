@@ -1,10 +1,15 @@
 //! Expression parsing and analysis.
 
 use anyhow::Result;
+use github_actions_models::common::expr::ExplicitExpr;
 use itertools::Itertools;
 use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
 
+mod eval;
+
+pub(crate) use eval::{Contexts, Value};
+
 /// A parser for GitHub Actions' expression language.
 #[derive(Parser)]
 #[grammar = "expr/expr.pest"]
@@ -27,6 +32,43 @@ pub(crate) enum UnOp {
     Not,
 }
 
+/// Context members that are believed to be always safe.
+pub(crate) const SAFE_CONTEXTS: &[&str] = &[
+    // The GitHub event name (i.e. trigger) is itself safe.
+    "github.event_name",
+    // Safe keys within the otherwise generally unsafe github.event context.
+    "github.event.number",
+    "github.event.workflow_run.id",
+    // Always a 40-char SHA-1 reference.
+    "github.sha",
+    // Like `secrets.*`: not safe to expose, but safe to interpolate.
+    "github.token",
+    // GitHub Actions-controlled local directory.
+    "github.workspace",
+    // GitHub Actions-controller runner architecture.
+    "runner.arch",
+];
+
+/// Context members outside of `github.event` that are believed to be
+/// fully attacker-controllable.
+const UNSAFE_CONTEXTS: &[&str] = &[
+    // The name of a pull request's source branch, which the PR author chooses.
+    "github.head_ref",
+    // Equivalent to the branch or tag name that triggered the workflow.
+    "github.ref_name",
+];
+
+/// Context members that carry the text of the comment that triggered
+/// an `issue_comment`, `discussion_comment` or similar workflow.
+pub(crate) const COMMENT_CONTEXTS: &[&str] = &["github.event.comment.body"];
+
+/// Returns whether the given context is believed to be fully
+/// attacker-controllable, e.g. `github.event.issue.title`.
+pub(crate) fn context_is_attacker_controlled(context: &str) -> bool {
+    !SAFE_CONTEXTS.contains(&context)
+        && (context.starts_with("github.event.") || UNSAFE_CONTEXTS.contains(&context))
+}
+
 /// Represents a GitHub Actions expression.
 #[derive(Debug, PartialEq)]
 pub(crate) enum Expr {
@@ -103,6 +145,113 @@ impl Expr {
         contexts
     }
 
+    /// Returns this expression and all of its subexpressions, in pre-order.
+    pub(crate) fn subexprs(&self) -> Vec<&Expr> {
+        let mut subexprs = vec![self];
+
+        match self {
+            Expr::Index { parent, indices } => {
+                subexprs.extend(parent.subexprs());
+                for index in indices {
+                    subexprs.extend(index.subexprs());
+                }
+            }
+            Expr::Call { func: _, args } => {
+                for arg in args {
+                    subexprs.extend(arg.subexprs());
+                }
+            }
+            Expr::BinOp { lhs, op: _, rhs } => {
+                subexprs.extend(lhs.subexprs());
+                subexprs.extend(rhs.subexprs());
+            }
+            Expr::UnOp { op: _, expr } => subexprs.extend(expr.subexprs()),
+            Expr::Number(_)
+            | Expr::String(_)
+            | Expr::Boolean(_)
+            | Expr::Null
+            | Expr::Star
+            | Expr::Context(_) => (),
+        }
+
+        subexprs
+    }
+
+    /// Returns every equality check (`==`) in this expression that
+    /// compares a context against some other operand, as pairs of
+    /// `(context, operand)`. This includes membership checks like
+    /// `contains(fromJSON('[...]'), context)`.
+    ///
+    /// Only checks that can make the overall expression true are
+    /// returned, i.e. checks beneath a negation are ignored.
+    pub(crate) fn context_equalities(&self) -> Vec<(&str, &Expr)> {
+        let mut equalities = vec![];
+
+        match self {
+            Expr::BinOp {
+                lhs,
+                op: BinOp::And | BinOp::Or,
+                rhs,
+            } => {
+                equalities.extend(lhs.context_equalities());
+                equalities.extend(rhs.context_equalities());
+            }
+            Expr::BinOp {
+                lhs,
+                op: BinOp::Eq,
+                rhs,
+            } => match (lhs.as_ref(), rhs.as_ref()) {
+                (Expr::Context(ctx), other) | (other, Expr::Context(ctx)) => {
+                    equalities.push((ctx.as_str(), other))
+                }
+                _ => (),
+            },
+            Expr::Call { func, args }
+                if func.eq_ignore_ascii_case("contains") && args.len() == 2 =>
+            {
+                if let Expr::Context(ctx) = &args[1] {
+                    equalities.push((ctx.as_str(), &args[0]));
+                }
+            }
+            _ => (),
+        }
+
+        equalities
+    }
+
+    /// Returns whether this expression is truthy regardless of any context,
+    /// e.g. `'false'` (a non-empty string) or `github.ref == 'main' || 'true'`.
+    ///
+    /// This is conservative: function calls and contexts are never
+    /// considered constant.
+    pub(crate) fn is_always_truthy(&self) -> bool {
+        match self {
+            Expr::String(s) => !s.is_empty(),
+            Expr::Number(n) => *n != 0.0 && !n.is_nan(),
+            Expr::Boolean(b) => *b,
+            Expr::BinOp {
+                lhs,
+                op: BinOp::Or,
+                rhs,
+            } => lhs.is_always_truthy() || rhs.is_always_truthy(),
+            Expr::BinOp {
+                lhs,
+                op: BinOp::And,
+                rhs,
+            } => lhs.is_always_truthy() && rhs.is_always_truthy(),
+            _ => false,
+        }
+    }
+
+    /// Parses an `if:` condition, which may be either a bare expression
+    /// or a single fenced (`${{ ... }}`) expression.
+    pub(crate) fn parse_condition(cond: &str) -> Result<Expr> {
+        match ExplicitExpr::from_curly(cond) {
+            Some(expr) => Self::parse(expr.as_bare()),
+            None => Self::parse(cond.trim()),
+        }
+    }
+
     pub(crate) fn parse(expr: &str) -> Result<Expr> {
         // Top level `expression` is a single `or_expr`.
         let or_expr = ExprParser::parse(Rule::expression, expr)?
@@ -410,10 +559,111 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_grouping_and_comparison() {
+        for case in &[
+            "(github.actor == 'foo' || github.actor == 'bar') && true",
+            "(foo || bar)[0]",
+            "inputs.count >= 3",
+            "inputs.count <= 3",
+            "foo &&\n\tbar",
+        ] {
+            assert!(Expr::parse(case).is_ok(), "failed to parse {case}");
+        }
+
+        assert_eq!(
+            Expr::parse("inputs.count >= 3").unwrap(),
+            Expr::BinOp {
+                lhs: Expr::Context("inputs.count".into()).into(),
+                op: BinOp::Ge,
+                rhs: Expr::Number(3.0).into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        for case in &[
+            "github.actor == 'foo'",
+            "${{ github.actor == 'foo' }}",
+            "  ${{github.actor == 'foo'}}  ",
+        ] {
+            assert_eq!(
+                Expr::parse_condition(case).unwrap(),
+                Expr::BinOp {
+                    lhs: Expr::Context("github.actor".into()).into(),
+                    op: BinOp::Eq,
+                    rhs: Expr::String("foo".into()).into(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_expr_context_equalities() {
+        let expr = Expr::parse(
+            "(github.actor == 'a' || 'b' == github.triggering_actor) && !(github.ref == 'c')",
+        )
+        .unwrap();
+        assert_eq!(
+            expr.context_equalities(),
+            [
+                ("github.actor", &Expr::String("a".into())),
+                ("github.triggering_actor", &Expr::String("b".into())),
+            ]
+        );
+
+        let expr = Expr::parse("contains(fromJSON('[\"a\"]'), github.actor)").unwrap();
+        let equalities = expr.context_equalities();
+        assert_eq!(equalities.len(), 1);
+        assert_eq!(equalities[0].0, "github.actor");
+
+        let expr = Expr::parse("github.actor != 'a'").unwrap();
+        assert!(expr.context_equalities().is_empty());
+    }
+
     #[test]
     fn test_expr_contexts() {
         let expr = Expr::parse("foo.bar && abc && d.e.f").unwrap();
 
         assert_eq!(expr.contexts(), ["foo.bar", "abc", "d.e.f"]);
     }
+
+    #[test]
+    fn test_expr_subexprs() {
+        let expr = Expr::parse("a && startsWith(b, 'c')").unwrap();
+        let subexprs = expr.subexprs();
+
+        assert_eq!(subexprs.len(), 5);
+        assert_eq!(subexprs[0], &expr);
+        assert_eq!(subexprs[1], &Expr::Context("a".into()));
+        assert!(matches!(subexprs[2], Expr::Call { func, .. } if func == "startsWith"));
+        assert_eq!(subexprs[3], &Expr::Context("b".into()));
+        assert_eq!(subexprs[4], &Expr::String("c".into()));
+    }
+
+    #[test]
+    fn test_expr_is_always_truthy() {
+        for truthy in [
+            "'false'",
+            "true",
+            "1",
+            "github.ref == 'refs/heads/main' || 'true'",
+            "'a' && 'b'",
+        ] {
+            assert!(Expr::parse(truthy).unwrap().is_always_truthy(), "{truthy}");
+        }
+
+        for maybe in [
+            "false",
+            "''",
+            "0",
+            "github.ref == 'refs/heads/main'",
+            "always()",
+            "'a' && github.actor",
+            "!'false'",
+        ] {
+            assert!(!Expr::parse(maybe).unwrap().is_always_truthy(), "{maybe}");
+        }
+    }
 }
@@ -0,0 +1,124 @@
+//! Baselines, i.e. records of a repository's existing findings.
+//!
+//! A baseline lets a repository with many existing findings adopt `zizmor`
+//! incrementally: findings that are recorded in it are suppressed, so that
+//! only new findings are reported. Findings are matched by their
+//! fingerprints, so a baselined finding stays suppressed as the lines
+//! around it change.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::Finding;
+
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct Baseline {
+    findings: BTreeSet<Entry>,
+    /// The fingerprints of `findings`, for lookups.
+    #[serde(skip)]
+    fingerprints: HashSet<String>,
+}
+
+/// A baselined finding. Only its fingerprint is used for matching; its
+/// audit and path are recorded to make the baseline reviewable.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+struct Entry {
+    path: String,
+    ident: String,
+    fingerprint: String,
+}
+
+impl Baseline {
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("couldn't read baseline {path}", path = path.display()))?;
+        let mut baseline = serde_json::from_str::<Self>(&contents)
+            .with_context(|| format!("invalid baseline {path}", path = path.display()))?;
+
+        baseline.fingerprints = baseline
+            .findings
+            .iter()
+            .map(|entry| entry.fingerprint.clone())
+            .collect();
+
+        Ok(baseline)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        let mut contents = serde_json::to_string_pretty(self)?;
+        contents.push('\n');
+
+        std::fs::write(path, contents)
+            .with_context(|| format!("couldn't write baseline {path}", path = path.display()))
+    }
+
+    /// Records `finding`, whose primary location is in the input at `path`.
+    pub(crate) fn insert(&mut self, path: &str, finding: &Finding<'_>) {
+        self.fingerprints.insert(finding.fingerprint.clone());
+        self.findings.insert(Entry {
+            path: path.into(),
+            ident: finding.ident.into(),
+            fingerprint: finding.fingerprint.clone(),
+        });
+    }
+
+    /// Returns whether `finding` is recorded in this baseline.
+    pub(crate) fn contains(&self, finding: &Finding<'_>) -> bool {
+        self.fingerprints.contains(&finding.fingerprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Baseline;
+    use crate::{
+        finding::{Finding, FindingBuilder, Severity},
+        models::{test_workflow, Workflow},
+    };
+
+    fn finding<'w>(workflow: &'w Workflow, job: &'static str) -> Finding<'w> {
+        FindingBuilder::new("example", "an example", Severity::Low)
+            .add_location(
+                workflow
+                    .location()
+                    .with_keys(&["jobs".into(), job.into(), "runs-on".into()])
+                    .annotated("here"),
+            )
+            .build(workflow)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_baseline() {
+        let dir = std::env::temp_dir();
+        let workflow = test_workflow(
+            r#"on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo hello
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo hello
+"#,
+        );
+
+        let mut baseline = Baseline::default();
+        baseline.insert("example.yml", &finding(&workflow, "build"));
+
+        let path = dir.join(format!("zizmor-baseline-test-{}.json", std::process::id()));
+        baseline.save(&path).unwrap();
+        let baseline = Baseline::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(baseline.contains(&finding(&workflow, "build")));
+        assert!(!baseline.contains(&finding(&workflow, "test")));
+    }
+}
@@ -0,0 +1,252 @@
+//! Structured fixes for findings.
+//!
+//! A [`Fix`] describes a change to a single node of an input, either
+//! structurally (as a replacement or additional YAML node) or textually.
+//! Fixes are concretized into an [`Edit`] of the input's source when their
+//! finding is built, so that they can be applied or rendered (e.g. as SARIF
+//! `fixes`) without the input.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+use super::{locate::Locator, ConcreteLocation, RouteComponent, SymbolicLocation};
+use crate::models::AuditInput;
+
+/// A change to the node at a fix's location.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case", tag = "op")]
+pub(crate) enum Patch {
+    /// Replaces the node (which must be a scalar) with `value`.
+    Replace { value: Value },
+    /// Adds `key: value` to the node, which must be a block mapping.
+    Add { key: String, value: Value },
+    /// Replaces the node's source text with `text` verbatim, e.g. to
+    /// follow a new value with a comment.
+    Rewrite { text: String },
+}
+
+/// A proposed fix for a finding, at a symbolic location.
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct Fix<'w> {
+    /// A short, human-readable description of the fix.
+    pub(crate) title: String,
+    /// The node that the fix changes.
+    pub(crate) location: SymbolicLocation<'w>,
+    pub(crate) patch: Patch,
+}
+
+/// A textual edit of an input, replacing the source at `location`
+/// with `replacement`. Insertions have an empty `location`.
+#[derive(Serialize)]
+pub(crate) struct Edit {
+    pub(crate) location: ConcreteLocation,
+    pub(crate) replacement: String,
+}
+
+/// A fix, along with the edit that applies it.
+#[derive(Serialize)]
+pub(crate) struct ConcreteFix<'w> {
+    #[serde(flatten)]
+    pub(crate) fix: Fix<'w>,
+    pub(crate) edit: Edit,
+}
+
+impl<'w> Fix<'w> {
+    pub(crate) fn new(
+        title: impl Into<String>,
+        location: SymbolicLocation<'w>,
+        patch: Patch,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            location,
+            patch,
+        }
+    }
+
+    /// Concretize this `Fix` into an edit of `input`, consuming it in
+    /// the process.
+    ///
+    /// Nodes that are reached through YAML aliases can't be fixed, since
+    /// changing their anchor would also change every other alias of it.
+    pub(crate) fn concretize(
+        self,
+        input: &'w (impl AuditInput + ?Sized),
+    ) -> Result<ConcreteFix<'w>> {
+        let (feature, aliases) = Locator::new().concretize(input, &self.location)?;
+        if let Some(alias) = aliases.first() {
+            return Err(anyhow!("can't fix a node via alias *{}", alias.name));
+        }
+
+        let source = input.document().source();
+        let (mut start, end) = (feature.location.start_offset, feature.location.end_offset);
+        // Routes that end at a key locate the whole `key: value` pair,
+        // but fixes change the value.
+        if matches!(
+            self.location.route.components.last(),
+            Some(RouteComponent::Key(_))
+        ) {
+            let node = &source[start..end];
+            if let Some((_, value)) = node.split_once(':') {
+                start = end - value.trim_start().len();
+            }
+        }
+        let location = ConcreteLocation::from_offsets(source, start, end);
+        // Continuation lines are indented to the node's own column.
+        let indent = " ".repeat(location.start_point.column);
+
+        let edit = match &self.patch {
+            Patch::Replace { value } => {
+                let text = render(value)?;
+                if text.contains('\n') {
+                    return Err(anyhow!("can only replace a node with a scalar"));
+                }
+
+                Edit {
+                    location,
+                    replacement: text,
+                }
+            }
+            Patch::Add { key, value } => {
+                if !matches!(source[start..end].chars().next(), Some(c) if c != '{' && c != '[') {
+                    return Err(anyhow!("can only add keys to a block mapping"));
+                }
+
+                let mut mapping = Mapping::new();
+                mapping.insert(Value::String(key.clone()), value.clone());
+                let lines = render(&Value::Mapping(mapping))?
+                    .lines()
+                    .map(|line| format!("{indent}{line}"))
+                    .collect::<Vec<_>>();
+
+                // The mapping's span may or may not include the newline
+                // after its last value, depending on the value.
+                let replacement = if source[..end].ends_with('\n') {
+                    lines.iter().map(|line| format!("{line}\n")).collect()
+                } else {
+                    lines.iter().map(|line| format!("\n{line}")).collect()
+                };
+
+                Edit {
+                    location: ConcreteLocation::from_offsets(source, end, end),
+                    replacement,
+                }
+            }
+            Patch::Rewrite { text } => Edit {
+                location,
+                replacement: text.replace('\n', &format!("\n{indent}")),
+            },
+        };
+
+        Ok(ConcreteFix { fix: self, edit })
+    }
+}
+
+/// Renders `value` as (block-style) YAML, without a trailing newline.
+fn render(value: &Value) -> Result<String> {
+    Ok(serde_yaml::to_string(value)?.trim_end().to_string())
+}
+
+/// Applies `edits` to `source`. Edits must not overlap.
+pub(crate) fn apply(source: &str, edits: &[&Edit]) -> Result<String> {
+    let mut edits = edits.to_vec();
+    edits.sort_by_key(|edit| edit.location.start_offset);
+
+    let mut patched = String::with_capacity(source.len());
+    let mut offset = 0;
+    for edit in edits {
+        if edit.location.start_offset < offset {
+            return Err(anyhow!("overlapping edits at offset {offset}"));
+        }
+
+        patched.push_str(&source[offset..edit.location.start_offset]);
+        patched.push_str(&edit.replacement);
+        offset = edit.location.end_offset;
+    }
+    patched.push_str(&source[offset..]);
+
+    Ok(patched)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_yaml::Value;
+
+    use super::{apply, Fix, Patch};
+    use crate::models::{test_workflow, AuditInput};
+
+    #[test]
+    fn concretize_fixes() {
+        let workflow = test_workflow(
+            r#"on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: actions/setup-python@v5
+        with:
+          python-version: "3.12"
+"#,
+        );
+
+        let steps = workflow
+            .location()
+            .with_keys(&["jobs".into(), "build".into(), "steps".into()]);
+        let fixes = [
+            Fix::new(
+                "pin",
+                steps.with_keys(&[0.into(), "uses".into()]),
+                Patch::Rewrite {
+                    text: "actions/checkout@0123456789abcdef0123456789abcdef01234567 # v4".into(),
+                },
+            ),
+            Fix::new(
+                "add with",
+                steps.with_keys(&[0.into()]),
+                Patch::Add {
+                    key: "with".into(),
+                    value: serde_yaml::from_str("persist-credentials: false").unwrap(),
+                },
+            ),
+            Fix::new(
+                "add input",
+                steps.with_keys(&[1.into(), "with".into()]),
+                Patch::Add {
+                    key: "cache".into(),
+                    value: Value::String("pip".into()),
+                },
+            ),
+            Fix::new(
+                "replace",
+                steps.with_keys(&[1.into(), "uses".into()]),
+                Patch::Replace {
+                    value: Value::String("actions/setup-python@v6".into()),
+                },
+            ),
+        ]
+        .map(|fix| fix.concretize(&workflow).unwrap());
+
+        let edits = fixes.iter().map(|fix| &fix.edit).collect::<Vec<_>>();
+        assert_eq!(
+            apply(workflow.document().source(), &edits).unwrap(),
+            r#"on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@0123456789abcdef0123456789abcdef01234567 # v4
+        with:
+          persist-credentials: false
+      - uses: actions/setup-python@v6
+        with:
+          python-version: "3.12"
+          cache: pip
+"#
+        );
+
+        // Overlapping edits can't both be applied.
+        assert!(apply(workflow.document().source(), &[edits[0], edits[0]]).is_err());
+    }
+}
@@ -1,59 +1,491 @@
 //! `tree-sitter` helpers for extracting and locating concrete features
 //! in the original YAML.
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use serde_yaml::Value;
 
-use super::{ConcreteLocation, Feature, SymbolicLocation};
-use crate::models::Workflow;
+use super::{ConcreteLocation, Feature, RouteComponent, Subfeature, SymbolicLocation};
+use crate::models::AuditInput;
+
+/// A YAML alias (e.g. `*defaults`) that a feature was reached through.
+///
+/// The feature itself is located at its anchor's definition
+/// (e.g. `&defaults`), while the alias is where it's used.
+pub(crate) struct Alias<'w> {
+    /// The alias's name, without the leading `*`.
+    pub(crate) name: &'w str,
+    /// The alias itself.
+    pub(crate) feature: Feature<'w>,
+}
 
 pub(crate) struct Locator {}
 
+/// The locations of the aliases that a feature was reached through, each
+/// along with its parent's location, outermost first.
+type Aliases = Vec<(yamlpath::Location, yamlpath::Location)>;
+
 impl Locator {
     pub(crate) fn new() -> Self {
         Self {}
     }
 
+    /// Concretizes `location`, following any aliases along its route to
+    /// their anchors. The aliases that were followed are returned
+    /// alongside the feature, outermost first.
     pub(crate) fn concretize<'w>(
         &self,
-        workflow: &'w Workflow,
+        input: &'w (impl AuditInput + ?Sized),
         location: &SymbolicLocation,
-    ) -> Result<Feature<'w>> {
-        // If we don't have a path into the workflow, all
-        // we have is the workflow itself.
-        let (feature, parent_feature) = if location.route.components.is_empty() {
-            (workflow.document.root(), workflow.document.root())
-        } else {
-            let mut builder = yamlpath::QueryBuilder::new();
-
-            for component in &location.route.components {
-                builder = match component {
-                    super::RouteComponent::Key(key) => builder.key(key.clone()),
-                    super::RouteComponent::Index(idx) => builder.index(*idx),
+    ) -> Result<(Feature<'w>, Vec<Alias<'w>>)> {
+        let document = input.document();
+        let components = &location.route.components;
+
+        // If we don't have a path into the input, all
+        // we have is the input itself.
+        if components.is_empty() {
+            let root = document.root().location;
+            return Ok((extract(document, &root, &root), vec![]));
+        }
+
+        log::trace!("querying {input}: {components:?}", input = input.name());
+
+        let (feature, aliases) = self.query(document, components)?;
+
+        let aliases = aliases
+            .iter()
+            .map(|(alias, parent)| {
+                let (start, end) = alias.byte_span;
+                Alias {
+                    // NOTE: Each alias's location starts at its `*`.
+                    name: &document.source()[start + 1..end],
+                    feature: extract(document, alias, parent),
                 }
+            })
+            .collect();
+
+        if let Some(subfeature) = &location.subfeature {
+            match narrow(document, &feature, subfeature) {
+                Some(feature) => return Ok((feature, aliases)),
+                None => log::debug!("couldn't narrow to {subfeature:?}, using whole feature"),
             }
+        }
 
-            let query = builder.build();
-            log::trace!(
-                "querying {workflow}: {query:?}",
-                workflow = workflow.filename()
-            );
+        let parent_feature = self.parent(document, components)?;
 
-            let parent_feature = if let Some(parent) = query.parent() {
-                workflow.document.query(&parent)?
-            } else {
-                workflow.document.root()
-            };
+        Ok((extract(document, &feature, &parent_feature), aliases))
+    }
 
-            (workflow.document.query(&query)?, parent_feature)
+    /// Queries `document` for the location of the node at the end of
+    /// `components`, along with the aliases that it's reached through.
+    fn query(
+        &self,
+        document: &yamlpath::Document,
+        components: &[RouteComponent<'_>],
+    ) -> Result<(yamlpath::Location, Aliases)> {
+        let err = match document.query(&build_query(components)) {
+            Ok(feature) => return Ok((feature.location, vec![])),
+            Err(e) => e,
         };
 
-        Ok(Feature {
-            location: ConcreteLocation::from(&feature.location),
-            parent_location: ConcreteLocation::from(&parent_feature.location),
-            feature: workflow.document.extract_with_leading_whitespace(&feature),
-            parent_feature: workflow
-                .document
-                .extract_with_leading_whitespace(&parent_feature),
+        // yamlpath doesn't follow aliases or descend through anchored
+        // nodes, so a route through either fails. Find the deepest prefix
+        // that ends at one, and continue from there instead.
+        let source = document.source();
+        for len in (1..components.len()).rev() {
+            let Ok(prefix) = document.query(&build_query(&components[..len])) else {
+                continue;
+            };
+            let (start, end) = prefix.location.byte_span;
+
+            // Routes that end at a key locate the whole `key: value` pair.
+            let node = &source[start..end];
+            let value = match components[len - 1] {
+                RouteComponent::Key(_) => node.split_once(':').map_or(node, |(_, value)| value),
+                RouteComponent::Index(_) => node,
+            }
+            .trim_start();
+            let value_start = end - value.len();
+
+            if let Some(name) = value.strip_prefix('*') {
+                let mut route = anchor_route(document, name, value_start)?;
+                route.extend(components[len..].iter().cloned());
+
+                let (location, mut aliases) = self.query(document, &route)?;
+                let parent = self.parent(document, &components[..len])?;
+                aliases.insert(0, (span(source, value_start, end), parent));
+
+                return Ok((location, aliases));
+            }
+
+            if let Some(anchored) = value.strip_prefix('&') {
+                // Query the rest of the route within the anchored node by
+                // itself, i.e. everything after its anchor.
+                let name_len = anchored.find(char::is_whitespace).unwrap_or(anchored.len());
+                let offset = value_start + 1 + name_len;
+                let fragment = yamlpath::Document::new(&source[offset..end])?;
+                let (location, aliases) = self.query(&fragment, &components[len..])?;
+
+                let shift = |location| shift(source, offset, location);
+                return Ok((
+                    shift(location),
+                    aliases
+                        .into_iter()
+                        .map(|(alias, parent)| (shift(alias), shift(parent)))
+                        .collect(),
+                ));
+            }
+
+            break;
+        }
+
+        Err(err.into())
+    }
+
+    /// Returns the location of the parent of the node at the end of
+    /// `components`.
+    fn parent(
+        &self,
+        document: &yamlpath::Document,
+        components: &[RouteComponent<'_>],
+    ) -> Result<yamlpath::Location> {
+        match components {
+            [] | [_] => Ok(document.root().location),
+            [parent @ .., _] => Ok(self.query(document, parent)?.0),
+        }
+    }
+}
+
+fn build_query(components: &[RouteComponent<'_>]) -> yamlpath::Query {
+    let mut builder = yamlpath::QueryBuilder::new();
+
+    for component in components {
+        builder = match component {
+            RouteComponent::Key(key) => builder.key(key.to_string()),
+            RouteComponent::Index(idx) => builder.index(*idx),
+        }
+    }
+
+    builder.build()
+}
+
+fn extract<'w>(
+    document: &'w yamlpath::Document,
+    location: &yamlpath::Location,
+    parent_location: &yamlpath::Location,
+) -> Feature<'w> {
+    Feature {
+        location: ConcreteLocation::from(location),
+        parent_location: ConcreteLocation::from(parent_location),
+        feature: extract_with_leading_whitespace(document.source(), location),
+        parent_feature: extract_with_leading_whitespace(document.source(), parent_location),
+    }
+}
+
+/// Returns the source at `location`, along with its indentation if it's
+/// the first thing on its line. This is the same as
+/// [`yamlpath::Document::extract_with_leading_whitespace`], for locations
+/// that don't come from a query.
+fn extract_with_leading_whitespace<'w>(source: &'w str, location: &yamlpath::Location) -> &'w str {
+    let (start, end) = location.byte_span;
+    let start = match source[..start].rfind('\n') {
+        Some(newline) if source[newline + 1..start].bytes().all(|b| b == b' ') => newline + 1,
+        _ => start,
+    };
+
+    &source[start..end]
+}
+
+/// Returns the `(row, column)` point of `offset` within `source`.
+fn point(source: &str, offset: usize) -> (usize, usize) {
+    let line_start = source[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+    (source[..offset].matches('\n').count(), offset - line_start)
+}
+
+/// Returns the location of `start..end` within `source`.
+fn span(source: &str, start: usize, end: usize) -> yamlpath::Location {
+    yamlpath::Location {
+        byte_span: (start, end),
+        point_span: (point(source, start), point(source, end)),
+    }
+}
+
+/// Shifts `location`, within the fragment of `source` that starts at
+/// `offset`, to be within `source` itself.
+fn shift(source: &str, offset: usize, location: yamlpath::Location) -> yamlpath::Location {
+    let (row, column) = point(source, offset);
+    // Only the fragment's first line starts partway through a line.
+    let shift_point = |(r, c): (usize, usize)| match r {
+        0 => (row, column + c),
+        _ => (row + r, c),
+    };
+
+    yamlpath::Location {
+        byte_span: (offset + location.byte_span.0, offset + location.byte_span.1),
+        point_span: (
+            shift_point(location.point_span.0),
+            shift_point(location.point_span.1),
+        ),
+    }
+}
+
+/// Narrows `feature` (a scalar) to `subfeature`, with the whole scalar
+/// as its parent.
+fn narrow<'w>(
+    document: &'w yamlpath::Document,
+    location: &yamlpath::Location,
+    subfeature: &Subfeature,
+) -> Option<Feature<'w>> {
+    let source = document.source();
+    let (start, end) = location.byte_span;
+    let raw = &source[start..end];
+
+    // Each line of a block scalar's value is indented in the source, so
+    // fragments that span lines need the same indentation.
+    let indent = raw
+        .lines()
+        .skip(1)
+        .find(|line| !line.trim().is_empty())
+        .map_or("", |line| &line[..line.len() - line.trim_start().len()]);
+    let needle = subfeature.fragment.replace('\n', &format!("\n{indent}"));
+
+    let (offset, _) = raw.match_indices(&needle).nth(subfeature.occurrence)?;
+    let (start, end) = (start + offset, start + offset + needle.len());
+
+    Some(Feature {
+        location: ConcreteLocation::from_offsets(source, start, end),
+        parent_location: ConcreteLocation::from(location),
+        feature: &source[start..end],
+        parent_feature: extract_with_leading_whitespace(source, location),
+    })
+}
+
+/// Returns the route to the node anchored as `&name`, as most recently
+/// defined before `offset` (where it's aliased).
+fn anchor_route(
+    document: &yamlpath::Document,
+    name: &str,
+    offset: usize,
+) -> Result<Vec<RouteComponent<'static>>> {
+    let source = document.source();
+    let anchor = format!("&{name}");
+
+    // Anchor names run until whitespace or a flow indicator, so e.g.
+    // `&foo` shouldn't match `&foobar`.
+    let start = source[..offset]
+        .match_indices(&anchor)
+        .map(|(start, _)| start)
+        .filter(|start| {
+            source[start + anchor.len()..]
+                .chars()
+                .next()
+                .map_or(true, |c| c.is_whitespace() || ",[]{}".contains(c))
         })
+        .last()
+        .ok_or_else(|| anyhow!("no anchor for alias *{name}"))?;
+
+    // Depending on the node, its span may or may not include its anchor.
+    let rest = &source[start + anchor.len()..];
+    let value_start = start + anchor.len() + (rest.len() - rest.trim_start().len());
+
+    // Walk down the document's structure, following whichever child
+    // contains the anchor, until we reach the anchored node itself.
+    let value: Value = serde_yaml::from_str(source)?;
+    let mut route = vec![];
+    let mut node = &value;
+    'descend: loop {
+        let children: Vec<(RouteComponent<'static>, &Value)> = match node {
+            Value::Mapping(mapping) => mapping
+                .iter()
+                .filter_map(|(key, child)| {
+                    Some((RouteComponent::Key(key.as_str()?.to_string().into()), child))
+                })
+                .collect(),
+            Value::Sequence(sequence) => sequence
+                .iter()
+                .enumerate()
+                .map(|(idx, child)| (idx.into(), child))
+                .collect(),
+            _ => break,
+        };
+
+        for (component, child) in children {
+            route.push(component);
+
+            if let Ok(feature) = document.query(&build_query(&route)) {
+                let (lo, hi) = feature.location.byte_span;
+                // Routes that end at a key span the whole `key: value` pair,
+                // so the anchor comes just after the key.
+                let after_key = lo < start
+                    && matches!(route.last(), Some(RouteComponent::Key(_)))
+                    && source[lo..start]
+                        .split_once(':')
+                        .is_some_and(|(_, rest)| rest.trim().is_empty());
+                if lo == start || lo == value_start || after_key {
+                    return Ok(route);
+                }
+                if lo < start && start < hi {
+                    node = child;
+                    continue 'descend;
+                }
+            }
+
+            route.pop();
+        }
+
+        break;
+    }
+
+    Err(anyhow!("couldn't locate anchor &{name}"))
+}
+
+/// Returns the comments adjacent to the node at `offset` in `source`: any
+/// comment at the end of the node's first line, and any comment lines
+/// directly above it.
+///
+/// Nodes that are located by key span their whole `key: value` pair, so
+/// a job's comments are those on and above its `build:` line, rather than
+/// those of its first child.
+pub(crate) fn adjacent_comments(source: &str, offset: usize) -> Vec<&str> {
+    let lines = source.lines().collect::<Vec<_>>();
+    let row = source[..offset].matches('\n').count();
+
+    let mut comments = vec![];
+    if let Some(line) = lines.get(row) {
+        comments.extend(comment(line));
+    }
+    comments.extend(
+        (0..row)
+            .rev()
+            .map(|row| lines[row].trim())
+            .take_while(|line| line.starts_with('#')),
+    );
+
+    comments
+}
+
+/// Returns the comment at the end of `line`, if any.
+fn comment(line: &str) -> Option<&str> {
+    let start = uncommented(line).len();
+    (start < line.len()).then(|| &line[start..])
+}
+
+/// Returns `line` up to its comment, if it has one. Quoted `#`s, and `#`s
+/// that aren't preceded by whitespace, don't start comments.
+fn uncommented(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (idx, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '#') if prev.is_whitespace() => return &line[..idx],
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            _ => (),
+        }
+        prev = c;
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Locator;
+    use crate::models::test_workflow;
+
+    #[test]
+    fn concretize_through_aliases() {
+        let workflow = test_workflow(
+            r#"
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    env: &env
+      FOO: foo
+      BAR: bar
+    steps: &steps
+      - run: echo "$FOO"
+  test:
+    runs-on: ubuntu-latest
+    env: *env
+    steps: *steps
+"#,
+        );
+
+        let locator = Locator::new();
+        let test = workflow
+            .location()
+            .with_keys(&["jobs".into(), "test".into()]);
+
+        let (feature, aliases) = locator
+            .concretize(&workflow, &test.with_keys(&["env".into(), "BAR".into()]))
+            .unwrap();
+        assert_eq!(feature.feature.trim(), "BAR: bar");
+        assert_eq!(feature.location.start_point.row, 7);
+        assert_eq!(feature.location.start_point.column, 6);
+        assert_eq!(aliases.len(), 1);
+        assert_eq!(aliases[0].name, "env");
+        assert_eq!(aliases[0].feature.feature.trim(), "*env");
+        assert_eq!(aliases[0].feature.location.start_point.row, 12);
+
+        let (feature, aliases) = locator
+            .concretize(
+                &workflow,
+                &test.with_keys(&["steps".into(), 0.into(), "run".into()]),
+            )
+            .unwrap();
+        assert_eq!(feature.feature.trim(), r#"run: echo "$FOO""#);
+        assert_eq!(aliases[0].name, "steps");
+
+        // Routes that don't pass through aliases are unaffected.
+        let (feature, aliases) = locator
+            .concretize(&workflow, &test.with_keys(&["runs-on".into()]))
+            .unwrap();
+        assert_eq!(feature.feature.trim(), "runs-on: ubuntu-latest");
+        assert!(aliases.is_empty());
+    }
+
+    #[test]
+    fn concretize_subspan() {
+        let workflow = test_workflow(
+            r#"on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - run: |
+          echo hello
+          curl https://example.com \
+            | bash
+          echo hello
+"#,
+        );
+
+        let script = "echo hello\ncurl https://example.com \\\n  | bash\necho hello\n";
+        let run = workflow.location().with_keys(&[
+            "jobs".into(),
+            "build".into(),
+            "steps".into(),
+            0.into(),
+            "run".into(),
+        ]);
+
+        let (feature, _) = Locator::new()
+            .concretize(&workflow, &run.clone().subspan(script, 11..46))
+            .unwrap();
+        assert_eq!(
+            feature.feature,
+            "curl https://example.com \\\n            | bash"
+        );
+        assert_eq!(feature.location.start_point.row, 7);
+        assert_eq!(feature.location.start_point.column, 10);
+        assert_eq!(feature.location.end_point.row, 8);
+
+        // Repeated fragments are told apart by their occurrence.
+        let (feature, _) = Locator::new()
+            .concretize(&workflow, &run.subspan(script, 47..57))
+            .unwrap();
+        assert_eq!(feature.feature, "echo hello");
+        assert_eq!(feature.location.start_point.row, 9);
     }
 }
@@ -1,19 +1,58 @@
 //! Models and APIs for handling findings and their locations.
 
-use std::borrow::Cow;
-
-use anyhow::Result;
+use std::{
+    borrow::Cow,
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use fix::{ConcreteFix, Fix};
 use locate::Locator;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use suppress::Suppression;
 use terminal_link::Link;
 
-use crate::models::{Job, Step, Workflow};
+use crate::{
+    models::{AuditInput, Job, Step},
+    rules,
+};
 
+pub(crate) mod baseline;
+pub(crate) mod fix;
 pub(crate) mod locate;
+pub(crate) mod store;
+pub(crate) mod suppress;
 
 // TODO: Traits + more flexible models here.
 
-#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq, Serialize)]
+/// The default number of lines of context in each location's [`Excerpt`].
+pub(crate) const DEFAULT_CONTEXT_LINES: usize = 2;
+
+static CONTEXT_LINES: AtomicUsize = AtomicUsize::new(DEFAULT_CONTEXT_LINES);
+
+/// Sets the number of lines of context to capture before and after each
+/// location in the findings that are built from now on.
+pub(crate) fn set_context_lines(lines: usize) {
+    CONTEXT_LINES.store(lines, Ordering::Relaxed);
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+    ValueEnum,
+)]
+#[serde(rename_all(deserialize = "lowercase"))]
 pub(crate) enum Confidence {
     #[default]
     Unknown,
@@ -22,34 +61,48 @@ pub(crate) enum Confidence {
     High,
 }
 
-#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq, Serialize)]
+/// How severe a finding is, from purely informational (e.g. a missed
+/// best practice) to critical (e.g. directly exploitable by anybody who
+/// can open a pull request).
+///
+/// Each audit declares a default severity for its findings, which users
+/// can override per audit in the configuration file.
+#[derive(
+    Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, ValueEnum,
+)]
+#[serde(rename_all(deserialize = "lowercase"))]
 pub(crate) enum Severity {
-    #[default]
-    Unknown,
     Informational,
     Low,
     Medium,
     High,
+    Critical,
 }
 
-#[derive(Serialize, Clone, Debug)]
-pub(crate) struct StepLocation<'w> {
-    pub(crate) index: usize,
-    pub(crate) id: Option<&'w str>,
-    pub(crate) name: Option<&'w str>,
+/// A machine-readable reason for a finding's confidence, so that
+/// downstream triage can filter on how a finding was established.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Rationale {
+    /// The finding was confirmed against GitHub's API, e.g. a runner
+    /// label that resolves to a registered self-hosted runner.
+    ResolvedViaApi,
+    /// The finding matches a literal value in the input.
+    LiteralMatch,
+    /// The finding depends on an expression that was evaluated
+    /// statically, e.g. with configured `vars`.
+    ExpressionEvaluated,
+    /// The finding depends on an expression that couldn't be evaluated,
+    /// so the worst case is assumed.
+    ExpressionUnevaluated,
+    /// The finding was established by tracing values through outputs and
+    /// environment variables, which is over-approximated.
+    DataflowTraced,
+    /// The finding is based on a naming or usage heuristic.
+    Heuristic,
 }
 
-impl<'w> From<&Step<'w>> for StepLocation<'w> {
-    fn from(step: &Step<'w>) -> Self {
-        Self {
-            index: step.index,
-            id: step.id.as_deref(),
-            name: step.name.as_deref(),
-        }
-    }
-}
-
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub(crate) enum RouteComponent<'w> {
     Key(Cow<'w, str>),
     Index(usize),
@@ -67,7 +120,7 @@ impl<'w> From<&'w str> for RouteComponent<'w> {
     }
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub(crate) struct Route<'w> {
     components: Vec<RouteComponent<'w>>,
 }
@@ -89,7 +142,7 @@ impl<'w> Route<'w> {
 /// Represents a symbolic workflow location.
 #[derive(Serialize, Clone, Debug)]
 pub(crate) struct SymbolicLocation<'w> {
-    /// The name of the workflow, as it appears in the workflow registry.
+    /// The name of the workflow or action, as it appears in the registry.
     pub(crate) name: &'w str,
 
     /// An annotation for this location.
@@ -103,6 +156,22 @@ pub(crate) struct SymbolicLocation<'w> {
 
     /// A symbolic route (of keys and indices) to the final location.
     pub(crate) route: Route<'w>,
+
+    /// A fragment of the value at the final location to narrow to, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) subfeature: Option<Subfeature>,
+}
+
+/// A fragment within a scalar value, e.g. a single statement or
+/// expression within a `run:` script.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct Subfeature {
+    /// The fragment's text, as it appears in the value.
+    pub(crate) fragment: String,
+
+    /// How many times the fragment appears in the value before this
+    /// occurrence of it.
+    pub(crate) occurrence: usize,
 }
 
 impl<'w> SymbolicLocation<'w> {
@@ -112,6 +181,7 @@ impl<'w> SymbolicLocation<'w> {
             annotation: self.annotation.clone(),
             link: None,
             route: self.route.with_keys(keys),
+            subfeature: None,
         }
     }
 
@@ -129,6 +199,21 @@ impl<'w> SymbolicLocation<'w> {
         self
     }
 
+    /// Narrows the current `SymbolicLocation` to `span`, a byte range within
+    /// `value` (the scalar value at this location), e.g. to a single
+    /// statement within a `run:` script.
+    ///
+    /// If the span can't be found in the original YAML (e.g. because the
+    /// scalar contains escapes), the location covers the whole value instead.
+    pub(crate) fn subspan(mut self, value: &str, span: Range<usize>) -> SymbolicLocation<'w> {
+        let fragment = &value[span.clone()];
+        self.subfeature = Some(Subfeature {
+            fragment: fragment.into(),
+            occurrence: value[..span.start].matches(fragment).count(),
+        });
+        self
+    }
+
     /// Adds a URL to the current `SymbolicLocation`.
     pub(crate) fn with_url(mut self, url: impl Into<String>) -> SymbolicLocation<'w> {
         self.link = Some(Link::new(&self.annotation, &url.into()).to_string());
@@ -136,13 +221,34 @@ impl<'w> SymbolicLocation<'w> {
     }
 
     /// Concretize this `SymbolicLocation`, consuming it in the process.
-    pub(crate) fn concretize(self, workflow: &'w Workflow) -> Result<Location<'w>> {
-        let feature = Locator::new().concretize(workflow, &self)?;
+    ///
+    /// If the location's route passes through YAML aliases, the location
+    /// is concretized at the anchor's definition, and each alias's use site
+    /// is returned as an additional location.
+    pub(crate) fn concretize(
+        self,
+        input: &'w (impl AuditInput + ?Sized),
+    ) -> Result<Vec<Location<'w>>> {
+        let (feature, aliases) = Locator::new().concretize(input, &self)?;
+        let source = input.document().source();
+
+        let mut locations = vec![];
+        for alias in aliases {
+            let mut location = Location::new(
+                SymbolicLocation {
+                    link: None,
+                    ..self.clone()
+                }
+                .annotated(format!("via alias *{name}", name = alias.name)),
+                alias.feature,
+                source,
+            );
+            location.via_alias = true;
+            locations.push(location);
+        }
+        locations.insert(0, Location::new(self, feature, source));
 
-        Ok(Location {
-            symbolic: self,
-            concrete: feature,
-        })
+        Ok(locations)
     }
 }
 
@@ -164,6 +270,26 @@ pub(crate) struct ConcreteLocation {
     pub(crate) end_offset: usize,
 }
 
+impl ConcreteLocation {
+    /// Returns the location of the byte range `start..end` within `source`.
+    pub(crate) fn from_offsets(source: &str, start: usize, end: usize) -> Self {
+        let point = |offset: usize| {
+            let line_start = source[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+            Point {
+                row: source[..offset].matches('\n').count(),
+                column: offset - line_start,
+            }
+        };
+
+        Self {
+            start_point: point(start),
+            end_point: point(end),
+            start_offset: start,
+            end_offset: end,
+        }
+    }
+}
+
 impl From<&yamlpath::Location> for ConcreteLocation {
     fn from(value: &yamlpath::Location) -> Self {
         Self {
@@ -206,12 +332,85 @@ pub(crate) struct Location<'w> {
     pub(crate) symbolic: SymbolicLocation<'w>,
     /// The concrete location, including extracted feature.
     pub(crate) concrete: Feature<'w>,
+    /// The source lines around the location.
+    pub(crate) excerpt: Excerpt<'w>,
+    /// Whether this is the use site of an alias that another of the
+    /// finding's locations was reached through, rather than a location
+    /// that the finding was built with.
+    #[serde(skip_serializing)]
+    pub(crate) via_alias: bool,
+}
+
+impl<'w> Location<'w> {
+    fn new(symbolic: SymbolicLocation<'w>, concrete: Feature<'w>, source: &'w str) -> Self {
+        let excerpt = Excerpt::new(
+            source,
+            &concrete.location,
+            CONTEXT_LINES.load(Ordering::Relaxed),
+        );
+
+        Self {
+            symbolic,
+            concrete,
+            excerpt,
+            via_alias: false,
+        }
+    }
+}
+
+/// The source lines spanned by a location, plus some lines of context
+/// before and after it. Excerpts are captured when findings are built, so
+/// that findings can be rendered without their inputs.
+#[derive(Serialize)]
+pub(crate) struct Excerpt<'w> {
+    /// The 0-based row of the excerpt's first line.
+    pub(crate) start_row: usize,
+    /// The byte offset of the excerpt's first line within its input.
+    pub(crate) start_offset: usize,
+    /// The excerpt's lines, without a trailing newline.
+    pub(crate) text: &'w str,
+    /// The input's entire source, for merging nearby excerpts.
+    #[serde(skip_serializing)]
+    pub(crate) source: &'w str,
+}
+
+impl<'w> Excerpt<'w> {
+    fn new(source: &'w str, location: &ConcreteLocation, context: usize) -> Self {
+        let start_row = location.start_point.row.saturating_sub(context);
+        let end_row = location.end_point.row + context;
+
+        // The byte offset of the start of each line.
+        let lines = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect::<Vec<_>>();
+
+        let start_offset = lines.get(start_row).copied().unwrap_or(source.len());
+        let end_offset = match lines.get(end_row + 1) {
+            Some(next) => next - 1,
+            None => source.len(),
+        };
+
+        Self {
+            start_row,
+            start_offset,
+            text: source[start_offset..end_offset.max(start_offset)].trim_end_matches('\r'),
+            source,
+        }
+    }
+
+    /// The byte offset just past the excerpt's last line.
+    pub(crate) fn end_offset(&self) -> usize {
+        self.start_offset + self.text.len()
+    }
 }
 
 /// A finding's "determination," i.e. its confidence and severity classifications.
 #[derive(Serialize)]
 pub(crate) struct Determinations {
     pub(crate) confidence: Confidence,
+    /// Why the finding has its confidence, if the audit says.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) rationale: Option<Rationale>,
     pub(crate) severity: Severity,
 }
 
@@ -221,15 +420,24 @@ pub(crate) struct Finding<'w> {
     pub(crate) desc: &'static str,
     pub(crate) determinations: Determinations,
     pub(crate) locations: Vec<Location<'w>>,
+    /// A content-based fingerprint, which stays the same across edits
+    /// that only move the finding around. See [`fingerprint`].
+    pub(crate) fingerprint: String,
+    /// A human-readable suggestion for remediating this finding, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) suggestion: Option<String>,
+    /// Machine-applicable fixes for this finding, if any.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) fixes: Vec<ConcreteFix<'w>>,
+    /// Why this finding is suppressed, if it is. Suppressed findings
+    /// are only reported with `--show-suppressed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) suppression: Option<Suppression>,
 }
 
 impl<'w> Finding<'w> {
     pub(crate) fn url(&self) -> String {
-        format!(
-            "{repo}/blob/main/docs/audits.md#{ident}",
-            repo = env!("CARGO_PKG_REPOSITORY"),
-            ident = self.ident
-        )
+        rules::url(self.ident)
     }
 }
 
@@ -238,17 +446,23 @@ pub(crate) struct FindingBuilder<'w> {
     desc: &'static str,
     severity: Severity,
     confidence: Confidence,
+    rationale: Option<Rationale>,
     locations: Vec<SymbolicLocation<'w>>,
+    suggestion: Option<String>,
+    fixes: Vec<Fix<'w>>,
 }
 
 impl<'w> FindingBuilder<'w> {
-    pub(crate) fn new(ident: &'static str, desc: &'static str) -> Self {
+    pub(crate) fn new(ident: &'static str, desc: &'static str, severity: Severity) -> Self {
         Self {
             ident,
             desc,
-            severity: Default::default(),
+            severity,
             confidence: Default::default(),
+            rationale: None,
             locations: vec![],
+            suggestion: None,
+            fixes: vec![],
         }
     }
 
@@ -262,24 +476,194 @@ impl<'w> FindingBuilder<'w> {
         self
     }
 
+    pub(crate) fn rationale(mut self, rationale: Rationale) -> Self {
+        self.rationale = Some(rationale);
+        self
+    }
+
     pub(crate) fn add_location(mut self, location: SymbolicLocation<'w>) -> Self {
         self.locations.push(location);
         self
     }
 
-    pub(crate) fn build(self, workflow: &'w Workflow) -> Result<Finding<'w>> {
+    pub(crate) fn suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    pub(crate) fn fix(mut self, fix: Fix<'w>) -> Self {
+        self.fixes.push(fix);
+        self
+    }
+
+    /// Builds the finding, concretizing its locations against `input`
+    /// (or, for locations in reusable workflows that it calls, against
+    /// the called workflow).
+    pub(crate) fn build(self, input: &'w (impl AuditInput + ?Sized)) -> Result<Finding<'w>> {
+        let mut suppressed = false;
+        let locations = self
+            .locations
+            .into_iter()
+            .map(|l| match input.resolve(l.name) {
+                Some(input) => {
+                    suppressed = suppressed || suppress::is_suppressed(self.ident, input, &l);
+                    l.concretize(input)
+                }
+                None => Err(anyhow!("location in unknown input: {name}", name = l.name)),
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        // A fix that can't be concretized doesn't invalidate its finding,
+        // which can still be fixed by hand.
+        let fixes = self
+            .fixes
+            .into_iter()
+            .filter_map(|fix| {
+                let name = fix.location.name;
+                let Some(input) = input.resolve(name) else {
+                    log::warn!("fix in unknown input: {name}");
+                    return None;
+                };
+                fix.concretize(input)
+                    .inspect_err(|e| log::warn!("couldn't concretize fix in {name}: {e}"))
+                    .ok()
+            })
+            .collect();
+
         Ok(Finding {
             ident: self.ident,
             desc: self.desc,
             determinations: Determinations {
                 confidence: self.confidence,
+                rationale: self.rationale,
                 severity: self.severity,
             },
-            locations: self
-                .locations
-                .into_iter()
-                .map(|l| l.concretize(workflow))
-                .collect::<Result<Vec<_>>>()?,
+            fingerprint: fingerprint(self.ident, &locations),
+            locations,
+            suggestion: self.suggestion,
+            fixes,
+            suppression: suppressed.then_some(Suppression::Comment),
         })
     }
 }
+
+/// Computes a fingerprint for a finding from its audit's identifier and,
+/// for each of its locations, the input's name, the symbolic route, and
+/// the (whitespace-normalized) feature.
+///
+/// Line numbers and annotations aren't included, so the fingerprint
+/// survives edits elsewhere in the input, and rewording of annotations.
+fn fingerprint(ident: &str, locations: &[Location<'_>]) -> String {
+    // 64-bit FNV-1a, which (unlike `std`'s hashers) is stable across
+    // Rust versions and platforms. Each part is terminated with a NUL,
+    // so that e.g. `["ab", "c"]` and `["a", "bc"]` hash differently.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut update = |part: &str| {
+        for byte in part.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+
+    update(ident);
+    for location in locations {
+        update(location.symbolic.name);
+        for component in &location.symbolic.route.components {
+            match component {
+                RouteComponent::Key(key) => update(key),
+                RouteComponent::Index(idx) => update(&idx.to_string()),
+            }
+        }
+        update(
+            &location
+                .concrete
+                .feature
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+    }
+
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        fingerprint, ConcreteLocation, Excerpt, Feature, Location, Route, SymbolicLocation,
+    };
+
+    fn location<'w>(route: &[&'w str], feature: &'w str, offset: usize) -> Location<'w> {
+        let concrete = || ConcreteLocation::from_offsets(feature, 0, 0);
+
+        Location::new(
+            SymbolicLocation {
+                name: "ci.yml",
+                annotation: format!("found at {offset}"),
+                link: None,
+                route: Route::new()
+                    .with_keys(&route.iter().map(|key| (*key).into()).collect::<Vec<_>>()),
+                subfeature: None,
+            },
+            Feature {
+                location: ConcreteLocation {
+                    start_offset: offset,
+                    ..concrete()
+                },
+                parent_location: concrete(),
+                feature,
+                parent_feature: feature,
+            },
+            feature,
+        )
+    }
+
+    #[test]
+    fn test_excerpt() {
+        let source = "a\nb\nc\nd\ne\n";
+        let feature = ConcreteLocation::from_offsets(source, 4, 5);
+
+        let excerpt = Excerpt::new(source, &feature, 1);
+        assert_eq!(excerpt.text, "b\nc\nd");
+        assert_eq!(excerpt.start_row, 1);
+        assert_eq!(excerpt.start_offset, 2);
+        assert_eq!(excerpt.end_offset(), 7);
+
+        // Context is clamped to the input.
+        let excerpt = Excerpt::new(source, &feature, 10);
+        assert_eq!(excerpt.text, "a\nb\nc\nd\ne\n");
+        assert_eq!(excerpt.start_row, 0);
+
+        assert_eq!(Excerpt::new(source, &feature, 0).text, "c");
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let base = fingerprint("example", &[location(&["jobs", "a"], "echo  hi", 10)]);
+
+        // Moving the feature, reindenting it, or changing its annotation
+        // doesn't change the fingerprint...
+        assert_eq!(
+            fingerprint("example", &[location(&["jobs", "a"], "\n  echo hi", 99)]),
+            base
+        );
+
+        // ...but changing what or where it is does.
+        assert_ne!(
+            fingerprint("other", &[location(&["jobs", "a"], "echo hi", 10)]),
+            base
+        );
+        assert_ne!(
+            fingerprint("example", &[location(&["jobs", "b"], "echo hi", 10)]),
+            base
+        );
+        assert_ne!(
+            fingerprint("example", &[location(&["jobs", "a"], "echo bye", 10)]),
+            base
+        );
+        assert_eq!(base.len(), 16);
+    }
+}
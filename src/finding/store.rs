@@ -0,0 +1,210 @@
+//! Input-independent representations of findings, for storing findings
+//! (e.g. in the result cache) and rebuilding them against their input later.
+//!
+//! A stored finding keeps only what its audit built it with: its
+//! determinations, symbolic locations, suggestion, and fixes. Everything
+//! concrete (spans, excerpts, fingerprints) is recomputed when it's rebuilt.
+
+use std::{borrow::Cow, fmt::Debug};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::{
+    fix::{Fix, Patch},
+    Confidence, Finding, FindingBuilder, Rationale, Route, RouteComponent, Severity, Subfeature,
+    SymbolicLocation,
+};
+use crate::models::AuditInput;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoredFinding {
+    #[serde(serialize_with = "serialize_lowercase")]
+    severity: Severity,
+    #[serde(serialize_with = "serialize_lowercase")]
+    confidence: Confidence,
+    rationale: Option<Rationale>,
+    locations: Vec<StoredLocation>,
+    suggestion: Option<String>,
+    fixes: Vec<StoredFix>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredLocation {
+    name: String,
+    annotation: String,
+    link: Option<String>,
+    route: Route<'static>,
+    subfeature: Option<Subfeature>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredFix {
+    title: String,
+    location: StoredLocation,
+    patch: Patch,
+}
+
+/// Severities and confidences are deserialized from lowercase (as in the
+/// configuration file), but serialized capitalized (as in our output formats).
+fn serialize_lowercase<T: Debug, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{value:?}").to_lowercase())
+}
+
+impl StoredLocation {
+    fn new(location: &SymbolicLocation<'_>) -> Self {
+        Self {
+            name: location.name.into(),
+            annotation: location.annotation.clone(),
+            link: location.link.clone(),
+            route: Route {
+                components: location
+                    .route
+                    .components
+                    .iter()
+                    .map(|component| match component {
+                        RouteComponent::Key(key) => {
+                            RouteComponent::Key(Cow::Owned(key.to_string()))
+                        }
+                        RouteComponent::Index(idx) => RouteComponent::Index(*idx),
+                    })
+                    .collect(),
+            },
+            subfeature: location.subfeature.clone(),
+        }
+    }
+
+    /// Returns this location as a symbolic location in `input`, or in one
+    /// of the inputs that it resolves.
+    fn symbolic<'w>(self, input: &'w dyn AuditInput) -> Result<SymbolicLocation<'w>> {
+        let name = input
+            .resolve(&self.name)
+            .ok_or_else(|| anyhow!("stored location in unknown input: {}", self.name))?
+            .name();
+
+        Ok(SymbolicLocation {
+            name,
+            annotation: self.annotation,
+            link: self.link,
+            route: self.route,
+            subfeature: self.subfeature,
+        })
+    }
+}
+
+impl StoredFinding {
+    pub(crate) fn new(finding: &Finding<'_>) -> Self {
+        Self {
+            severity: finding.determinations.severity,
+            confidence: finding.determinations.confidence,
+            rationale: finding.determinations.rationale,
+            // Alias use sites are recomputed when the finding is rebuilt.
+            locations: finding
+                .locations
+                .iter()
+                .filter(|location| !location.via_alias)
+                .map(|location| StoredLocation::new(&location.symbolic))
+                .collect(),
+            suggestion: finding.suggestion.clone(),
+            fixes: finding
+                .fixes
+                .iter()
+                .map(|fix| StoredFix {
+                    title: fix.fix.title.clone(),
+                    location: StoredLocation::new(&fix.fix.location),
+                    patch: fix.fix.patch.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Rebuilds this finding against `input`, as a finding of the audit
+    /// with the given identifier and description.
+    pub(crate) fn rebuild<'w>(
+        self,
+        ident: &'static str,
+        desc: &'static str,
+        input: &'w dyn AuditInput,
+    ) -> Result<Finding<'w>> {
+        let mut builder =
+            FindingBuilder::new(ident, desc, self.severity).confidence(self.confidence);
+
+        if let Some(rationale) = self.rationale {
+            builder = builder.rationale(rationale);
+        }
+        if let Some(suggestion) = self.suggestion {
+            builder = builder.suggestion(suggestion);
+        }
+        for location in self.locations {
+            builder = builder.add_location(location.symbolic(input)?);
+        }
+        for fix in self.fixes {
+            builder = builder.fix(Fix::new(
+                fix.title,
+                fix.location.symbolic(input)?,
+                fix.patch,
+            ));
+        }
+
+        builder.build(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StoredFinding;
+    use crate::{
+        finding::{Confidence, FindingBuilder, Rationale, Severity},
+        models::test_workflow,
+    };
+
+    #[test]
+    fn test_rebuild() {
+        let workflow = test_workflow(
+            r#"on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    env: &env
+      FOO: foo
+    steps: &steps
+      - run: echo "$FOO"
+  test:
+    runs-on: ubuntu-latest
+    env: *env
+    steps: *steps
+"#,
+        );
+
+        let finding = FindingBuilder::new("example", "an example", Severity::Low)
+            .severity(Severity::High)
+            .confidence(Confidence::Medium)
+            .rationale(Rationale::Heuristic)
+            .add_location(
+                workflow
+                    .location()
+                    .with_keys(&["jobs".into(), "test".into(), "env".into(), "FOO".into()])
+                    .annotated("foo"),
+            )
+            .build(&workflow)
+            .unwrap();
+        assert_eq!(finding.locations.len(), 2);
+
+        let stored = serde_json::to_string(&StoredFinding::new(&finding)).unwrap();
+        let rebuilt = serde_json::from_str::<StoredFinding>(&stored)
+            .unwrap()
+            .rebuild("example", "an example", &workflow)
+            .unwrap();
+
+        assert_eq!(rebuilt.determinations.severity, Severity::High);
+        assert_eq!(rebuilt.determinations.confidence, Confidence::Medium);
+        assert_eq!(rebuilt.determinations.rationale, Some(Rationale::Heuristic));
+        assert_eq!(rebuilt.fingerprint, finding.fingerprint);
+        // The alias's use site isn't duplicated.
+        assert_eq!(rebuilt.locations.len(), 2);
+        assert!(rebuilt.locations[1].via_alias);
+    }
+}
@@ -0,0 +1,153 @@
+//! Inline suppression comments, e.g. `# zizmor: ignore[unpinned-uses]`.
+//!
+//! A suppression comment applies to the findings of the audits that it
+//! lists (separated by commas) when it's adjacent to one of the finding's
+//! locations, or to the job or step that the location is in. It can be
+//! followed by a reason:
+//!
+//! ```yaml
+//! - uses: actions/checkout@v4 # zizmor: ignore[unpinned-uses]: internal mirror
+//! ```
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use super::{locate, RouteComponent, SymbolicLocation};
+use crate::models::AuditInput;
+
+/// Why a finding is suppressed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Suppression {
+    /// By an inline `# zizmor: ignore[...]` comment.
+    Comment,
+    /// By an `ignore` entry in the configuration file.
+    Config,
+    /// By the `--baseline` file.
+    Baseline,
+}
+
+static IGNORE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"zizmor:\s*ignore\[([^\]]*)\]").unwrap());
+
+/// Returns whether `comment` suppresses findings of the audit `ident`.
+fn suppresses(comment: &str, ident: &str) -> bool {
+    IGNORE.captures_iter(comment).any(|captures| {
+        captures[1]
+            .split(',')
+            .any(|suppressed| suppressed.trim() == ident)
+    })
+}
+
+/// Returns the number of leading components of `components` that make up
+/// each of the enclosing scopes that a suppression comment can be attached
+/// to: the job and step that it's in, if any.
+fn scopes(components: &[RouteComponent<'_>]) -> Vec<usize> {
+    let is_key = |idx: usize, key: &str| matches!(components.get(idx), Some(RouteComponent::Key(k)) if k == key);
+
+    let mut scopes = vec![];
+    if is_key(0, "jobs") && components.len() > 2 {
+        scopes.push(2);
+        if is_key(2, "steps") && components.len() > 4 {
+            scopes.push(4);
+        }
+    }
+    // Composite actions' steps.
+    if is_key(0, "runs") && is_key(1, "steps") && components.len() > 3 {
+        scopes.push(3);
+    }
+
+    scopes
+}
+
+/// Returns whether findings of the audit `ident` at `location` (within
+/// `input`) are suppressed by a comment.
+pub(crate) fn is_suppressed(
+    ident: &str,
+    input: &(impl AuditInput + ?Sized),
+    location: &SymbolicLocation<'_>,
+) -> bool {
+    let source = input.document().source();
+    let locator = locate::Locator::new();
+
+    let scopes = scopes(&location.route.components)
+        .into_iter()
+        .map(|len| {
+            let mut scope = location.clone();
+            scope.route.components.truncate(len);
+            scope.subfeature = None;
+            scope
+        })
+        .collect::<Vec<_>>();
+
+    std::iter::once(location)
+        .chain(&scopes)
+        .filter_map(|location| locator.concretize(input, location).ok())
+        .flat_map(|(feature, _)| locate::adjacent_comments(source, feature.location.start_offset))
+        .any(|comment| suppresses(comment, ident))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_suppressed, suppresses};
+    use crate::models::test_workflow;
+
+    #[test]
+    fn test_suppresses() {
+        assert!(suppresses("# zizmor: ignore[example]", "example"));
+        assert!(suppresses("#zizmor:ignore[other, example]", "example"));
+        assert!(suppresses(
+            "# zizmor: ignore[example]: a good reason",
+            "example"
+        ));
+
+        assert!(!suppresses("# zizmor: ignore[other]", "example"));
+        assert!(!suppresses("# zizmor: ignore[example-audit]", "example"));
+        assert!(!suppresses("# ignore[example]", "example"));
+    }
+
+    #[test]
+    fn test_is_suppressed() {
+        let workflow = test_workflow(
+            r#"on: push
+jobs:
+  # zizmor: ignore[job-level]
+  build:
+    runs-on: ubuntu-latest # zizmor: ignore[line-level]
+    steps:
+      # zizmor: ignore[step-level]
+      - name: "not # a comment"
+        run: echo hello
+      - uses: actions/checkout@v4
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo hello
+"#,
+        );
+
+        let build = workflow
+            .location()
+            .with_keys(&["jobs".into(), "build".into()]);
+        let runs_on = build.with_keys(&["runs-on".into()]);
+        let run = build.with_keys(&["steps".into(), 0.into(), "run".into()]);
+        let uses = build.with_keys(&["steps".into(), 1.into(), "uses".into()]);
+        let test = workflow
+            .location()
+            .with_keys(&["jobs".into(), "test".into(), "runs-on".into()]);
+
+        assert!(is_suppressed("line-level", &workflow, &runs_on));
+        assert!(!is_suppressed("line-level", &workflow, &uses));
+
+        assert!(is_suppressed("step-level", &workflow, &run));
+        assert!(!is_suppressed("step-level", &workflow, &uses));
+
+        assert!(is_suppressed("job-level", &workflow, &runs_on));
+        assert!(is_suppressed("job-level", &workflow, &uses));
+        assert!(!is_suppressed("job-level", &workflow, &test));
+
+        assert!(!is_suppressed("a", &workflow, &run));
+    }
+}
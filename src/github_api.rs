@@ -2,25 +2,102 @@
 //!
 //! Build on synchronous reqwest to avoid octocrab's need to taint
 //! the whole codebase with async.
+//!
+//! Requests are scheduled within GitHub's rate limits (see
+//! [`crate::ratelimit`]), and requests that hit them anyway (or transient
+//! server errors) are retried with backoff. Successful responses are cached on disk along with
+//! their `ETag`, so that later runs can revalidate them with conditional
+//! requests, which don't count against the rate limit.
+//!
+//! Data that changes occasionally (resolved refs, tags, advisories, and
+//! runners) is also cached on disk for a while, so that repeated runs (e.g.
+//! over every repository in an organization) don't make any requests for
+//! it at all. If GitHub can't be reached, expired data is used instead.
+//!
+//! GitHub Enterprise Server instances don't necessarily have every endpoint
+//! that GitHub.com does (depending on their version), so endpoints that might
+//! be missing are surfaced as such, for their audits to fall back on.
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::{anyhow, Result};
 use reqwest::{
     blocking,
-    header::{HeaderMap, ACCEPT, AUTHORIZATION, USER_AGENT},
+    header::{HeaderMap, ACCEPT, AUTHORIZATION, ETAG, IF_NONE_MATCH, USER_AGENT},
     StatusCode,
 };
-use serde::{de::DeserializeOwned, Deserialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    models::Uses,
+    ratelimit::{Budget, Exhausted, Usage},
+    state::Caches,
+};
+
+/// The number of times a request is retried before giving up.
+const MAX_RETRIES: u32 = 3;
+
+/// The longest we're willing to wait before retrying a request, e.g. for
+/// a rate limit to reset.
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// How long resolved refs and tags are reused from the on-disk cache.
+const REF_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// How long self-hosted runners are reused from the on-disk cache.
+const RUNNER_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// How long advisories are reused from the on-disk cache.
+const ADVISORY_MAX_AGE: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// A response from GitHub's API, possibly revalidated from the cache.
+struct Response {
+    status: StatusCode,
+    body: String,
+}
+
+impl Response {
+    fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_str(&self.body)?)
+    }
+}
+
+/// A response body in the on-disk cache, along with its `ETag`.
+#[derive(Deserialize, Serialize)]
+struct CachedResponse {
+    etag: String,
+    body: String,
+}
 
-use crate::state::Caches;
+/// Returns the base URL of the API of the GitHub instance at `hostname`,
+/// i.e. `github.com` or a GitHub Enterprise Server instance.
+pub(crate) fn api_url(hostname: &str) -> String {
+    match hostname {
+        "github.com" => "https://api.github.com".into(),
+        hostname => format!("https://{hostname}/api/v3"),
+    }
+}
 
+#[derive(Clone)]
 pub(crate) struct Client {
-    api_base: &'static str,
+    api_base: String,
     http: blocking::Client,
     caches: Caches,
+    /// The rate limit budget, shared by every copy of this client.
+    budget: Arc<Budget>,
+    /// The audit that this copy of the client makes requests for, if any.
+    audit: Option<&'static str>,
 }
 
 impl Client {
-    pub(crate) fn new(token: &str, caches: Caches) -> Self {
+    /// Creates a client for the GitHub API at `api_url` (see [`api_url`]),
+    /// which makes at most `budget` requests, if given.
+    pub(crate) fn new(api_url: &str, token: &str, caches: Caches, budget: Option<u64>) -> Self {
         let mut headers = HeaderMap::new();
         headers.insert(USER_AGENT, "zizmor".parse().unwrap());
         headers.insert(
@@ -33,16 +110,186 @@ impl Client {
         headers.insert(ACCEPT, "application/vnd.github+json".parse().unwrap());
 
         Self {
-            api_base: "https://api.github.com",
+            api_base: api_url.trim_end_matches('/').into(),
             http: blocking::Client::builder()
                 .default_headers(headers)
                 .build()
                 .expect("couldn't build GitHub client?"),
             caches,
+            budget: Arc::new(Budget::new(budget, MAX_RETRY_WAIT)),
+            audit: None,
         }
     }
 
-    fn paginate<T: DeserializeOwned>(&self, endpoint: &str) -> reqwest::Result<Vec<T>> {
+    /// Returns a copy of this client that makes its requests on behalf of
+    /// `audit`, which is truncated if the budget runs out.
+    pub(crate) fn for_audit(&self, audit: &'static str) -> Self {
+        Self {
+            audit: Some(audit),
+            ..self.clone()
+        }
+    }
+
+    /// Returns whether `audit` was truncated because the budget ran out.
+    pub(crate) fn is_truncated(&self, audit: &str) -> bool {
+        self.budget.is_truncated(audit)
+    }
+
+    /// Returns how much of the rate limit has been used so far.
+    pub(crate) fn usage(&self) -> Usage {
+        self.budget.usage()
+    }
+
+    /// Sends a `GET` request for `url`, retrying it if we're rate limited
+    /// or GitHub has a transient failure, and revalidating any cached
+    /// response for it.
+    fn get(&self, url: &str, query: &[(&str, &str)], accept: Option<&str>) -> Result<Response> {
+        let key = {
+            let mut hasher = DefaultHasher::new();
+            (url, query, accept).hash(&mut hasher);
+            format!("{:016x}.json", hasher.finish())
+        };
+        let cached = self
+            .caches
+            .api_response_disk
+            .get(&key)
+            .and_then(|cached| serde_json::from_str::<CachedResponse>(&cached).ok());
+
+        let mut attempt = 0;
+        let resp = loop {
+            let permit = self.budget.acquire(self.audit)?;
+
+            let mut request = self.http.get(url).query(query);
+            if let Some(accept) = accept {
+                request = request.header(ACCEPT, accept);
+            }
+            if let Some(cached) = &cached {
+                request = request.header(IF_NONE_MATCH, &cached.etag);
+            }
+
+            let resp = request.send();
+            if let Ok(resp) = &resp {
+                self.budget
+                    .record(resp.headers(), resp.status() == StatusCode::NOT_MODIFIED);
+            }
+            drop(permit);
+
+            let limited = resp.as_ref().is_ok_and(rate_limited);
+            let delay = match &resp {
+                Ok(resp) => retry_delay(resp, attempt),
+                Err(e) if e.is_timeout() || e.is_connect() => Some(backoff(attempt)),
+                Err(_) => None,
+            };
+
+            match delay {
+                // Rate limits apply to every request, so they pause every
+                // request (or spend the budget, if they're too long).
+                Some(delay) if limited && attempt < MAX_RETRIES => {
+                    log::warn!("GitHub API rate limit hit; retrying {url} in {delay:?}");
+                    self.budget.limited(delay, self.audit);
+                    attempt += 1;
+                }
+                Some(_) if limited => {
+                    self.budget.exhaust(self.audit);
+                    return Err(Exhausted.into());
+                }
+                Some(delay) if attempt < MAX_RETRIES && delay <= MAX_RETRY_WAIT => {
+                    log::warn!("GitHub API request failed; retrying {url} in {delay:?}");
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                _ => break resp?,
+            }
+        };
+
+        let status = resp.status();
+        if let (StatusCode::NOT_MODIFIED, Some(cached)) = (status, cached) {
+            return Ok(Response {
+                status: StatusCode::OK,
+                body: cached.body,
+            });
+        }
+
+        let etag = resp
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(String::from);
+        let body = resp.text()?;
+
+        if let (StatusCode::OK, Some(etag)) = (status, etag) {
+            let cached = CachedResponse {
+                etag,
+                body: body.clone(),
+            };
+            self.caches
+                .api_response_disk
+                .put(&key, &serde_json::to_string(&cached)?);
+        }
+
+        Ok(Response { status, body })
+    }
+
+    /// Returns the OAuth scopes of our token, or `None` if it doesn't have
+    /// any, i.e. isn't a classic personal access token.
+    pub(crate) fn token_scopes(&self) -> Result<Option<Vec<String>>> {
+        let resp = self.http.get(&self.api_base).send()?;
+
+        Ok(resp
+            .headers()
+            .get("x-oauth-scopes")
+            .and_then(|scopes| scopes.to_str().ok())
+            .map(|scopes| {
+                scopes
+                    .split(',')
+                    .map(|scope| scope.trim().to_string())
+                    .filter(|scope| !scope.is_empty())
+                    .collect()
+            }))
+    }
+
+    /// Returns the result of `fetch`, or the on-disk cache's copy of it (under
+    /// `key`) if that's younger than `max_age`.
+    ///
+    /// If `fetch` fails (e.g. because GitHub is unreachable), an expired copy
+    /// is returned instead, if there is one.
+    fn cached<T: Serialize + DeserializeOwned>(
+        &self,
+        key: &str,
+        max_age: Duration,
+        fetch: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let disk = &self.caches.api_data_disk;
+        // Each GitHub instance has its own namespace, e.g.
+        // `api.github.com` or `ghes.example.com_api_v3`.
+        let instance = self
+            .api_base
+            .split_once("://")
+            .map_or(self.api_base.as_str(), |(_, rest)| rest)
+            .replace(['/', ':'], "_");
+        let key = format!("{instance}/{key}.json");
+        let load = |cached: String| serde_json::from_str::<T>(&cached).ok();
+
+        if let Some(cached) = disk.get_fresh(&key, max_age).and_then(load) {
+            return Ok(cached);
+        }
+
+        match fetch() {
+            Ok(value) => {
+                disk.put(&key, &serde_json::to_string(&value)?);
+                Ok(value)
+            }
+            Err(e) => match disk.get(&key).and_then(load) {
+                Some(cached) => {
+                    log::warn!("{e}; using expired data from the cache");
+                    Ok(cached)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    fn paginate<T: DeserializeOwned>(&self, endpoint: &str) -> Result<Vec<T>> {
         let mut dest = vec![];
         let url = format!("{api_base}/{endpoint}", api_base = self.api_base);
 
@@ -50,14 +297,19 @@ impl Client {
         // the remaining number of pages. But this is annoying, and we are
         // not nice, so we simply request pages until GitHub bails on us
         // and returns empty results.
-        let mut pageno = 0;
+        let mut pageno = 1;
         loop {
-            let resp = self
-                .http
-                .get(&url)
-                .query(&[("page", pageno), ("per_page", 100)])
-                .send()?
-                .error_for_status()?;
+            let resp = self.get(
+                &url,
+                &[("page", &pageno.to_string()), ("per_page", "100")],
+                None,
+            )?;
+            if resp.status != StatusCode::OK {
+                return Err(anyhow!(
+                    "error from GitHub API while accessing {endpoint}: {s}",
+                    s = resp.status
+                ));
+            }
 
             let page = resp.json::<Vec<T>>()?;
             if page.is_empty() {
@@ -77,23 +329,40 @@ impl Client {
             .try_get_with((owner.into(), repo.into()), || {
                 self.paginate(&format!("repos/{owner}/{repo}/branches"))
             })
-            .map_err(Into::into)
+            .map_err(|e| anyhow!("{e}"))
     }
 
     pub(crate) fn list_tags(&self, owner: &str, repo: &str) -> Result<Vec<Tag>> {
         self.caches
             .tag_cache
             .try_get_with((owner.into(), repo.into()), || {
-                self.paginate(&format!("repos/{owner}/{repo}/tags"))
+                self.cached(&format!("tags/{owner}/{repo}"), REF_MAX_AGE, || {
+                    self.paginate(&format!("repos/{owner}/{repo}/tags"))
+                })
             })
-            .map_err(Into::into)
+            .map_err(|e| anyhow!("{e}"))
     }
 
+    /// Returns the commit that `git_ref` (a branch or tag) points to in
+    /// `owner/repo`, or `None` if there's no such ref.
     pub(crate) fn commit_for_ref(
         &self,
         owner: &str,
         repo: &str,
         git_ref: &str,
+    ) -> Result<Option<String>> {
+        self.cached(
+            &format!("refs/{owner}/{repo}/{git_ref}"),
+            REF_MAX_AGE,
+            || self.fetch_commit_for_ref(owner, repo, git_ref),
+        )
+    }
+
+    fn fetch_commit_for_ref(
+        &self,
+        owner: &str,
+        repo: &str,
+        git_ref: &str,
     ) -> Result<Option<String>> {
         // GitHub Actions generally resolves branches before tags, so try
         // the repo's branches first.
@@ -102,8 +371,8 @@ impl Client {
             api_base = self.api_base
         );
 
-        let resp = self.http.get(url).send()?;
-        match resp.status() {
+        let resp = self.get(&url, &[], None)?;
+        match resp.status {
             StatusCode::OK => Ok(Some(resp.json::<GitRef>()?.object.sha)),
             StatusCode::NOT_FOUND => {
                 let url = format!(
@@ -111,8 +380,8 @@ impl Client {
                     api_base = self.api_base
                 );
 
-                let resp = self.http.get(url).send()?;
-                match resp.status() {
+                let resp = self.get(&url, &[], None)?;
+                match resp.status {
                     StatusCode::OK => Ok(Some(resp.json::<GitRef>()?.object.sha)),
                     StatusCode::NOT_FOUND => Ok(None),
                     s => Err(anyhow!(
@@ -163,41 +432,360 @@ impl Client {
                     api_base = self.api_base
                 );
 
-                let resp = self.http.get(url).send()?;
+                let resp = self.get(&url, &[], None)?;
 
-                match resp.status() {
+                match resp.status {
                     StatusCode::OK => {
-                        Ok::<_, reqwest::Error>(Some(resp.json::<Comparison>()?.status))
+                        Ok::<_, anyhow::Error>(Some(resp.json::<Comparison>()?.status))
                     }
                     StatusCode::NOT_FOUND => Ok(None),
-                    _ => Err(resp.error_for_status().unwrap_err()),
+                    s => Err(anyhow!(
+                        "{owner}/{repo}: error from GitHub API while comparing {base}...{head}: {s}"
+                    )),
+                }
+            })
+            .map_err(|e| anyhow!("{e}"))
+    }
+
+    /// Returns every self-hosted runner available to `owner/repo`, including
+    /// runners shared from `owner` if it's an organization.
+    ///
+    /// Returns `None` if our token can't see the repository's runners, which
+    /// requires admin access.
+    pub(crate) fn list_self_hosted_runners(
+        &self,
+        owner: &str,
+        repo: &str,
+    ) -> Result<Option<Vec<Runner>>> {
+        self.caches
+            .runner_cache
+            .try_get_with((owner.into(), repo.into()), || {
+                self.cached(&format!("runners/{owner}/{repo}"), RUNNER_MAX_AGE, || {
+                    self.fetch_self_hosted_runners(owner, repo)
+                })
+            })
+            .map_err(|e| anyhow!("{e}"))
+    }
+
+    fn fetch_self_hosted_runners(&self, owner: &str, repo: &str) -> Result<Option<Vec<Runner>>> {
+        let Some(repo) =
+            self.get_optional::<Runners>(&format!("repos/{owner}/{repo}/actions/runners"))?
+        else {
+            return Ok(None);
+        };
+
+        let mut runners = repo.runners;
+
+        // Organization runners are only visible to organization admins,
+        // and don't exist at all for user-owned repositories.
+        if let Some(org) = self.get_optional::<Runners>(&format!("orgs/{owner}/actions/runners"))? {
+            runners.extend(org.runners);
+        }
+
+        Ok(Some(runners))
+    }
+
+    /// Returns the self-hosted runners in the organization runner group
+    /// named `group`.
+    ///
+    /// Returns `None` if our token can't see the organization's runner groups.
+    pub(crate) fn list_runner_group_runners(
+        &self,
+        org: &str,
+        group: &str,
+    ) -> Result<Option<Vec<Runner>>> {
+        self.caches
+            .runner_group_cache
+            .try_get_with((org.into(), group.into()), || {
+                self.cached(
+                    &format!("runner-groups/{org}/{group}"),
+                    RUNNER_MAX_AGE,
+                    || self.fetch_runner_group_runners(org, group),
+                )
+            })
+            .map_err(|e| anyhow!("{e}"))
+    }
+
+    fn fetch_runner_group_runners(&self, org: &str, group: &str) -> Result<Option<Vec<Runner>>> {
+        let Some(groups) =
+            self.get_optional::<RunnerGroups>(&format!("orgs/{org}/actions/runner-groups"))?
+        else {
+            return Ok(None);
+        };
+
+        let Some(group) = groups.runner_groups.iter().find(|g| g.name == group) else {
+            return Ok(Some(vec![]));
+        };
+
+        Ok(self
+            .get_optional::<Runners>(&format!(
+                "orgs/{org}/actions/runner-groups/{id}/runners",
+                id = group.id
+            ))?
+            .map(|r| r.runners))
+    }
+
+    /// Fetches a single page (of up to 100 items) from `endpoint`, returning
+    /// `None` if the endpoint is missing or inaccessible to us.
+    fn get_optional<T: DeserializeOwned>(&self, endpoint: &str) -> Result<Option<T>> {
+        // TODO: Paginate these; >100 runners per repo or group is unusual.
+        let url = format!("{api_base}/{endpoint}", api_base = self.api_base);
+
+        let resp = self.get(&url, &[("per_page", "100")], None)?;
+        match resp.status {
+            StatusCode::OK => Ok(Some(resp.json()?)),
+            StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => Ok(None),
+            s => Err(anyhow!(
+                "error from GitHub API while accessing {endpoint}: {s}"
+            )),
+        }
+    }
+
+    /// Returns the contents of the action definition (`action.yml` or
+    /// `action.yaml`) for the given action, or `None` if the action
+    /// has no definition at its ref.
+    ///
+    /// Definitions for commit refs never change, so they're also cached
+    /// on disk between runs; definitions for other refs are cached for
+    /// a while.
+    pub(crate) fn fetch_action_metadata(&self, uses: &Uses<'_>) -> Result<Option<String>> {
+        let Some(git_ref) = uses.git_ref else {
+            return Ok(None);
+        };
+
+        let (owner, repo) = (uses.owner, uses.repo);
+        let dir = uses
+            .subpath
+            .map(|subpath| format!("{subpath}/"))
+            .unwrap_or_default();
+
+        let fetch = || -> Result<Option<String>> {
+            for filename in ["action.yml", "action.yaml"] {
+                let url = format!(
+                    "{api_base}/repos/{owner}/{repo}/contents/{dir}{filename}",
+                    api_base = self.api_base
+                );
+
+                let resp = self.get(
+                    &url,
+                    &[("ref", git_ref)],
+                    Some("application/vnd.github.raw+json"),
+                )?;
+
+                match resp.status {
+                    StatusCode::OK => return Ok(Some(resp.body)),
+                    StatusCode::NOT_FOUND => continue,
+                    s => {
+                        return Err(anyhow!(
+                        "{owner}/{repo}: error from GitHub API while fetching {dir}{filename}: {s}"
+                    ))
+                    }
+                }
+            }
+
+            Ok(None)
+        };
+
+        self.caches
+            .action_metadata_cache
+            .try_get_with(format!("{owner}/{repo}/{dir}@{git_ref}"), || {
+                // Symbolic refs can move, so their definitions expire.
+                let Some(commit) = uses.commit_ref() else {
+                    return self.cached(
+                        &format!("actions/{owner}/{repo}/{git_ref}/{dir}action"),
+                        REF_MAX_AGE,
+                        fetch,
+                    );
+                };
+
+                let disk_key = format!("{owner}/{repo}/{commit}/{dir}action.yml");
+                if let Some(cached) = self.caches.action_metadata_disk.get(&disk_key) {
+                    return Ok(Some(cached));
+                }
+
+                let contents = fetch()?;
+                if let Some(contents) = &contents {
+                    self.caches.action_metadata_disk.put(&disk_key, contents);
                 }
+
+                Ok::<_, anyhow::Error>(contents)
+            })
+            .map_err(|e| anyhow!("{e}"))
+    }
+
+    /// Returns the metadata for `owner/repo`, or `None` if the repository
+    /// doesn't exist or isn't visible to us.
+    ///
+    /// Repository metadata changes rarely, so it's also cached on disk
+    /// for a day.
+    pub(crate) fn get_repository(&self, owner: &str, repo: &str) -> Result<Option<Repository>> {
+        const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+        self.caches
+            .repository_cache
+            .try_get_with((owner.into(), repo.into()), || {
+                let disk_key = format!("{owner}/{repo}.json");
+
+                if let Some(cached) = self
+                    .caches
+                    .repository_disk
+                    .get_fresh(&disk_key, MAX_AGE)
+                    .and_then(|cached| serde_json::from_str(&cached).ok())
+                {
+                    return Ok(cached);
+                }
+
+                let repository =
+                    self.get_optional::<Repository>(&format!("repos/{owner}/{repo}"))?;
+
+                self.caches
+                    .repository_disk
+                    .put(&disk_key, &serde_json::to_string(&repository)?);
+
+                Ok::<_, anyhow::Error>(repository)
             })
-            .map_err(Into::into)
+            .map_err(|e| anyhow!("{e}"))
+    }
+
+    /// Returns every repository in the organization `org`.
+    pub(crate) fn list_org_repositories(&self, org: &str) -> Result<Vec<Repository>> {
+        self.paginate(&format!("orgs/{org}/repos"))
     }
 
+    /// Returns every file in `owner/repo` at `git_ref` (a branch, tag,
+    /// or commit), via the repository's recursive Git tree.
+    pub(crate) fn list_files(
+        &self,
+        owner: &str,
+        repo: &str,
+        git_ref: &str,
+    ) -> Result<Vec<TreeEntry>> {
+        let url = format!(
+            "{api_base}/repos/{owner}/{repo}/git/trees/{git_ref}",
+            api_base = self.api_base
+        );
+
+        let resp = self.get(&url, &[("recursive", "1")], None)?;
+        match resp.status {
+            StatusCode::OK => {
+                let tree = resp.json::<Tree>()?;
+                if tree.truncated {
+                    log::warn!(
+                        "{owner}/{repo}: tree is too large to list fully; some files may be missed"
+                    );
+                }
+
+                Ok(tree
+                    .tree
+                    .into_iter()
+                    .filter(|entry| entry.kind == "blob")
+                    .collect())
+            }
+            s => Err(anyhow!(
+                "{owner}/{repo}: error from GitHub API while listing files at {git_ref}: {s}"
+            )),
+        }
+    }
+
+    /// Returns the contents of the blob (i.e. file) `sha` in `owner/repo`.
+    pub(crate) fn fetch_blob(&self, owner: &str, repo: &str, sha: &str) -> Result<String> {
+        let url = format!(
+            "{api_base}/repos/{owner}/{repo}/git/blobs/{sha}",
+            api_base = self.api_base
+        );
+
+        let resp = self.get(&url, &[], Some("application/vnd.github.raw+json"))?;
+        match resp.status {
+            StatusCode::OK => Ok(resp.body),
+            s => Err(anyhow!(
+                "{owner}/{repo}: error from GitHub API while fetching blob {sha}: {s}"
+            )),
+        }
+    }
+
+    /// Returns the advisories that affect `owner/repo@version`, or `None`
+    /// if the instance doesn't have the global advisories endpoint (which
+    /// older GitHub Enterprise Server versions don't).
     pub(crate) fn gha_advisories(
         &self,
         owner: &str,
         repo: &str,
         version: &str,
-    ) -> Result<Vec<Advisory>> {
+    ) -> Result<Option<Vec<Advisory>>> {
+        self.cached(
+            &format!("advisories/{owner}/{repo}/{version}"),
+            ADVISORY_MAX_AGE,
+            || self.fetch_gha_advisories(owner, repo, version),
+        )
+    }
+
+    fn fetch_gha_advisories(
+        &self,
+        owner: &str,
+        repo: &str,
+        version: &str,
+    ) -> Result<Option<Vec<Advisory>>> {
         // TODO: Paginate this as well.
         let url = format!("{api_base}/advisories", api_base = self.api_base);
 
-        self.http
-            .get(url)
-            .query(&[
+        let resp = self.get(
+            &url,
+            &[
                 ("ecosystem", "actions"),
                 ("affects", &format!("{owner}/{repo}@{version}")),
-            ])
-            .send()?
-            .error_for_status()?
-            .json()
-            .map_err(Into::into)
+            ],
+            None,
+        )?;
+
+        match resp.status {
+            StatusCode::OK => Ok(Some(resp.json()?)),
+            StatusCode::NOT_FOUND => Ok(None),
+            s => Err(anyhow!(
+                "{owner}/{repo}: error from GitHub API while fetching advisories: {s}"
+            )),
+        }
     }
 }
 
+/// Returns how long to wait before retrying the request that `resp`
+/// answered, or `None` if it shouldn't be retried.
+fn retry_delay(resp: &blocking::Response, attempt: u32) -> Option<Duration> {
+    let header =
+        |name: &str| -> Option<u64> { resp.headers().get(name)?.to_str().ok()?.parse().ok() };
+
+    // Secondary rate limits come with a `Retry-After`, and primary ones with
+    // the time (in epoch seconds) at which the limit resets.
+    if rate_limited(resp) {
+        if let Some(secs) = header("retry-after") {
+            return Some(Duration::from_secs(secs));
+        }
+        if let Some(reset) = header("x-ratelimit-reset") {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+            return Some(Duration::from_secs(reset.saturating_sub(now) + 1));
+        }
+        return Some(backoff(attempt));
+    }
+
+    resp.status().is_server_error().then(|| backoff(attempt))
+}
+
+/// Returns whether `resp` is GitHub refusing a request because of a primary
+/// or secondary rate limit.
+fn rate_limited(resp: &blocking::Response) -> bool {
+    let header =
+        |name: &str| -> Option<u64> { resp.headers().get(name)?.to_str().ok()?.parse().ok() };
+
+    let status = resp.status();
+    status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN
+            && (header("retry-after").is_some() || header("x-ratelimit-remaining") == Some(0)))
+}
+
+/// Returns the exponential backoff before the given retry attempt.
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt)
+}
+
 /// A single branch, as returned by GitHub's branches endpoints.
 ///
 /// This model is intentionally incomplete.
@@ -206,20 +794,35 @@ impl Client {
 #[derive(Deserialize, Clone)]
 pub(crate) struct Branch {
     pub(crate) name: String,
+    pub(crate) commit: Commit,
+}
+
+/// A repository's metadata, as returned by GitHub's repositories endpoint.
+///
+/// This model is intentionally incomplete.
+///
+/// See <https://docs.github.com/en/rest/repos/repos?apiVersion=2022-11-28#get-a-repository>.
+#[derive(Deserialize, Serialize, Clone)]
+pub(crate) struct Repository {
+    pub(crate) name: String,
+    pub(crate) archived: bool,
+    pub(crate) default_branch: String,
+    /// When anything was last pushed to the repository, as an RFC 3339 timestamp.
+    pub(crate) pushed_at: Option<String>,
 }
 
 /// A single tag, as returned by GitHub's tags endpoints.
 ///
 /// This model is intentionally incomplete.
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 pub(crate) struct Tag {
     pub(crate) name: String,
-    pub(crate) commit: TagCommit,
+    pub(crate) commit: Commit,
 }
 
-/// Represents the SHA ref bound to a tag.
-#[derive(Deserialize, Clone)]
-pub(crate) struct TagCommit {
+/// Represents the SHA ref bound to a branch or tag.
+#[derive(Deserialize, Serialize, Clone)]
+pub(crate) struct Commit {
     pub(crate) sha: String,
 }
 
@@ -233,6 +836,26 @@ pub(crate) struct GitObj {
     pub(crate) sha: String,
 }
 
+/// A repository's recursive Git tree.
+///
+/// See <https://docs.github.com/en/rest/git/trees?apiVersion=2022-11-28>.
+#[derive(Deserialize)]
+struct Tree {
+    tree: Vec<TreeEntry>,
+    truncated: bool,
+}
+
+/// A single entry in a Git tree.
+///
+/// This model is intentionally incomplete.
+#[derive(Deserialize)]
+pub(crate) struct TreeEntry {
+    pub(crate) path: String,
+    #[serde(rename = "type")]
+    pub(crate) kind: String,
+    pub(crate) sha: String,
+}
+
 #[derive(Clone, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub(crate) enum ComparisonStatus {
@@ -251,8 +874,80 @@ pub(crate) struct Comparison {
 }
 
 /// Represents a GHSA advisory.
-#[derive(Deserialize)]
+///
+/// This model is intentionally incomplete.
+///
+/// See <https://docs.github.com/en/rest/security-advisories/global-advisories?apiVersion=2022-11-28>
+#[derive(Deserialize, Serialize)]
 pub(crate) struct Advisory {
     pub(crate) ghsa_id: String,
     pub(crate) severity: String,
+    #[serde(default)]
+    pub(crate) vulnerabilities: Vec<Vulnerability>,
+}
+
+/// A single vulnerable package (i.e. action) within an [`Advisory`].
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Vulnerability {
+    pub(crate) package: Package,
+    pub(crate) vulnerable_version_range: Option<String>,
+    pub(crate) first_patched_version: Option<String>,
+}
+
+/// The package (i.e. action) that a [`Vulnerability`] applies to.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Package {
+    pub(crate) name: String,
+}
+
+/// A page of self-hosted runners, as returned by GitHub's runner endpoints.
+///
+/// See <https://docs.github.com/en/rest/actions/self-hosted-runners?apiVersion=2022-11-28>
+#[derive(Deserialize)]
+pub(crate) struct Runners {
+    pub(crate) runners: Vec<Runner>,
+}
+
+/// A single self-hosted runner.
+///
+/// This model is intentionally incomplete.
+#[derive(Deserialize, Serialize, Clone)]
+pub(crate) struct Runner {
+    pub(crate) name: String,
+    pub(crate) labels: Vec<RunnerLabel>,
+}
+
+impl Runner {
+    /// Returns whether this runner has every one of the given labels.
+    ///
+    /// Like GitHub, labels are matched case-insensitively.
+    pub(crate) fn matches(&self, labels: &[String]) -> bool {
+        labels.iter().all(|label| {
+            self.labels
+                .iter()
+                .any(|l| l.name.eq_ignore_ascii_case(label))
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub(crate) struct RunnerLabel {
+    pub(crate) name: String,
+}
+
+/// A page of organization runner groups.
+///
+/// See <https://docs.github.com/en/rest/actions/self-hosted-runner-groups?apiVersion=2022-11-28>
+#[derive(Deserialize)]
+pub(crate) struct RunnerGroups {
+    pub(crate) runner_groups: Vec<RunnerGroup>,
+}
+
+/// A single organization runner group.
+///
+/// This model is intentionally incomplete.
+#[derive(Deserialize)]
+pub(crate) struct RunnerGroup {
+    pub(crate) id: u64,
+    pub(crate) name: String,
 }
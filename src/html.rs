@@ -0,0 +1,311 @@
+//! HTML reports (`--format html`), for sharing results with people who
+//! don't use the CLI.
+//!
+//! A report is a single file, with its styles and scripts inlined: each
+//! finding has its snippets and a link to its audit's documentation, and
+//! findings can be filtered by severity, rule, and file.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::{
+    finding::{suppress::Suppression, Finding, Severity},
+    registry::WorkflowRegistry,
+    render::Summary,
+    rules,
+};
+
+const STYLE: &str = r#"
+:root { color-scheme: light dark; --muted: #6e7781; --border: #d0d7de; --code: #f6f8fa; }
+@media (prefers-color-scheme: dark) { :root { --muted: #8b949e; --border: #30363d; --code: #161b22; } }
+body { font-family: system-ui, sans-serif; line-height: 1.5; max-width: 72rem; margin: 0 auto; padding: 1rem 2rem; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid var(--border); padding: 0.25rem 0.75rem; text-align: left; }
+td.count { text-align: right; }
+nav { position: sticky; top: 0; display: flex; flex-wrap: wrap; gap: 1rem; align-items: center; padding: 0.75rem 0; background: Canvas; border-bottom: 1px solid var(--border); }
+article { border: 1px solid var(--border); border-left-width: 0.4rem; border-radius: 0.4rem; margin: 1rem 0; padding: 0 1rem; }
+article.suppressed { opacity: 0.6; }
+article h2 { font-size: 1.1rem; margin: 0.75rem 0; }
+.location { color: var(--muted); font-weight: normal; }
+.badge { border-radius: 1rem; padding: 0.1rem 0.6rem; font-size: 0.85rem; color: #fff; }
+.severity-informational { border-left-color: #8c959f; } .severity-informational .badge { background: #8c959f; }
+.severity-low { border-left-color: #0969da; } .severity-low .badge { background: #0969da; }
+.severity-medium { border-left-color: #bf8700; } .severity-medium .badge { background: #bf8700; }
+.severity-high { border-left-color: #d1242f; } .severity-high .badge { background: #d1242f; }
+.severity-critical { border-left-color: #8250df; } .severity-critical .badge { background: #8250df; }
+details { margin: 0.5rem 0 1rem; }
+pre { background: var(--code); padding: 0.75rem; overflow-x: auto; white-space: pre-wrap; }
+.line-number { display: inline-block; min-width: 3ch; margin-right: 1ch; color: var(--muted); text-align: right; user-select: none; }
+"#;
+
+const SCRIPT: &str = r#"
+(() => {
+  const findings = [...document.querySelectorAll("article")];
+  const severity = document.getElementById("filter-severity");
+  const rule = document.getElementById("filter-rule");
+  const file = document.getElementById("filter-file");
+  const shown = document.getElementById("shown");
+
+  for (const [select, key] of [[rule, "rule"], [file, "file"]]) {
+    const values = [...new Set(findings.map((finding) => finding.dataset[key]))].sort();
+    for (const value of values) {
+      select.add(new Option(value, value));
+    }
+  }
+
+  const update = () => {
+    let n = 0;
+    for (const finding of findings) {
+      const visible =
+        Number(finding.dataset.rank) >= Number(severity.value) &&
+        (!rule.value || finding.dataset.rule === rule.value) &&
+        (!file.value || finding.dataset.file === file.value);
+      finding.hidden = !visible;
+      n += visible;
+    }
+    shown.textContent = `${n} of ${findings.length} findings shown`;
+  };
+  for (const select of [severity, rule, file]) {
+    select.addEventListener("change", update);
+  }
+  update();
+})();
+"#;
+
+/// Escapes `text` for use in HTML attributes and text.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Returns `severity`'s rank, from 0 for the lowest.
+fn rank(severity: Severity) -> usize {
+    Severity::value_variants()
+        .iter()
+        .position(|variant| *variant == severity)
+        .unwrap_or_default()
+}
+
+/// Renders `findings` into `report`.
+///
+/// Findings are rendered as their inputs are audited; [`render`] wraps
+/// them in the rest of the report once every input has been.
+pub(crate) fn render_findings(
+    registry: &WorkflowRegistry,
+    findings: &[Finding],
+    report: &mut String,
+) -> Result<()> {
+    for finding in findings {
+        render_finding(registry, finding, report)?;
+    }
+
+    Ok(())
+}
+
+fn render_finding(
+    registry: &WorkflowRegistry,
+    finding: &Finding,
+    report: &mut String,
+) -> Result<()> {
+    let Some(primary) = finding.locations.first() else {
+        return Ok(());
+    };
+    let path = registry.get_relative_path(primary.symbolic.name);
+    let file = match &registry.remote {
+        Some(slug) => format!("{slug}/{path}"),
+        None => path.to_string(),
+    };
+    let severity = finding.determinations.severity;
+
+    writeln!(
+        report,
+        r#"<article class="severity-{class}{suppressed}" data-rank="{rank}" data-rule="{ident}" data-file="{file}">"#,
+        class = format!("{severity:?}").to_lowercase(),
+        suppressed = if finding.suppression.is_some() {
+            " suppressed"
+        } else {
+            ""
+        },
+        rank = rank(severity),
+        ident = finding.ident,
+        file = escape(&file),
+    )?;
+    writeln!(
+        report,
+        r#"<h2><span class="badge">{severity:?}</span> <a href="{url}">{ident}</a> <span class="location">{file}:{line}</span></h2>"#,
+        url = finding.url(),
+        ident = finding.ident,
+        file = escape(&file),
+        line = primary.concrete.location.start_point.row + 1,
+    )?;
+    writeln!(
+        report,
+        "<p>{desc} (confidence: {confidence:?})</p>",
+        desc = escape(finding.desc),
+        confidence = finding.determinations.confidence,
+    )?;
+
+    match finding.suppression {
+        Some(Suppression::Comment) => {
+            report.push_str("<p><em>Suppressed by an inline comment.</em></p>\n")
+        }
+        Some(Suppression::Config) => {
+            report.push_str("<p><em>Suppressed by the configuration file.</em></p>\n")
+        }
+        Some(Suppression::Baseline) => {
+            report.push_str("<p><em>Suppressed by the baseline.</em></p>\n")
+        }
+        None => (),
+    }
+
+    report.push_str("<ul>\n");
+    for location in &finding.locations {
+        writeln!(
+            report,
+            "<li><code>{path}:{line}</code>: {annotation}</li>",
+            path = escape(registry.get_relative_path(location.symbolic.name)),
+            line = location.concrete.location.start_point.row + 1,
+            annotation = escape(&location.symbolic.annotation),
+        )?;
+    }
+    if let Some(suggestion) = &finding.suggestion {
+        writeln!(
+            report,
+            "<li>💡 {suggestion}</li>",
+            suggestion = escape(suggestion)
+        )?;
+    }
+    for fix in &finding.fixes {
+        writeln!(
+            report,
+            "<li>🔧 Fix available: {title}</li>",
+            title = escape(&fix.fix.title)
+        )?;
+    }
+    report.push_str("</ul>\n");
+
+    for location in &finding.locations {
+        write!(
+            report,
+            "<details open>\n<summary>Snippet: <code>{path}:{line}</code></summary>\n<pre><code>",
+            path = escape(registry.get_relative_path(location.symbolic.name)),
+            line = location.excerpt.start_row + 1,
+        )?;
+        for (n, line) in location.excerpt.text.lines().enumerate() {
+            writeln!(
+                report,
+                r#"<span class="line-number">{number}</span>{line}"#,
+                number = location.excerpt.start_row + n + 1,
+                line = escape(line),
+            )?;
+        }
+        report.push_str("</code></pre>\n</details>\n");
+    }
+
+    if let Some(remediation) = rules::remediation(finding.ident) {
+        writeln!(
+            report,
+            "<details>\n<summary>Remediation</summary>\n<pre>{remediation}</pre>\n</details>",
+            remediation = escape(remediation),
+        )?;
+    }
+
+    report.push_str("</article>\n");
+    Ok(())
+}
+
+/// Renders the whole report: a summary of the run and the filters,
+/// followed by the `findings` rendered by [`render_findings`].
+pub(crate) fn render(summary: &Summary, findings: &str) -> Result<String> {
+    let mut report = String::new();
+    writeln!(
+        report,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>zizmor report</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>zizmor report</h1>\n<p>Generated by zizmor {version}.</p>",
+        version = env!("CARGO_PKG_VERSION"),
+    )?;
+
+    report.push_str("<table>\n<tr><th>Severity</th><th>Findings</th></tr>\n");
+    for severity in Severity::value_variants().iter().rev() {
+        writeln!(
+            report,
+            r#"<tr class="severity-{class}"><td><span class="badge">{severity:?}</span></td><td class="count">{count}</td></tr>"#,
+            class = format!("{severity:?}").to_lowercase(),
+            count = summary.findings(*severity),
+        )?;
+    }
+    report.push_str("</table>\n");
+
+    let notes = summary.notes();
+    if !notes.is_empty() {
+        report.push_str("<ul>\n");
+        for note in &notes {
+            writeln!(report, "<li>{note}</li>", note = escape(note))?;
+        }
+        report.push_str("</ul>\n");
+    }
+
+    report.push_str(
+        "<nav>\n<label>Severity <select id=\"filter-severity\">\n\
+         <option value=\"0\">All</option>\n",
+    );
+    for severity in &Severity::value_variants()[1..] {
+        writeln!(
+            report,
+            r#"<option value="{rank}">{severity:?} and above</option>"#,
+            rank = rank(*severity),
+        )?;
+    }
+    report.push_str(
+        "</select></label>\n\
+         <label>Rule <select id=\"filter-rule\"><option value=\"\">All</option></select></label>\n\
+         <label>File <select id=\"filter-file\"><option value=\"\">All</option></select></label>\n\
+         <span id=\"shown\"></span>\n</nav>\n<main>\n",
+    );
+
+    report.push_str(findings);
+
+    writeln!(
+        report,
+        "</main>\n<script>{SCRIPT}</script>\n</body>\n</html>"
+    )?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::finding::Severity;
+
+    use super::{escape, rank};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain text"), "plain text");
+        assert_eq!(
+            escape(r#"<script>alert("${{ github.head_ref }}")</script>"#),
+            "&lt;script&gt;alert(&quot;${{ github.head_ref }}&quot;)&lt;/script&gt;"
+        );
+        assert_eq!(escape("a & 'b'"), "a &amp; &#39;b&#39;");
+    }
+
+    #[test]
+    fn test_rank() {
+        assert_eq!(rank(Severity::Informational), 0);
+        assert_eq!(rank(Severity::Critical), 4);
+        assert!(rank(Severity::Medium) < rank(Severity::High));
+    }
+}
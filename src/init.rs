@@ -0,0 +1,231 @@
+//! `zizmor init`, which scaffolds a configuration file for a repository.
+//!
+//! The repository is audited as usual, and its existing findings are
+//! recorded as `ignore` entries, each with a TODO for the reason that it's
+//! accepted. Every audit gets a `rules` entry to adjust, with pedantic
+//! audits commented out (since they only run with `--pedantic`).
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::{config, finding::Finding, registry::WorkflowRegistry, rules::Rule};
+
+pub(crate) struct Init {
+    /// Where the configuration file is written.
+    path: PathBuf,
+    /// The `path:line` locations of the repository's findings, by audit.
+    accepted: BTreeMap<&'static str, BTreeSet<String>>,
+}
+
+impl Init {
+    /// Prepares to scaffold a configuration file for the repository at
+    /// `root`, which mustn't have one already.
+    pub(crate) fn new(root: &Path) -> Result<Self> {
+        if !root.is_dir() {
+            return Err(anyhow!("{root:?} isn't a directory"));
+        }
+        if let Some(existing) = config::DISCOVERABLE
+            .iter()
+            .map(|candidate| root.join(candidate))
+            .find(|candidate| candidate.exists())
+        {
+            return Err(anyhow!("{existing:?} already exists"));
+        }
+
+        let path = if root.join(".github").is_dir() {
+            root.join(".github/zizmor.yml")
+        } else {
+            root.join("zizmor.yml")
+        };
+
+        Ok(Self {
+            path,
+            accepted: BTreeMap::new(),
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records the (unsuppressed) `findings` as accepted, by their
+    /// primary locations.
+    pub(crate) fn record(&mut self, registry: &WorkflowRegistry, findings: &[Finding<'_>]) {
+        for finding in findings.iter().filter(|f| f.suppression.is_none()) {
+            let Some(location) = finding.locations.first() else {
+                continue;
+            };
+
+            self.accepted
+                .entry(finding.ident)
+                .or_default()
+                .insert(format!(
+                    "{path}:{line}",
+                    path = registry.get_relative_path(location.symbolic.name),
+                    line = location.concrete.location.start_point.row + 1
+                ));
+        }
+    }
+
+    /// The number of accepted findings recorded so far.
+    pub(crate) fn len(&self) -> usize {
+        self.accepted.values().map(BTreeSet::len).sum()
+    }
+
+    /// Renders the configuration file, for the given audits. `online` is
+    /// whether the repository was audited with GitHub API access.
+    pub(crate) fn render(&self, rules: &[Rule], online: bool) -> String {
+        let mut out = String::new();
+        // NOTE: Writing to a `String` can't fail.
+        let mut line = |text: &str| writeln!(out, "{text}").unwrap();
+
+        line("# Configuration for zizmor, generated by `zizmor init`. See:");
+        line(&format!(
+            "# {repo}/blob/main/docs/usage.md#configuration",
+            repo = env!("CARGO_PKG_REPOSITORY")
+        ));
+        line("");
+
+        let online_rules = rules
+            .iter()
+            .filter(|rule| rule.online)
+            .map(|rule| rule.ident)
+            .collect::<Vec<_>>()
+            .join(", ");
+        if online {
+            line("# GitHub API access was available when this file was generated, so");
+            line(&format!("# these audits ran: {online_rules}."));
+            line("# Uncomment to only run audits that don't need network access:");
+            line("# offline: true");
+            line("# Or, to only let some audits use GitHub's API:");
+            line("# online-audits: [impostor-commit]");
+        } else {
+            line("# No GitHub API token was available when this file was generated, so");
+            line(&format!("# these audits were skipped: {online_rules}."));
+            line("# Remove this to run them whenever a token is available, e.g. via");
+            line("# --gh-token or GH_TOKEN:");
+            line("offline: true");
+        }
+        line("# gh-hostname: github.com");
+        line("");
+
+        line("rules:");
+        let (pedantic, rest): (Vec<_>, Vec<_>) = rules.iter().partition(|rule| rule.pedantic);
+        for rule in rest {
+            render_rule(&mut line, rule, "");
+        }
+        if !pedantic.is_empty() {
+            line("  # These audits only run with --pedantic:");
+            for rule in pedantic {
+                render_rule(&mut line, rule, "# ");
+            }
+        }
+
+        if !self.accepted.is_empty() {
+            line("");
+            line("# Findings that existed when this file was generated.");
+            line("ignore:");
+            for (ident, locations) in &self.accepted {
+                line(&format!("  {ident}:"));
+                for location in locations {
+                    // JSON strings are valid (double-quoted) YAML strings.
+                    line(&format!(
+                        "    - {location} # TODO: why is this accepted?",
+                        location = serde_json::to_string(location).unwrap()
+                    ));
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Writes the configuration file. See [`Init::render`].
+    pub(crate) fn write(&self, rules: &[Rule], online: bool) -> Result<()> {
+        std::fs::write(&self.path, self.render(rules, online))
+            .with_context(|| format!("couldn't write {path:?}", path = self.path))
+    }
+}
+
+/// Renders `rule`'s entry under `rules`, with each line prefixed by
+/// `comment` (i.e. commented out, or not).
+fn render_rule(line: &mut impl FnMut(&str), rule: &Rule, comment: &str) {
+    line(&format!("  {comment}{ident}:", ident = rule.ident));
+    line(&format!("  {comment}  disable: false"));
+
+    if !rule.config_keys.is_empty() {
+        line(&format!(
+            "    # See `zizmor explain {ident}` for these settings:",
+            ident = rule.ident
+        ));
+        line("    # config:");
+        for key in rule.config_keys {
+            line(&format!("    #   {key}:"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Init;
+    use crate::{
+        config::Config,
+        finding::{Confidence, Severity},
+        rules::Rule,
+    };
+
+    #[test]
+    fn test_render() {
+        let rule = |ident, pedantic, online, config_keys: &'static [&'static str]| Rule {
+            ident,
+            desc: "an example",
+            default_severity: Severity::Low,
+            default_confidence: Confidence::Unknown,
+            pedantic,
+            online,
+            config_keys,
+            github_scopes: &[],
+        };
+        let rules = [
+            rule("example", false, false, &[]),
+            rule("example-online", false, true, &[]),
+            rule("example-pedantic", true, false, &["max-minutes"]),
+        ];
+
+        let dir = std::env::temp_dir().join(format!("zizmor-init-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut init = Init::new(&dir).unwrap();
+        init.accepted
+            .entry("example")
+            .or_default()
+            .insert("ci.yml:12".into());
+
+        for online in [true, false] {
+            let rendered = init.render(&rules, online);
+            assert_eq!(rendered.contains("\noffline: true\n"), !online);
+            assert!(rendered.contains("\n  example:\n    disable: false\n"));
+            assert!(rendered.contains("\n  # example-pedantic:\n"));
+            assert!(rendered.contains("\n    - \"ci.yml:12\" # TODO"));
+
+            // The scaffolded configuration is valid, and ignores the
+            // existing findings.
+            init.write(&rules, online).unwrap();
+            let config = Config::load(init.path()).unwrap();
+            assert!(config.is_ignored("example", "ci.yml", 12..=12));
+            assert!(!config.is_ignored("example", "ci.yml", 13..=13));
+            assert!(!config.is_disabled("example"));
+            assert_eq!(config.offline(), !online);
+            std::fs::remove_file(init.path()).unwrap();
+        }
+
+        // Existing configuration files aren't overwritten.
+        std::fs::write(dir.join("zizmor.yml"), "").unwrap();
+        assert!(Init::new(&dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+//! Classification of template expansions by attacker controllability.
+//!
+//! This is shared by the audits that detect template injection into
+//! different kinds of code, e.g. shell scripts in `run:` and JavaScript
+//! in `actions/github-script`.
+
+use std::ops::{Deref, Range};
+
+use github_actions_models::{
+    common::expr::LoE,
+    workflow::{
+        self,
+        job::{Matrix, Strategy},
+    },
+};
+
+use crate::{
+    dataflow::{Dataflow, Sink},
+    expr::{context_is_attacker_controlled, Expr, SAFE_CONTEXTS},
+    finding::{Confidence, Rationale, Severity},
+    models::Job,
+    utils::{extract_expression_spans, extract_expressions},
+};
+
+/// Checks whether the given `expr` into `matrix` is static.
+fn matrix_is_static(expr: &str, matrix: &Matrix) -> bool {
+    // If the matrix's dimensions are an expression, then it's not static.
+    let LoE::Literal(dimensions) = &matrix.dimensions else {
+        return false;
+    };
+
+    // Our `expr` should be a literal path of `matrix.foo.bar.baz.etc`,
+    // so we descend through the matrix based on it.
+    let mut keys = expr.split('.').skip(1);
+
+    let Some(key) = keys.next() else {
+        // No path means that we're effectively expanding the entire matrix,
+        // meaning *any* non-static component makes the entire expansion
+        // non-static.
+
+        // HACK: The correct way to do this is to walk `matrix.dimensions`,
+        // but it could be arbitrarily deep. Instead, we YOLO the dimensions
+        // back into YAML and see if the serialized equivalent has
+        // any indicators of expansion (`${{ ... }}`) in it.
+        // NOTE: Safe unwrap since `dimensions` was loaded directly from YAML
+        let dimensions_yaml = serde_yaml::to_string(&dimensions).unwrap();
+        return !(dimensions_yaml.contains("${{") && dimensions_yaml.contains("}}"));
+    };
+
+    match dimensions.get(key) {
+        // This indicates a malformed matrix or matrix ref, which is
+        // static for our purposes.
+        None => true,
+        // If our key is an expression, it's definitely not static.
+        Some(LoE::Expr(_)) => false,
+        Some(LoE::Literal(dim)) => {
+            // TODO: This is imprecise: technically we should walk the
+            // entire set of keys to determine if a specific index is
+            // accessed + whether that index is an expression.
+            // But doing that is hard, so we do the same YOLO reserialize
+            // trick as above and consider this non-static
+            // if it has any hint of a template expansion in it.
+            let dim_yaml = serde_yaml::to_string(&dim).unwrap();
+            !(dim_yaml.contains("${{") && dim_yaml.contains("}}"))
+        }
+    }
+}
+
+/// Returns every expression context in `sink`'s code that could be
+/// attacker-controllable when expanded within `job`, along with the
+/// severity and confidence of the resulting injection, and the span of
+/// its expression within the code.
+///
+/// Contexts that aren't attacker-controllable themselves (e.g. step
+/// outputs) are traced back to their sources with `dataflow`.
+pub(crate) fn injectable_template_expressions(
+    sink: &Sink<'_>,
+    job: &Job<'_>,
+    dataflow: &Dataflow<'_>,
+) -> Vec<(String, Severity, Confidence, Rationale, Range<usize>)> {
+    let workflow::Job::NormalJob(normal) = job.deref() else {
+        return vec![];
+    };
+
+    // Returns the context, annotated with the sources that flow into it.
+    let tainted = |context: &str| {
+        let sources = dataflow.context_sources(context, Some(job), Some(&sink.step));
+        (!sources.is_empty()).then(|| format!("{context} (from {})", sources.join(", ")))
+    };
+
+    classify_expressions(&sink.code, normal.strategy.as_ref(), &tainted)
+}
+
+/// Like [`injectable_template_expressions`], but for a script in a composite
+/// action, which has no matrix of its own.
+///
+/// Step outputs within composite actions aren't traced to their sources.
+pub(crate) fn injectable_action_expressions(
+    text: &str,
+) -> Vec<(String, Severity, Confidence, Rationale, Range<usize>)> {
+    classify_expressions(text, None, &|_| None)
+}
+
+/// Classifies each expression context in `text` by how attacker-controllable
+/// it is, with `tainted` annotating the contexts that aren't inherently
+/// attacker-controllable but that attacker-controllable values flow into.
+fn classify_expressions(
+    text: &str,
+    strategy: Option<&Strategy>,
+    tainted: &dyn Fn(&str) -> Option<String>,
+) -> Vec<(String, Severity, Confidence, Rationale, Range<usize>)> {
+    let mut bad_expressions = vec![];
+    for (expr, span) in extract_expression_spans(text) {
+        let Ok(expr) = Expr::parse(expr.as_bare()) else {
+            log::warn!("couldn't parse expression: {expr}", expr = expr.as_bare());
+            continue;
+        };
+
+        for context in expr.contexts() {
+            if context.starts_with("secrets.") {
+                // While not ideal, secret expansion is typically not exploitable.
+                continue;
+            } else if SAFE_CONTEXTS.contains(&context) {
+                continue;
+            } else if context.starts_with("inputs.") {
+                // TODO: Currently low confidence because we don't check the
+                // input's type. In the future, we should index back into
+                // the workflow's triggers and exclude input expansions
+                // from innocuous types, e.g. booleans.
+                bad_expressions.push((
+                    context.into(),
+                    Severity::High,
+                    Confidence::Low,
+                    Rationale::Heuristic,
+                    span.clone(),
+                ));
+            } else if context.starts_with("env.") {
+                match tainted(context) {
+                    Some(context) => bad_expressions.push((
+                        context,
+                        Severity::High,
+                        Confidence::Medium,
+                        Rationale::DataflowTraced,
+                        span.clone(),
+                    )),
+                    // Almost never exploitable.
+                    None => bad_expressions.push((
+                        context.into(),
+                        Severity::Low,
+                        Confidence::High,
+                        Rationale::LiteralMatch,
+                        span.clone(),
+                    )),
+                }
+            } else if context_is_attacker_controlled(context) {
+                // TODO: Filter these more finely; not everything in the event
+                // context is actually attacker-controllable.
+                bad_expressions.push((
+                    context.into(),
+                    Severity::High,
+                    Confidence::High,
+                    Rationale::LiteralMatch,
+                    span.clone(),
+                ));
+            } else if context.starts_with("matrix.") || context == "matrix" {
+                if let Some(Strategy { matrix, .. }) = strategy {
+                    let matrix_is_static = match matrix {
+                        // The matrix is statically defined, but one
+                        // or more keys might contain expressions.
+                        Some(LoE::Literal(matrix)) => matrix_is_static(context, matrix),
+                        // The matrix is generated by an expression, meaning
+                        // that it's trivially not static.
+                        Some(LoE::Expr(_)) => false,
+                        // Context specifies a matrix, but there is no matrix defined.
+                        // This is an invalid workflow so there's no point in flagging it.
+                        None => continue,
+                    };
+
+                    if !matrix_is_static {
+                        bad_expressions.push((
+                            context.into(),
+                            Severity::Medium,
+                            Confidence::Medium,
+                            Rationale::ExpressionUnevaluated,
+                            span.clone(),
+                        ));
+                    }
+                }
+                continue;
+            } else if let Some(context) = tainted(context) {
+                // Step and job outputs that carry attacker-controllable
+                // values. Medium confidence, since outputs are
+                // over-approximated.
+                bad_expressions.push((
+                    context,
+                    Severity::High,
+                    Confidence::Medium,
+                    Rationale::DataflowTraced,
+                    span.clone(),
+                ));
+            } else {
+                // All other contexts are typically not attacker controllable,
+                // but may be in obscure cases.
+                bad_expressions.push((
+                    context.into(),
+                    Severity::Informational,
+                    Confidence::Low,
+                    Rationale::Heuristic,
+                    span.clone(),
+                ));
+            }
+        }
+    }
+
+    bad_expressions
+}
+
+/// Returns every input that `caller` passes to the reusable workflow
+/// containing `sink`, where the input is expanded into `sink`'s code and
+/// its value is attacker-controllable, along with the value's sources.
+///
+/// `dataflow` should be the reusable workflow's [`Dataflow`], as called by
+/// `caller`. Each input is returned with its name as written in the
+/// caller's `with:`.
+pub(crate) fn injectable_inputs<'w>(
+    sink: &Sink<'_>,
+    job: &Job<'_>,
+    dataflow: &Dataflow<'_>,
+    caller: &Job<'w>,
+) -> Vec<(&'w str, Vec<String>)> {
+    let mut inputs: Vec<(&'w str, Vec<String>)> = vec![];
+    for expr in extract_expressions(&sink.code) {
+        let Ok(expr) = Expr::parse(expr.as_bare()) else {
+            continue;
+        };
+
+        for context in expr.contexts() {
+            let Some(name) = context.strip_prefix("inputs.") else {
+                continue;
+            };
+            let Some((name, _)) = caller.reusable_input(name) else {
+                continue;
+            };
+            if inputs.iter().any(|(existing, _)| *existing == name) {
+                continue;
+            }
+
+            let sources = dataflow.context_sources(context, Some(job), Some(&sink.step));
+            if !sources.is_empty() {
+                inputs.push((name, sources));
+            }
+        }
+    }
+
+    inputs
+}
@@ -0,0 +1,113 @@
+//! zizmor's own JSON output format (`--format json`).
+//!
+//! The output is a single versioned document, described by the JSON Schema
+//! in `docs/schemas/` (which `zizmor schema` prints). Incompatible changes
+//! to the format get a new version, and a new schema.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// The version of the output format.
+pub(crate) const VERSION: u32 = 1;
+
+/// The JSON Schema for the current version of the output format.
+pub(crate) const SCHEMA: &str = include_str!("../docs/schemas/v1.json");
+
+/// The URL that the schema is published at.
+const SCHEMA_URL: &str = "https://woodruffw.github.io/zizmor/schemas/v1.json";
+
+/// A whole run's output.
+#[derive(Serialize)]
+pub(crate) struct Output {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: u32,
+    tool: Tool,
+    run: Run,
+    /// The findings, each serialized along with any extra keys (e.g. its
+    /// `repository`).
+    findings: Vec<Value>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+/// Metadata about the run that produced the output.
+#[derive(Serialize)]
+pub(crate) struct Run {
+    /// When the run started, as an RFC 3339 timestamp.
+    pub(crate) started_at: String,
+    /// How long the run took, in milliseconds.
+    pub(crate) duration_ms: u64,
+    /// The number of inputs audited.
+    pub(crate) inputs: usize,
+    /// The number of repositories audited.
+    pub(crate) repositories: usize,
+    /// The audits that ran.
+    pub(crate) audits: Vec<&'static str>,
+    /// The audits that were skipped because they require GitHub API access.
+    pub(crate) skipped_audits: Vec<&'static str>,
+    /// The audits that were truncated because the GitHub API budget ran out.
+    pub(crate) truncated_audits: Vec<&'static str>,
+    /// The repositories that couldn't be audited, with `--org`.
+    pub(crate) failed_repositories: Vec<String>,
+    /// The number of suppressed findings.
+    pub(crate) suppressed: usize,
+}
+
+impl Output {
+    pub(crate) fn new(run: Run, findings: Vec<Value>) -> Self {
+        Self {
+            schema: SCHEMA_URL,
+            version: VERSION,
+            tool: Tool {
+                name: env!("CARGO_CRATE_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            run,
+            findings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{Output, Run, SCHEMA, SCHEMA_URL, VERSION};
+
+    #[test]
+    fn test_schema() {
+        let schema = serde_json::from_str::<Value>(SCHEMA).unwrap();
+        assert_eq!(schema["$id"], SCHEMA_URL);
+        assert_eq!(schema["properties"]["version"]["const"], VERSION);
+
+        // Every key of the output (and of its run) is in the schema.
+        let output = serde_json::to_value(Output::new(
+            Run {
+                started_at: "2024-01-01T00:00:00Z".into(),
+                duration_ms: 0,
+                inputs: 0,
+                repositories: 0,
+                audits: vec![],
+                skipped_audits: vec![],
+                truncated_audits: vec![],
+                failed_repositories: vec![],
+                suppressed: 0,
+            },
+            vec![],
+        ))
+        .unwrap();
+        for (object, properties) in [
+            (&output, &schema["properties"]),
+            (&output["run"], &schema["properties"]["run"]["properties"]),
+        ] {
+            for key in object.as_object().unwrap().keys() {
+                assert!(properties.get(key).is_some(), "{key} isn't in the schema");
+            }
+        }
+    }
+}
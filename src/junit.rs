@@ -0,0 +1,193 @@
+//! JUnit XML output (`--format junit`), for CI systems (e.g. Jenkins and
+//! GitLab) that render test reports natively.
+//!
+//! Each finding is a test case: its audit is the case's class, its primary
+//! location is the case's name, and its annotation is the failure message.
+//! Suppressed findings (with `--show-suppressed`) are skipped test cases.
+//! Test cases are grouped into a test suite per file.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::{
+    finding::{suppress::Suppression, Finding},
+    registry::WorkflowRegistry,
+};
+
+/// Escapes `text` for use in XML attributes and text.
+///
+/// Characters that XML doesn't allow at all (i.e. most control characters)
+/// are dropped.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Returns the path of the file that `finding` is in, per its primary
+/// location.
+fn path<'a>(registry: &'a WorkflowRegistry, finding: &Finding<'a>) -> &'a str {
+    finding
+        .locations
+        .first()
+        .map(|location| registry.get_relative_path(location.symbolic.name))
+        .unwrap_or_default()
+}
+
+/// A JUnit report, built up as each input is audited.
+#[derive(Default)]
+pub(crate) struct Report {
+    suites: String,
+    tests: usize,
+    failures: usize,
+    skipped: usize,
+}
+
+impl Report {
+    /// Adds `findings` to the report, as a test suite for each file.
+    pub(crate) fn add(&mut self, registry: &WorkflowRegistry, findings: &[Finding]) -> Result<()> {
+        // Each input's findings are contiguous.
+        let mut findings = findings;
+        while let Some(first) = findings.first() {
+            let file = path(registry, first);
+            let n = findings
+                .iter()
+                .take_while(|&f| path(registry, f) == file)
+                .count();
+            let (suite, rest) = findings.split_at(n);
+            findings = rest;
+
+            let skipped = suite.iter().filter(|f| f.suppression.is_some()).count();
+            let name = match &registry.remote {
+                Some(slug) => format!("{slug}/{file}"),
+                None => file.to_string(),
+            };
+            writeln!(
+                self.suites,
+                r#"  <testsuite name="{name}" tests="{tests}" failures="{failures}" skipped="{skipped}">"#,
+                name = escape(&name),
+                tests = suite.len(),
+                failures = suite.len() - skipped,
+            )?;
+            for finding in suite {
+                self.add_case(registry, finding)?;
+            }
+            self.suites.push_str("  </testsuite>\n");
+
+            self.tests += suite.len();
+            self.failures += suite.len() - skipped;
+            self.skipped += skipped;
+        }
+
+        Ok(())
+    }
+
+    fn add_case(&mut self, registry: &WorkflowRegistry, finding: &Finding) -> Result<()> {
+        let Some(primary) = finding.locations.first() else {
+            return Ok(());
+        };
+        let path = registry.get_relative_path(primary.symbolic.name);
+        let point = &primary.concrete.location.start_point;
+
+        writeln!(
+            self.suites,
+            r#"    <testcase classname="{ident}" name="{path}:{line}:{column}" file="{path}" line="{line}">"#,
+            ident = finding.ident,
+            path = escape(path),
+            line = point.row + 1,
+            column = point.column + 1,
+        )?;
+
+        match finding.suppression {
+            Some(suppression) => {
+                let message = match suppression {
+                    Suppression::Comment => "suppressed by an inline comment",
+                    Suppression::Config => "suppressed by the configuration file",
+                    Suppression::Baseline => "suppressed by the baseline",
+                };
+                writeln!(self.suites, r#"      <skipped message="{message}"/>"#)?;
+            }
+            None => {
+                // The failure's text has everything that the plain output
+                // does, short of the rendered snippets.
+                let mut details = format!("{desc}\n", desc = finding.desc);
+                for location in &finding.locations {
+                    let point = &location.concrete.location.start_point;
+                    writeln!(
+                        details,
+                        "{path}:{line}:{column}: {annotation}",
+                        path = registry.get_relative_path(location.symbolic.name),
+                        line = point.row + 1,
+                        column = point.column + 1,
+                        annotation = location.symbolic.annotation,
+                    )?;
+                }
+                if let Some(suggestion) = &finding.suggestion {
+                    writeln!(details, "help: {suggestion}")?;
+                }
+                writeln!(details, "see: {url}", url = finding.url())?;
+
+                writeln!(
+                    self.suites,
+                    r#"      <failure message="{message}" type="{severity:?}">{details}</failure>"#,
+                    message = escape(&primary.symbolic.annotation),
+                    severity = finding.determinations.severity,
+                    details = escape(&details),
+                )?;
+            }
+        }
+
+        self.suites.push_str("    </testcase>\n");
+        Ok(())
+    }
+
+    /// Renders the whole report.
+    pub(crate) fn render(&self) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuites name=\"zizmor\" tests=\"{tests}\" failures=\"{failures}\" skipped=\"{skipped}\">\n\
+             {suites}</testsuites>\n",
+            tests = self.tests,
+            failures = self.failures,
+            skipped = self.skipped,
+            suites = self.suites,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, Report};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(
+            escape(r#"<a href="x">&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&apos;&lt;/a&gt;"
+        );
+        assert_eq!(escape("tab\there\u{1b}[0m\n"), "tab\there[0m\n");
+    }
+
+    #[test]
+    fn test_empty_report() {
+        assert_eq!(
+            Report::default().render(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuites name=\"zizmor\" tests=\"0\" failures=\"0\" skipped=\"0\">\n\
+             </testsuites>\n"
+        );
+    }
+}
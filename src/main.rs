@@ -1,28 +1,72 @@
-use std::{io::stdout, path::PathBuf, time::Duration};
+use std::{
+    io::{stdout, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime},
+};
 
 use anyhow::{anyhow, Context, Result};
-use audit::WorkflowAudit;
-use clap::{Parser, ValueEnum};
+use audit::{Audit, Input};
+use clap::{builder::PossibleValuesParser, CommandFactory, Parser, Subcommand, ValueEnum};
+use collect::Pending;
+use config::Config;
+use finding::{baseline::Baseline, suppress::Suppression, Confidence, Finding, Severity};
 use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use owo_colors::OwoColorize;
 use registry::{AuditRegistry, WorkflowRegistry};
+use result_cache::ResultCache;
 use state::{AuditConfig, AuditState};
 
+mod advisories;
+mod annotations;
 mod audit;
+mod auth;
+mod autofix;
+mod cache;
+mod code_climate;
+mod collect;
+mod config;
+mod dataflow;
 mod expr;
 mod finding;
 mod github_api;
+mod html;
+mod init;
+mod injection;
+mod json;
+mod junit;
+mod markdown;
 mod models;
+mod parallel;
+mod ratelimit;
+mod refs;
 mod registry;
+mod remote;
 mod render;
+mod result_cache;
+mod rules;
 mod sarif;
+mod secrets;
+mod shell;
 mod state;
+mod symbols;
 mod utils;
+mod watch;
 
 /// Finds security issues in GitHub Actions setups.
 #[derive(Parser)]
-#[command(version)]
+#[command(
+    version,
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Emit findings even when the context suggests an explicit security decision made by the user.
     #[arg(short, long)]
     pedantic: bool,
@@ -39,16 +83,196 @@ struct Args {
     #[arg(short, long)]
     no_progress: bool,
 
-    /// The GitHub API token to use.
-    #[arg(long, env)]
+    /// The number of threads to parse and audit inputs on. Defaults to
+    /// the number of available CPUs.
+    #[arg(short, long)]
+    jobs: Option<NonZeroUsize>,
+
+    /// Don't load or store cached audit results. Results are cached by
+    /// default, so that repeated runs only re-audit changed inputs.
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Only let the given audits (separated by commas) use GitHub's API,
+    /// e.g. `--online-audits impostor-commit`. Audits that require it are
+    /// skipped unless they're given; others fall back on offline checks.
+    /// By default, every audit may use the API, when online.
+    #[arg(long, value_delimiter = ',', value_name = "AUDITS")]
+    online_audits: Option<Vec<String>>,
+
+    /// The GitHub API token to use. Defaults to `GH_TOKEN`, then
+    /// `GITHUB_TOKEN`, then the GitHub CLI's token (`gh auth token`).
+    #[arg(long)]
     gh_token: Option<String>,
 
+    /// The hostname of the GitHub instance to use, e.g. for
+    /// GitHub Enterprise Server. Defaults to `github.com`.
+    #[arg(long, env = "GH_HOST")]
+    gh_hostname: Option<String>,
+
+    /// The base URL of the GitHub API to use, e.g.
+    /// `https://github.example.com/api/v3`. Takes precedence over
+    /// `--gh-hostname`, which it's derived from by default.
+    #[arg(long, env = "GITHUB_API_URL", value_name = "URL")]
+    github_api_url: Option<String>,
+
+    /// The most GitHub API requests to make. Once they're spent, online
+    /// audits are truncated (and reported as such) rather than failing.
+    #[arg(long, value_name = "REQUESTS")]
+    github_api_budget: Option<u64>,
+
+    /// The configuration file to load. By default, `.github/zizmor.yml`
+    /// or `zizmor.yml` (or `.toml`) is loaded from the audited repository,
+    /// if present.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Only run the given audits (separated by commas).
+    #[arg(long, value_delimiter = ',', conflicts_with = "except")]
+    only: Vec<String>,
+
+    /// Don't run the given audits (separated by commas).
+    #[arg(long, value_delimiter = ',')]
+    except: Vec<String>,
+
+    /// Only report findings with at least this severity.
+    #[arg(long, value_enum)]
+    min_severity: Option<Severity>,
+
+    /// Only report findings with at least this confidence.
+    #[arg(long, value_enum)]
+    min_confidence: Option<Confidence>,
+
+    /// Which findings fail the run, i.e. exit with a failing code: `none`,
+    /// findings with at least a given severity (e.g. `high` or
+    /// `severity:high`), or with at least a given confidence
+    /// (e.g. `confidence:medium`). Defaults to any finding.
+    #[arg(long)]
+    fail_on: Option<FailOn>,
+
+    /// Exit with a distinct code (11) when there are findings, but none of
+    /// them fail the run, rather than succeeding.
+    #[arg(long)]
+    tolerated_exit_code: bool,
+
+    /// Suppress findings that are recorded in the given baseline file,
+    /// so that only new findings are reported.
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// Record the current findings in the given baseline file, for use
+    /// with `--baseline`. Findings that are suppressed by inline comments
+    /// or by the configuration file aren't recorded.
+    #[arg(long)]
+    write_baseline: Option<PathBuf>,
+
+    /// Report findings that are suppressed by inline comments, by the
+    /// configuration file, or by the baseline, marked as such.
+    #[arg(long)]
+    show_suppressed: bool,
+
+    /// Follow each finding with remediation guidance for its audit (see
+    /// `zizmor explain`). Only applies to the plain and JSON formats.
+    #[arg(long)]
+    explain: bool,
+
+    /// Apply the fixes that are available for findings (e.g. pinning
+    /// actions to commits) to the audited files, in place. Only applies to
+    /// local inputs.
+    #[arg(long, conflicts_with = "org")]
+    fix: bool,
+
+    /// With `--fix`, print the fixes as a unified diff instead of applying
+    /// them. Findings are rendered to stderr, so that stdout is just the diff.
+    #[arg(long, requires = "fix", conflicts_with = "format")]
+    dry_run: bool,
+
+    /// With `--fix`, write the fixes to the given file as a patch (which
+    /// `git apply` accepts) instead of applying them.
+    #[arg(long, value_name = "PATH", requires = "fix")]
+    patch_out: Option<PathBuf>,
+
+    /// Keep running, re-auditing the inputs whenever they change, and
+    /// report the findings that are new or resolved since the last audit.
+    /// Only applies to local inputs.
+    #[arg(long, conflicts_with_all = ["format", "write_baseline", "org"])]
+    watch: bool,
+
     /// The output format to emit. By default, plain text will be emitted
     #[arg(long, value_enum)]
     format: Option<OutputFormat>,
 
-    /// The workflow filename or directory to audit.
-    input: PathBuf,
+    /// The number of lines of source to capture before and after each
+    /// location in a finding.
+    #[arg(long, default_value_t = finding::DEFAULT_CONTEXT_LINES)]
+    context_lines: usize,
+
+    /// The path to label an input read from stdin with, which also
+    /// determines whether it's a workflow, action definition, or
+    /// Dependabot configuration.
+    #[arg(long, default_value = "stdin.yml")]
+    stdin_filename: PathBuf,
+
+    /// The workflow files, action definitions, Dependabot configurations,
+    /// or directories to audit, or glob patterns matching any of them.
+    /// Directories are searched recursively. `-` reads a single input
+    /// from stdin, and `owner/repo[@ref]` audits a repository on GitHub.
+    #[arg(required_unless_present = "org")]
+    inputs: Vec<PathBuf>,
+
+    /// Audit every (non-archived) repository in the given GitHub
+    /// organization, in addition to any inputs.
+    #[arg(long, value_name = "ORG")]
+    org: Option<String>,
+
+    /// With `--org`, only audit the repositories whose names match any of
+    /// the given glob patterns, e.g. `--org-repos 'api-*,web'`.
+    #[arg(long, value_name = "PATTERNS", value_delimiter = ',', requires = "org")]
+    org_repos: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List every audit, with its default severity and confidence, whether
+    /// it's pedantic-only or requires GitHub API access, and its settings.
+    Rules {
+        /// Emit the list as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the documentation for an audit: what it detects, why it
+    /// matters, and how to fix its findings.
+    Explain {
+        /// The audit to explain, e.g. `template-injection`.
+        audit: String,
+    },
+    /// Generate a starter configuration file for a repository, which
+    /// ignores its existing findings (with TODOs for why they're accepted).
+    Init {
+        /// The repository to generate a configuration file for.
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Generate shell completions, e.g. `zizmor completions bash`.
+    Completions {
+        /// The shell to generate completions for.
+        shell: clap_complete::Shell,
+    },
+    /// Print the JSON Schema of `--format json` output.
+    Schema,
+    /// Manage the on-disk cache of GitHub API data and audit results.
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Remove everything in the cache.
+    Clear,
+    /// Print the cache's directory.
+    Dir,
 }
 
 #[derive(Debug, Copy, Clone, ValueEnum)]
@@ -56,124 +280,938 @@ pub(crate) enum OutputFormat {
     Plain,
     Json,
     Sarif,
+    Markdown,
+    Junit,
+    Github,
+    Html,
+    CodeClimate,
 }
 
-fn main() -> Result<()> {
-    human_panic::setup_panic!();
+/// The exit code when any findings meet the `--fail-on` threshold,
+/// distinct from errors (1) and usage errors (2).
+const FINDINGS_EXIT_CODE: u8 = 10;
 
-    let args = Args::parse();
+/// The exit code with `--tolerated-exit-code` when there are findings, but
+/// none of them meet the `--fail-on` threshold (e.g. because they're
+/// suppressed).
+const TOLERATED_FINDINGS_EXIT_CODE: u8 = 11;
 
-    env_logger::Builder::new()
-        .filter_level(args.verbose.log_level_filter())
-        .init();
-
-    let config = AuditConfig::from(&args);
-
-    let mut workflow_paths = vec![];
-    if args.input.is_file() {
-        workflow_paths.push(args.input.clone());
-    } else if args.input.is_dir() {
-        let mut absolute = std::fs::canonicalize(&args.input)?;
-        if !absolute.ends_with(".github/workflows") {
-            absolute.push(".github/workflows")
-        }
+/// Which findings make `zizmor` exit with [`FINDINGS_EXIT_CODE`].
+#[derive(Clone, Copy, Debug)]
+enum FailOn {
+    /// No findings.
+    None,
+    /// Findings with at least the given severity.
+    Severity(Severity),
+    /// Findings with at least the given confidence.
+    Confidence(Confidence),
+}
 
-        log::debug!("collecting workflows from {absolute:?}");
+impl FromStr for FailOn {
+    type Err = String;
 
-        for entry in std::fs::read_dir(absolute)? {
-            let workflow_path = entry?.path();
-            match workflow_path.extension() {
-                Some(ext) if ext == "yml" || ext == "yaml" => workflow_paths.push(workflow_path),
-                _ => continue,
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "none" => Ok(Self::None),
+            None => <Severity as ValueEnum>::from_str(s, true).map(Self::Severity),
+            Some(("severity", severity)) => {
+                <Severity as ValueEnum>::from_str(severity, true).map(Self::Severity)
             }
+            Some(("confidence", confidence)) => {
+                <Confidence as ValueEnum>::from_str(confidence, true).map(Self::Confidence)
+            }
+            Some(_) => Err(format!(
+                "expected none, <severity>, severity:<severity>, or confidence:<confidence>; got {s}"
+            )),
         }
+    }
+}
 
-        if workflow_paths.is_empty() {
-            return Err(anyhow!(
-                "no workflow files collected; empty or wrong directory?"
-            ));
+impl FailOn {
+    /// Returns whether `finding` (which isn't suppressed) fails the run.
+    fn fails(&self, finding: &Finding) -> bool {
+        match self {
+            Self::None => false,
+            Self::Severity(severity) => finding.determinations.severity >= *severity,
+            Self::Confidence(confidence) => finding.determinations.confidence >= *confidence,
         }
-    } else {
-        return Err(anyhow!("input must be a single workflow file or directory"));
     }
+}
 
-    let audit_state = AuditState::new(config);
+/// Invokes the macro `$callback` with the path of every audit.
+macro_rules! for_each_audit {
+    ($callback:ident) => {
+        $callback!(audit::artipacked::Artipacked);
+        $callback!(audit::excessive_permissions::ExcessivePermissions);
+        $callback!(audit::dangerous_triggers::DangerousTriggers);
+        $callback!(audit::impostor_commit::ImpostorCommit);
+        $callback!(audit::ref_confusion::RefConfusion);
+        $callback!(audit::use_trusted_publishing::UseTrustedPublishing);
+        $callback!(audit::template_injection::TemplateInjection);
+        $callback!(audit::hardcoded_container_credentials::HardcodedContainerCredentials);
+        $callback!(audit::self_hosted_runner::SelfHostedRunner);
+        $callback!(audit::known_vulnerable_actions::KnownVulnerableActions);
+        $callback!(audit::unpinned_uses::UnpinnedUses);
+        $callback!(audit::cache_poisoning::CachePoisoning);
+        $callback!(audit::hardcoded_credentials::HardcodedCredentials);
+        $callback!(audit::secrets_inherit::SecretsInherit);
+        $callback!(audit::github_env::GitHubEnv);
+        $callback!(audit::deprecated_commands::DeprecatedCommands);
+        $callback!(audit::insecure_commands::InsecureCommands);
+        $callback!(audit::pipe_to_shell::PipeToShell);
+        $callback!(audit::unpinned_images::UnpinnedImages);
+        $callback!(audit::github_script_injection::GitHubScriptInjection);
+        $callback!(audit::actor_spoofing::ActorSpoofing);
+        $callback!(audit::typosquatting::Typosquatting);
+        $callback!(audit::deprecated_runner_images::DeprecatedRunnerImages);
+        $callback!(audit::deprecated_node_runtime::DeprecatedNodeRuntime);
+        $callback!(audit::missing_timeout::MissingTimeout);
+        $callback!(audit::missing_concurrency::MissingConcurrency);
+        $callback!(audit::label_gating::LabelGating);
+        $callback!(audit::artifact_execution::ArtifactExecution);
+        $callback!(audit::sensitive_artifact::SensitiveArtifact);
+        $callback!(audit::checkout_confusion::CheckoutConfusion);
+        $callback!(audit::untrusted_checkout::UntrustedCheckout);
+        $callback!(audit::untrusted_trigger_chain::UntrustedTriggerChain);
+        $callback!(audit::secrets_in_env::SecretsInEnv);
+        $callback!(audit::untrusted_matrix::UntrustedMatrix);
+        $callback!(audit::security_gate_bypass::SecurityGateBypass);
+        $callback!(audit::unsound_condition::UnsoundCondition);
+        $callback!(audit::secret_outputs::SecretOutputs);
+        $callback!(audit::third_party_token::ThirdPartyToken);
+        $callback!(audit::unmaintained_actions::UnmaintainedActions);
+        $callback!(audit::branch_pinned_reusable::BranchPinnedReusable);
+        $callback!(audit::auto_approve::AutoApprove);
+        $callback!(audit::privileged_execution::PrivilegedExecution);
+        $callback!(audit::missing_egress_control::MissingEgressControl);
+        $callback!(audit::missing_environment::MissingEnvironment);
+        $callback!(audit::ref_type_confusion::RefTypeConfusion);
+        $callback!(audit::secrets_in_cache_key::SecretsInCacheKey);
+        $callback!(audit::wildcard_branch_trigger::WildcardBranchTrigger);
+        $callback!(audit::comment_command_execution::CommentCommandExecution);
+        $callback!(audit::untrusted_path::UntrustedPath);
+        $callback!(audit::untrusted_install::UntrustedInstall);
+        $callback!(audit::privileged_container::PrivilegedContainer);
+        $callback!(audit::insecure_env::InsecureEnv);
+        $callback!(audit::needs_cycle::NeedsCycle);
+        $callback!(audit::missing_shell::MissingShell);
+    };
+}
 
-    let mut workflow_registry = WorkflowRegistry::new();
-    for workflow_path in workflow_paths.iter() {
-        workflow_registry.register_workflow(workflow_path)?;
+/// The number of inputs to load and audit at a time, per job.
+const BATCH_SIZE_PER_JOB: usize = 4;
+
+/// What's collected for the output formats that are printed once every
+/// input has been audited.
+#[derive(Default)]
+struct Collected {
+    json: Vec<serde_json::Value>,
+    sarif: Vec<serde_sarif::sarif::Result>,
+    markdown: String,
+    junit: junit::Report,
+    html: String,
+    code_climate: Vec<code_climate::Issue>,
+}
+
+fn main() -> Result<ExitCode> {
+    human_panic::setup_panic!();
+
+    let started = SystemTime::now();
+
+    let mut args = Args::parse();
+
+    env_logger::Builder::new()
+        .filter_level(args.verbose.log_level_filter())
+        .init();
+
+    // `zizmor init` audits its directory like any other input, but records
+    // the findings in a new configuration file rather than reporting them.
+    let mut scaffold = None;
+    match args.command.take() {
+        Some(Command::Rules { json }) => return list_rules(json),
+        Some(Command::Explain { audit }) => return explain(&audit),
+        Some(Command::Completions { shell }) => return completions(shell),
+        Some(Command::Schema) => {
+            print!("{schema}", schema = json::SCHEMA);
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Cache { command }) => return manage_cache(command),
+        Some(Command::Init { dir }) => {
+            scaffold = Some(init::Init::new(&dir)?);
+            args.inputs = vec![dir];
+        }
+        None => (),
     }
 
+    let config = AuditConfig::try_from(&args)?;
+
+    let audit_state = AuditState::new(config);
+
     let mut audit_registry = AuditRegistry::new();
+    let mut known_audits = vec![];
+    let mut skipped_online = vec![];
+    let selected = |ident: &str| {
+        (args.only.is_empty() || args.only.iter().any(|only| only == ident))
+            && !args.except.iter().any(|except| except == ident)
+    };
     macro_rules! register_audit {
         ($rule:path) => {{
             // HACK: https://github.com/rust-lang/rust/issues/48067
             use $rule as base;
-            match base::new(audit_state.clone()) {
-                Ok(audit) => audit_registry.register_workflow_audit(base::ident(), Box::new(audit)),
-                Err(e) => log::warn!("{audit} is being skipped: {e}", audit = base::ident()),
+            known_audits.push(<base as Audit>::ident());
+            if !selected(<base as Audit>::ident()) {
+                log::info!(
+                    "{audit} is being skipped: it wasn't selected",
+                    audit = <base as Audit>::ident()
+                );
+            } else if audit_state
+                .config
+                .file
+                .is_disabled(<base as Audit>::ident())
+            {
+                log::info!(
+                    "{audit} is being skipped: it's disabled in the config",
+                    audit = <base as Audit>::ident()
+                );
+            } else if <base as Audit>::requires_github_api()
+                && !audit_state.is_online(<base as Audit>::ident())
+            {
+                log::info!(
+                    "{audit} is being skipped: it requires GitHub API access",
+                    audit = <base as Audit>::ident()
+                );
+                skipped_online.push(<base as Audit>::ident());
+            } else {
+                // Audits that may use the API, but aren't allowed to, fall
+                // back on their offline checks.
+                let state = if audit_state.is_online(<base as Audit>::ident()) {
+                    audit_state.for_audit(<base as Audit>::ident())
+                } else {
+                    audit_state.offline()
+                };
+                match <base as Audit>::new(state) {
+                    Ok(audit) => audit_registry.register_audit(
+                        <base as Audit>::ident(),
+                        <base as Audit>::desc(),
+                        Box::new(audit),
+                    ),
+                    Err(e) => log::warn!(
+                        "{audit} is being skipped: {e}",
+                        audit = <base as Audit>::ident()
+                    ),
+                }
             }
         }};
     }
 
-    register_audit!(audit::artipacked::Artipacked);
-    register_audit!(audit::excessive_permissions::ExcessivePermissions);
-    register_audit!(audit::dangerous_triggers::DangerousTriggers);
-    register_audit!(audit::impostor_commit::ImpostorCommit);
-    register_audit!(audit::ref_confusion::RefConfusion);
-    register_audit!(audit::use_trusted_publishing::UseTrustedPublishing);
-    register_audit!(audit::template_injection::TemplateInjection);
-    register_audit!(audit::hardcoded_container_credentials::HardcodedContainerCredentials);
-    register_audit!(audit::self_hosted_runner::SelfHostedRunner);
-    register_audit!(audit::known_vulnerable_actions::KnownVulnerableActions);
+    for_each_audit!(register_audit);
 
-    let bar = ProgressBar::new((workflow_registry.len() * audit_registry.len()) as u64);
+    let online_audits = audit_state.config.online_audits.iter().flatten();
+    for ident in args.only.iter().chain(&args.except).chain(online_audits) {
+        if !known_audits.contains(&ident.as_str()) {
+            return Err(unknown_audit(ident, &known_audits));
+        }
+    }
 
-    // Hide the bar if the user has explicitly asked for quiet output
-    // or to disable just the progress bar.
-    if args.verbose.is_silent() || args.no_progress {
-        bar.set_draw_target(ProgressDrawTarget::hidden());
-    } else {
-        bar.enable_steady_tick(Duration::from_millis(100));
-        bar.set_style(
-            ProgressStyle::with_template("[{elapsed_precise}] {msg} {bar:!30.cyan/blue}").unwrap(),
-        );
+    // Audits that need more than public data from the API quietly see
+    // less without it, so we check that the token can see everything.
+    if let (Some(client), Some(token)) = (audit_state.github_client(), &audit_state.config.gh_token)
+    {
+        let needed = known_rules()
+            .into_iter()
+            .filter(|rule| {
+                !rule.github_scopes.is_empty()
+                    && audit_registry.audits.contains_key(rule.ident)
+                    && audit_state.is_online(rule.ident)
+            })
+            .map(|rule| (rule.ident, rule.github_scopes))
+            .collect::<Vec<_>>();
+        if !needed.is_empty() {
+            auth::check_scopes(&client, &token.source, &needed);
+        }
     }
 
-    let mut results = vec![];
-    for (_, workflow) in workflow_registry.iter_workflows() {
-        bar.set_message(format!(
-            "auditing {workflow}",
-            workflow = workflow.filename().cyan()
-        ));
-        for (name, audit) in audit_registry.iter_workflow_audits() {
-            results.extend(audit.audit(workflow).with_context(|| {
-                format!(
-                    "{name} failed on {workflow}",
-                    workflow = workflow.filename()
-                )
-            })?);
-            bar.inc(1);
+    let jobs = args.jobs.unwrap_or_else(parallel::default_jobs);
+
+    // Remote repositories are fetched into temporary checkouts, which are
+    // removed once we're done with them.
+    let mut checkouts = vec![];
+    let mut local_inputs = vec![];
+    for input in &args.inputs {
+        let slug = input
+            .to_str()
+            .filter(|_| !input.exists())
+            .and_then(remote::Slug::parse);
+
+        match slug {
+            Some(slug) => {
+                let Some(client) = audit_state.github_client() else {
+                    return Err(anyhow!(
+                        "can't audit {input:?}: remote repositories require GitHub API access"
+                    ));
+                };
+                match remote::fetch(&client, &slug, jobs)? {
+                    Some(checkout) => checkouts.push(checkout),
+                    None => {
+                        return Err(anyhow!(
+                            "{input:?}: no workflow, action, or Dependabot files found"
+                        ))
+                    }
+                }
+            }
+            None => local_inputs.push(input.clone()),
         }
-        bar.println(format!(
-            "🌈 completed {workflow}",
-            workflow = &workflow.filename().cyan()
-        ));
     }
 
-    bar.finish_and_clear();
+    // With `--org`, a repository that can't be fetched or loaded is
+    // reported as failed, rather than failing the whole run.
+    let mut failed_repositories = vec![];
+    if let Some(org) = &args.org {
+        let Some(client) = audit_state.github_client() else {
+            return Err(anyhow!(
+                "can't audit {org}: --org requires GitHub API access"
+            ));
+        };
+        let (fetched, failed) = remote::fetch_org(&client, org, &args.org_repos, jobs)?;
+        checkouts.extend(fetched);
+        failed_repositories.extend(failed);
+    }
+
+    if args.watch && (!checkouts.is_empty() || local_inputs == [Path::new("-")]) {
+        return Err(anyhow!("--watch only applies to local inputs"));
+    }
+    if args.fix && (!checkouts.is_empty() || local_inputs == [Path::new("-")]) {
+        return Err(anyhow!("--fix only applies to local inputs"));
+    }
+
+    finding::set_context_lines(args.context_lines);
+
+    let result_cache = ResultCache::new(&audit_state.config, !args.no_cache);
 
     let format = match args.format {
         None => OutputFormat::Plain,
         Some(f) => f,
     };
 
+    // Flags take precedence over the configuration file.
+    let min_severity = args
+        .min_severity
+        .or(audit_state.config.file.min_severity())
+        .unwrap_or(Severity::Informational);
+    let min_confidence = args
+        .min_confidence
+        .or(audit_state.config.file.min_confidence())
+        .unwrap_or(Confidence::Unknown);
+
+    let baseline = match &args.baseline {
+        Some(path) => Baseline::load(path)?,
+        None => Baseline::default(),
+    };
+    let mut new_baseline = args.write_baseline.as_ref().map(|_| Baseline::default());
+
+    // By default, every (unsuppressed) finding fails the run.
+    let fail_on = args
+        .fail_on
+        .unwrap_or(FailOn::Severity(Severity::Informational));
+    // With `--dry-run` or `--patch-out`, fixes are collected into a patch
+    // rather than applied.
+    let mut patch = (args.dry_run || args.patch_out.is_some()).then(String::new);
+    // With `--dry-run`, stdout is reserved for the patch, so that it can be
+    // piped into `git apply`; plain output goes to stderr instead.
+    let mut plain: Box<dyn Write> = if args.dry_run {
+        Box::new(anstream::stderr())
+    } else {
+        Box::new(anstream::stdout())
+    };
+
+    // With `--watch`, everything from collecting the inputs onwards is
+    // repeated whenever they change. Unchanged inputs aren't re-audited,
+    // thanks to the result cache.
+    let mut watched: Option<watch::Findings> = None;
+    let (failing, tolerated, mut summary, collected) = loop {
+        // The inputs are snapshotted before they're collected, so that
+        // changes made while they're audited trigger another audit.
+        let before = args
+            .watch
+            .then(|| watch::snapshot(&local_inputs))
+            .transpose()?;
+
+        let mut repositories = if local_inputs == [Path::new("-")] {
+            vec![collect::stdin(&args.stdin_filename)?]
+        } else if local_inputs.is_empty() {
+            vec![]
+        } else {
+            collect::collect(&local_inputs)?
+        };
+        for checkout in &checkouts {
+            for mut repository in collect::collect(std::slice::from_ref(&checkout.dir))? {
+                repository.remote = Some(checkout.slug.clone());
+                repositories.push(repository);
+            }
+        }
+
+        let repositories = repositories
+            .into_iter()
+            .map(|mut repository| {
+                repository.inputs.retain(|pending| {
+                    let excluded = audit_state.config.file.is_excluded(pending.relative_path());
+                    if excluded {
+                        log::info!(
+                            "{path} is excluded in the config",
+                            path = pending.relative_path()
+                        );
+                    }
+                    !excluded
+                });
+                repository
+            })
+            .collect::<Vec<_>>();
+        let ninputs = repositories
+            .iter()
+            .map(|repository| repository.inputs.len())
+            .sum::<usize>();
+
+        let bar = ProgressBar::new((ninputs * audit_registry.len()) as u64);
+
+        // Hide the bar if the user has explicitly asked for quiet output
+        // or to disable just the progress bar.
+        if args.verbose.is_silent() || args.no_progress {
+            bar.set_draw_target(ProgressDrawTarget::hidden());
+        } else {
+            bar.enable_steady_tick(Duration::from_millis(100));
+            bar.set_style(
+                ProgressStyle::with_template("[{elapsed_precise}] {msg} {bar:!30.cyan/blue}")
+                    .unwrap(),
+            );
+        }
+
+        let (mut failing, mut tolerated) = (0, 0);
+        let mut summary = render::Summary::default();
+        summary.inputs = ninputs;
+        let mut collected = Collected::default();
+        let mut current = args.watch.then(watch::Findings::default);
+        'repositories: for repository in &repositories {
+            if repositories.len() > 1 {
+                log::info!("auditing repository {root:?}", root = repository.root);
+            }
+
+            // Only repositories from `--org` are skipped on failure; anything
+            // the user asked for explicitly fails the run. With `--watch`,
+            // an input that's mid-edit shouldn't end the watch, so the
+            // failure is just reported.
+            let skip_failure = |e: anyhow::Error, failed: &mut Vec<String>| match &repository.remote
+            {
+                Some(slug) if args.org.is_some() => {
+                    log::warn!("couldn't audit {slug}: {e:#}");
+                    failed.push(slug.clone());
+                    Ok(())
+                }
+                _ if args.watch => {
+                    log::error!("couldn't audit {root:?}: {e:#}", root = repository.root);
+                    Ok(())
+                }
+                _ => Err(e),
+            };
+
+            // Inputs are loaded, audited, and rendered a batch at a time, so
+            // that only a batch's worth of inputs is ever in memory. Linking
+            // workflows to the ones that trigger them needs all of them, so
+            // we do a first (lighter) pass over them for that. Workflows can
+            // only trigger others in the same repository.
+            let workflow_paths = repository
+                .inputs
+                .iter()
+                .filter_map(|pending| match pending {
+                    Pending::Workflow(path) => Some(path.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let sources = match registry::trigger_sources(&workflow_paths, jobs) {
+                Ok(sources) => sources,
+                Err(e) => {
+                    skip_failure(e, &mut failed_repositories)?;
+                    continue 'repositories;
+                }
+            };
+
+            for batch in repository.inputs.chunks(jobs.get() * BATCH_SIZE_PER_JOB) {
+                let workflow_registry = match load_batch(repository, batch, &sources, jobs) {
+                    Ok(workflow_registry) => workflow_registry,
+                    Err(e) => {
+                        skip_failure(e, &mut failed_repositories)?;
+                        continue 'repositories;
+                    }
+                };
+
+                let mut results = audit_inputs(
+                    &workflow_registry,
+                    &audit_registry,
+                    &audit_state.config.file,
+                    &result_cache,
+                    audit_state.github_client().as_ref(),
+                    jobs,
+                    &bar,
+                )?;
+
+                // Apply any user-configured severities and confidences.
+                for finding in &mut results {
+                    if let Some(severity) = audit_state.config.file.severity(finding.ident) {
+                        finding.determinations.severity = severity;
+                    }
+                    if let Some(confidence) = audit_state.config.file.confidence(finding.ident) {
+                        finding.determinations.confidence = confidence;
+                    }
+
+                    let ignored = finding.locations.iter().any(|location| {
+                        let span = &location.concrete.location;
+                        audit_state.config.file.is_ignored(
+                            finding.ident,
+                            workflow_registry.get_relative_path(location.symbolic.name),
+                            span.start_point.row + 1..=span.end_point.row + 1,
+                        )
+                    });
+                    if ignored && finding.suppression.is_none() {
+                        finding.suppression = Some(Suppression::Config);
+                    }
+
+                    // Baselined findings are recorded again, so that a rewritten
+                    // baseline only loses the findings that have been fixed.
+                    if finding.suppression.is_none() {
+                        if let Some(new_baseline) = &mut new_baseline {
+                            let path = finding.locations.first().map_or("", |location| {
+                                workflow_registry.get_relative_path(location.symbolic.name)
+                            });
+                            new_baseline.insert(path, finding);
+                        }
+                        if baseline.contains(finding) {
+                            finding.suppression = Some(Suppression::Baseline);
+                        }
+                    }
+                }
+
+                // Findings below the minimum severity or confidence aren't
+                // reported at all, even with `--show-suppressed`.
+                let found = results.len();
+                results.retain(|finding| {
+                    finding.determinations.severity >= min_severity
+                        && finding.determinations.confidence >= min_confidence
+                });
+                tolerated += found - results.len();
+
+                for finding in &results {
+                    if finding.suppression.is_none() && fail_on.fails(finding) {
+                        failing += 1;
+                    } else {
+                        tolerated += 1;
+                    }
+                }
+
+                if !args.show_suppressed {
+                    let (suppressed, unsuppressed): (Vec<_>, Vec<_>) = results
+                        .into_iter()
+                        .partition(|finding| finding.suppression.is_some());
+                    summary.suppressed += suppressed.len();
+                    results = unsuppressed;
+                }
+
+                if args.fix {
+                    summary.fixed += autofix::apply(&workflow_registry, &results, patch.as_mut())?;
+                }
+
+                if let Some(current) = &mut current {
+                    for finding in results.iter().filter(|f| f.suppression.is_none()) {
+                        current.record(&workflow_registry, finding);
+                    }
+                }
+
+                if let Some(scaffold) = &mut scaffold {
+                    scaffold.record(&workflow_registry, &results);
+                    continue;
+                }
+
+                match format {
+                    // With `--watch`, only the first audit's findings are
+                    // rendered in full; after that, only the changes are.
+                    OutputFormat::Plain if watched.is_some() => (),
+                    OutputFormat::Plain => bar.suspend(|| {
+                        render::render_findings(
+                            &mut plain,
+                            &workflow_registry,
+                            &results,
+                            &mut summary,
+                            args.explain,
+                        )
+                    })?,
+                    OutputFormat::Json => {
+                        for finding in &results {
+                            let mut value = serde_json::to_value(finding)?;
+                            if let (Some(slug), Some(object)) =
+                                (&repository.remote, value.as_object_mut())
+                            {
+                                object.insert("repository".into(), slug.clone().into());
+                            }
+                            let remediation =
+                                rules::remediation(finding.ident).filter(|_| args.explain);
+                            if let (Some(remediation), Some(object)) =
+                                (remediation, value.as_object_mut())
+                            {
+                                object.insert("remediation".into(), remediation.into());
+                            }
+                            collected.json.push(value);
+                        }
+                    }
+                    OutputFormat::Sarif => collected
+                        .sarif
+                        .extend(sarif::build_results(&workflow_registry, &results)),
+                    OutputFormat::Markdown => {
+                        for finding in &results {
+                            summary.count(&workflow_registry, finding);
+                        }
+                        markdown::render_findings(
+                            &workflow_registry,
+                            &results,
+                            &mut collected.markdown,
+                        )?
+                    }
+                    OutputFormat::Junit => collected.junit.add(&workflow_registry, &results)?,
+                    OutputFormat::Github => {
+                        let commands = annotations::render_findings(&workflow_registry, &results)?;
+                        bar.suspend(|| print!("{commands}"))
+                    }
+                    OutputFormat::Html => {
+                        for finding in &results {
+                            summary.count(&workflow_registry, finding);
+                        }
+                        html::render_findings(&workflow_registry, &results, &mut collected.html)?
+                    }
+                    OutputFormat::CodeClimate => collected
+                        .code_climate
+                        .extend(code_climate::build_issues(&workflow_registry, &results)),
+                }
+            }
+
+            summary.repositories += 1;
+        }
+
+        bar.finish_and_clear();
+
+        let (Some(current), Some(before)) = (current, before) else {
+            break (failing, tolerated, summary, collected);
+        };
+        match &watched {
+            None => render::render_summary(&mut plain, &summary)?,
+            Some(previous) => watch::render_diff(previous, &current)?,
+        }
+        watched = Some(current);
+        watch::wait(&local_inputs, &before)?;
+    };
+
+    if let Some(scaffold) = &scaffold {
+        scaffold.write(&known_rules(), audit_state.github_client().is_some())?;
+        println!(
+            "wrote {path} ({n} existing findings ignored)",
+            path = scaffold.path().display(),
+            n = scaffold.len()
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(patch) = &patch {
+        if args.dry_run {
+            print!("{patch}");
+        }
+        if let Some(path) = &args.patch_out {
+            std::fs::write(path, patch).with_context(|| format!("couldn't write {path:?}"))?;
+        }
+    }
+
+    summary.failed_repositories = failed_repositories;
+    summary.skipped_online = skipped_online;
+    summary.offline_reason = audit_state.offline_reason();
+    summary.api_usage = audit_state.github_client().map(|client| client.usage());
+    summary.patched = patch.is_some();
     match format {
-        OutputFormat::Plain => render::render_findings(&workflow_registry, &results),
-        OutputFormat::Json => serde_json::to_writer_pretty(stdout(), &results)?,
+        OutputFormat::Plain => render::render_summary(&mut plain, &summary)?,
+        OutputFormat::Json => {
+            let run = json::Run {
+                started_at: humantime::format_rfc3339_seconds(started).to_string(),
+                duration_ms: started
+                    .elapsed()
+                    .map_or(0, |elapsed| elapsed.as_millis() as u64),
+                inputs: summary.inputs,
+                repositories: summary.repositories,
+                audits: audit_registry
+                    .iter_audits()
+                    .map(|(ident, _, _)| ident)
+                    .collect(),
+                skipped_audits: summary.skipped_online.clone(),
+                truncated_audits: summary
+                    .api_usage
+                    .as_ref()
+                    .map(|usage| usage.truncated.clone())
+                    .unwrap_or_default(),
+                failed_repositories: summary.failed_repositories.clone(),
+                suppressed: summary.suppressed,
+            };
+            serde_json::to_writer_pretty(stdout(), &json::Output::new(run, collected.json))?
+        }
         OutputFormat::Sarif => {
-            serde_json::to_writer_pretty(stdout(), &sarif::build(&workflow_registry, results))?
+            serde_json::to_writer_pretty(stdout(), &sarif::build(&known_rules(), collected.sarif))?
+        }
+        OutputFormat::Markdown => print!("{}", markdown::render(&summary, &collected.markdown)?),
+        OutputFormat::Junit => print!("{}", collected.junit.render()),
+        // The annotations are printed as each input is audited.
+        OutputFormat::Github => (),
+        OutputFormat::Html => print!("{}", html::render(&summary, &collected.html)?),
+        OutputFormat::CodeClimate => {
+            serde_json::to_writer_pretty(stdout(), &collected.code_climate)?
         }
     };
-    Ok(())
+
+    if let (Some(path), Some(new_baseline)) = (&args.write_baseline, &new_baseline) {
+        new_baseline.save(path)?;
+    }
+
+    Ok(match (failing, tolerated) {
+        (0, 0) => ExitCode::SUCCESS,
+        (0, _) if args.tolerated_exit_code => ExitCode::from(TOLERATED_FINDINGS_EXIT_CODE),
+        (0, _) => ExitCode::SUCCESS,
+        _ => ExitCode::from(FINDINGS_EXIT_CODE),
+    })
+}
+
+/// Returns the metadata of every audit, sorted by identifier.
+fn known_rules() -> Vec<rules::Rule> {
+    let mut rules = vec![];
+    macro_rules! describe_audit {
+        ($rule:path) => {
+            rules.push(rules::Rule::of::<$rule>())
+        };
+    }
+    for_each_audit!(describe_audit);
+    rules.sort_by_key(|rule| rule.ident);
+
+    rules
+}
+
+/// `zizmor rules`: lists every audit's metadata.
+fn list_rules(json: bool) -> Result<ExitCode> {
+    let rules = known_rules();
+    if json {
+        serde_json::to_writer_pretty(stdout(), &rules)?;
+    } else {
+        rules::render(&rules)?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `zizmor completions`: generates completions for `shell`.
+fn completions(shell: clap_complete::Shell) -> Result<ExitCode> {
+    // Arguments that take audits accept any value when parsing, so that
+    // unknown audits get a suggestion. They're only constrained to the known
+    // audits here, so that the audits complete.
+    let audits = PossibleValuesParser::new(
+        known_rules()
+            .iter()
+            .map(|rule| rule.ident)
+            .collect::<Vec<_>>(),
+    );
+    let mut command = Args::command()
+        .mut_arg("only", |arg| arg.value_parser(audits.clone()))
+        .mut_arg("except", |arg| arg.value_parser(audits.clone()))
+        .mut_subcommand("explain", |explain| {
+            explain.mut_arg("audit", |arg| arg.value_parser(audits))
+        });
+
+    clap_complete::generate(shell, &mut command, env!("CARGO_PKG_NAME"), &mut stdout());
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `zizmor cache`: clears the cache, or shows where it is.
+fn manage_cache(command: CacheCommand) -> Result<ExitCode> {
+    match command {
+        CacheCommand::Clear => match cache::clear()? {
+            Some(dir) => eprintln!("cleared {dir}", dir = dir.display()),
+            None => eprintln!("no cache directory; nothing to clear"),
+        },
+        CacheCommand::Dir => match cache::dir() {
+            Some(dir) => println!("{dir}", dir = dir.display()),
+            None => return Err(anyhow!("no cache directory (is $HOME set?)")),
+        },
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `zizmor explain`: shows an audit's documentation.
+fn explain(audit: &str) -> Result<ExitCode> {
+    let known = known_rules()
+        .iter()
+        .map(|rule| rule.ident)
+        .collect::<Vec<_>>();
+    if !known.contains(&audit) {
+        return Err(unknown_audit(audit, &known));
+    }
+
+    let explanation =
+        rules::explanation(audit).ok_or_else(|| anyhow!("no documentation for {audit}"))?;
+    println!("{explanation}");
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Loads a batch of `repository`'s inputs into a fresh registry, linking
+/// its workflows to their trigger `sources`.
+fn load_batch(
+    repository: &collect::Repository,
+    batch: &[Pending],
+    sources: &[models::TriggerSource],
+    jobs: NonZeroUsize,
+) -> Result<WorkflowRegistry> {
+    let mut workflow_registry = WorkflowRegistry::new();
+    workflow_registry.remote = repository.remote.clone();
+
+    let workflow_paths = batch
+        .iter()
+        .filter_map(|pending| match pending {
+            Pending::Workflow(path) => Some(path.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    workflow_registry.register_workflows(&workflow_paths, sources, jobs)?;
+    for pending in batch {
+        match pending {
+            Pending::Workflow(_) => continue,
+            Pending::Stdin(path, contents) => {
+                workflow_registry.register_stdin(path, contents.clone())?
+            }
+            Pending::Action(path, name) => workflow_registry.register_action(path, name.clone())?,
+            Pending::Dependabot(path, name) => {
+                workflow_registry.register_dependabot(path, name.clone())?
+            }
+        }
+    }
+
+    Ok(workflow_registry)
+}
+
+/// Returns an error for the unknown audit `ident`, suggesting the closest
+/// of the `known` audits if there's a plausible one.
+fn unknown_audit(ident: &str, known: &[&str]) -> anyhow::Error {
+    match suggest_audit(ident, known) {
+        Some(suggestion) => anyhow!("unknown audit: {ident} (did you mean {suggestion}?)"),
+        None => anyhow!("unknown audit: {ident}"),
+    }
+}
+
+/// Returns the known audit whose identifier is closest to `unknown`,
+/// if any is close enough to be a plausible typo.
+fn suggest_audit<'a>(unknown: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|ident| (*ident, strsim::damerau_levenshtein(unknown, ident)))
+        .filter(|(_, distance)| *distance <= 3)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(ident, _)| ident)
+}
+
+/// Runs every audit on every input in `workflow_registry`, in parallel,
+/// and returns their findings in order of input, then audit.
+fn audit_inputs<'w>(
+    workflow_registry: &'w WorkflowRegistry,
+    audit_registry: &AuditRegistry,
+    config: &Config,
+    result_cache: &ResultCache,
+    client: Option<&github_api::Client>,
+    jobs: NonZeroUsize,
+    bar: &ProgressBar,
+) -> Result<Vec<Finding<'w>>> {
+    // Each input's progress is tracked separately, so that we can report
+    // when it's complete.
+    let inputs = workflow_registry.iter_inputs().collect::<Vec<_>>();
+    let audits = audit_registry.iter_audits().collect::<Vec<_>>();
+    let remaining = inputs
+        .iter()
+        .map(|_| AtomicUsize::new(audits.len()))
+        .collect::<Vec<_>>();
+    let tasks = (0..inputs.len())
+        .flat_map(|input| (0..audits.len()).map(move |audit| (input, audit)))
+        .collect::<Vec<_>>();
+
+    let results = parallel::map(jobs, &tasks, |&(idx, audit)| {
+        let input = inputs[idx];
+        let (ident, desc, audit) = audits[audit];
+        let name = input.raw().name();
+        bar.set_message(format!("auditing {name}", name = name.cyan()));
+
+        let path = workflow_registry.get_relative_path(name);
+        let findings = if config.is_excluded_from(ident, path) {
+            Ok(vec![])
+        } else {
+            run_audit(input, ident, desc, audit, result_cache, client)
+        };
+
+        bar.inc(1);
+        if remaining[idx].fetch_sub(1, Ordering::Relaxed) == 1 {
+            bar.println(format!("🌈 completed {name}", name = name.cyan()));
+        }
+
+        findings
+    });
+
+    // Findings are in a deterministic order, no matter how the tasks
+    // were scheduled.
+    Ok(results
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+/// Runs `audit` (identified by `ident` and described by `desc`) on
+/// `input`, or loads its findings from `result_cache`.
+///
+/// Once `audit` has been truncated because `client`'s budget ran out, its
+/// failures are expected, and it's treated as having found nothing.
+fn run_audit<'w>(
+    input: Input<'w>,
+    ident: &'static str,
+    desc: &'static str,
+    audit: &dyn Audit,
+    result_cache: &ResultCache,
+    client: Option<&github_api::Client>,
+) -> Result<Vec<Finding<'w>>> {
+    let name = input.raw().name();
+
+    // Audits that use GitHub's API can't be cached, since their
+    // results can change without their input changing.
+    let cacheable = !audit.uses_github_api();
+
+    let cached = if cacheable {
+        result_cache.get(input, ident, desc)
+    } else {
+        None
+    };
+
+    match cached {
+        Some(cached) => Ok(cached),
+        None => audit
+            .audit(input)
+            .or_else(|e| match client {
+                Some(client) if client.is_truncated(ident) => {
+                    log::debug!("{ident} truncated on {name}: {e}");
+                    Ok(vec![])
+                }
+                _ => Err(e),
+            })
+            .with_context(|| format!("{ident} failed on {name}"))
+            .inspect(|findings| {
+                if cacheable {
+                    result_cache.put(input, ident, findings);
+                }
+            }),
+    }
 }
@@ -0,0 +1,207 @@
+//! Markdown reports (`--format markdown`), e.g. for pull request
+//! descriptions or GitHub Actions job summaries (`$GITHUB_STEP_SUMMARY`).
+//!
+//! Findings are grouped into a section per file, with their snippets and
+//! their audit's remediation guidance collapsed, so that long reports stay
+//! skimmable.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::{
+    finding::{suppress::Suppression, Finding, Severity},
+    registry::WorkflowRegistry,
+    render::Summary,
+    rules,
+};
+
+/// Returns the badge for `severity`.
+fn badge(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Informational => "⚪ Informational",
+        Severity::Low => "🔵 Low",
+        Severity::Medium => "🟡 Medium",
+        Severity::High => "🟠 High",
+        Severity::Critical => "🔴 Critical",
+    }
+}
+
+/// Escapes `text`, so that it's rendered as-is.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Returns a code fence that's longer than any run of backticks in `code`.
+fn fence(code: &str) -> String {
+    let longest = code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    "`".repeat((longest + 1).max(3))
+}
+
+/// Renders `findings` into `report`, with a section for each file.
+///
+/// Findings are rendered as their inputs are audited, and each input's
+/// findings are contiguous, so each call starts new sections.
+pub(crate) fn render_findings(
+    registry: &WorkflowRegistry,
+    findings: &[Finding],
+    report: &mut String,
+) -> Result<()> {
+    let mut current = None;
+
+    for finding in findings {
+        let path = finding
+            .locations
+            .first()
+            .map(|location| registry.get_relative_path(location.symbolic.name));
+        if let Some(path) = path.filter(|path| current != Some(*path)) {
+            match &registry.remote {
+                Some(slug) => writeln!(report, "## `{slug}`: `{path}`\n")?,
+                None => writeln!(report, "## `{path}`\n")?,
+            }
+            current = Some(path);
+        }
+
+        render_finding(registry, finding, report)?;
+    }
+
+    Ok(())
+}
+
+fn render_finding(
+    registry: &WorkflowRegistry,
+    finding: &Finding,
+    report: &mut String,
+) -> Result<()> {
+    writeln!(
+        report,
+        "### {badge}: [`{ident}`]({url})\n\n{desc} (confidence: {confidence:?})\n",
+        badge = badge(finding.determinations.severity),
+        ident = finding.ident,
+        url = finding.url(),
+        desc = escape(finding.desc),
+        confidence = finding.determinations.confidence,
+    )?;
+
+    match finding.suppression {
+        Some(Suppression::Comment) => report.push_str("*Suppressed by an inline comment.*\n\n"),
+        Some(Suppression::Config) => report.push_str("*Suppressed by the configuration file.*\n\n"),
+        Some(Suppression::Baseline) => report.push_str("*Suppressed by the baseline.*\n\n"),
+        None => (),
+    }
+
+    for location in &finding.locations {
+        writeln!(
+            report,
+            "- `{path}:{line}`: {annotation}",
+            path = registry.get_relative_path(location.symbolic.name),
+            line = location.concrete.location.start_point.row + 1,
+            annotation = escape(&location.symbolic.annotation),
+        )?;
+    }
+    if let Some(suggestion) = &finding.suggestion {
+        writeln!(report, "- 💡 {suggestion}", suggestion = escape(suggestion))?;
+    }
+    for fix in &finding.fixes {
+        writeln!(
+            report,
+            "- 🔧 Fix available: {title}",
+            title = escape(&fix.fix.title)
+        )?;
+    }
+    report.push('\n');
+
+    for location in &finding.locations {
+        let excerpt = location.excerpt.text;
+        let fence = fence(excerpt);
+        writeln!(
+            report,
+            "<details>\n<summary>Snippet: <code>{path}:{line}</code></summary>\n\n\
+             {fence}yaml\n{excerpt}\n{fence}\n\n</details>\n",
+            path = registry.get_relative_path(location.symbolic.name),
+            line = location.excerpt.start_row + 1,
+        )?;
+    }
+
+    if let Some(remediation) = rules::remediation(finding.ident) {
+        writeln!(
+            report,
+            "<details>\n<summary>Remediation</summary>\n\n{remediation}\n\n</details>\n"
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Renders the whole report: a summary of the run, followed by the
+/// `findings` rendered by [`render_findings`].
+pub(crate) fn render(summary: &Summary, findings: &str) -> Result<String> {
+    let mut report = String::from("# zizmor report\n\n");
+
+    let total = Severity::value_variants()
+        .iter()
+        .map(|severity| summary.findings(*severity))
+        .sum::<usize>();
+    if total == 0 {
+        report.push_str("No findings to report. Good job!\n\n");
+    } else {
+        report.push_str("| Severity | Findings |\n| --- | --: |\n");
+        for severity in Severity::value_variants().iter().rev() {
+            writeln!(
+                report,
+                "| {badge} | {count} |",
+                badge = badge(*severity),
+                count = summary.findings(*severity),
+            )?;
+        }
+        report.push('\n');
+    }
+
+    let notes = summary.notes();
+    for note in &notes {
+        writeln!(report, "- {note}", note = escape(note))?;
+    }
+    if !notes.is_empty() {
+        report.push('\n');
+    }
+
+    report.push_str(findings);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, fence};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain text"), "plain text");
+        assert_eq!(
+            escape("uses ${{ github.event.issue.title }} in <run>"),
+            "uses ${{ github.event.issue.title }} in \\<run\\>"
+        );
+        assert_eq!(escape("`a|b`"), "\\`a\\|b\\`");
+    }
+
+    #[test]
+    fn test_fence() {
+        assert_eq!(fence("run: echo hi"), "```");
+        assert_eq!(fence("run: echo ```hi```"), "````");
+        assert_eq!(fence("``````"), "```````");
+    }
+}
@@ -1,17 +1,233 @@
 //! Enriching/context-bearing wrappers over GitHub Actions models
 //! from the `github-actions-models` crate.
 
-use std::{collections::hash_map, iter::Enumerate, ops::Deref, path::Path};
+use std::{collections::hash_map, iter::Enumerate, ops::Deref, path::Path, sync::LazyLock};
 
 use anyhow::{anyhow, Context, Result};
-use github_actions_models::workflow;
+use github_actions_models::{
+    common::{expr::LoE, BasePermission, Env, EnvValue, Permission, Permissions},
+    dependabot,
+    workflow::{
+        self,
+        event::{BareEvent, OptionalBody, Push},
+        job::{Container, DeploymentEnvironment, RunsOn, StepBody},
+        Trigger,
+    },
+};
+use regex::Regex;
+use serde::Deserialize;
 
-use crate::finding::{Route, SymbolicLocation};
+use crate::{
+    dataflow::Dataflow,
+    expr::{context_is_attacker_controlled, Contexts, Expr, Value},
+    finding::{Confidence, Route, SymbolicLocation},
+    shell::statements,
+    utils::extract_expressions,
+};
+
+/// Fragments of `actions/checkout` refs that indicate a checkout of
+/// the triggering event's (untrusted) head, rather than the base repository.
+const UNTRUSTED_HEAD_REFS: &[&str] = &[
+    "github.event.pull_request.head.sha",
+    "github.event.pull_request.head.ref",
+    "github.event.workflow_run.head_sha",
+    "github.event.workflow_run.head_branch",
+    "github.head_ref",
+    "refs/pull/",
+];
+
+/// Actions that download artifacts, whose contents may be attacker-controlled.
+const ARTIFACT_DOWNLOADERS: &[&str] = &[
+    "actions/download-artifact",
+    "dawidd6/action-download-artifact",
+];
+
+/// Actions (as `owner/repo`) that deploy or publish something.
+const DEPLOY_ACTIONS: &[&str] = &[
+    "actions/deploy-pages",
+    "aws-actions/amazon-ecs-deploy-task-definition",
+    "azure/k8s-deploy",
+    "azure/webapps-deploy",
+    "cloudflare/wrangler-action",
+    "google-github-actions/deploy-appengine",
+    "google-github-actions/deploy-cloudrun",
+    "jamesives/github-pages-deploy-action",
+    "peaceiris/actions-gh-pages",
+    "pypa/gh-action-pypi-publish",
+    "superfly/flyctl-actions",
+];
+
+/// Actions (as `owner/repo`) that push to a registry, but only when their
+/// `push` input is set.
+const REGISTRY_PUSH_ACTIONS: &[&str] = &["docker/build-push-action"];
+
+/// Commands that deploy something, along with the subcommands that do the
+/// deploying. An empty list of subcommands means that any use deploys.
+const DEPLOY_COMMANDS: &[(&str, &[&str])] = &[
+    ("ansible-playbook", &[]),
+    ("aws", &["deploy", "update-service", "update-function-code"]),
+    ("az", &["deploy", "up"]),
+    ("cargo", &["publish"]),
+    ("cdk", &["deploy"]),
+    ("docker", &["push"]),
+    ("firebase", &["deploy"]),
+    ("fly", &["deploy"]),
+    ("flyctl", &["deploy"]),
+    ("gcloud", &["deploy"]),
+    ("helm", &["install", "upgrade"]),
+    ("kubectl", &["apply", "replace", "rollout", "set"]),
+    ("npm", &["publish"]),
+    ("podman", &["push"]),
+    ("pulumi", &["up"]),
+    ("serverless", &["deploy"]),
+    ("terraform", &["apply"]),
+    ("twine", &["upload"]),
+    ("vercel", &["deploy", "--prod"]),
+    ("wrangler", &["deploy", "publish"]),
+];
+
+/// Actions (as `owner/repo`) that send `repository_dispatch` events,
+/// along with the input that gives the event's type.
+const DISPATCH_ACTIONS: &[(&str, &str)] = &[("peter-evans/repository-dispatch", "event-type")];
+
+/// Matches the event type in a script's call to the `repository_dispatch`
+/// API, e.g. `gh api repos/{owner}/{repo}/dispatches -f event_type=deploy`.
+static DISPATCH_EVENT_TYPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"event_type\\?["']?\s*[:=]\s*\\?["']?([\w.-]+)"#).unwrap());
+
+/// Fragments of job IDs and names that suggest a deployment.
+const DEPLOY_NAMES: &[&str] = &["deploy", "release", "publish"];
+
+/// The reason a job is believed to deploy something, from strongest
+/// to weakest signal.
+pub(crate) enum DeployIntent<'w> {
+    /// The job targets the named deployment environment.
+    Environment(&'w str),
+    /// The job uses a well-known deployment action.
+    Action(Step<'w>),
+    /// The job runs a deployment command (e.g. `kubectl apply`).
+    Command(Step<'w>, String),
+    /// The job's ID or name suggests a deployment.
+    Name(&'w str),
+}
+
+impl<'w> DeployIntent<'w> {
+    /// Returns the location of the evidence for this intent, if it's more
+    /// specific than the job itself, along with our confidence that the
+    /// evidence actually indicates a deployment.
+    pub(crate) fn evidence(&self, job: &Job<'w>) -> (Option<SymbolicLocation<'w>>, Confidence) {
+        match self {
+            DeployIntent::Environment(name) => (
+                Some(
+                    job.location()
+                        .with_keys(&["environment".into()])
+                        .annotated(format!("job deploys to the {name} environment")),
+                ),
+                Confidence::High,
+            ),
+            DeployIntent::Action(step) => (
+                Some(
+                    step.location()
+                        .with_keys(&["uses".into()])
+                        .annotated("this step deploys"),
+                ),
+                Confidence::Medium,
+            ),
+            DeployIntent::Command(step, command) => (
+                Some(
+                    step.location()
+                        .with_keys(&["run".into()])
+                        .annotated(format!("this step deploys: {command}")),
+                ),
+                Confidence::Medium,
+            ),
+            DeployIntent::Name(_) => (None, Confidence::Low),
+        }
+    }
+}
+
+/// Prefixes for the labels of GitHub's standard hosted runner images,
+/// e.g. `ubuntu-latest` or `windows-2022`.
+const GITHUB_HOSTED_PREFIXES: &[&str] = &["ubuntu-", "windows-", "macos-"];
+
+/// Returns whether `label` refers to one of GitHub's standard hosted runners.
+pub(crate) fn is_github_hosted(label: &str) -> bool {
+    GITHUB_HOSTED_PREFIXES.iter().any(|prefix| {
+        label
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with("latest") || rest.starts_with(char::is_numeric))
+    })
+}
+
+/// A YAML document that zizmor can audit, i.e. a workflow, an action
+/// definition or a Dependabot configuration.
+///
+/// Inputs are audited concurrently, so they must be thread-safe.
+pub(crate) trait AuditInput: Sync {
+    /// The input's name, as it appears in its registry.
+    fn name(&self) -> &str;
+
+    /// The input's path on disk.
+    fn path(&self) -> &str;
+
+    fn document(&self) -> &yamlpath::Document;
+
+    /// Returns the input named `name` that this input's findings may have
+    /// locations in, i.e. this input itself or a reusable workflow that
+    /// it calls.
+    fn resolve(&self, name: &str) -> Option<&dyn AuditInput>;
+}
+
+/// The maximum depth of reusable workflow calls that are linked, matching
+/// GitHub's own limit on nesting.
+const MAX_CALL_DEPTH: usize = 4;
 
 pub(crate) struct Workflow {
     pub(crate) path: String,
     pub(crate) document: yamlpath::Document,
     inner: workflow::Workflow,
+    /// The reusable workflows in the same repository that this
+    /// workflow's jobs call. Populated by [`Workflow::link`].
+    calls: Vec<Call>,
+    /// The other workflows that can trigger this one. Populated by
+    /// [`Workflow::link_upstreams`].
+    upstreams: Vec<Upstream>,
+}
+
+/// How one workflow triggers another.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Chain {
+    /// The downstream workflow runs via `workflow_run` when the
+    /// upstream one completes.
+    WorkflowRun,
+    /// The upstream workflow sends a `repository_dispatch` event of the
+    /// given type, which the downstream workflow listens for.
+    RepositoryDispatch(String),
+}
+
+/// What's needed to tell whether a workflow triggers others, without
+/// keeping the whole workflow around. See [`Workflow::trigger_source`].
+pub(crate) struct TriggerSource {
+    /// The workflow's path.
+    pub(crate) path: String,
+    /// The name that `workflow_run` triggers refer to the workflow by.
+    name: String,
+    /// The types of the `repository_dispatch` events that the workflow sends.
+    dispatched: Vec<String>,
+}
+
+/// A workflow that triggers another workflow.
+struct Upstream {
+    chain: Chain,
+    workflow: Workflow,
+}
+
+/// A reusable workflow in the same repository, called by one
+/// of a workflow's jobs.
+struct Call {
+    /// The ID of the calling job.
+    job_id: String,
+    callee: Workflow,
 }
 
 impl Deref for Workflow {
@@ -24,69 +240,1064 @@ impl Deref for Workflow {
 
 impl Workflow {
     pub(crate) fn from_file<P: AsRef<Path>>(p: P) -> Result<Self> {
-        let raw = std::fs::read_to_string(p.as_ref())?;
+        Self::from_string(std::fs::read_to_string(p.as_ref())?, p)
+    }
 
+    /// Loads a workflow from `raw`, as if it were read from `p`.
+    pub(crate) fn from_string<P: AsRef<Path>>(raw: String, p: P) -> Result<Self> {
         let inner = serde_yaml::from_str(&raw)
             .with_context(|| format!("invalid GitHub Actions workflow: {:?}", p.as_ref()))?;
 
-        let document = yamlpath::Document::new(raw)?;
+        let document = yamlpath::Document::new(raw)?;
+
+        Ok(Self {
+            path: p
+                .as_ref()
+                .to_str()
+                .ok_or_else(|| anyhow!("invalid workflow: path is not UTF-8"))?
+                .to_string(),
+            document,
+            inner,
+            calls: vec![],
+            upstreams: vec![],
+        })
+    }
+
+    /// Loads the reusable workflows in the same repository that this
+    /// workflow's jobs call (i.e. `uses: ./.github/workflows/...`), along
+    /// with the workflows that they call in turn.
+    ///
+    /// Callees that can't be loaded are skipped, since GitHub only
+    /// resolves them when the calling job actually runs.
+    pub(crate) fn link(&mut self) {
+        self.link_calls(MAX_CALL_DEPTH);
+    }
+
+    fn link_calls(&mut self, depth: usize) {
+        if depth == 0 {
+            return;
+        }
+
+        let Some(dir) = Path::new(&self.path).parent() else {
+            return;
+        };
+
+        let mut calls = vec![];
+        for job in self.jobs() {
+            let Some(uses) = job.local_reusable_uses() else {
+                continue;
+            };
+
+            // Local reusable workflows always live in `.github/workflows`,
+            // i.e. alongside their callers.
+            let Some(filename) = Path::new(uses).file_name() else {
+                continue;
+            };
+
+            match Workflow::from_file(dir.join(filename)) {
+                Ok(mut callee) => {
+                    callee.link_calls(depth - 1);
+                    calls.push(Call {
+                        job_id: job.id.into(),
+                        callee,
+                    });
+                }
+                Err(e) => log::warn!("couldn't load reusable workflow {uses}: {e}"),
+            }
+        }
+
+        self.calls = calls;
+    }
+
+    /// Returns each job that calls a linked reusable workflow, along with
+    /// that workflow. See [`Workflow::link`].
+    pub(crate) fn calls(&self) -> impl Iterator<Item = (Job<'_>, &Workflow)> {
+        self.calls
+            .iter()
+            .filter_map(|call| Some((self.job(&call.job_id)?, &call.callee)))
+    }
+
+    /// Returns what's needed to tell whether this workflow triggers others.
+    pub(crate) fn trigger_source(&self) -> TriggerSource {
+        TriggerSource {
+            path: self.path.clone(),
+            // `workflow_run` refers to workflows by their names, which
+            // default to their paths.
+            name: self
+                .name
+                .clone()
+                .unwrap_or_else(|| self.relative_path().into()),
+            dispatched: self
+                .jobs()
+                .flat_map(|job| job.steps().collect::<Vec<_>>())
+                .flat_map(|step| step.dispatched_events())
+                .collect(),
+        }
+    }
+
+    /// Returns how this workflow is triggered by `upstream`, if it is:
+    /// either when `upstream` completes (via `workflow_run`), or by a
+    /// `repository_dispatch` event that `upstream` sends.
+    pub(crate) fn triggered_by(&self, upstream: &TriggerSource) -> Option<Chain> {
+        let Trigger::Events(events) = &self.on else {
+            // Neither trigger is useful without a body.
+            return None;
+        };
+
+        if let OptionalBody::Body(run) = &events.workflow_run {
+            if run.workflows.contains(&upstream.name) {
+                return Some(Chain::WorkflowRun);
+            }
+        }
+
+        let types = match &events.repository_dispatch {
+            OptionalBody::Missing => return None,
+            OptionalBody::Default => &[][..],
+            OptionalBody::Body(dispatch) => &dispatch.types[..],
+        };
+
+        upstream
+            .dispatched
+            .iter()
+            .find(|event| types.is_empty() || types.contains(event))
+            .map(|event| Chain::RepositoryDispatch(event.clone()))
+    }
+
+    /// Links the workflows at the given paths, which trigger this workflow
+    /// as described by each [`Chain`]. See [`Workflow::triggered_by`].
+    ///
+    /// Workflows that can't be loaded are skipped.
+    pub(crate) fn link_upstreams(&mut self, upstreams: Vec<(Chain, String)>) {
+        for (chain, path) in upstreams {
+            match Workflow::from_file(&path) {
+                Ok(workflow) => self.upstreams.push(Upstream { chain, workflow }),
+                Err(e) => log::warn!("couldn't load upstream workflow {path}: {e}"),
+            }
+        }
+    }
+
+    /// Returns each workflow that can trigger this one, along with how.
+    /// See [`Workflow::link_upstreams`].
+    pub(crate) fn upstreams(&self) -> impl Iterator<Item = (&Chain, &Workflow)> {
+        self.upstreams
+            .iter()
+            .map(|upstream| (&upstream.chain, &upstream.workflow))
+    }
+
+    /// Returns this workflow's path relative to the repository root
+    /// (e.g. `.github/workflows/ci.yml`), if it's within a repository,
+    /// and otherwise its path as given.
+    fn relative_path(&self) -> &str {
+        match self.path.rfind(".github/workflows") {
+            Some(start) => &self.path[start..],
+            None => &self.path,
+        }
+    }
+
+    pub(crate) fn filename(&self) -> &str {
+        // NOTE: Unwraps are safe here since we enforce UTF-8 paths
+        // and require a filename as an invariant.
+        Path::new(&self.path).file_name().unwrap().to_str().unwrap()
+    }
+
+    pub(crate) fn location(&self) -> SymbolicLocation<'_> {
+        SymbolicLocation {
+            name: self.filename(),
+            annotation: "this workflow".to_string(),
+            link: None,
+            route: Route::new(),
+            subfeature: None,
+        }
+    }
+
+    pub(crate) fn jobs(&self) -> Jobs<'_> {
+        Jobs::new(self)
+    }
+
+    /// Returns the job with the given ID, if there is one.
+    pub(crate) fn job(&self, id: &str) -> Option<Job<'_>> {
+        self.jobs
+            .get_key_value(id)
+            .map(|(id, job)| Job::new(id, job, self.location()))
+    }
+
+    /// Returns the graph of this workflow's jobs and their `needs:`.
+    pub(crate) fn job_graph(&self) -> JobGraph<'_> {
+        JobGraph::new(self)
+    }
+
+    /// Returns the permissions that the given job's `GITHUB_TOKEN` has,
+    /// i.e. its own if it sets any, and otherwise the workflow's.
+    fn effective_permissions<'a>(&'a self, job: &Job<'a>) -> &'a Permissions {
+        let job_permissions = match job.inner {
+            workflow::Job::NormalJob(normal) => &normal.permissions,
+            workflow::Job::ReusableWorkflowCallJob(reusable) => &reusable.permissions,
+        };
+
+        match job_permissions {
+            Permissions::Base(BasePermission::Default) => &self.permissions,
+            permissions => permissions,
+        }
+    }
+
+    /// Returns whether the given job's `GITHUB_TOKEN` has write access to
+    /// anything, or `None` if it has the repository's default permissions.
+    pub(crate) fn token_can_write(&self, job: &Job<'_>) -> Option<bool> {
+        match self.effective_permissions(job) {
+            Permissions::Base(BasePermission::Default) => None,
+            Permissions::Base(BasePermission::ReadAll) => Some(false),
+            Permissions::Base(BasePermission::WriteAll) => Some(true),
+            Permissions::Explicit(perms) => {
+                Some(perms.values().any(|perm| *perm == Permission::Write))
+            }
+        }
+    }
+
+    /// Returns whether the given job's `GITHUB_TOKEN` has write access to
+    /// `permission` (e.g. `id-token`), or `None` if it has the repository's
+    /// default permissions.
+    pub(crate) fn token_can_write_to(&self, job: &Job<'_>, permission: &str) -> Option<bool> {
+        match self.effective_permissions(job) {
+            Permissions::Base(BasePermission::Default) => None,
+            Permissions::Base(BasePermission::ReadAll) => Some(false),
+            Permissions::Base(BasePermission::WriteAll) => Some(true),
+            Permissions::Explicit(perms) => Some(perms.get(permission) == Some(&Permission::Write)),
+        }
+    }
+
+    /// Returns the attacker-controllable contexts whose values may flow into
+    /// the expressions in `text`. See [`Dataflow::sources`].
+    pub(crate) fn untrusted_sources(&self, text: &str, job: Option<&Job<'_>>) -> Vec<String> {
+        Dataflow::new(self, context_is_attacker_controlled).sources(text, job, None)
+    }
+
+    /// Returns the `secrets.*` contexts whose values may flow into
+    /// the expressions in `text`. See [`Dataflow::sources`].
+    pub(crate) fn secret_sources(&self, text: &str, job: Option<&Job<'_>>) -> Vec<String> {
+        Dataflow::new(self, is_secret_context).sources(text, job, None)
+    }
+
+    /// Returns whether the given step is an `actions/checkout` step that
+    /// checks out the triggering event's head, either directly (see
+    /// [`Step::is_untrusted_checkout`]) or through a `ref:` that's computed
+    /// from the head via job or step outputs.
+    pub(crate) fn is_untrusted_checkout(&self, step: &Step<'_>, job: &Job<'_>) -> bool {
+        if step.is_untrusted_checkout() {
+            return true;
+        }
+
+        let StepBody::Uses { uses, with } = &step.inner.body else {
+            return false;
+        };
+
+        if !uses.starts_with("actions/checkout") {
+            return false;
+        }
+
+        let Some(EnvValue::String(git_ref)) = with.get("ref") else {
+            return false;
+        };
+
+        !Dataflow::new(self, |ctx| UNTRUSTED_HEAD_REFS.contains(&ctx))
+            .sources(git_ref, Some(job), Some(step))
+            .is_empty()
+    }
+
+    /// Returns the variables in this workflow's top-level `env:`, which
+    /// are visible to every step of every job.
+    pub(crate) fn env_vars(&self) -> Vec<EnvVar<'_>> {
+        EnvVar::from_env(&self.env, EnvScope::Workflow, self.location())
+    }
+
+    /// Returns whether this workflow is triggered by the given event,
+    /// regardless of which form its `on:` takes.
+    ///
+    /// Events that the underlying workflow model doesn't support
+    /// with bodies are only detected in their "bare" forms.
+    pub(crate) fn has_trigger(&self, event: BareEvent) -> bool {
+        fn present<T>(body: &OptionalBody<T>) -> bool {
+            !matches!(body, OptionalBody::Missing)
+        }
+
+        match &self.on {
+            Trigger::BareEvent(bare) => *bare == event,
+            Trigger::BareEvents(bares) => bares.contains(&event),
+            Trigger::Events(events) => match event {
+                BareEvent::BranchProtectionRule => present(&events.branch_protection_rule),
+                BareEvent::CheckRun => present(&events.check_run),
+                BareEvent::CheckSuite => present(&events.check_suite),
+                BareEvent::Discussion => present(&events.discussion),
+                BareEvent::DiscussionComment => present(&events.discussion_comment),
+                BareEvent::IssueComment => present(&events.issue_comment),
+                BareEvent::Issues => present(&events.issues),
+                BareEvent::Label => present(&events.label),
+                BareEvent::MergeGroup => present(&events.merge_group),
+                BareEvent::Milestone => present(&events.milestone),
+                BareEvent::Project => present(&events.project),
+                BareEvent::ProjectCard => present(&events.project_card),
+                BareEvent::ProjectColumn => present(&events.project_column),
+                BareEvent::PullRequest => present(&events.pull_request),
+                BareEvent::PullRequestComment => present(&events.pull_request_comment),
+                BareEvent::PullRequestReview => present(&events.pull_request_review),
+                BareEvent::PullRequestReviewComment => present(&events.pull_request_review_comment),
+                BareEvent::PullRequestTarget => present(&events.pull_request_target),
+                BareEvent::Push => present(&events.push),
+                BareEvent::RegistryPackage => present(&events.registry_package),
+                BareEvent::Release => present(&events.release),
+                BareEvent::RepositoryDispatch => present(&events.repository_dispatch),
+                BareEvent::Watch => present(&events.watch),
+                BareEvent::WorkflowCall => present(&events.workflow_call),
+                BareEvent::WorkflowDispatch => present(&events.workflow_dispatch),
+                BareEvent::WorkflowRun => present(&events.workflow_run),
+                // Not modeled with bodies.
+                _ => false,
+            },
+        }
+    }
+
+    /// Returns this workflow's `push` trigger, if it has one.
+    ///
+    /// The inner `Option` is `None` when the trigger has no body
+    /// (e.g. `on: push`), i.e. has no branch, tag or path filters.
+    pub(crate) fn push_trigger(&self) -> Option<Option<&Push>> {
+        match &self.on {
+            Trigger::BareEvent(bare) => (*bare == BareEvent::Push).then_some(None),
+            Trigger::BareEvents(bares) => bares.contains(&BareEvent::Push).then_some(None),
+            Trigger::Events(events) => match &events.push {
+                OptionalBody::Missing => None,
+                OptionalBody::Default => Some(None),
+                OptionalBody::Body(push) => Some(Some(push)),
+            },
+        }
+    }
+}
+
+impl AuditInput for Workflow {
+    fn name(&self) -> &str {
+        self.filename()
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn document(&self) -> &yamlpath::Document {
+        &self.document
+    }
+
+    fn resolve(&self, name: &str) -> Option<&dyn AuditInput> {
+        if name == self.name() {
+            return Some(self);
+        }
+
+        self.calls
+            .iter()
+            .find_map(|call| call.callee.resolve(name))
+            .or_else(|| {
+                self.upstreams
+                    .iter()
+                    .find_map(|upstream| upstream.workflow.resolve(name))
+            })
+    }
+}
+
+/// A permissive model of an action definition (i.e. `action.yml`).
+///
+/// Unlike `github_actions_models::action::Action`, this accepts composite
+/// `run:` steps without a `shell:`, since that's something we want to
+/// report rather than reject.
+#[derive(Deserialize)]
+pub(crate) struct ActionDefinition {
+    pub(crate) runs: ActionRuns,
+}
+
+/// An action definition's `runs:`.
+#[derive(Deserialize)]
+pub(crate) struct ActionRuns {
+    pub(crate) using: String,
+    /// The steps that make up a composite action. Always empty for
+    /// other kinds of actions.
+    #[serde(default)]
+    pub(crate) steps: Vec<CompositeStep>,
+}
+
+/// A single step within a composite action.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct CompositeStep {
+    pub(crate) name: Option<String>,
+    pub(crate) uses: Option<String>,
+    pub(crate) run: Option<String>,
+    pub(crate) shell: Option<String>,
+}
+
+pub(crate) struct Action {
+    /// The action's name, as it appears in the registry.
+    ///
+    /// Action definitions are always named `action.yml` (or `action.yaml`),
+    /// so this is a path relative to its repository's root rather than
+    /// a bare filename.
+    pub(crate) name: String,
+    pub(crate) path: String,
+    pub(crate) document: yamlpath::Document,
+    inner: ActionDefinition,
+}
+
+impl Deref for Action {
+    type Target = ActionDefinition;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl Action {
+    pub(crate) fn from_file<P: AsRef<Path>>(p: P, name: impl Into<String>) -> Result<Self> {
+        Self::from_string(std::fs::read_to_string(p.as_ref())?, p, name)
+    }
+
+    /// Loads an action definition from `raw`, as if it were read from `p`.
+    pub(crate) fn from_string<P: AsRef<Path>>(
+        raw: String,
+        p: P,
+        name: impl Into<String>,
+    ) -> Result<Self> {
+        let inner = serde_yaml::from_str(&raw)
+            .with_context(|| format!("invalid GitHub Actions definition: {:?}", p.as_ref()))?;
+
+        let document = yamlpath::Document::new(raw)?;
+
+        Ok(Self {
+            name: name.into(),
+            path: p
+                .as_ref()
+                .to_str()
+                .ok_or_else(|| anyhow!("invalid action: path is not UTF-8"))?
+                .to_string(),
+            document,
+            inner,
+        })
+    }
+
+    pub(crate) fn location(&self) -> SymbolicLocation<'_> {
+        SymbolicLocation {
+            name: &self.name,
+            annotation: "this action".to_string(),
+            link: None,
+            route: Route::new(),
+            subfeature: None,
+        }
+    }
+
+    /// Returns whether this is a composite action.
+    pub(crate) fn is_composite(&self) -> bool {
+        self.runs.using == "composite"
+    }
+
+    /// Returns each of this action's composite steps, if it has any.
+    pub(crate) fn steps(&self) -> impl Iterator<Item = ActionStep<'_>> {
+        self.runs
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(index, inner)| ActionStep {
+                index,
+                inner,
+                parent: self.location(),
+            })
+    }
+}
+
+impl AuditInput for Action {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn document(&self) -> &yamlpath::Document {
+        &self.document
+    }
+
+    fn resolve(&self, name: &str) -> Option<&dyn AuditInput> {
+        (name == self.name()).then_some(self as &dyn AuditInput)
+    }
+}
+
+/// A step within a composite action, along with its location.
+pub(crate) struct ActionStep<'a> {
+    pub(crate) index: usize,
+    inner: &'a CompositeStep,
+    parent: SymbolicLocation<'a>,
+}
+
+impl<'a> Deref for ActionStep<'a> {
+    type Target = &'a CompositeStep;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a> ActionStep<'a> {
+    /// Returns a symbolic location for this [`ActionStep`].
+    pub(crate) fn location(&self) -> SymbolicLocation<'a> {
+        self.parent
+            .with_keys(&["runs".into(), "steps".into(), self.index.into()])
+    }
+
+    /// Like [`ActionStep::location`], except with the step's `name`
+    /// key as the final path component if present.
+    pub(crate) fn location_with_name(&self) -> SymbolicLocation<'a> {
+        match self.inner.name {
+            Some(_) => self.location().with_keys(&["name".into()]),
+            None => self.location(),
+        }
+        .annotated("this step")
+    }
+}
+
+/// A Dependabot configuration, i.e. `.github/dependabot.yml`.
+pub(crate) struct Dependabot {
+    /// The configuration's name, as it appears in the registry. Like an
+    /// action's, this is a path relative to its repository's root.
+    pub(crate) name: String,
+    pub(crate) path: String,
+    pub(crate) document: yamlpath::Document,
+    inner: dependabot::v2::Dependabot,
+}
+
+impl Deref for Dependabot {
+    type Target = dependabot::v2::Dependabot;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl Dependabot {
+    pub(crate) fn from_file<P: AsRef<Path>>(p: P, name: impl Into<String>) -> Result<Self> {
+        Self::from_string(std::fs::read_to_string(p.as_ref())?, p, name)
+    }
+
+    /// Loads a Dependabot configuration from `raw`, as if it were read
+    /// from `p`.
+    pub(crate) fn from_string<P: AsRef<Path>>(
+        raw: String,
+        p: P,
+        name: impl Into<String>,
+    ) -> Result<Self> {
+        let inner = serde_yaml::from_str(&raw)
+            .with_context(|| format!("invalid Dependabot configuration: {:?}", p.as_ref()))?;
+
+        let document = yamlpath::Document::new(raw)?;
+
+        Ok(Self {
+            name: name.into(),
+            path: p
+                .as_ref()
+                .to_str()
+                .ok_or_else(|| anyhow!("invalid Dependabot configuration: path is not UTF-8"))?
+                .to_string(),
+            document,
+            inner,
+        })
+    }
+}
+
+impl AuditInput for Dependabot {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn document(&self) -> &yamlpath::Document {
+        &self.document
+    }
+
+    fn resolve(&self, name: &str) -> Option<&dyn AuditInput> {
+        (name == self.name()).then_some(self as &dyn AuditInput)
+    }
+}
+
+pub(crate) struct Job<'w> {
+    pub(crate) id: &'w str,
+    inner: &'w workflow::Job,
+    parent: SymbolicLocation<'w>,
+}
+
+impl<'w> Deref for Job<'w> {
+    type Target = &'w workflow::Job;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'w> Job<'w> {
+    pub(crate) fn new(id: &'w str, inner: &'w workflow::Job, parent: SymbolicLocation<'w>) -> Self {
+        Self { id, inner, parent }
+    }
+
+    pub(crate) fn location(&self) -> SymbolicLocation<'w> {
+        self.parent.with_job(self)
+    }
+
+    pub(crate) fn steps(&self) -> Steps<'w> {
+        Steps::new(self)
+    }
+
+    /// Returns the reusable workflow called by this job, if this is a
+    /// reusable workflow call to a workflow in another repository.
+    ///
+    /// Local reusable workflows (i.e. `./.github/workflows/...`) are
+    /// not returned.
+    pub(crate) fn reusable_uses(&self) -> Option<Uses<'w>> {
+        match self.inner {
+            workflow::Job::ReusableWorkflowCallJob(reusable) => Uses::from_reusable(&reusable.uses),
+            workflow::Job::NormalJob(_) => None,
+        }
+    }
+
+    /// Returns the path of the reusable workflow called by this job, if this
+    /// is a call to a workflow in the same repository,
+    /// e.g. `./.github/workflows/build.yml`.
+    pub(crate) fn local_reusable_uses(&self) -> Option<&'w str> {
+        match self.inner {
+            workflow::Job::ReusableWorkflowCallJob(reusable) if reusable.uses.starts_with("./") => {
+                Some(&reusable.uses)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the `with:` input that this job passes to the reusable
+    /// workflow it calls, along with the input's name as written.
+    pub(crate) fn reusable_input(&self, name: &str) -> Option<(&'w str, &'w EnvValue)> {
+        match self.inner {
+            workflow::Job::ReusableWorkflowCallJob(reusable) => reusable
+                .with
+                .get_key_value(name)
+                .map(|(name, value)| (name.as_str(), value)),
+            workflow::Job::NormalJob(_) => None,
+        }
+    }
+
+    /// Returns this job's `runs-on:` labels, including the labels that
+    /// select runners within a runner group.
+    ///
+    /// Reusable workflow calls have no labels, since their runners are
+    /// chosen by the called workflow.
+    pub(crate) fn runner_labels(&self) -> &'w [String] {
+        match self.inner {
+            workflow::Job::NormalJob(normal) => match &normal.runs_on {
+                RunsOn::Target(labels) => labels,
+                RunsOn::Group { labels, .. } => labels,
+            },
+            workflow::Job::ReusableWorkflowCallJob(_) => &[],
+        }
+    }
+
+    /// Returns every distinct set of labels that this job's `runs-on:`
+    /// labels expand to across its matrix, or `None` if any of their
+    /// expressions can't be evaluated statically with `contexts`.
+    pub(crate) fn expand_runner_labels(&self, contexts: &Contexts) -> Option<Vec<Vec<String>>> {
+        let mut expansions = vec![];
+
+        for matrix in self.matrix_combinations()? {
+            let contexts = contexts.clone().with("matrix", matrix);
+
+            let mut expanded = vec![];
+            for label in self.runner_labels() {
+                match contexts.expand(label)? {
+                    Value::Array(values) => expanded.extend(values.iter().map(Value::to_string)),
+                    value => expanded.push(value.to_string()),
+                }
+            }
+            expanded.retain(|label| !label.is_empty());
+
+            if !expansions.contains(&expanded) {
+                expansions.push(expanded);
+            }
+        }
+
+        Some(expansions)
+    }
+
+    /// Returns whether this job may run on a self-hosted runner, and
+    /// whether that's certain (i.e. via the `self-hosted` label), or
+    /// `None` if it only runs on GitHub-hosted runners.
+    ///
+    /// `runs-on:` expressions are evaluated with `contexts` for each of the
+    /// job's matrix combinations. Like the `self-hosted-runner` audit's
+    /// offline heuristic, runner groups, unevaluable expressions and labels
+    /// that aren't GitHub-hosted images may be self-hosted.
+    pub(crate) fn self_hosted(&self, contexts: &Contexts) -> Option<bool> {
+        let workflow::Job::NormalJob(normal) = self.inner else {
+            return None;
+        };
+
+        if matches!(normal.runs_on, RunsOn::Group { .. }) {
+            return Some(false);
+        }
+
+        let Some(candidates) = self.expand_runner_labels(contexts) else {
+            return Some(false);
+        };
+
+        candidates
+            .iter()
+            .map(|labels| {
+                if labels.iter().any(|label| label == "self-hosted") {
+                    Some(true)
+                } else if labels.is_empty() || labels.iter().any(|label| is_github_hosted(label)) {
+                    None
+                } else {
+                    Some(false)
+                }
+            })
+            .max()
+            .flatten()
+    }
+
+    /// Returns the strongest signal that this job deploys something,
+    /// if there is one.
+    pub(crate) fn deploy_intent(&self) -> Option<DeployIntent<'w>> {
+        let workflow::Job::NormalJob(normal) = self.inner else {
+            return None;
+        };
+
+        if let Some(environment) = &normal.environment {
+            return Some(DeployIntent::Environment(match environment {
+                DeploymentEnvironment::Name(name) => name,
+                DeploymentEnvironment::NameURL { name, .. } => name,
+            }));
+        }
+
+        for step in self.steps() {
+            match &step.inner.body {
+                StepBody::Uses { uses, with } => {
+                    let Some(uses) = Uses::from_step(uses) else {
+                        continue;
+                    };
+                    let action = format!("{}/{}", uses.owner, uses.repo);
+
+                    // `push:` is typically disabled for pull requests with an
+                    // expression, so anything but a literal `false` may push.
+                    let pushes = REGISTRY_PUSH_ACTIONS
+                        .iter()
+                        .any(|pusher| pusher.eq_ignore_ascii_case(&action))
+                        && with
+                            .get("push")
+                            .is_some_and(|push| push.to_string() != "false");
+
+                    if pushes
+                        || DEPLOY_ACTIONS
+                            .iter()
+                            .any(|deployer| deployer.eq_ignore_ascii_case(&action))
+                    {
+                        return Some(DeployIntent::Action(step));
+                    }
+                }
+                StepBody::Run { run, .. } => {
+                    if let Some(command) = deploy_command(run) {
+                        return Some(DeployIntent::Command(step, command));
+                    }
+                }
+            }
+        }
+
+        [Some(self.id), normal.name.as_deref()]
+            .into_iter()
+            .flatten()
+            .find(|name| {
+                let name = name.to_lowercase();
+                DEPLOY_NAMES.iter().any(|fragment| name.contains(fragment))
+            })
+            .map(DeployIntent::Name)
+    }
+
+    /// Returns this job's containers, i.e. its `container:` and each of its
+    /// `services:`, along with their locations.
+    pub(crate) fn containers(&self) -> Vec<(SymbolicLocation<'w>, &'w Container)> {
+        let workflow::Job::NormalJob(normal) = self.inner else {
+            return vec![];
+        };
+
+        let mut containers = vec![];
+        if let Some(container) = &normal.container {
+            containers.push((self.location().with_keys(&["container".into()]), container));
+        }
+
+        for (name, service) in &normal.services {
+            containers.push((
+                self.location()
+                    .with_keys(&["services".into(), name.as_str().into()]),
+                service,
+            ));
+        }
+
+        containers
+    }
+
+    /// Returns the value of the `matrix` context in each of this job's
+    /// matrixed runs, following GitHub's expansion rules: the product of
+    /// the matrix's dimensions, less its `exclude:`s, plus its `include:`s.
+    ///
+    /// Jobs without a matrix run once, with an empty `matrix` context.
+    /// Returns `None` if any part of the matrix is computed by an
+    /// expression, since its combinations can't be known statically.
+    pub(crate) fn matrix_combinations(&self) -> Option<Vec<Value>> {
+        let workflow::Job::NormalJob(normal) = self.inner else {
+            return None;
+        };
+
+        let matrix = match normal.strategy.as_ref().and_then(|s| s.matrix.as_ref()) {
+            None => return Some(vec![Value::Object(vec![])]),
+            Some(LoE::Expr(_)) => return None,
+            Some(LoE::Literal(matrix)) => matrix,
+        };
+
+        let LoE::Literal(dimensions) = &matrix.dimensions else {
+            return None;
+        };
+
+        // Dimensions are unordered in the model, so we expand them in
+        // name order to keep the combinations deterministic.
+        let mut dimensions = dimensions
+            .iter()
+            .map(|(name, values)| match values {
+                LoE::Literal(values) => Some((name, values)),
+                LoE::Expr(_) => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        dimensions.sort_by_key(|(name, _)| *name);
+
+        let mut combinations: Vec<Vec<(String, Value)>> = if dimensions.is_empty() {
+            vec![]
+        } else {
+            dimensions
+                .iter()
+                .fold(vec![vec![]], |combinations, (name, values)| {
+                    combinations
+                        .iter()
+                        .flat_map(|combination| {
+                            values.iter().map(|value| {
+                                let mut combination = combination.clone();
+                                combination.push((name.to_string(), Value::from(value)));
+                                combination
+                            })
+                        })
+                        .collect()
+                })
+        };
+
+        let value_of = |combination: &[(String, Value)], name: &str| {
+            combination
+                .iter()
+                .position(|(existing, _)| existing == name)
+        };
+
+        // Exclusions only need to match part of a combination.
+        let LoE::Literal(excludes) = &matrix.exclude else {
+            return None;
+        };
+        combinations.retain(|combination| {
+            !excludes.iter().any(|exclude| {
+                exclude.iter().all(|(name, value)| {
+                    value_of(combination, name)
+                        .is_some_and(|index| combination[index].1 == Value::from(value))
+                })
+            })
+        });
+
+        // Inclusions extend every combination whose original dimensions
+        // they don't conflict with, and become new combinations otherwise.
+        // Values added by earlier inclusions can be overwritten.
+        let LoE::Literal(includes) = &matrix.include else {
+            return None;
+        };
+        let originals = combinations.len();
+        for include in includes {
+            let mut entries = include
+                .iter()
+                .map(|(name, value)| (name.as_str(), Value::from(value)))
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|(name, _)| *name);
+
+            let is_dimension =
+                |name: &str| dimensions.iter().any(|(dimension, _)| *dimension == name);
+
+            let mut matched = false;
+            for combination in combinations.iter_mut().take(originals) {
+                let conflicts = entries.iter().any(|(name, value)| {
+                    is_dimension(name)
+                        && value_of(combination, name)
+                            .is_some_and(|index| combination[index].1 != *value)
+                });
+                if conflicts {
+                    continue;
+                }
+
+                matched = true;
+                for (name, value) in &entries {
+                    match value_of(combination, name) {
+                        Some(_) if is_dimension(name) => (),
+                        Some(index) => combination[index].1 = value.clone(),
+                        None => combination.push((name.to_string(), value.clone())),
+                    }
+                }
+            }
+
+            if !matched {
+                combinations.push(
+                    entries
+                        .into_iter()
+                        .map(|(name, value)| (name.into(), value))
+                        .collect(),
+                );
+            }
+        }
+
+        Some(combinations.into_iter().map(Value::Object).collect())
+    }
+
+    /// Returns the expression for the given `outputs:` entry, if any.
+    pub(crate) fn output(&self, name: &str) -> Option<&'w str> {
+        match self.inner {
+            workflow::Job::NormalJob(normal) => normal.outputs.get(name).map(String::as_str),
+            workflow::Job::ReusableWorkflowCallJob(_) => None,
+        }
+    }
 
-        Ok(Self {
-            path: p
-                .as_ref()
-                .to_str()
-                .ok_or_else(|| anyhow!("invalid workflow: path is not UTF-8"))?
-                .to_string(),
-            document,
-            inner,
-        })
+    /// Returns the step with the given `id:`, if any.
+    pub(crate) fn step(&self, id: &str) -> Option<Step<'w>> {
+        self.steps().find(|step| step.id.as_deref() == Some(id))
     }
 
-    pub(crate) fn filename(&self) -> &str {
-        // NOTE: Unwraps are safe here since we enforce UTF-8 paths
-        // and require a filename as an invariant.
-        Path::new(&self.path).file_name().unwrap().to_str().unwrap()
+    /// Returns the IDs of the jobs that this job `needs:`, as written.
+    pub(crate) fn needs(&self) -> &'w [String] {
+        match self.inner {
+            workflow::Job::NormalJob(normal) => &normal.needs,
+            workflow::Job::ReusableWorkflowCallJob(reusable) => &reusable.needs,
+        }
     }
 
-    pub(crate) fn location(&self) -> SymbolicLocation {
-        SymbolicLocation {
-            name: self.filename(),
-            annotation: "this workflow".to_string(),
-            link: None,
-            route: Route::new(),
+    /// Returns the variables in this job's `env:`, which are visible to
+    /// every step in the job.
+    pub(crate) fn env_vars(&self) -> Vec<EnvVar<'w>> {
+        match self.inner {
+            workflow::Job::NormalJob(normal) => {
+                EnvVar::from_env(&normal.env, EnvScope::Job, self.location())
+            }
+            workflow::Job::ReusableWorkflowCallJob(_) => vec![],
         }
     }
 
-    pub(crate) fn jobs(&self) -> Jobs<'_> {
-        Jobs::new(self)
+    /// Returns whether this job has access to any secrets, either through
+    /// `secrets.*` expressions in its body or (for reusable workflow calls)
+    /// by passing secrets to the called workflow.
+    pub(crate) fn uses_secrets(&self) -> bool {
+        match self.inner {
+            workflow::Job::ReusableWorkflowCallJob(reusable) => reusable.secrets.is_some(),
+            workflow::Job::NormalJob(normal) => {
+                env_uses_secrets(&normal.env) || self.steps().any(|step| step.uses_secrets())
+            }
+        }
     }
 }
 
-pub(crate) struct Job<'w> {
-    pub(crate) id: &'w str,
-    inner: &'w workflow::Job,
-    parent: SymbolicLocation<'w>,
+/// The graph of a workflow's jobs, with an edge from each job to each
+/// of the jobs in its `needs:`. See [`Workflow::job_graph`].
+pub(crate) struct JobGraph<'w> {
+    /// Each job's ID, along with its `needs:`, sorted by ID.
+    needs: Vec<(&'w str, &'w [String])>,
 }
 
-impl<'w> Deref for Job<'w> {
-    type Target = &'w workflow::Job;
+impl<'w> JobGraph<'w> {
+    fn new(workflow: &'w Workflow) -> Self {
+        let mut needs = workflow
+            .jobs()
+            .map(|job| (job.id, job.needs()))
+            .collect::<Vec<_>>();
+        needs.sort_by_key(|(id, _)| *id);
 
-    fn deref(&self) -> &Self::Target {
-        &self.inner
+        Self { needs }
     }
-}
 
-impl<'w> Job<'w> {
-    pub(crate) fn new(id: &'w str, inner: &'w workflow::Job, parent: SymbolicLocation<'w>) -> Self {
-        Self { id, inner, parent }
+    /// Returns the IDs of the jobs that the given job needs directly,
+    /// including any that don't exist in the workflow.
+    pub(crate) fn dependencies(&self, id: &str) -> impl Iterator<Item = &'w str> {
+        self.needs
+            .iter()
+            .find(|(job, _)| *job == id)
+            .map(|(_, needs)| *needs)
+            .unwrap_or_default()
+            .iter()
+            .map(String::as_str)
     }
 
-    pub(crate) fn location(&self) -> SymbolicLocation<'w> {
-        self.parent.with_job(self)
+    /// Returns the IDs of the jobs that directly need the given job.
+    pub(crate) fn dependents(&self, id: &str) -> Vec<&'w str> {
+        self.needs
+            .iter()
+            .filter(|(_, needs)| needs.iter().any(|need| need == id))
+            .map(|(job, _)| *job)
+            .collect()
     }
 
-    pub(crate) fn steps(&self) -> Steps<'w> {
-        Steps::new(self)
+    /// Returns the IDs of every job that must complete before the given
+    /// job can run, i.e. its dependencies and theirs in turn, sorted.
+    ///
+    /// A job only appears among its own ancestors if it's in a cycle.
+    pub(crate) fn ancestors(&self, id: &str) -> Vec<&'w str> {
+        let mut ancestors = vec![];
+        let mut queue = self.dependencies(id).collect::<Vec<_>>();
+        while let Some(next) = queue.pop() {
+            if ancestors.contains(&next) || !self.needs.iter().any(|(job, _)| *job == next) {
+                continue;
+            }
+
+            ancestors.push(next);
+            queue.extend(self.dependencies(next));
+        }
+
+        ancestors.sort();
+        ancestors
+    }
+
+    /// Returns the IDs of the jobs in each of this graph's cycles, i.e. each
+    /// set of jobs that (transitively) need each other, sorted.
+    ///
+    /// GitHub refuses to run workflows with cycles.
+    pub(crate) fn cycles(&self) -> Vec<Vec<&'w str>> {
+        let mut cycles: Vec<Vec<&'w str>> = vec![];
+        for (id, _) in &self.needs {
+            if cycles.iter().any(|cycle| cycle.contains(id)) {
+                continue;
+            }
+
+            let ancestors = self.ancestors(id);
+            if !ancestors.contains(id) {
+                continue;
+            }
+
+            // The rest of the cycle is every ancestor that this job
+            // is in turn an ancestor of.
+            cycles.push(
+                ancestors
+                    .into_iter()
+                    .filter(|other| self.ancestors(other).contains(id))
+                    .collect(),
+            );
+        }
+
+        cycles
     }
 }
 
@@ -150,6 +1361,107 @@ impl<'w> Step<'w> {
         self.parent.with_step(self)
     }
 
+    /// Returns the variables in this step's `env:`.
+    pub(crate) fn env_vars(&self) -> Vec<EnvVar<'w>> {
+        match &self.inner.body {
+            StepBody::Run { env, .. } => EnvVar::from_env(env, EnvScope::Step, self.location()),
+            StepBody::Uses { .. } => vec![],
+        }
+    }
+
+    /// Returns whether this step expands any `secrets.*` contexts
+    /// into its inputs, script, or environment.
+    pub(crate) fn uses_secrets(&self) -> bool {
+        match &self.inner.body {
+            StepBody::Uses { with, .. } => env_uses_secrets(with),
+            StepBody::Run { run, env, .. } => text_uses_secrets(run) || env_uses_secrets(env),
+        }
+    }
+
+    /// Returns every free-form text in this step's body that may contain
+    /// expressions, i.e. its script or inputs, and its environment.
+    pub(crate) fn texts(&self) -> Vec<&'w str> {
+        let (text, env) = match &self.inner.body {
+            StepBody::Uses { with, .. } => (None, with),
+            StepBody::Run { run, env, .. } => (Some(run.as_str()), env),
+        };
+
+        text.into_iter().chain(env_strings(env)).collect()
+    }
+
+    /// Returns the types of the `repository_dispatch` events that this step
+    /// sends, either through an action like `peter-evans/repository-dispatch`
+    /// or by calling the API directly.
+    pub(crate) fn dispatched_events(&self) -> Vec<String> {
+        match &self.inner.body {
+            StepBody::Uses { uses, with } => {
+                let Some(uses) = Uses::from_step(uses) else {
+                    return vec![];
+                };
+                let action = format!("{}/{}", uses.owner, uses.repo);
+
+                DISPATCH_ACTIONS
+                    .iter()
+                    .filter(|(dispatcher, _)| dispatcher.eq_ignore_ascii_case(&action))
+                    .filter_map(|(_, input)| with.get(*input))
+                    .map(|event| event.to_string())
+                    .collect()
+            }
+            StepBody::Run { run, .. } if run.contains("/dispatches") => DISPATCH_EVENT_TYPE
+                .captures_iter(run)
+                .map(|caps| caps[1].to_string())
+                .collect(),
+            StepBody::Run { .. } => vec![],
+        }
+    }
+
+    /// Returns whether this step downloads artifacts, e.g. with
+    /// `actions/download-artifact`.
+    pub(crate) fn is_artifact_download(&self) -> bool {
+        let StepBody::Uses { uses, .. } = &self.inner.body else {
+            return false;
+        };
+
+        ARTIFACT_DOWNLOADERS
+            .iter()
+            .any(|downloader| uses.starts_with(downloader))
+    }
+
+    /// Returns whether this step is an `actions/checkout` step that persists
+    /// its credentials into the checkout's `.git/config`, which it does
+    /// unless `persist-credentials: false` is set.
+    pub(crate) fn persists_credentials(&self) -> bool {
+        let StepBody::Uses { uses, with } = &self.inner.body else {
+            return false;
+        };
+
+        uses.starts_with("actions/checkout")
+            && !matches!(
+                with.get("persist-credentials"),
+                Some(EnvValue::Boolean(false))
+            )
+    }
+
+    /// Returns whether this step is an `actions/checkout` step that checks
+    /// out the triggering event's head, rather than the base repository.
+    pub(crate) fn is_untrusted_checkout(&self) -> bool {
+        let StepBody::Uses { uses, with } = &self.inner.body else {
+            return false;
+        };
+
+        if !uses.starts_with("actions/checkout") {
+            return false;
+        }
+
+        let Some(EnvValue::String(git_ref)) = with.get("ref") else {
+            return false;
+        };
+
+        UNTRUSTED_HEAD_REFS
+            .iter()
+            .any(|fragment| git_ref.contains(fragment))
+    }
+
     /// Like [`Step::location`], except with the step's `name`
     /// key as the final path component if present.
     pub(crate) fn location_with_name(&self) -> SymbolicLocation<'w> {
@@ -167,16 +1479,16 @@ pub(crate) struct Steps<'w> {
 }
 
 impl<'w> Steps<'w> {
+    /// Reusable workflow jobs have no steps of their own, so they yield nothing.
     pub(crate) fn new(job: &Job<'w>) -> Self {
-        // TODO: do something less silly here.
-        match &job.inner {
-            workflow::Job::ReusableWorkflowCallJob(_) => {
-                panic!("API misuse: can't call steps() on a reusable job")
-            }
-            workflow::Job::NormalJob(ref n) => Self {
-                inner: n.steps.iter().enumerate(),
-                location: job.location(),
-            },
+        let steps: &'w [workflow::job::Step] = match &job.inner {
+            workflow::Job::ReusableWorkflowCallJob(_) => &[],
+            workflow::Job::NormalJob(ref n) => &n.steps,
+        };
+
+        Self {
+            inner: steps.iter().enumerate(),
+            location: job.location(),
         }
     }
 }
@@ -194,6 +1506,99 @@ impl<'w> Iterator for Steps<'w> {
     }
 }
 
+/// Returns the first statement in `script` that runs a deployment command.
+fn deploy_command(script: &str) -> Option<String> {
+    statements(script).iter().find_map(|stmt| {
+        stmt.commands().into_iter().find_map(|cmd| {
+            let name = cmd.name()?;
+            let (_, subcommands) = DEPLOY_COMMANDS
+                .iter()
+                .find(|(deployer, _)| *deployer == name)?;
+
+            (subcommands.is_empty() || cmd.words.iter().any(|word| subcommands.contains(word)))
+                .then(|| stmt.text.trim().to_string())
+        })
+    })
+}
+
+/// Returns whether the given context is `secrets` or one of its members.
+fn is_secret_context(ctx: &str) -> bool {
+    ctx == "secrets" || ctx.starts_with("secrets.")
+}
+
+/// Returns whether the given free-form text contains any expressions
+/// that reference the `secrets` context.
+fn text_uses_secrets(text: &str) -> bool {
+    extract_expressions(text)
+        .iter()
+        .any(|expr| match Expr::parse(expr.as_bare()) {
+            Ok(expr) => expr.contexts().iter().any(|ctx| is_secret_context(ctx)),
+            // Be conservative if we can't parse the expression.
+            Err(_) => expr.as_bare().contains("secrets."),
+        })
+}
+
+/// Returns the string values in `env`, i.e. the ones that may
+/// contain expressions.
+fn env_strings(env: &Env) -> Vec<&str> {
+    env.values()
+        .filter_map(|value| match value {
+            EnvValue::String(value) => Some(value.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn env_uses_secrets(env: &Env) -> bool {
+    env.values().any(|value| match value {
+        EnvValue::String(s) => text_uses_secrets(s),
+        _ => false,
+    })
+}
+
+/// The scope of a shared `env:` block, i.e. one that isn't on a step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum EnvScope {
+    /// Visible to every step of every job.
+    Workflow,
+    /// Visible to every step of a single job.
+    Job,
+    /// Visible to a single step.
+    Step,
+}
+
+/// A single variable in an `env:` block, along with its scope.
+pub(crate) struct EnvVar<'w> {
+    pub(crate) name: &'w str,
+    pub(crate) value: &'w EnvValue,
+    pub(crate) scope: EnvScope,
+    pub(crate) location: SymbolicLocation<'w>,
+}
+
+impl<'w> EnvVar<'w> {
+    /// Returns the variables in `env`, sorted by name, whose block is
+    /// under the given `parent`.
+    fn from_env(env: &'w Env, scope: EnvScope, parent: SymbolicLocation<'w>) -> Vec<Self> {
+        let mut vars = env
+            .iter()
+            .map(|(name, value)| Self {
+                name,
+                value,
+                scope,
+                location: parent.with_keys(&["env".into(), name.as_str().into()]),
+            })
+            .collect::<Vec<_>>();
+
+        vars.sort_by_key(|var| var.name);
+        vars
+    }
+
+    /// Returns whether this variable's value references the `secrets` context.
+    pub(crate) fn uses_secrets(&self) -> bool {
+        matches!(self.value, EnvValue::String(value) if text_uses_secrets(value))
+    }
+}
+
 /// Represents the components of an "action ref", i.e. the value
 /// of a `uses:` clause in a normal job step or a reusable workflow job.
 /// Does not support `docker://` refs, or "local" (i.e. `./`) refs.
@@ -267,6 +1672,12 @@ impl<'a> Uses<'a> {
         }
     }
 
+    /// Returns whether this `uses:` refers to a first-party action,
+    /// i.e. one maintained by GitHub under the `actions` organization.
+    pub(crate) fn is_first_party(&self) -> bool {
+        self.owner == "actions"
+    }
+
     pub(crate) fn ref_is_commit(&self) -> bool {
         match self.git_ref {
             Some(git_ref) => git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit()),
@@ -289,9 +1700,193 @@ impl<'a> Uses<'a> {
     }
 }
 
+/// A Docker image reference, e.g. `ghcr.io/foo/bar:v1` or
+/// `ubuntu@sha256:...`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct DockerImage<'a> {
+    /// The image's registry, if explicitly specified.
+    pub(crate) registry: Option<&'a str>,
+    /// The image's name, including any namespace (e.g. `library/ubuntu`).
+    pub(crate) name: &'a str,
+    pub(crate) tag: Option<&'a str>,
+    pub(crate) digest: Option<&'a str>,
+}
+
+impl<'a> DockerImage<'a> {
+    /// Parses a Docker image reference.
+    ///
+    /// This follows the same rules as Docker itself: the first path component
+    /// is only a registry if it looks like a hostname (contains a `.` or `:`,
+    /// or is `localhost`).
+    pub(crate) fn parse(image: &'a str) -> Option<Self> {
+        let image = image.strip_prefix("docker://").unwrap_or(image);
+        if image.is_empty() || image.contains(char::is_whitespace) {
+            return None;
+        }
+
+        let (image, digest) = match image.split_once('@') {
+            Some((image, digest)) => (image, Some(digest)),
+            None => (image, None),
+        };
+
+        let (registry, path) = match image.split_once('/') {
+            Some((first, rest))
+                if first.contains('.') || first.contains(':') || first == "localhost" =>
+            {
+                (Some(first), rest)
+            }
+            _ => (None, image),
+        };
+
+        let (name, tag) = match path.rsplit_once(':') {
+            Some((name, tag)) => (name, Some(tag)),
+            None => (path, None),
+        };
+
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            registry,
+            name,
+            tag,
+            digest,
+        })
+    }
+
+    /// Returns this image's registry, defaulting to Docker Hub.
+    pub(crate) fn registry(&self) -> &'a str {
+        self.registry.unwrap_or("docker.io")
+    }
+}
+
+/// Docker flags that don't take a value, e.g. `--privileged`.
+const DOCKER_BOOLEAN_FLAGS: &[&str] = &[
+    "--detach",
+    "--init",
+    "--interactive",
+    "--no-healthcheck",
+    "--oom-kill-disable",
+    "--privileged",
+    "--publish-all",
+    "--read-only",
+    "--rm",
+    "--tty",
+    "-P",
+    "-d",
+    "-i",
+    "-t",
+];
+
+/// A container's parsed `options:`, i.e. the extra flags that the runner
+/// passes to `docker create`.
+#[derive(Debug)]
+pub(crate) struct DockerOptions {
+    flags: Vec<(String, Option<String>)>,
+}
+
+impl DockerOptions {
+    /// Parses the given `options:` string, which is split into words
+    /// like a shell would (the runner does the same).
+    pub(crate) fn parse(options: &str) -> Self {
+        let words = shlex::split(options)
+            .unwrap_or_else(|| options.split_whitespace().map(String::from).collect());
+
+        let mut flags = vec![];
+        let mut words = words.into_iter();
+        while let Some(word) = words.next() {
+            // Stray values can only come from flags we don't model correctly.
+            if !word.starts_with('-') {
+                continue;
+            }
+
+            if let Some((flag, value)) = word.split_once('=') {
+                flags.push((flag.to_string(), Some(value.to_string())));
+            } else if DOCKER_BOOLEAN_FLAGS.contains(&word.as_str()) {
+                flags.push((word, None));
+            } else {
+                let value = words.next();
+                flags.push((word, value));
+            }
+        }
+
+        Self { flags }
+    }
+
+    /// Returns whether the given boolean flag is enabled, e.g. as
+    /// `--privileged` or `--privileged=true`.
+    pub(crate) fn enabled(&self, flag: &str) -> bool {
+        self.flags
+            .iter()
+            .any(|(name, value)| name == flag && value.as_deref() != Some("false"))
+    }
+
+    /// Returns the values of every occurrence of any of the given flags,
+    /// e.g. `["-v", "--volume"]`.
+    pub(crate) fn values<'a>(&'a self, flags: &'a [&str]) -> impl Iterator<Item = &'a str> {
+        self.flags
+            .iter()
+            .filter(|(name, _)| flags.contains(&name.as_str()))
+            .filter_map(|(_, value)| value.as_deref())
+    }
+}
+
+/// Loads a workflow from `raw` for unit tests, without touching the
+/// filesystem.
+#[cfg(test)]
+pub(crate) fn test_workflow(raw: &str) -> Workflow {
+    Workflow::from_string(raw.into(), "test.yml").unwrap()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Uses;
+    use super::{
+        deploy_command, is_github_hosted, test_workflow, Action, Chain, Contexts, DockerImage,
+        DockerOptions, Uses, Value,
+    };
+
+    #[test]
+    fn deploy_commands() {
+        assert_eq!(
+            deploy_command("make build\nkubectl apply -f deploy.yml\n").as_deref(),
+            Some("kubectl apply -f deploy.yml")
+        );
+        assert_eq!(
+            deploy_command("sudo terraform apply -auto-approve").as_deref(),
+            Some("sudo terraform apply -auto-approve")
+        );
+        assert_eq!(
+            deploy_command("ansible-playbook site.yml").as_deref(),
+            Some("ansible-playbook site.yml")
+        );
+
+        assert_eq!(
+            deploy_command("docker push ghcr.io/example/app:latest").as_deref(),
+            Some("docker push ghcr.io/example/app:latest")
+        );
+
+        assert_eq!(deploy_command("kubectl get pods"), None);
+        assert_eq!(deploy_command("npm install"), None);
+        assert_eq!(deploy_command("terraform plan"), None);
+        assert_eq!(deploy_command("echo deploy"), None);
+    }
+
+    #[test]
+    fn github_hosted_labels() {
+        for label in [
+            "ubuntu-latest",
+            "ubuntu-22.04",
+            "windows-2019",
+            "macos-13-xlarge",
+        ] {
+            assert!(is_github_hosted(label), "{label}");
+        }
+
+        for label in ["self-hosted", "ubuntu", "macos-arm", "my-ubuntu-22.04"] {
+            assert!(!is_github_hosted(label), "{label}");
+        }
+    }
 
     #[test]
     fn uses_from_step() {
@@ -423,4 +2018,436 @@ mod tests {
             .unwrap()
             .ref_is_commit());
     }
+
+    #[test]
+    fn uses_is_first_party() {
+        assert!(Uses::from_step("actions/checkout@v4")
+            .unwrap()
+            .is_first_party());
+
+        assert!(!Uses::from_step("pypa/gh-action-pypi-publish@release/v1")
+            .unwrap()
+            .is_first_party());
+    }
+
+    #[test]
+    fn docker_options_parse() {
+        let options = DockerOptions::parse(
+            r#"--privileged --health-cmd "pg_isready -U postgres" --network=host -v /a:/b --volume /var/run/docker.sock:/var/run/docker.sock --init=false"#,
+        );
+
+        assert!(options.enabled("--privileged"));
+        assert!(!options.enabled("--init"));
+        assert!(!options.enabled("--read-only"));
+        assert_eq!(options.values(&["--network"]).collect::<Vec<_>>(), ["host"]);
+        assert_eq!(
+            options.values(&["--health-cmd"]).collect::<Vec<_>>(),
+            ["pg_isready -U postgres"]
+        );
+        assert_eq!(
+            options.values(&["-v", "--volume"]).collect::<Vec<_>>(),
+            ["/a:/b", "/var/run/docker.sock:/var/run/docker.sock"]
+        );
+
+        // Unbalanced quotes fall back to splitting on whitespace.
+        let options = DockerOptions::parse(r#"--privileged --label "x"#);
+        assert!(options.enabled("--privileged"));
+    }
+
+    #[test]
+    fn docker_image_parse() {
+        let vectors = [
+            (
+                "ubuntu",
+                Some(DockerImage {
+                    registry: None,
+                    name: "ubuntu",
+                    tag: None,
+                    digest: None,
+                }),
+            ),
+            (
+                "node:18",
+                Some(DockerImage {
+                    registry: None,
+                    name: "node",
+                    tag: Some("18"),
+                    digest: None,
+                }),
+            ),
+            (
+                "docker://ghcr.io/foo/bar:v1",
+                Some(DockerImage {
+                    registry: Some("ghcr.io"),
+                    name: "foo/bar",
+                    tag: Some("v1"),
+                    digest: None,
+                }),
+            ),
+            (
+                "localhost:5000/app@sha256:abcd",
+                Some(DockerImage {
+                    registry: Some("localhost:5000"),
+                    name: "app",
+                    tag: None,
+                    digest: Some("sha256:abcd"),
+                }),
+            ),
+            (
+                "bitnami/redis:7.2",
+                Some(DockerImage {
+                    registry: None,
+                    name: "bitnami/redis",
+                    tag: Some("7.2"),
+                    digest: None,
+                }),
+            ),
+            ("", None),
+            (":latest", None),
+        ];
+
+        for (image, expected) in vectors {
+            assert_eq!(DockerImage::parse(image), expected, "{image}");
+        }
+    }
+
+    #[test]
+    fn action_composite_steps() {
+        let action = Action::from_string(
+            r#"
+name: example
+runs:
+  using: composite
+  steps:
+    - name: checkout
+      uses: actions/checkout@v4
+    - run: echo "${{ inputs.title }}"
+      shell: bash
+"#
+            .into(),
+            "action.yml",
+            "action.yml",
+        )
+        .unwrap();
+
+        assert!(action.is_composite());
+        let steps = action.steps().collect::<Vec<_>>();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].name.as_deref(), Some("checkout"));
+        assert_eq!(steps[0].uses.as_deref(), Some("actions/checkout@v4"));
+        assert!(steps[0].run.is_none());
+        assert_eq!(steps[1].shell.as_deref(), Some("bash"));
+    }
+
+    #[test]
+    fn workflow_untrusted_sources() {
+        let workflow = test_workflow(
+            r#"
+on: issue_comment
+jobs:
+  setup:
+    runs-on: ubuntu-latest
+    outputs:
+      tainted: ${{ steps.gen.outputs.value }}
+      clean: ${{ steps.clean.outputs.value }}
+    steps:
+      - id: gen
+        run: echo "value=$BODY" >> $GITHUB_OUTPUT
+        env:
+          BODY: ${{ github.event.comment.body }}
+      - id: clean
+        run: echo "value=[1, 2]" >> $GITHUB_OUTPUT
+  consumer:
+    needs: setup
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo hi
+"#,
+        );
+
+        let consumer = workflow.job("consumer").unwrap();
+        assert_eq!(
+            workflow.untrusted_sources(
+                "${{ fromJSON(needs.setup.outputs.tainted) }}",
+                Some(&consumer)
+            ),
+            ["github.event.comment.body"]
+        );
+        assert!(workflow
+            .untrusted_sources("${{ needs.setup.outputs.clean }}", Some(&consumer))
+            .is_empty());
+        assert_eq!(
+            workflow.untrusted_sources("${{ github.head_ref }}", None),
+            ["github.head_ref"]
+        );
+        assert_eq!(
+            workflow.secret_sources("${{ secrets.TOKEN }}-${{ github.sha }}", None),
+            ["secrets.TOKEN"]
+        );
+    }
+
+    #[test]
+    fn workflow_is_untrusted_checkout() {
+        let workflow = test_workflow(
+            r#"
+on: pull_request_target
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - id: pr
+        run: echo "sha=$SHA" >> $GITHUB_OUTPUT
+        env:
+          SHA: ${{ github.event.pull_request.head.sha }}
+      - uses: actions/checkout@v4
+        with:
+          ref: ${{ steps.pr.outputs.sha }}
+      - uses: actions/checkout@v4
+        with:
+          ref: ${{ github.event.pull_request.head.ref }}
+      - uses: actions/checkout@v4
+"#,
+        );
+
+        let job = workflow.job("build").unwrap();
+        let untrusted = job
+            .steps()
+            .map(|step| workflow.is_untrusted_checkout(&step, &job))
+            .collect::<Vec<_>>();
+        assert_eq!(untrusted, [false, true, true, false]);
+    }
+
+    #[test]
+    fn job_matrix_combinations() {
+        let workflow = test_workflow(
+            r#"
+on: push
+jobs:
+  matrix:
+    runs-on: ${{ matrix.os }}
+    strategy:
+      matrix:
+        os: [ubuntu-latest, windows-latest]
+        node: [18, 20]
+        exclude:
+          - os: windows-latest
+            node: 18
+        include:
+          - os: ubuntu-latest
+            experimental: true
+          - os: self-hosted
+            node: 20
+    steps:
+      - run: true
+  dynamic:
+    runs-on: ubuntu-latest
+    strategy:
+      matrix: ${{ fromJSON(needs.setup.outputs.matrix) }}
+    steps:
+      - run: true
+  plain:
+    runs-on: ubuntu-latest
+    steps:
+      - run: true
+"#,
+        );
+
+        let combinations = workflow
+            .job("matrix")
+            .unwrap()
+            .matrix_combinations()
+            .unwrap();
+        let described = combinations
+            .iter()
+            .map(|combination| {
+                let Value::Object(members) = combination else {
+                    panic!("unexpected matrix combination: {combination:?}");
+                };
+                members
+                    .iter()
+                    .map(|(name, value)| format!("{name}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            described,
+            [
+                "node=18,os=ubuntu-latest,experimental=true",
+                "node=20,os=ubuntu-latest,experimental=true",
+                "node=20,os=windows-latest",
+                "node=20,os=self-hosted",
+            ]
+        );
+
+        assert!(workflow
+            .job("dynamic")
+            .unwrap()
+            .matrix_combinations()
+            .is_none());
+        assert_eq!(
+            workflow.job("plain").unwrap().matrix_combinations(),
+            Some(vec![Value::Object(vec![])])
+        );
+    }
+
+    #[test]
+    fn job_self_hosted() {
+        let workflow = test_workflow(
+            r#"
+on: push
+jobs:
+  hosted:
+    runs-on: ubuntu-latest
+    steps:
+      - run: true
+  hosted-matrix:
+    runs-on: ${{ matrix.os }}
+    strategy:
+      matrix:
+        os: [ubuntu-latest, windows-latest]
+    steps:
+      - run: true
+  self-hosted-matrix:
+    runs-on: ${{ matrix.os }}
+    strategy:
+      matrix:
+        os: [ubuntu-latest, self-hosted]
+    steps:
+      - run: true
+  custom:
+    runs-on: [linux, gpu]
+    steps:
+      - run: true
+  unevaluable:
+    runs-on: ${{ needs.setup.outputs.runner }}
+    steps:
+      - run: true
+  group:
+    runs-on:
+      group: large
+    steps:
+      - run: true
+"#,
+        );
+
+        let contexts = Contexts::default();
+        for (id, self_hosted) in [
+            ("hosted", None),
+            ("hosted-matrix", None),
+            ("self-hosted-matrix", Some(true)),
+            ("custom", Some(false)),
+            ("unevaluable", Some(false)),
+            ("group", Some(false)),
+        ] {
+            assert_eq!(
+                workflow.job(id).unwrap().self_hosted(&contexts),
+                self_hosted,
+                "{id}"
+            );
+        }
+    }
+
+    #[test]
+    fn workflow_job_graph() {
+        let workflow = test_workflow(
+            r#"
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    outputs:
+      version: ${{ steps.version.outputs.version }}
+    steps:
+      - id: version
+        run: echo "version=1.0" >> $GITHUB_OUTPUT
+  test:
+    needs: build
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo "${{ needs.build.outputs.version }}"
+  release:
+    needs: [build, test]
+    if: needs.build.outputs.version != ''
+    uses: ./.github/workflows/release.yml
+    with:
+      tag: v${{ needs.build.outputs.version }}
+  left:
+    needs: [right, lint]
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo left
+  right:
+    needs: left
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo right
+"#,
+        );
+
+        let graph = workflow.job_graph();
+        assert_eq!(
+            graph.dependencies("release").collect::<Vec<_>>(),
+            ["build", "test"]
+        );
+        assert_eq!(graph.dependents("build"), ["release", "test"]);
+        assert_eq!(graph.ancestors("release"), ["build", "test"]);
+        assert!(graph.ancestors("build").is_empty());
+        assert_eq!(graph.ancestors("left"), ["left", "right"]);
+
+        assert_eq!(graph.cycles(), [vec!["left", "right"]]);
+    }
+
+    #[test]
+    fn workflow_triggered_by() {
+        let build = test_workflow(
+            r#"
+name: Build
+on: pull_request
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: peter-evans/repository-dispatch@v3
+        with:
+          event-type: built
+      - run: gh api repos/o/r/dispatches -f event_type=tested
+"#,
+        );
+        let by_name = test_workflow(
+            r#"
+on:
+  workflow_run:
+    workflows: [Build]
+jobs: {}
+"#,
+        );
+        let by_type = test_workflow(
+            r#"
+on:
+  repository_dispatch:
+    types: [tested]
+jobs: {}
+"#,
+        );
+        let unrelated = test_workflow(
+            r#"
+on:
+  workflow_run:
+    workflows: [Release]
+  repository_dispatch:
+    types: [released]
+jobs: {}
+"#,
+        );
+
+        let source = build.trigger_source();
+        assert_eq!(by_name.triggered_by(&source), Some(Chain::WorkflowRun));
+        assert_eq!(
+            by_type.triggered_by(&source),
+            Some(Chain::RepositoryDispatch("tested".into()))
+        );
+        assert_eq!(unrelated.triggered_by(&source), None);
+        assert_eq!(build.triggered_by(&by_name.trigger_source()), None);
+    }
 }
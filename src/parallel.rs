@@ -0,0 +1,77 @@
+//! A minimal scoped thread pool, for parsing and auditing inputs
+//! in parallel.
+
+use std::{
+    num::NonZeroUsize,
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// Returns the default number of jobs: one per available CPU.
+pub(crate) fn default_jobs() -> NonZeroUsize {
+    thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Applies `f` to each of `items` on up to `jobs` threads, returning
+/// the results in the same order as `items`.
+pub(crate) fn map<T, U, F>(jobs: NonZeroUsize, items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync,
+{
+    let jobs = jobs.get().min(items.len());
+    if jobs <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    // Each worker takes the next unclaimed item until there are none left,
+    // so that a few slow items don't hold up the rest.
+    let next = AtomicUsize::new(0);
+    let mut results = thread::scope(|scope| {
+        let workers = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = vec![];
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(idx) else {
+                            break;
+                        };
+                        results.push((idx, f(item)));
+                    }
+                    results
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect::<Vec<_>>()
+    });
+
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::map;
+
+    #[test]
+    fn test_map_preserves_order() {
+        let items = (0..1000).collect::<Vec<u64>>();
+        let expected = items.iter().map(|n| n * n).collect::<Vec<_>>();
+
+        for jobs in [1, 2, 7, 2000] {
+            let jobs = NonZeroUsize::new(jobs).unwrap();
+            assert_eq!(map(jobs, &items, |n| n * n), expected);
+        }
+
+        assert!(map(NonZeroUsize::MIN, &[] as &[u64], |n| *n).is_empty());
+    }
+}
@@ -0,0 +1,281 @@
+//! Scheduling GitHub API requests within GitHub's rate limits.
+//!
+//! Online audits run concurrently and share a single client, and so a
+//! single rate limit. Every request that the client sends goes through a
+//! [`Budget`], which:
+//!
+//! * caps the number of requests in flight at once, since bursts of
+//!   concurrent requests are what trip GitHub's secondary rate limits;
+//! * pauses every request (not just the one that was limited) when a rate
+//!   limit is hit, until it's lifted;
+//! * refuses requests once the run's budget (`--github-api-budget`) or the
+//!   rate limit's quota is spent. The audits whose requests are refused are
+//!   truncated, rather than failing the whole run.
+
+use std::{
+    collections::BTreeSet,
+    fmt::Display,
+    sync::{Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::header::HeaderMap;
+
+/// The most requests that may be in flight at once.
+const MAX_IN_FLIGHT: usize = 4;
+
+/// The error for requests that weren't sent, because the budget is spent.
+#[derive(Debug)]
+pub(crate) struct Exhausted;
+
+impl Display for Exhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the GitHub API budget for this run is spent")
+    }
+}
+
+impl std::error::Error for Exhausted {}
+
+/// How much of the rate limit a run consumed, and what that cost it.
+pub(crate) struct Usage {
+    /// The number of requests that counted against the rate limit.
+    pub(crate) used: u64,
+    /// The quota that remains in the rate limit, and its size, as of the
+    /// last response.
+    pub(crate) remaining: Option<(u64, u64)>,
+    /// The audits that were truncated because the budget was spent.
+    pub(crate) truncated: Vec<&'static str>,
+}
+
+#[derive(Default)]
+struct State {
+    in_flight: usize,
+    used: u64,
+    remaining: Option<u64>,
+    limit: Option<u64>,
+    /// Until when every request is paused, because a rate limit was hit.
+    paused_until: Option<Instant>,
+    exhausted: bool,
+    truncated: BTreeSet<&'static str>,
+}
+
+/// The requests that a run may still make; see the module documentation.
+pub(crate) struct Budget {
+    /// The most requests that the run may make, if it's limited.
+    max: Option<u64>,
+    /// The longest we're willing to wait for a rate limit to be lifted.
+    max_wait: Duration,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+/// A request that's in flight. The request is complete when this is
+/// dropped.
+pub(crate) struct Permit<'a> {
+    budget: &'a Budget,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.budget.lock().in_flight -= 1;
+        self.budget.changed.notify_all();
+    }
+}
+
+impl Budget {
+    pub(crate) fn new(max: Option<u64>, max_wait: Duration) -> Self {
+        Self {
+            max,
+            max_wait,
+            state: Mutex::default(),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // A panic while holding the lock can't leave the state inconsistent.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Waits until a request may be sent on behalf of `audit` (if it's for
+    /// an audit), and reserves it. Fails, truncating `audit`, if the budget
+    /// is spent.
+    pub(crate) fn acquire(&self, audit: Option<&'static str>) -> Result<Permit<'_>, Exhausted> {
+        let mut state = self.lock();
+        loop {
+            let reserved = state.used + state.in_flight as u64;
+            if state.exhausted || self.max.is_some_and(|max| reserved >= max) {
+                truncate(&mut state, audit);
+                return Err(Exhausted);
+            }
+
+            if let Some(until) = state.paused_until {
+                let now = Instant::now();
+                if until > now {
+                    state = self
+                        .changed
+                        .wait_timeout(state, until - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0;
+                    continue;
+                }
+
+                // The limit has been lifted, so what we knew about it is stale.
+                state.paused_until = None;
+                state.remaining = None;
+            }
+
+            // Requests in flight will use up some of the remaining quota.
+            let unreserved = state
+                .remaining
+                .map_or(true, |remaining| remaining > state.in_flight as u64);
+            if state.in_flight < MAX_IN_FLIGHT && (state.in_flight == 0 || unreserved) {
+                break;
+            }
+            state = self.changed.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+
+        state.in_flight += 1;
+        Ok(Permit { budget: self })
+    }
+
+    /// Records a response's rate limit `headers`. Responses to conditional
+    /// requests that were `not_modified` don't count against the limit.
+    ///
+    /// Once the quota is spent, requests are paused until it resets, or
+    /// refused if that's too far off.
+    pub(crate) fn record(&self, headers: &HeaderMap, not_modified: bool) {
+        let header = |name: &str| -> Option<u64> { headers.get(name)?.to_str().ok()?.parse().ok() };
+
+        let mut state = self.lock();
+        if !not_modified {
+            state.used += 1;
+        }
+        if let Some(remaining) = header("x-ratelimit-remaining") {
+            state.remaining = Some(remaining);
+            state.limit = header("x-ratelimit-limit");
+        }
+
+        if state.remaining == Some(0) {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs());
+            match header("x-ratelimit-reset") {
+                Some(reset) => self.pause(
+                    &mut state,
+                    Duration::from_secs(reset.saturating_sub(now) + 1),
+                ),
+                None => state.exhausted = true,
+            }
+        }
+    }
+
+    /// Pauses every request for `delay`, e.g. because of a secondary rate
+    /// limit. A pause that's longer than we're willing to wait spends the
+    /// budget instead, truncating `audit`.
+    pub(crate) fn limited(&self, delay: Duration, audit: Option<&'static str>) {
+        let mut state = self.lock();
+        self.pause(&mut state, delay);
+        if state.exhausted {
+            truncate(&mut state, audit);
+        }
+    }
+
+    /// Spends the budget, e.g. because we're still rate limited after
+    /// retrying, truncating `audit`.
+    pub(crate) fn exhaust(&self, audit: Option<&'static str>) {
+        let mut state = self.lock();
+        state.exhausted = true;
+        truncate(&mut state, audit);
+        self.changed.notify_all();
+    }
+
+    fn pause(&self, state: &mut State, delay: Duration) {
+        if delay > self.max_wait {
+            log::warn!("GitHub API rate limit won't reset for {delay:?}; not waiting for it");
+            state.exhausted = true;
+        } else {
+            let until = Instant::now() + delay;
+            state.paused_until = Some(state.paused_until.map_or(until, |u| u.max(until)));
+        }
+        self.changed.notify_all();
+    }
+
+    /// Returns whether `audit` was truncated because the budget was spent.
+    pub(crate) fn is_truncated(&self, audit: &str) -> bool {
+        self.lock().truncated.contains(audit)
+    }
+
+    pub(crate) fn usage(&self) -> Usage {
+        let state = self.lock();
+        Usage {
+            used: state.used,
+            remaining: state.remaining.zip(state.limit),
+            truncated: state.truncated.iter().copied().collect(),
+        }
+    }
+}
+
+/// Marks `audit` (if the request was for one) as truncated.
+fn truncate(state: &mut State, audit: Option<&'static str>) {
+    if let Some(audit) = audit {
+        if state.truncated.insert(audit) {
+            log::warn!("{audit} is incomplete: the GitHub API budget for this run is spent");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::header::HeaderMap;
+
+    use super::Budget;
+
+    #[test]
+    fn test_budget() {
+        let budget = Budget::new(Some(2), Duration::from_secs(60));
+
+        let first = budget.acquire(Some("impostor-commit")).unwrap();
+        budget.record(&HeaderMap::new(), false);
+        drop(first);
+
+        // Revalidated responses are free.
+        let second = budget.acquire(Some("impostor-commit")).unwrap();
+        budget.record(&HeaderMap::new(), true);
+        drop(second);
+
+        let third = budget.acquire(Some("ref-confusion")).unwrap();
+        budget.record(&HeaderMap::new(), false);
+        drop(third);
+
+        assert!(budget.acquire(Some("ref-confusion")).is_err());
+        assert!(budget.acquire(None).is_err());
+        assert!(budget.is_truncated("ref-confusion"));
+        assert!(!budget.is_truncated("impostor-commit"));
+
+        let usage = budget.usage();
+        assert_eq!(usage.used, 2);
+        assert_eq!(usage.truncated, ["ref-confusion"]);
+    }
+
+    #[test]
+    fn test_budget_rate_limit() {
+        let budget = Budget::new(None, Duration::from_secs(60));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", "4999".parse().unwrap());
+        headers.insert("x-ratelimit-limit", "5000".parse().unwrap());
+        budget.record(&headers, false);
+        assert_eq!(budget.usage().remaining, Some((4999, 5000)));
+
+        // A rate limit that resets soon pauses requests; one that doesn't
+        // spends the budget.
+        budget.limited(Duration::from_millis(10), Some("unpinned-uses"));
+        assert!(budget.acquire(Some("unpinned-uses")).is_ok());
+        budget.limited(Duration::from_secs(3600), Some("unpinned-uses"));
+        assert!(budget.acquire(None).is_err());
+        assert_eq!(budget.usage().truncated, ["unpinned-uses"]);
+    }
+}
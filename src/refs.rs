@@ -0,0 +1,185 @@
+//! Git ref and ref filter semantics.
+//!
+//! GitHub Actions filters branches and tags (e.g. `on.push.branches`) with
+//! its own glob dialect, which isn't quite any standard one:
+//!
+//! * `*` matches zero or more characters, except `/`
+//! * `**` matches zero or more of any character
+//! * `?` matches zero or one of the preceding character
+//! * `+` matches one or more of the preceding character
+//! * `[...]` matches one character from a set or range
+//! * a leading `!` negates a pattern, excluding refs an earlier pattern matched
+//!
+//! See: <https://docs.github.com/en/actions/writing-workflows/workflow-syntax-for-github-actions#filter-pattern-cheat-sheet>
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// Names that are conventionally (but not necessarily) tags,
+/// used to check whether a branch filter also matches release-like refs.
+pub(crate) const TAG_LIKE_NAMES: &[&str] = &["v1", "v1.2.3", "1.2.3", "v1.2.3-rc.1", "release-1.2"];
+
+/// A single pattern from a branch or tag filter.
+#[derive(Debug)]
+pub(crate) struct RefPattern {
+    negated: bool,
+    regex: Regex,
+}
+
+impl RefPattern {
+    pub(crate) fn parse(pattern: &str) -> Result<Self> {
+        let (negated, glob) = match pattern.strip_prefix('!') {
+            Some(glob) => (true, glob),
+            None => (false, pattern),
+        };
+
+        let mut regex = String::from("^");
+        let mut chars = glob.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    regex.push_str(".*");
+                }
+                '*' => regex.push_str("[^/]*"),
+                // These apply to the preceding character, just like in a regex.
+                '?' | '+' => regex.push(c),
+                '[' => {
+                    regex.push('[');
+                    for c in chars.by_ref() {
+                        match c {
+                            ']' => break,
+                            '\\' | '[' | '&' | '~' => {
+                                regex.push_str(&regex::escape(&c.to_string()))
+                            }
+                            _ => regex.push(c),
+                        }
+                    }
+                    regex.push(']');
+                }
+                _ => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        regex.push('$');
+
+        let regex =
+            Regex::new(&regex).with_context(|| format!("invalid ref filter pattern: {pattern}"))?;
+
+        Ok(Self { negated, regex })
+    }
+
+    /// Returns whether this pattern matches `name`, ignoring negation.
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+/// Returns whether the given filter (e.g. the list under `branches:`)
+/// includes `name`.
+///
+/// Like GitHub, patterns are evaluated in order: a name is included if the
+/// last pattern that matches it isn't negated. Unparseable patterns are
+/// ignored.
+pub(crate) fn filter_includes<S: AsRef<str>>(patterns: &[S], name: &str) -> bool {
+    patterns
+        .iter()
+        .filter_map(|pattern| RefPattern::parse(pattern.as_ref()).ok())
+        .filter(|pattern| pattern.matches(name))
+        .last()
+        .is_some_and(|pattern| !pattern.negated)
+}
+
+/// Returns whether `name` looks like a version tag, e.g. `v1` or `1.2.3`,
+/// or is the conventional `v` prefix of one.
+pub(crate) fn looks_like_version(name: &str) -> bool {
+    name == "v"
+        || name
+            .strip_prefix('v')
+            .unwrap_or(name)
+            .starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// The kinds of refs that a workflow can run against.
+#[derive(Debug)]
+pub(crate) enum RefKind {
+    Branch,
+    Tag,
+}
+
+impl RefKind {
+    /// Guesses which kind of ref a short name (e.g. `main`) refers to.
+    pub(crate) fn guess(name: &str) -> Self {
+        if looks_like_version(name) {
+            Self::Tag
+        } else {
+            Self::Branch
+        }
+    }
+
+    /// Returns the prefix that qualifies names of this kind of ref.
+    pub(crate) fn prefix(&self) -> &'static str {
+        match self {
+            Self::Branch => "refs/heads/",
+            Self::Tag => "refs/tags/",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{filter_includes, looks_like_version, RefKind, RefPattern};
+
+    #[test]
+    fn test_ref_pattern() {
+        for (pattern, name, matches) in [
+            ("main", "main", true),
+            ("main", "mainline", false),
+            ("v*", "v1.2.3", true),
+            ("v*", "v1/feature", false),
+            ("v**", "v1/feature", true),
+            ("releases/**", "releases/1/2", true),
+            ("**", "any/thing", true),
+            ("*", "any/thing", false),
+            ("v[12].[0-9]+.[0-9]+", "v1.10.3", true),
+            ("v[12].[0-9]+.[0-9]+", "v3.1.1", false),
+            ("feature/ab?c", "feature/ac", true),
+            ("feature/ab?c", "feature/abbc", false),
+            ("a.b", "aXb", false),
+        ] {
+            assert_eq!(
+                RefPattern::parse(pattern).unwrap().matches(name),
+                matches,
+                "{pattern} vs {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter_includes() {
+        assert!(filter_includes(&["main", "v*"], "v1"));
+        assert!(!filter_includes(&["main"], "v1"));
+        assert!(!filter_includes(
+            &["releases/**", "!releases/**-alpha"],
+            "releases/1-alpha"
+        ));
+        assert!(filter_includes(
+            &["releases/**", "!releases/**-alpha"],
+            "releases/1"
+        ));
+        // Later patterns can re-include names excluded by earlier ones.
+        assert!(filter_includes(&["**", "!v*", "v1"], "v1"));
+        assert!(!filter_includes::<&str>(&[], "main"));
+    }
+
+    #[test]
+    fn test_ref_kind() {
+        assert!(looks_like_version("v1"));
+        assert!(looks_like_version("1.2.3"));
+        assert!(!looks_like_version("main"));
+        assert!(looks_like_version("v"));
+        assert!(!looks_like_version("vNext"));
+
+        assert_eq!(RefKind::guess("v1.2.3").prefix(), "refs/tags/");
+        assert_eq!(RefKind::guess("main").prefix(), "refs/heads/");
+    }
+}
@@ -1,55 +1,172 @@
 //! Functionality for registering and managing the lifecycles of
 //! audits.
 
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{anyhow, Result};
 
-use crate::{audit::WorkflowAudit, models::Workflow};
+use crate::{
+    audit::{Audit, Input},
+    collect::{is_action_definition, is_dependabot_config},
+    models::{Action, AuditInput, Dependabot, TriggerSource, Workflow},
+    parallel,
+};
 
 pub(crate) struct WorkflowRegistry {
-    pub(crate) workflows: HashMap<String, Workflow>,
+    pub(crate) workflows: BTreeMap<String, Workflow>,
+    pub(crate) actions: BTreeMap<String, Action>,
+    pub(crate) dependabots: BTreeMap<String, Dependabot>,
+    /// The slug of the remote repository that the inputs are from, if any.
+    /// Their paths on disk (in a temporary checkout) are meaningless to
+    /// users.
+    pub(crate) remote: Option<String>,
 }
 
 impl WorkflowRegistry {
     pub(crate) fn new() -> Self {
         Self {
             workflows: Default::default(),
+            actions: Default::default(),
+            dependabots: Default::default(),
+            remote: None,
         }
     }
 
-    pub(crate) fn len(&self) -> usize {
-        self.workflows.len()
+    fn contains(&self, name: &str) -> bool {
+        self.workflows.contains_key(name)
+            || self.actions.contains_key(name)
+            || self.dependabots.contains_key(name)
     }
 
-    pub(crate) fn register_workflow(&mut self, path: &Path) -> Result<()> {
+    /// Registers the workflows at `paths`, parsing them (and the reusable
+    /// workflows that they call) on up to `jobs` threads.
+    ///
+    /// Each is linked to the workflows among `sources` that can trigger it.
+    /// See [`trigger_sources`].
+    pub(crate) fn register_workflows(
+        &mut self,
+        paths: &[PathBuf],
+        sources: &[TriggerSource],
+        jobs: NonZeroUsize,
+    ) -> Result<()> {
+        let parsed = parallel::map(jobs, paths, |path| -> Result<Workflow> {
+            let mut workflow = Workflow::from_file(path)?;
+            workflow.link();
+
+            let upstreams = sources
+                .iter()
+                .filter(|source| source.path != workflow.path)
+                .filter_map(|source| Some((workflow.triggered_by(source)?, source.path.clone())))
+                .collect();
+            workflow.link_upstreams(upstreams);
+
+            Ok(workflow)
+        });
+
+        for (path, workflow) in paths.iter().zip(parsed) {
+            let name = path
+                .file_name()
+                .ok_or_else(|| anyhow!("invalid workflow: no filename component"))?
+                .to_str()
+                .ok_or_else(|| anyhow!("invalid workflow: path is not UTF-8"))?
+                .to_string();
+
+            if self.contains(&name) {
+                return Err(anyhow!("can't register {name} more than once"));
+            }
+
+            self.workflows.insert(name, workflow?);
+        }
+
+        Ok(())
+    }
+
+    /// Registers the action definition at `path` under `name`, which
+    /// should be its path relative to its repository's root.
+    pub(crate) fn register_action(&mut self, path: &Path, name: String) -> Result<()> {
+        if self.contains(&name) {
+            return Err(anyhow!("can't register {name} more than once"));
+        }
+
+        let action = Action::from_file(path, name.clone())?;
+        self.actions.insert(name, action);
+
+        Ok(())
+    }
+
+    /// Registers the Dependabot configuration at `path` under `name`, which
+    /// should be its path relative to its repository's root.
+    pub(crate) fn register_dependabot(&mut self, path: &Path, name: String) -> Result<()> {
+        if self.contains(&name) {
+            return Err(anyhow!("can't register {name} more than once"));
+        }
+
+        let dependabot = Dependabot::from_file(path, name.clone())?;
+        self.dependabots.insert(name, dependabot);
+
+        Ok(())
+    }
+
+    /// Registers the input `contents`, which was read from stdin and labeled
+    /// with `path`, as whichever kind of input `path` looks like.
+    pub(crate) fn register_stdin(&mut self, path: &Path, contents: String) -> Result<()> {
         let name = path
-            .file_name()
-            .ok_or_else(|| anyhow!("invalid workflow: no filename component"))?
             .to_str()
-            .ok_or_else(|| anyhow!("invalid workflow: path is not UTF-8"))?
+            .ok_or_else(|| anyhow!("invalid input: path is not UTF-8"))?
             .to_string();
 
-        if self.workflows.contains_key(&name) {
-            return Err(anyhow!("can't register {name} more than once"));
+        if is_action_definition(path) {
+            let action = Action::from_string(contents, path, name.clone())?;
+            self.actions.insert(name, action);
+        } else if is_dependabot_config(path) {
+            let dependabot = Dependabot::from_string(contents, path, name.clone())?;
+            self.dependabots.insert(name, dependabot);
+        } else {
+            let mut workflow = Workflow::from_string(contents, path)?;
+            workflow.link();
+            self.workflows.insert(workflow.filename().into(), workflow);
         }
 
-        self.workflows.insert(name, Workflow::from_file(path)?);
-
         Ok(())
     }
 
-    pub(crate) fn iter_workflows(&self) -> std::collections::hash_map::Iter<'_, String, Workflow> {
-        self.workflows.iter()
+    /// Returns every registered input: workflows, then actions, then
+    /// Dependabot configurations, each in order of name.
+    pub(crate) fn iter_inputs(&self) -> impl Iterator<Item = Input<'_>> {
+        self.workflows
+            .values()
+            .map(Input::Workflow)
+            .chain(self.actions.values().map(Input::Action))
+            .chain(self.dependabots.values().map(Input::Dependabot))
     }
 
-    pub(crate) fn get_workflow(&self, name: &str) -> &Workflow {
+    /// Returns the registered workflow or action with the given name,
+    /// or a reusable workflow called by one of the registered workflows.
+    pub(crate) fn get_input(&self, name: &str) -> &dyn AuditInput {
+        if let Some(workflow) = self.workflows.get(name) {
+            return workflow;
+        }
+        if let Some(action) = self.actions.get(name) {
+            return action;
+        }
+        if let Some(dependabot) = self.dependabots.get(name) {
+            return dependabot;
+        }
+
         self.workflows
-            .get(name)
-            .expect("API misuse: requested an un-registered workflow")
+            .values()
+            .find_map(|workflow| workflow.resolve(name))
+            .expect("API misuse: requested an un-registered input")
     }
 
-    /// Returns a subjective relative path for the given workflow.
+    /// Returns a subjective relative path for the given workflow or action.
+    ///
+    /// Actions and Dependabot configurations are always registered under
+    /// their relative paths, so their names are returned as-is.
     ///
     /// In general, this will be a relative path within the repository root,
     /// e.g. if zizmor was told to scan `/tmp/src` then one of the discovered
@@ -58,45 +175,82 @@ impl WorkflowRegistry {
     /// The exceptional case here is when zizmor is asked to scan a single
     /// workflow at some arbitrary location on disk. In that case, just
     /// the base workflow filename itself is returned.
-    pub(crate) fn get_workflow_relative_path(&self, name: &str) -> &str {
-        let workflow = self.get_workflow(name);
-        let workflow_path = Path::new(&workflow.path);
-
-        match workflow.path.rfind(".github/workflows") {
-            Some(start) => &workflow.path[start..],
-            // NOTE: Unwraps are safe since file component is always present and
-            // all paths are UTF-8 by construction.
-            None => workflow_path.file_name().unwrap().to_str().unwrap(),
+    pub(crate) fn get_relative_path<'a>(&'a self, name: &'a str) -> &'a str {
+        if self.actions.contains_key(name) || self.dependabots.contains_key(name) {
+            return name;
+        }
+
+        workflow_relative_path(self.get_input(name).path())
+    }
+
+    /// Returns the path to show for the given workflow or action: its path
+    /// on disk, or for a remote repository's, its relative path.
+    pub(crate) fn display_path<'a>(&'a self, name: &'a str) -> &'a str {
+        if self.remote.is_some() {
+            self.get_relative_path(name)
+        } else {
+            self.get_input(name).path()
         }
     }
 }
 
+/// Returns a subjective relative path for the workflow at `path`.
+/// See [`WorkflowRegistry::get_relative_path`].
+pub(crate) fn workflow_relative_path(path: &str) -> &str {
+    match path.rfind(".github/workflows") {
+        Some(start) => &path[start..],
+        // NOTE: Unwraps are safe since file component is always present and
+        // all paths are UTF-8 by construction.
+        None => Path::new(path).file_name().unwrap().to_str().unwrap(),
+    }
+}
+
+/// Returns the [`TriggerSource`] of each of the workflows at `paths`,
+/// parsing them on up to `jobs` threads.
+///
+/// Workflows are registered (and audited) a few at a time, to keep memory
+/// bounded on large trees; this lets each be linked to every workflow that
+/// can trigger it, without keeping them all around.
+pub(crate) fn trigger_sources(paths: &[PathBuf], jobs: NonZeroUsize) -> Result<Vec<TriggerSource>> {
+    parallel::map(jobs, paths, |path| {
+        Ok(Workflow::from_file(path)?.trigger_source())
+    })
+    .into_iter()
+    .collect()
+}
+
 pub(crate) struct AuditRegistry {
-    pub(crate) workflow_audits: HashMap<&'static str, Box<dyn WorkflowAudit>>,
+    /// Each audit, along with its description, by identifier.
+    pub(crate) audits: BTreeMap<&'static str, (&'static str, Box<dyn Audit>)>,
 }
 
 impl AuditRegistry {
     pub(crate) fn new() -> Self {
         Self {
-            workflow_audits: Default::default(),
+            audits: Default::default(),
         }
     }
 
     pub(crate) fn len(&self) -> usize {
-        self.workflow_audits.len()
+        self.audits.len()
     }
 
-    pub(crate) fn register_workflow_audit(
+    pub(crate) fn register_audit(
         &mut self,
         ident: &'static str,
-        audit: Box<dyn WorkflowAudit>,
+        desc: &'static str,
+        audit: Box<dyn Audit>,
     ) {
-        self.workflow_audits.insert(ident, audit);
+        self.audits.insert(ident, (desc, audit));
     }
 
-    pub(crate) fn iter_workflow_audits(
-        &mut self,
-    ) -> std::collections::hash_map::IterMut<'_, &str, Box<dyn WorkflowAudit>> {
-        self.workflow_audits.iter_mut()
+    /// Returns an iterator over each audit's identifier, description,
+    /// and instance, in order of identifier.
+    pub(crate) fn iter_audits(
+        &self,
+    ) -> impl Iterator<Item = (&'static str, &'static str, &dyn Audit)> {
+        self.audits
+            .iter()
+            .map(|(ident, (desc, audit))| (*ident, *desc, audit.as_ref()))
     }
 }
@@ -0,0 +1,207 @@
+//! Auditing remote repositories, i.e. `zizmor owner/repo[@ref]`.
+//!
+//! A remote repository's inputs are fetched via GitHub's API into a
+//! temporary checkout, which is then collected like any other directory.
+//! Only the files that would be collected are fetched, rather than the
+//! whole repository.
+//!
+//! With `--org`, every repository in an organization is fetched this way.
+
+use std::{
+    num::NonZeroUsize,
+    path::{Component, Path, PathBuf},
+    sync::LazyLock,
+};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::{collect, config::glob, github_api::Client, parallel};
+
+static SLUG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?<owner>[A-Za-z0-9][A-Za-z0-9-]*)/(?<repo>[A-Za-z0-9._-]+)(?:@(?<ref>\S+))?$")
+        .unwrap()
+});
+
+/// A remote repository on GitHub, optionally at a specific ref.
+pub(crate) struct Slug<'a> {
+    pub(crate) owner: &'a str,
+    pub(crate) repo: &'a str,
+    pub(crate) git_ref: Option<&'a str>,
+}
+
+impl<'a> Slug<'a> {
+    /// Parses `owner/repo` or `owner/repo@ref`.
+    pub(crate) fn parse(slug: &'a str) -> Option<Self> {
+        let captures = SLUG.captures(slug)?;
+
+        Some(Self {
+            owner: captures.name("owner")?.as_str(),
+            repo: captures.name("repo")?.as_str(),
+            git_ref: captures.name("ref").map(|git_ref| git_ref.as_str()),
+        })
+    }
+}
+
+/// A temporary checkout of a remote repository's inputs, which is removed
+/// when it's dropped.
+pub(crate) struct Checkout {
+    /// The repository's `owner/repo` slug.
+    pub(crate) slug: String,
+    pub(crate) dir: PathBuf,
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+            log::warn!("couldn't remove checkout {dir:?}: {e}", dir = self.dir);
+        }
+    }
+}
+
+/// Fetches the inputs of the repository `slug` (at its default branch,
+/// unless it has a ref) into a temporary checkout, on up to `jobs` threads.
+///
+/// Returns `None` if the repository has no inputs to audit.
+pub(crate) fn fetch(
+    client: &Client,
+    slug: &Slug<'_>,
+    jobs: NonZeroUsize,
+) -> Result<Option<Checkout>> {
+    let (owner, repo) = (slug.owner, slug.repo);
+    let git_ref = slug.git_ref.unwrap_or("HEAD");
+    log::info!("fetching {owner}/{repo}@{git_ref}");
+
+    let files = client
+        .list_files(owner, repo, git_ref)?
+        .into_iter()
+        .filter(|file| {
+            let path = Path::new(&file.path);
+            // Tree paths are always relative, but we're about to write
+            // them to disk, so we make sure.
+            path.components()
+                .all(|component| matches!(component, Component::Normal(_)))
+                && collect::is_discoverable(path)
+        })
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let checkout = Checkout {
+        slug: format!("{owner}/{repo}"),
+        dir: std::env::temp_dir().join(format!(
+            "zizmor-{pid}-{owner}-{repo}",
+            pid = std::process::id()
+        )),
+    };
+    std::fs::create_dir_all(&checkout.dir)
+        .with_context(|| format!("couldn't create checkout {dir:?}", dir = checkout.dir))?;
+
+    parallel::map(jobs, &files, |file| {
+        let contents = client.fetch_blob(owner, repo, &file.sha)?;
+
+        let path = checkout.dir.join(&file.path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents).with_context(|| format!("couldn't write {path:?}"))
+    })
+    .into_iter()
+    .collect::<Result<()>>()?;
+
+    Ok(Some(checkout))
+}
+
+/// Fetches the inputs of every repository in the organization `org` whose
+/// name matches any of `patterns` (or of every repository, if there are
+/// none) into temporary checkouts, fetching up to `jobs` at a time.
+/// Archived repositories are skipped, since their workflows can't run.
+///
+/// Repositories that can't be fetched are skipped with a warning, and
+/// returned (by slug) along with the checkouts.
+pub(crate) fn fetch_org(
+    client: &Client,
+    org: &str,
+    patterns: &[String],
+    jobs: NonZeroUsize,
+) -> Result<(Vec<Checkout>, Vec<String>)> {
+    let patterns = patterns
+        .iter()
+        .map(|pattern| glob(pattern))
+        .collect::<Result<Vec<_>>>()?;
+
+    let names = client
+        .list_org_repositories(org)
+        .with_context(|| format!("couldn't list repositories in {org}"))?
+        .into_iter()
+        .filter(|repository| {
+            if repository.archived {
+                log::info!(
+                    "skipping {org}/{name}: it's archived",
+                    name = repository.name
+                );
+            }
+            !repository.archived
+        })
+        .map(|repository| repository.name)
+        .filter(|name| patterns.is_empty() || patterns.iter().any(|p| p.is_match(name)))
+        .collect::<Vec<_>>();
+    log::info!("fetching {n} repositories from {org}", n = names.len());
+
+    // Each repository is fetched on a single thread, so that `jobs` bounds
+    // the number of concurrent requests.
+    let fetched = parallel::map(jobs, &names, |name| {
+        let slug = Slug {
+            owner: org,
+            repo: name,
+            git_ref: None,
+        };
+        fetch(client, &slug, NonZeroUsize::MIN)
+    });
+
+    let mut checkouts = vec![];
+    let mut failed = vec![];
+    for (name, fetched) in names.iter().zip(fetched) {
+        match fetched {
+            Ok(Some(checkout)) => checkouts.push(checkout),
+            Ok(None) => log::info!("skipping {org}/{name}: nothing to audit"),
+            Err(e) => {
+                log::warn!("couldn't fetch {org}/{name}: {e:#}");
+                failed.push(format!("{org}/{name}"));
+            }
+        }
+    }
+
+    Ok((checkouts, failed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Slug;
+
+    #[test]
+    fn test_parse_slug() {
+        for (slug, expected) in [
+            ("example/repo", Some(("example", "repo", None))),
+            (
+                "example/repo.js@v1",
+                Some(("example", "repo.js", Some("v1"))),
+            ),
+            (
+                "example/repo@feature/branch",
+                Some(("example", "repo", Some("feature/branch"))),
+            ),
+            ("example", None),
+            ("./example/repo", None),
+            ("example/repo/nested", None),
+            ("example/repo@", None),
+        ] {
+            assert_eq!(
+                Slug::parse(slug).map(|slug| (slug.owner, slug.repo, slug.git_ref)),
+                expected,
+                "{slug}"
+            );
+        }
+    }
+}
@@ -1,24 +1,28 @@
 //! APIs for rendering zizmor's "plain" (i.e. terminal) output format.
 
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    io::Write,
+};
 
 use crate::{
-    finding::{Finding, Location, Severity},
+    finding::{suppress::Suppression, Finding, Location, Severity},
+    ratelimit::Usage,
     registry::WorkflowRegistry,
+    rules,
 };
 use annotate_snippets::{Level, Renderer, Snippet};
-use anstream::println;
+use anyhow::Result;
 use owo_colors::OwoColorize;
 use terminal_link::Link;
 
 impl From<&Severity> for Level {
     fn from(sev: &Severity) -> Self {
         match sev {
-            Severity::Unknown => Level::Note,
             Severity::Informational => Level::Info,
             Severity::Low => Level::Help,
             Severity::Medium => Level::Warning,
-            Severity::High => Level::Error,
+            Severity::High | Severity::Critical => Level::Error,
         }
     }
 }
@@ -27,8 +31,8 @@ pub(crate) fn finding_snippet<'w>(
     registry: &'w WorkflowRegistry,
     finding: &'w Finding<'w>,
 ) -> Vec<Snippet<'w>> {
-    // Our finding might span multiple workflows, so we need to group locations
-    // by their enclosing workflow to generate each snippet correctly.
+    // Our finding might span multiple workflows (or actions), so we need to
+    // group locations by their enclosing input to generate each snippet correctly.
     let mut locations_by_workflow: HashMap<&str, Vec<&Location<'w>>> = HashMap::new();
     for location in &finding.locations {
         match locations_by_workflow.entry(location.symbolic.name) {
@@ -42,72 +46,332 @@ pub(crate) fn finding_snippet<'w>(
     }
 
     let mut snippets = vec![];
-    for (workflow_name, locations) in locations_by_workflow {
-        let workflow = registry.get_workflow(workflow_name);
-
-        snippets.push(
-            Snippet::source(workflow.document.source())
-                .fold(true)
-                .line_start(1)
-                .origin(&workflow.path)
-                .annotations(locations.iter().map(|loc| {
-                    let annotation = match loc.symbolic.link {
-                        Some(ref link) => link,
-                        None => &loc.symbolic.annotation,
-                    };
-
-                    Level::from(&finding.determinations.severity)
-                        .span(loc.concrete.location.start_offset..loc.concrete.location.end_offset)
-                        .label(annotation)
-                })),
-        );
+    for (name, mut locations) in locations_by_workflow {
+        let origin = registry.display_path(name);
+
+        // Each location carries an excerpt of its input; overlapping
+        // excerpts are merged, so that nearby locations share a snippet.
+        locations.sort_by_key(|loc| loc.excerpt.start_offset);
+        let mut groups: Vec<(usize, usize, usize, Vec<&Location<'w>>)> = vec![];
+        for loc in locations {
+            match groups.last_mut() {
+                Some((_, end, _, group)) if loc.excerpt.start_offset <= *end + 1 => {
+                    *end = (*end).max(loc.excerpt.end_offset());
+                    group.push(loc);
+                }
+                _ => groups.push((
+                    loc.excerpt.start_offset,
+                    loc.excerpt.end_offset(),
+                    loc.excerpt.start_row,
+                    vec![loc],
+                )),
+            }
+        }
+
+        for (start, end, start_row, group) in groups {
+            let source = &group[0].excerpt.source[start..end];
+
+            snippets.push(
+                Snippet::source(source)
+                    .fold(true)
+                    .line_start(start_row + 1)
+                    .origin(origin)
+                    .annotations(group.into_iter().map(move |loc| {
+                        let annotation = match loc.symbolic.link {
+                            Some(ref link) => link,
+                            None => &loc.symbolic.annotation,
+                        };
+
+                        // Clamp to the excerpt, since a feature can end
+                        // with the newline that terminates its last line.
+                        let span = &loc.concrete.location;
+                        Level::from(&finding.determinations.severity)
+                            .span(
+                                span.start_offset.min(end) - start
+                                    ..span.end_offset.min(end) - start,
+                            )
+                            .label(annotation)
+                    })),
+            );
+        }
     }
 
     snippets
 }
 
-pub(crate) fn render_findings(registry: &WorkflowRegistry, findings: &[Finding]) {
-    for finding in findings {
-        render_finding(registry, finding);
-        println!();
+/// Counts of the findings rendered so far, by severity (and, when auditing
+/// remote repositories, by repository).
+#[derive(Default)]
+pub(crate) struct Summary {
+    findings_by_severity: HashMap<Severity, usize>,
+    findings_by_repository: BTreeMap<String, usize>,
+    /// The remote repository whose findings were last rendered, if any.
+    current_repository: Option<String>,
+    /// The number of suppressed findings, which aren't counted by severity
+    /// (and are only rendered with `--show-suppressed`).
+    pub(crate) suppressed: usize,
+    /// The number of inputs audited.
+    pub(crate) inputs: usize,
+    /// The number of findings fixed, with `--fix`.
+    pub(crate) fixed: usize,
+    /// Whether the fixes were only collected into a patch, rather than
+    /// applied.
+    pub(crate) patched: bool,
+    /// The number of repositories audited.
+    pub(crate) repositories: usize,
+    /// The repositories that couldn't be audited, with `--org`.
+    pub(crate) failed_repositories: Vec<String>,
+    /// The audits that were skipped because they require GitHub API
+    /// access, and why they couldn't have it.
+    pub(crate) skipped_online: Vec<&'static str>,
+    pub(crate) offline_reason: &'static str,
+    /// How much of the GitHub API's rate limit was used, if we're online.
+    pub(crate) api_usage: Option<Usage>,
+}
+
+impl Summary {
+    /// Counts `finding`, from an input in `registry`.
+    pub(crate) fn count(&mut self, registry: &WorkflowRegistry, finding: &Finding) {
+        if finding.suppression.is_some() {
+            self.suppressed += 1;
+            return;
+        }
+
+        if let Some(slug) = &registry.remote {
+            *self.findings_by_repository.entry(slug.clone()).or_default() += 1;
+        }
+
+        match self
+            .findings_by_severity
+            .entry(finding.determinations.severity)
+        {
+            Entry::Occupied(mut e) => {
+                *e.get_mut() += 1;
+            }
+            Entry::Vacant(e) => {
+                e.insert(1);
+            }
+        }
     }
 
-    if findings.is_empty() {
-        println!("{}", "No findings to report. Good job!".green());
-    } else {
-        let mut findings_by_severity = HashMap::new();
+    /// Returns the number of (unsuppressed) findings with `severity`.
+    pub(crate) fn findings(&self, severity: Severity) -> usize {
+        self.findings_by_severity
+            .get(&severity)
+            .copied()
+            .unwrap_or_default()
+    }
 
-        for finding in findings {
-            match findings_by_severity.entry(&finding.determinations.severity) {
-                Entry::Occupied(mut e) => {
-                    *e.get_mut() += 1;
-                }
-                Entry::Vacant(e) => {
-                    e.insert(1);
-                }
+    /// Returns notes about the run for reports, e.g. skipped audits and
+    /// repositories that couldn't be audited.
+    pub(crate) fn notes(&self) -> Vec<String> {
+        let mut notes = vec![];
+        if self.suppressed > 0 {
+            notes.push(format!("{n} findings suppressed", n = self.suppressed));
+        }
+        if !self.skipped_online.is_empty() {
+            notes.push(format!(
+                "{n} audits skipped: they require GitHub API access, but {reason}: {skipped}",
+                n = self.skipped_online.len(),
+                reason = self.offline_reason,
+                skipped = self.skipped_online.join(", "),
+            ));
+        }
+        if let Some(usage) = self
+            .api_usage
+            .as_ref()
+            .filter(|usage| !usage.truncated.is_empty())
+        {
+            notes.push(format!(
+                "{n} audits truncated: the GitHub API budget ran out: {truncated}",
+                n = usage.truncated.len(),
+                truncated = usage.truncated.join(", "),
+            ));
+        }
+        if !self.failed_repositories.is_empty() {
+            notes.push(format!(
+                "{n} repositories couldn't be audited: {failed}",
+                n = self.failed_repositories.len(),
+                failed = self.failed_repositories.join(", "),
+            ));
+        }
+        notes
+    }
+}
+
+/// Renders `findings`, counting them in `summary`. With `explain`, each
+/// finding is followed by its audit's remediation guidance.
+///
+/// Findings are rendered as their inputs are audited, so the summary is
+/// rendered separately, once every input has been; see [`render_summary`].
+pub(crate) fn render_findings(
+    out: &mut impl Write,
+    registry: &WorkflowRegistry,
+    findings: &[Finding],
+    summary: &mut Summary,
+    explain: bool,
+) -> Result<()> {
+    for finding in findings {
+        // Findings in remote repositories are grouped under their slug.
+        if let Some(slug) = &registry.remote {
+            if summary.current_repository.as_ref() != Some(slug) {
+                writeln!(out, "{}\n", slug.bold())?;
+                summary.current_repository = Some(slug.clone());
             }
         }
 
-        println!(
-            "{nfindings} findings ({nunknown} unknown, {ninformational} informational, {nlow} low, {nmedium} medium, {nhigh} high)",
-            nfindings = findings.len().green(),
-            nunknown = findings_by_severity.get(&Severity::Unknown).unwrap_or(&0),
+        render_finding(out, registry, finding)?;
+        if let Some(remediation) = rules::remediation(finding.ident).filter(|_| explain) {
+            writeln!(out, "{}", "remediation:".bold())?;
+            for line in remediation.lines() {
+                writeln!(out, "  {line}")?;
+            }
+        }
+        writeln!(out)?;
+
+        summary.count(registry, finding);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn render_summary(out: &mut impl Write, summary: &Summary) -> Result<()> {
+    let findings_by_severity = &summary.findings_by_severity;
+    let nfindings = findings_by_severity.values().sum::<usize>();
+
+    if nfindings == 0 {
+        writeln!(out, "{}", "No findings to report. Good job!".green())?;
+    } else {
+        writeln!(
+            out,
+            "{nfindings} findings ({ninformational} informational, {nlow} low, {nmedium} medium, {nhigh} high, {ncritical} critical)",
+            nfindings = nfindings.green(),
             ninformational = findings_by_severity.get(&Severity::Informational).unwrap_or(&0).purple(),
             nlow = findings_by_severity.get(&Severity::Low).unwrap_or(&0).cyan(),
             nmedium = findings_by_severity.get(&Severity::Medium).unwrap_or(&0).yellow(),
             nhigh = findings_by_severity.get(&Severity::High).unwrap_or(&0).red(),
-        );
+            ncritical = findings_by_severity.get(&Severity::Critical).unwrap_or(&0).bright_red(),
+        )?;
+    }
+
+    if summary.suppressed > 0 {
+        writeln!(
+            out,
+            "{nsuppressed} findings suppressed",
+            nsuppressed = summary.suppressed.bright_black()
+        )?;
+    }
+
+    if summary.fixed > 0 {
+        writeln!(
+            out,
+            "{nfixed} findings {fixed}",
+            nfixed = summary.fixed.green(),
+            fixed = if summary.patched {
+                "fixed in the patch"
+            } else {
+                "fixed"
+            }
+        )?;
+    }
+
+    if !summary.skipped_online.is_empty() {
+        writeln!(
+            out,
+            "{nskipped} audits skipped: they require GitHub API access, but {reason}: {skipped}",
+            nskipped = summary.skipped_online.len().yellow(),
+            reason = summary.offline_reason,
+            skipped = summary.skipped_online.join(", ")
+        )?;
+    }
+
+    if let Some(usage) = summary.api_usage.as_ref().filter(|usage| usage.used > 0) {
+        match usage.remaining {
+            Some((remaining, limit)) => writeln!(
+                out,
+                "{used} GitHub API requests made ({remaining} of {limit} remaining)",
+                used = usage.used,
+            )?,
+            None => writeln!(out, "{used} GitHub API requests made", used = usage.used)?,
+        }
     }
+    if let Some(usage) = summary
+        .api_usage
+        .as_ref()
+        .filter(|usage| !usage.truncated.is_empty())
+    {
+        writeln!(
+            out,
+            "{ntruncated} audits truncated: the GitHub API budget ran out: {truncated}",
+            ntruncated = usage.truncated.len().yellow(),
+            truncated = usage.truncated.join(", ")
+        )?;
+    }
+
+    if summary.findings_by_repository.len() > 1 {
+        for (slug, nfindings) in &summary.findings_by_repository {
+            writeln!(out, "  {slug}: {nfindings} findings")?;
+        }
+    }
+    if summary.repositories > 1 || !summary.failed_repositories.is_empty() {
+        writeln!(
+            out,
+            "{nrepositories} repositories audited",
+            nrepositories = summary.repositories
+        )?;
+    }
+    if !summary.failed_repositories.is_empty() {
+        writeln!(
+            out,
+            "{nfailed} repositories couldn't be audited: {failed}",
+            nfailed = summary.failed_repositories.len().red(),
+            failed = summary.failed_repositories.join(", ")
+        )?;
+    }
+
+    Ok(())
 }
 
-fn render_finding(registry: &WorkflowRegistry, finding: &Finding) {
+fn render_finding(
+    out: &mut impl Write,
+    registry: &WorkflowRegistry,
+    finding: &Finding,
+) -> Result<()> {
     let link = Link::new(finding.ident, &finding.url()).to_string();
+    let fixes = finding
+        .fixes
+        .iter()
+        .map(|fix| format!("fix available: {title}", title = fix.fix.title))
+        .collect::<Vec<_>>();
 
-    let message = Level::from(&finding.determinations.severity)
+    let mut message = Level::from(&finding.determinations.severity)
         .title(finding.desc)
         .id(&link)
         .snippets(finding_snippet(registry, finding));
 
+    if let Some(suggestion) = &finding.suggestion {
+        message = message.footer(Level::Help.title(suggestion));
+    }
+
+    for fix in &fixes {
+        message = message.footer(Level::Note.title(fix));
+    }
+
+    match finding.suppression {
+        Some(Suppression::Comment) => {
+            message = message.footer(Level::Note.title("suppressed by an inline comment"))
+        }
+        Some(Suppression::Config) => {
+            message = message.footer(Level::Note.title("suppressed by the configuration file"))
+        }
+        Some(Suppression::Baseline) => {
+            message = message.footer(Level::Note.title("suppressed by the baseline"))
+        }
+        None => (),
+    }
+
     let renderer = Renderer::styled();
-    println!("{}", renderer.render(message));
+    writeln!(out, "{}", renderer.render(message))?;
+
+    Ok(())
 }
@@ -0,0 +1,121 @@
+//! A cache of audit results, so that repeated runs over the same inputs
+//! (e.g. in CI, or after editing a single workflow) only re-audit the
+//! inputs that have changed.
+//!
+//! Results are keyed by the input's name and contents (along with those of
+//! any workflows that it's linked to), the audit, `zizmor`'s version, and
+//! the configuration. They're stored symbolically (see
+//! [`crate::finding::store`]), and rebuilt against the input when loaded.
+//!
+//! The results of audits that use GitHub's API depend on more than their
+//! input, so they're never cached.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use anyhow::Result;
+
+use crate::{
+    audit::Input,
+    cache::DiskCache,
+    finding::{store::StoredFinding, Finding},
+    models::{AuditInput, Workflow},
+    state::AuditConfig,
+};
+
+pub(crate) struct ResultCache {
+    /// The on-disk cache, or `None` if caching is disabled.
+    disk: Option<DiskCache>,
+    /// A digest of everything besides the input that results depend on.
+    digest: u64,
+}
+
+impl ResultCache {
+    pub(crate) fn new(config: &AuditConfig, enabled: bool) -> Self {
+        let mut hasher = DefaultHasher::new();
+        (
+            env!("CARGO_PKG_VERSION"),
+            config.pedantic,
+            config.file.source(),
+        )
+            .hash(&mut hasher);
+
+        Self {
+            disk: enabled.then(|| DiskCache::new("results")),
+            digest: hasher.finish(),
+        }
+    }
+
+    fn key(&self, input: Input<'_>, ident: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.digest.hash(&mut hasher);
+        input.raw().name().hash(&mut hasher);
+        sources(input).hash(&mut hasher);
+
+        format!("{ident}/{digest:016x}.json", digest = hasher.finish())
+    }
+
+    /// Returns the cached findings of the audit `ident` (described by
+    /// `desc`) on `input`, if there are any.
+    pub(crate) fn get<'a>(
+        &self,
+        input: Input<'a>,
+        ident: &'static str,
+        desc: &'static str,
+    ) -> Option<Vec<Finding<'a>>> {
+        let cached = self.disk.as_ref()?.get(&self.key(input, ident))?;
+
+        let rebuilt = serde_json::from_str::<Vec<StoredFinding>>(&cached)
+            .map_err(Into::into)
+            .and_then(|stored| {
+                stored
+                    .into_iter()
+                    .map(|finding| finding.rebuild(ident, desc, input.raw()))
+                    .collect::<Result<Vec<_>>>()
+            });
+
+        match rebuilt {
+            Ok(findings) => Some(findings),
+            Err(e) => {
+                log::debug!("ignoring unusable cached results for {ident}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Caches `findings`, the findings of the audit `ident` on `input`.
+    pub(crate) fn put(&self, input: Input<'_>, ident: &str, findings: &[Finding<'_>]) {
+        let Some(disk) = &self.disk else {
+            return;
+        };
+
+        let stored = findings.iter().map(StoredFinding::new).collect::<Vec<_>>();
+        match serde_json::to_string(&stored) {
+            Ok(stored) => disk.put(&self.key(input, ident), &stored),
+            Err(e) => log::debug!("couldn't cache results for {ident}: {e}"),
+        }
+    }
+}
+
+/// Returns the sources that audits of `input` can depend on: its own,
+/// and for workflows, those of the workflows that it calls or is
+/// triggered by.
+fn sources(input: Input<'_>) -> Vec<&str> {
+    fn workflow_sources<'w>(workflow: &'w Workflow, sources: &mut Vec<&'w str>) {
+        sources.push(workflow.document().source());
+        for (_, callee) in workflow.calls() {
+            workflow_sources(callee, sources);
+        }
+        for (_, upstream) in workflow.upstreams() {
+            sources.push(upstream.document().source());
+        }
+    }
+
+    match input {
+        Input::Workflow(workflow) => {
+            let mut sources = vec![];
+            workflow_sources(workflow, &mut sources);
+            sources
+        }
+        input => vec![input.raw().document().source()],
+    }
+}
@@ -0,0 +1,155 @@
+//! Metadata about zizmor's audits (i.e. its rules), for `zizmor rules`,
+//! and their documentation, for `zizmor explain` and `--explain`.
+//!
+//! Each audit's documentation is its section of `docs/audits.md`, which is
+//! embedded in the binary so that it's available offline.
+
+use std::io::Write;
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::{
+    audit::Audit,
+    finding::{Confidence, Severity},
+};
+
+/// The documentation for every audit, one `` ## `ident` `` section each.
+const AUDITS_DOC: &str = include_str!("../docs/audits.md");
+
+/// An audit's metadata, independent of any configuration.
+#[derive(Serialize)]
+pub(crate) struct Rule {
+    pub(crate) ident: &'static str,
+    pub(crate) desc: &'static str,
+    pub(crate) default_severity: Severity,
+    pub(crate) default_confidence: Confidence,
+    /// Whether the audit only produces findings with `--pedantic`.
+    pub(crate) pedantic: bool,
+    /// Whether the audit requires GitHub API access, i.e. only runs online.
+    pub(crate) online: bool,
+    /// The keys of the audit's settings in the configuration file.
+    pub(crate) config_keys: &'static [&'static str],
+    /// The OAuth scopes that a classic GitHub API token needs for the audit.
+    pub(crate) github_scopes: &'static [&'static str],
+}
+
+impl Rule {
+    pub(crate) fn of<A: Audit>() -> Self {
+        Self {
+            ident: A::ident(),
+            desc: A::desc(),
+            default_severity: A::default_severity(),
+            default_confidence: A::default_confidence(),
+            pedantic: A::pedantic(),
+            online: A::requires_github_api(),
+            config_keys: A::config_keys(),
+            github_scopes: A::github_scopes(),
+        }
+    }
+}
+
+/// Returns the URL of the documentation for the audit `ident`.
+pub(crate) fn url(ident: &str) -> String {
+    format!(
+        "{repo}/blob/main/docs/audits.md#{ident}",
+        repo = env!("CARGO_PKG_REPOSITORY"),
+    )
+}
+
+/// Returns the documentation for the audit `ident`, i.e. its section of
+/// the audit docs (without its heading), if it's documented.
+pub(crate) fn explanation(ident: &str) -> Option<&'static str> {
+    let heading = format!("\n## `{ident}`\n");
+    let start = AUDITS_DOC.find(&heading)? + heading.len();
+    let section = &AUDITS_DOC[start..];
+    let end = section.find("\n## ").unwrap_or(section.len());
+
+    Some(section[..end].trim())
+}
+
+/// Returns the remediation guidance for the audit `ident`, i.e. the
+/// "Remediation" subsection of its documentation, if it has one.
+pub(crate) fn remediation(ident: &str) -> Option<&'static str> {
+    const HEADING: &str = "### Remediation\n";
+
+    let explanation = explanation(ident)?;
+    let start = explanation.find(HEADING)? + HEADING.len();
+    let subsection = &explanation[start..];
+    let end = subsection.find("\n### ").unwrap_or(subsection.len());
+
+    Some(subsection[..end].trim())
+}
+
+/// Renders `rules` as plain text.
+pub(crate) fn render(rules: &[Rule]) -> Result<()> {
+    let mut stdout = anstream::stdout();
+
+    for rule in rules {
+        writeln!(
+            stdout,
+            "{ident}: {desc}",
+            ident = rule.ident.bold(),
+            desc = rule.desc
+        )?;
+        writeln!(
+            stdout,
+            "  default severity: {severity:?}, default confidence: {confidence:?}",
+            severity = rule.default_severity,
+            confidence = rule.default_confidence,
+        )?;
+
+        let mut tags = vec![];
+        if rule.pedantic {
+            tags.push("pedantic only");
+        }
+        if rule.online {
+            tags.push("requires GitHub API access");
+        }
+        if !tags.is_empty() {
+            writeln!(stdout, "  {tags}", tags = tags.join("; ").bright_black())?;
+        }
+
+        if !rule.config_keys.is_empty() {
+            writeln!(
+                stdout,
+                "  config: {keys}",
+                keys = rule.config_keys.join(", ")
+            )?;
+        }
+        if !rule.github_scopes.is_empty() {
+            writeln!(
+                stdout,
+                "  GitHub token scopes: {scopes}",
+                scopes = rule.github_scopes.join(", ")
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explanation, remediation};
+
+    #[test]
+    fn test_explanation() {
+        let text = explanation("artipacked").unwrap();
+        assert!(text.starts_with("| Type |"));
+        assert!(text.contains("### Why"));
+        // The next audit's section isn't included.
+        assert!(!text.contains("## `auto-approve`"));
+
+        let text = remediation("artipacked").unwrap();
+        assert!(text.starts_with("Set `persist-credentials: false`"));
+        assert!(!text.contains("### Other resources"));
+
+        // The last audit's section runs to the end of the document.
+        assert!(remediation("wildcard-branch-trigger").is_some());
+
+        assert!(explanation("not-an-audit").is_none());
+        assert!(explanation("artipacked`").is_none());
+    }
+}
@@ -1,24 +1,75 @@
 //! APIs for rendering SARIF outputs.
+//!
+//! The output is SARIF 2.1.0, as accepted by GitHub's code scanning: each
+//! audit is a rule (with its documentation as the rule's help), and each
+//! finding is a result of one.
+
+use std::collections::BTreeMap;
 
 use serde_sarif::sarif::{
-    ArtifactContent, ArtifactLocation, Location as SarifLocation, LogicalLocation, Message,
-    PhysicalLocation, PropertyBag, Region, Result as SarifResult, Run, Sarif, Tool, ToolComponent,
+    ArtifactChange, ArtifactContent, ArtifactLocation, Fix as SarifFix, Location as SarifLocation,
+    LogicalLocation, Message, MultiformatMessageString, PhysicalLocation, PropertyBag, Region,
+    Replacement, ReportingDescriptor, Result as SarifResult, ResultLevel, Run, Sarif,
+    Suppression as SarifSuppression, Tool, ToolComponent,
 };
 
 use crate::{
-    finding::{Finding, Location},
+    finding::{suppress::Suppression, Confidence, Finding, Location, Severity},
     registry::WorkflowRegistry,
+    rules::{self, Rule},
 };
 
-pub(crate) fn build(registry: &WorkflowRegistry, findings: Vec<Finding<'_>>) -> Sarif {
+impl From<Severity> for ResultLevel {
+    fn from(value: Severity) -> Self {
+        // SARIF only has three levels, so we fold ours into them.
+        match value {
+            Severity::Informational => ResultLevel::Note,
+            Severity::Low | Severity::Medium => ResultLevel::Warning,
+            Severity::High | Severity::Critical => ResultLevel::Error,
+        }
+    }
+}
+
+/// Returns the SARIF level for `severity`.
+fn level(severity: Severity) -> serde_json::Value {
+    // NOTE: `ResultLevel`'s `Serialize` impl is untagged, so its variants
+    // serialize as `null`; its `Display` impl gives the SARIF names.
+    ResultLevel::from(severity).to_string().into()
+}
+
+/// Returns the `security-severity` (a CVSS-like score, from 0.0 to 10.0)
+/// that GitHub's code scanning ranks alerts of `severity` by.
+fn security_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Informational => "1.0",
+        Severity::Low => "3.0",
+        Severity::Medium => "5.5",
+        Severity::High => "8.0",
+        Severity::Critical => "9.5",
+    }
+}
+
+/// Builds a SARIF log of `rules` (every audit) and `results`, which are
+/// built (with [`build_results`]) as each input is audited.
+pub(crate) fn build(rules: &[Rule], results: Vec<SarifResult>) -> Sarif {
     Sarif::builder()
         .version("2.1.0")
-        .schema("https://docs.oasis-open.org/sarif/sarif/v2.1.0/errata01/os/schemas/sarif-external-property-file-schema-2.1.0.json")
-        .runs([build_run(registry, findings)])
+        .schema("https://docs.oasis-open.org/sarif/sarif/v2.1.0/errata01/os/schemas/sarif-schema-2.1.0.json")
+        .runs([build_run(rules, results)])
         .build()
 }
 
-fn build_run(registry: &WorkflowRegistry, findings: Vec<Finding<'_>>) -> Run {
+fn build_run(rules: &[Rule], mut results: Vec<SarifResult>) -> Run {
+    // Results refer to their rule by index as well as by ID, which spares
+    // consumers a lookup.
+    for result in &mut results {
+        result.rule_index = result
+            .rule_id
+            .as_ref()
+            .and_then(|id| rules.iter().position(|rule| rule.ident == id.as_str()))
+            .map(|idx| idx as i64);
+    }
+
     Run::builder()
         .tool(
             Tool::builder()
@@ -29,23 +80,130 @@ fn build_run(registry: &WorkflowRegistry, findings: Vec<Finding<'_>>) -> Run {
                         .semantic_version(env!("CARGO_PKG_VERSION"))
                         .download_uri(env!("CARGO_PKG_REPOSITORY"))
                         .information_uri(env!("CARGO_PKG_HOMEPAGE"))
+                        .rules(rules.iter().map(build_rule).collect::<Vec<_>>())
                         .build(),
                 )
                 .build(),
         )
-        .results(build_results(registry, findings))
+        .results(results)
         .build()
 }
 
-fn build_results(registry: &WorkflowRegistry, findings: Vec<Finding<'_>>) -> Vec<SarifResult> {
+fn build_rule(rule: &Rule) -> ReportingDescriptor {
+    let help = rules::explanation(rule.ident).unwrap_or(rule.desc);
+    let precision = match rule.default_confidence {
+        Confidence::Unknown | Confidence::Low => "low",
+        Confidence::Medium => "medium",
+        Confidence::High => "high",
+    };
+
+    ReportingDescriptor::builder()
+        .id(rule.ident)
+        .name(rule.ident)
+        .short_description(MultiformatMessageString::builder().text(rule.desc).build())
+        .help(
+            MultiformatMessageString::builder()
+                .text(help)
+                .markdown(help)
+                .build(),
+        )
+        .help_uri(rules::url(rule.ident))
+        .properties(
+            PropertyBag::builder()
+                .additional_properties([
+                    ("tags".into(), serde_json::json!(["security"])),
+                    ("precision".into(), precision.into()),
+                    (
+                        "security-severity".into(),
+                        security_severity(rule.default_severity).into(),
+                    ),
+                ])
+                .build(),
+        )
+        .build()
+}
+
+pub(crate) fn build_results(
+    registry: &WorkflowRegistry,
+    findings: &[Finding<'_>],
+) -> Vec<SarifResult> {
     findings.iter().map(|f| build_result(registry, f)).collect()
 }
 
 fn build_result(registry: &WorkflowRegistry, finding: &Finding<'_>) -> SarifResult {
     SarifResult::builder()
-        .message(finding.ident)
+        .message(finding.desc)
         .rule_id(finding.ident)
+        .level(level(finding.determinations.severity))
         .locations(build_locations(registry, &finding.locations))
+        .partial_fingerprints(BTreeMap::from([(
+            "zizmor/v1".to_string(),
+            finding.fingerprint.clone(),
+        )]))
+        .properties(build_properties(finding))
+        .fixes(build_fixes(registry, finding))
+        .suppressions(build_suppressions(finding))
+        .build()
+}
+
+fn build_fixes(registry: &WorkflowRegistry, finding: &Finding<'_>) -> Vec<SarifFix> {
+    finding
+        .fixes
+        .iter()
+        .map(|fix| {
+            let edit = &fix.edit;
+            SarifFix::builder()
+                .description(Message::builder().text(&fix.fix.title).build())
+                .artifact_changes([ArtifactChange::builder()
+                    .artifact_location(
+                        ArtifactLocation::builder()
+                            .uri_base_id("%SRCROOT%")
+                            .uri(registry.get_relative_path(fix.fix.location.name))
+                            .build(),
+                    )
+                    .replacements([Replacement::builder()
+                        .deleted_region(
+                            Region::builder()
+                                .byte_offset(edit.location.start_offset as i64)
+                                .byte_length(
+                                    (edit.location.end_offset - edit.location.start_offset) as i64,
+                                )
+                                .build(),
+                        )
+                        .inserted_content(
+                            ArtifactContent::builder().text(&edit.replacement).build(),
+                        )
+                        .build()])
+                    .build()])
+                .build()
+        })
+        .collect()
+}
+
+fn build_suppressions(finding: &Finding<'_>) -> Vec<SarifSuppression> {
+    let kind = match finding.suppression {
+        Some(Suppression::Comment) => "inSource",
+        Some(Suppression::Config | Suppression::Baseline) => "external",
+        None => return vec![],
+    };
+
+    vec![SarifSuppression::builder().kind(kind).build()]
+}
+
+fn build_properties(finding: &Finding<'_>) -> PropertyBag {
+    let mut properties = BTreeMap::from([(
+        "confidence".into(),
+        serde_json::value::to_value(finding.determinations.confidence).unwrap(),
+    )]);
+    if let Some(rationale) = finding.determinations.rationale {
+        properties.insert(
+            "rationale".into(),
+            serde_json::value::to_value(rationale).unwrap(),
+        );
+    }
+
+    PropertyBag::builder()
+        .additional_properties(properties)
         .build()
 }
 
@@ -69,7 +227,7 @@ fn build_locations(registry: &WorkflowRegistry, locations: &[Location<'_>]) -> V
                         .artifact_location(
                             ArtifactLocation::builder()
                                 .uri_base_id("%SRCROOT%")
-                                .uri(registry.get_workflow_relative_path(location.symbolic.name))
+                                .uri(registry.get_relative_path(location.symbolic.name))
                                 .build(),
                         )
                         .region(
@@ -83,6 +241,12 @@ fn build_locations(registry: &WorkflowRegistry, locations: &[Location<'_>]) -> V
                                 .end_column(
                                     (location.concrete.location.end_point.column as i64) + 1,
                                 )
+                                .byte_offset(location.concrete.location.start_offset as i64)
+                                .byte_length(
+                                    (location.concrete.location.end_offset
+                                        - location.concrete.location.start_offset)
+                                        as i64,
+                                )
                                 .source_language("yaml")
                                 .snippet(
                                     ArtifactContent::builder()
@@ -91,6 +255,22 @@ fn build_locations(registry: &WorkflowRegistry, locations: &[Location<'_>]) -> V
                                 )
                                 .build(),
                         )
+                        .context_region(
+                            Region::builder()
+                                .start_line((location.excerpt.start_row as i64) + 1)
+                                .end_line(
+                                    (location.excerpt.start_row
+                                        + location.excerpt.text.lines().count().max(1))
+                                        as i64,
+                                )
+                                .source_language("yaml")
+                                .snippet(
+                                    ArtifactContent::builder()
+                                        .text(location.excerpt.text)
+                                        .build(),
+                                )
+                                .build(),
+                        )
                         .build(),
                 )
                 .message(
@@ -102,3 +282,18 @@ fn build_locations(registry: &WorkflowRegistry, locations: &[Location<'_>]) -> V
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::level;
+    use crate::finding::Severity;
+
+    #[test]
+    fn test_level() {
+        assert_eq!(level(Severity::Informational), "note");
+        assert_eq!(level(Severity::Low), "warning");
+        assert_eq!(level(Severity::Medium), "warning");
+        assert_eq!(level(Severity::High), "error");
+        assert_eq!(level(Severity::Critical), "error");
+    }
+}
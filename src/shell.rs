@@ -0,0 +1,698 @@
+//! Lightweight analysis of `run:` scripts.
+//!
+//! This is *not* a shell parser: `run:` blocks can be written for any shell,
+//! and fully parsing even one of them is a tall order. Instead, we split
+//! scripts into approximate "statements" on line boundaries and inspect them
+//! for well-known patterns (redirections, variable references, etc.).
+//!
+//! For patterns that depend on how commands are connected (e.g. pipelines),
+//! statements can also be split into a shell-agnostic stream of [`Token`]s
+//! and [`Command`]s, which is approximately right for `bash`, `pwsh` and `cmd`.
+
+use std::{ops::Range, sync::LazyLock};
+
+use regex::Regex;
+
+/// Matches shell and PowerShell variable references, e.g.
+/// `$FOO`, `${FOO}`, and `$env:FOO`.
+static VARIABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\$(?i:env:)([A-Za-z_]\w*)|\$\{([A-Za-z_]\w*)|\$([A-Za-z_]\w*)|%([A-Za-z_]\w*)%")
+        .unwrap()
+});
+
+/// Matches the opening of a heredoc, e.g. `<<EOF`, `<<-"EOF"`, or `<< 'EOF'`,
+/// capturing its delimiter.
+static HEREDOC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<<-?\s*["']?([A-Za-z_]\w*)["']?"#).unwrap());
+
+/// Matches the names assigned in environment files like `$GITHUB_OUTPUT`,
+/// i.e. `NAME` in `NAME=value` or `NAME<<DELIMITER` lines written by
+/// `echo`, PowerShell strings and heredocs, or in the deprecated
+/// `::set-output name=NAME::` command.
+static ASSIGNMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?m)(?:\b(?:echo|printf)\s+(?:-\w+\s+)*|^\s*)["']?([A-Za-z_][\w-]*)(?:=|<<)|::set-output name=([A-Za-z_][\w-]*)::"#,
+    )
+    .unwrap()
+});
+
+/// Writers that, when followed by a file, write to that file.
+const FILE_WRITERS: &[&str] = &[">", "tee ", "Add-Content", "Out-File", "Set-Content"];
+
+/// Operators that separate words, longest first.
+const OPERATORS: &[&str] = &[
+    "||", "&&", "$(", "<(", "|", "&", ";", "(", ")", "{", "}", ">", "<",
+];
+
+/// Shells that can run a command string passed via a flag like `-c`.
+const WRAPPER_SHELLS: &[&str] = &["bash", "sh", "zsh", "pwsh", "powershell", "cmd"];
+
+/// Flags that cause a [`WRAPPER_SHELLS`] shell to run the next argument.
+const WRAPPER_FLAGS: &[&str] = &["-c", "-command", "/c", "/k"];
+
+/// Commands that execute their input (or arguments) as code.
+const INTERPRETERS: &[&str] = &[
+    "sh",
+    "bash",
+    "zsh",
+    "dash",
+    "ksh",
+    "fish",
+    "eval",
+    "source",
+    ".",
+    "python",
+    "python3",
+    "perl",
+    "ruby",
+    "node",
+    "php",
+    "pwsh",
+    "powershell",
+    "cmd",
+    "iex",
+    "invoke-expression",
+];
+
+/// A single token within a statement.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Token<'s> {
+    /// A "word," i.e. a command name or argument, with quotes removed.
+    Word(&'s str),
+    /// An operator, e.g. `|` or `$(`.
+    Op(&'s str),
+}
+
+/// A single command within a statement, e.g. `curl -sSL example.com`.
+#[derive(Debug)]
+pub(crate) struct Command<'s> {
+    /// The command's words, including its name.
+    pub(crate) words: Vec<&'s str>,
+    /// The index of the command whose output is piped into this one, if any.
+    pub(crate) piped_from: Option<usize>,
+    /// The index of the command that this one is nested within via
+    /// a substitution or grouping, e.g. `bash` for `bash <(curl ...)`.
+    pub(crate) enclosing: Option<usize>,
+}
+
+impl<'s> Command<'s> {
+    /// Returns this command's normalized name, i.e. its lowercased basename.
+    ///
+    /// Leading environment assignments and `sudo` are skipped, as are
+    /// wrapper shells like `bash -c` (so `bash -c curl` is named `curl`).
+    pub(crate) fn name(&self) -> Option<String> {
+        self.resolve().map(|(_, name)| name)
+    }
+
+    /// Returns this command's arguments, i.e. every word after its name.
+    pub(crate) fn args(&self) -> &[&'s str] {
+        match self.resolve() {
+            Some((index, _)) => &self.words[index + 1..],
+            None => &[],
+        }
+    }
+
+    /// Returns the index of this command's name within its words,
+    /// along with the normalized name. See [`Command::name`].
+    fn resolve(&self) -> Option<(usize, String)> {
+        let mut words = self
+            .words
+            .iter()
+            .enumerate()
+            .skip_while(|(_, word)| word.contains('=') || **word == "sudo" || word.starts_with('-'))
+            .peekable();
+
+        loop {
+            let (index, word) = words.next()?;
+            let name = word.rsplit(['/', '\\']).next()?.to_lowercase();
+            let name = name
+                .strip_suffix(".exe")
+                .map(str::to_string)
+                .unwrap_or(name);
+
+            if WRAPPER_SHELLS.contains(&name.as_str())
+                && words
+                    .peek()
+                    .is_some_and(|(_, flag)| WRAPPER_FLAGS.contains(&flag.to_lowercase().as_str()))
+            {
+                words.next();
+                if words.peek().is_some() {
+                    continue;
+                }
+            }
+
+            return Some((index, name));
+        }
+    }
+
+    /// Returns this command as a GitHub CLI invocation, if it is one.
+    pub(crate) fn gh(&self) -> Option<GhCommand<'s>> {
+        if self.name()? != "gh" {
+            return None;
+        }
+
+        let mut subcommand = vec![];
+        let mut flags = vec![];
+
+        let mut args = self.args().iter();
+        while let Some(arg) = args.next() {
+            if GH_REPO_FLAGS.contains(arg) {
+                args.next();
+            } else if arg.starts_with('-') {
+                flags.push(*arg);
+            } else if subcommand.len() < 2 {
+                subcommand.push(*arg);
+            }
+        }
+
+        Some(GhCommand { subcommand, flags })
+    }
+
+    /// Returns whether the code this command runs is chosen at runtime,
+    /// i.e. its name is itself an expansion (e.g. `$CMD args`), or it
+    /// evaluates its arguments as shell code (e.g. `eval "$CMD"`).
+    pub(crate) fn is_dynamic(&self) -> bool {
+        self.name()
+            .is_some_and(|name| name.starts_with('$') || EVALUATORS.contains(&name.as_str()))
+    }
+
+    /// Returns the manifest that this command installs dependencies from,
+    /// if it's a package manager install that can run the dependencies'
+    /// install scripts (or the manifest's own build code).
+    ///
+    /// For example, `npm ci` returns `package.json`, and
+    /// `pip install -r dev.txt` returns `dev.txt`.
+    pub(crate) fn package_install(&self) -> Option<&'s str> {
+        let name = self.name()?;
+        let mut args = self.args();
+
+        // `python -m pip install ...` is `pip install ...`.
+        let name = match args {
+            ["-m", "pip", rest @ ..] if name.starts_with("python") || name == "py" => {
+                args = rest;
+                "pip"
+            }
+            _ if name == "pip3" => "pip",
+            _ => name.as_str(),
+        };
+
+        if args.contains(&"--ignore-scripts") {
+            return None;
+        }
+
+        let subcommand = args.iter().find(|arg| !arg.starts_with('-')).copied();
+        match (name, subcommand) {
+            ("npm", Some("install" | "i" | "ci" | "add")) => Some("package.json"),
+            ("pnpm", Some("install" | "i" | "add")) => Some("package.json"),
+            ("yarn", None | Some("install" | "add")) => Some("package.json"),
+            ("bundle", Some("install")) => Some("Gemfile"),
+            ("composer", Some("install")) => Some("composer.json"),
+            ("poetry", Some("install")) => Some("pyproject.toml"),
+            ("pip", Some("install")) => {
+                let mut args = args.iter();
+                while let Some(arg) = args.next() {
+                    match *arg {
+                        "-r" | "--requirement" => return args.next().copied(),
+                        "-e" | "--editable" | "." => return Some("pyproject.toml"),
+                        _ => (),
+                    }
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether this command executes its input or arguments as code,
+    /// e.g. `bash` or `python`.
+    pub(crate) fn is_interpreter(&self) -> bool {
+        self.name()
+            .is_some_and(|name| INTERPRETERS.contains(&name.as_str()))
+    }
+}
+
+/// Commands that evaluate their arguments as shell code.
+const EVALUATORS: &[&str] = &["eval", "iex", "invoke-expression"];
+
+/// Flags that select a repository for any `gh` subcommand, and take a value.
+const GH_REPO_FLAGS: &[&str] = &["-R", "--repo"];
+
+/// A GitHub CLI (`gh`) invocation, e.g. `gh pr merge --auto 123`.
+#[derive(Debug)]
+pub(crate) struct GhCommand<'s> {
+    /// The (up to two-level) subcommand, e.g. `["pr", "merge"]`.
+    pub(crate) subcommand: Vec<&'s str>,
+    /// The invocation's flags, e.g. `["--auto"]`.
+    pub(crate) flags: Vec<&'s str>,
+}
+
+impl GhCommand<'_> {
+    /// Returns whether this invocation has the given flag, as either
+    /// `--flag` or `--flag=value`.
+    pub(crate) fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|candidate| {
+            candidate
+                .strip_prefix(flag)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+        })
+    }
+}
+
+/// A single logical statement within a script.
+pub(crate) struct Statement<'s> {
+    /// The statement's text, including any continuation lines and
+    /// heredoc bodies.
+    pub(crate) text: &'s str,
+    /// The byte range of the statement's text within its script,
+    /// excluding any leading whitespace.
+    pub(crate) span: Range<usize>,
+}
+
+impl<'s> Statement<'s> {
+    /// Returns whether this statement writes to the file named by the
+    /// environment variable `var`, e.g. `echo foo=bar >> $GITHUB_ENV`.
+    pub(crate) fn writes_to(&self, var: &str) -> bool {
+        VARIABLE.captures_iter(self.text).any(|caps| {
+            let Some(name) = caps.iter().skip(1).flatten().next() else {
+                return false;
+            };
+
+            // NOTE: Safe unwrap, since every capture has a group 0.
+            let start = caps.get(0).unwrap().start();
+            name.as_str() == var
+                && FILE_WRITERS
+                    .iter()
+                    .any(|writer| self.text[..start].contains(writer))
+        })
+    }
+
+    /// Returns whether this statement uses the deprecated `::set-output`
+    /// command to set a step output.
+    pub(crate) fn sets_output(&self) -> bool {
+        self.text.contains("::set-output ")
+    }
+
+    /// Returns whether this statement may assign `name` when it writes to
+    /// an environment file, e.g. `foo` for `echo "foo=bar" >> $GITHUB_OUTPUT`.
+    ///
+    /// Statements whose assigned names can't be determined (e.g.
+    /// `echo "$KEY=$VALUE"`) may assign any name.
+    pub(crate) fn may_assign(&self, name: &str) -> bool {
+        let mut names = ASSIGNMENT
+            .captures_iter(self.text)
+            .filter_map(|caps| caps.iter().skip(1).flatten().next())
+            .map(|m| m.as_str())
+            .peekable();
+
+        names.peek().is_none() || names.any(|assigned| assigned == name)
+    }
+
+    /// Splits this statement into words and operators.
+    ///
+    /// Quotes are treated as word boundaries and discarded, meaning that
+    /// quoted strings are tokenized like any other part of the statement.
+    /// This is wrong for the purposes of execution, but right for the
+    /// purposes of finding commands within substitutions like `"$(curl ...)"`.
+    pub(crate) fn tokens(&self) -> Vec<Token<'s>> {
+        let mut tokens = vec![];
+        let text = self.text;
+
+        let mut word_start = None;
+        let mut idx = 0;
+        while idx < text.len() {
+            let rest = &text[idx..];
+
+            let boundary = if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                Some((Some(Token::Op(op)), op.len()))
+            } else if rest.starts_with("\\\n") {
+                Some((None, 2))
+            } else {
+                // NOTE: Safe unwrap, since `rest` is non-empty.
+                let c = rest.chars().next().unwrap();
+                (c.is_whitespace() || c == '"' || c == '\'' || c == '`')
+                    .then(|| (None, c.len_utf8()))
+            };
+
+            match boundary {
+                Some((op, len)) => {
+                    if let Some(start) = word_start.take() {
+                        tokens.push(Token::Word(&text[start..idx]));
+                    }
+                    tokens.extend(op);
+                    idx += len;
+                }
+                None => {
+                    if word_start.is_none() {
+                        // Comments run to the end of the line.
+                        if rest.starts_with('#') {
+                            idx += rest.find('\n').unwrap_or(rest.len());
+                            continue;
+                        }
+                        word_start = Some(idx);
+                    }
+                    idx += rest.chars().next().unwrap().len_utf8();
+                }
+            }
+        }
+
+        if let Some(start) = word_start {
+            tokens.push(Token::Word(&text[start..]));
+        }
+
+        tokens
+    }
+
+    /// Splits this statement into its constituent commands.
+    pub(crate) fn commands(&self) -> Vec<Command<'s>> {
+        let mut commands: Vec<Command<'s>> = vec![];
+
+        let mut current: Option<usize> = None;
+        let mut pipe_from: Option<usize> = None;
+        let mut skip_word = false;
+        // For each open group: the command enclosing it, and the first
+        // command within it.
+        let mut groups: Vec<(Option<usize>, Option<usize>)> = vec![];
+
+        for token in self.tokens() {
+            match token {
+                // The target of a redirection isn't part of the command.
+                Token::Word(_) if skip_word => skip_word = false,
+                Token::Word(word) => match current {
+                    Some(idx) => commands[idx].words.push(word),
+                    None => {
+                        commands.push(Command {
+                            words: vec![word],
+                            piped_from: pipe_from.take(),
+                            enclosing: groups.last().and_then(|(enclosing, _)| *enclosing),
+                        });
+                        current = Some(commands.len() - 1);
+
+                        if let Some((_, first @ None)) = groups.last_mut() {
+                            *first = current;
+                        }
+                    }
+                },
+                Token::Op("|") => {
+                    pipe_from = current.take();
+                }
+                Token::Op("$(" | "<(" | "(" | "{") => {
+                    groups.push((current, None));
+                    current = None;
+                    pipe_from = None;
+                }
+                Token::Op(")" | "}") => {
+                    // A group with no enclosing command (e.g. `(irm ...) | iex`)
+                    // acts like its first command.
+                    current = groups
+                        .pop()
+                        .and_then(|(enclosing, first)| enclosing.or(first));
+                }
+                Token::Op(">" | "<") => skip_word = true,
+                Token::Op(_) => {
+                    current = None;
+                    pipe_from = None;
+                }
+            }
+        }
+
+        commands
+    }
+}
+
+/// Returns the names of all shell variables referenced in `text`,
+/// e.g. `FOO` for `$FOO`, `${FOO}` or `$env:FOO`.
+pub(crate) fn variables(text: &str) -> Vec<&str> {
+    VARIABLE
+        .captures_iter(text)
+        .filter_map(|caps| caps.iter().skip(1).flatten().next())
+        .map(|m| m.as_str())
+        .collect()
+}
+
+/// Splits `script` into approximate logical statements.
+///
+/// Each statement is a single line, plus any lines joined to it via trailing
+/// backslash continuations or (if the line opens a heredoc) the heredoc's body.
+/// Empty lines and comment lines are skipped.
+pub(crate) fn statements(script: &str) -> Vec<Statement<'_>> {
+    let mut statements = vec![];
+
+    // Byte offsets of the start of each line, plus a trailing sentinel.
+    let mut offsets = script
+        .match_indices('\n')
+        .map(|(idx, _)| idx + 1)
+        .collect::<Vec<_>>();
+    offsets.insert(0, 0);
+    if *offsets.last().unwrap() != script.len() {
+        offsets.push(script.len());
+    }
+
+    let line = |idx: usize| script[offsets[idx]..offsets[idx + 1]].trim_end_matches(['\n', '\r']);
+
+    let mut idx = 0;
+    while idx + 1 < offsets.len() {
+        let start = idx;
+        let first = line(idx).trim();
+
+        if first.is_empty() || first.starts_with('#') {
+            idx += 1;
+            continue;
+        }
+
+        // Join continuation lines.
+        while line(idx).ends_with('\\') && idx + 2 < offsets.len() {
+            idx += 1;
+        }
+
+        // Consume heredoc bodies, up to and including the delimiter.
+        let joined = &script[offsets[start]..offsets[idx + 1]];
+        if let Some(caps) = HEREDOC.captures(joined) {
+            if !joined.contains("<<<") {
+                let delimiter = &caps[1];
+                while idx + 2 < offsets.len() {
+                    idx += 1;
+                    if line(idx).trim() == delimiter {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let text = script[offsets[start]..offsets[idx + 1]].trim_end();
+        let indent = text.len() - text.trim_start().len();
+        statements.push(Statement {
+            text,
+            span: offsets[start] + indent..offsets[start] + text.len(),
+        });
+
+        idx += 1;
+    }
+
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{statements, variables, Token};
+
+    #[test]
+    fn test_statements() {
+        let script = r#"
+# a comment
+echo "hello"
+foo --bar \
+  --baz
+cat >> $GITHUB_ENV <<EOF
+A=b
+EOF
+echo done
+"#;
+
+        let stmts = statements(script);
+        let texts = stmts.iter().map(|s| s.text).collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            &[
+                r#"echo "hello""#,
+                "foo --bar \\\n  --baz",
+                "cat >> $GITHUB_ENV <<EOF\nA=b\nEOF",
+                "echo done",
+            ]
+        );
+
+        for stmt in &stmts {
+            assert_eq!(&script[stmt.span.clone()], stmt.text.trim_start());
+        }
+    }
+
+    #[test]
+    fn test_statement_writes_to() {
+        let cases = &[
+            (r#"echo "FOO=bar" >> $GITHUB_ENV"#, true),
+            (r#"echo "FOO=bar" >> "${GITHUB_ENV}""#, true),
+            ("echo $PWD | tee -a $GITHUB_PATH", false),
+            ("echo $PWD | tee -a $GITHUB_ENV", true),
+            (
+                r#""FOO=bar" | Out-File -FilePath $env:GITHUB_ENV -Append"#,
+                true,
+            ),
+            ("echo FOO=bar >> %GITHUB_ENV%", true),
+            ("echo $GITHUB_ENV", false),
+            ("echo $GITHUB_ENV > foo.txt", false),
+        ];
+
+        for (text, expected) in cases {
+            let stmt = &statements(text)[0];
+            assert_eq!(stmt.writes_to("GITHUB_ENV"), *expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_statement_may_assign() {
+        let cases = &[
+            (r#"echo "foo=bar" >> $GITHUB_OUTPUT"#, "foo", true),
+            (r#"echo "foo=bar" >> $GITHUB_OUTPUT"#, "baz", false),
+            ("echo -n foo-bar=$X >> $GITHUB_OUTPUT", "foo-bar", true),
+            (r#""foo=$x" >> $env:GITHUB_OUTPUT"#, "foo", true),
+            ("echo '::set-output name=foo::bar'", "foo", true),
+            ("echo '::set-output name=foo::bar'", "bar", false),
+            (
+                "cat <<EOF >> $GITHUB_OUTPUT\nfoo<<X\n$BODY\nX\nEOF",
+                "foo",
+                true,
+            ),
+            (r#"echo "$KEY=$VALUE" >> $GITHUB_OUTPUT"#, "foo", true),
+        ];
+
+        for (text, name, expected) in cases {
+            let stmt = &statements(text)[0];
+            assert_eq!(stmt.may_assign(name), *expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_statement_variables() {
+        let stmt = &statements("echo ${FOO} $BAR $env:BAZ ${{ github.sha }} >> $GITHUB_ENV")[0];
+        assert_eq!(variables(stmt.text), &["FOO", "BAR", "BAZ", "GITHUB_ENV"]);
+    }
+
+    #[test]
+    fn test_statement_tokens() {
+        let stmt =
+            &statements(r#"sh -c "$(curl -fsSL https://example.com)" && echo 'done' # hi"#)[0];
+        assert_eq!(
+            stmt.tokens(),
+            &[
+                Token::Word("sh"),
+                Token::Word("-c"),
+                Token::Op("$("),
+                Token::Word("curl"),
+                Token::Word("-fsSL"),
+                Token::Word("https://example.com"),
+                Token::Op(")"),
+                Token::Op("&&"),
+                Token::Word("echo"),
+                Token::Word("done"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_statement_commands() {
+        let stmt = &statements("curl -sSL https://example.com | sudo -E bash -s -- --yes")[0];
+        let commands = stmt.commands();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].name().as_deref(), Some("curl"));
+        assert_eq!(commands[1].name().as_deref(), Some("bash"));
+        assert_eq!(commands[1].piped_from, Some(0));
+
+        let stmt = &statements("bash <(/usr/bin/curl https://example.com) > out.txt")[0];
+        let commands = stmt.commands();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[1].name().as_deref(), Some("curl"));
+        assert_eq!(commands[1].enclosing, Some(0));
+
+        let stmt = &statements("(irm https://example.com) | IEX")[0];
+        let commands = stmt.commands();
+        assert_eq!(commands[1].name().as_deref(), Some("iex"));
+        assert_eq!(commands[1].piped_from, Some(0));
+
+        let stmt = &statements(r#"powershell -Command "iwr https://example.com | iex""#)[0];
+        let commands = stmt.commands();
+        assert_eq!(commands[0].name().as_deref(), Some("iwr"));
+        assert_eq!(commands[1].piped_from, Some(0));
+
+        let stmt = &statements("curl -sSL https://example.com | sudo -E bash -s -- --yes")[0];
+        let commands = stmt.commands();
+        assert!(!commands[0].is_interpreter());
+        assert!(commands[1].is_interpreter());
+        assert!(statements("source ./env.sh")[0].commands()[0].is_interpreter());
+
+        let stmt = &statements("sudo -E bash -c apt-get install -y jq")[0];
+        assert_eq!(stmt.commands()[0].args(), &["install", "-y", "jq"]);
+    }
+
+    #[test]
+    fn test_command_gh() {
+        let stmt = &statements(r#"gh -R "$REPO" pr merge --auto --squash "$PR_URL""#)[0];
+        let gh = stmt.commands()[0].gh().unwrap();
+        assert_eq!(gh.subcommand, &["pr", "merge"]);
+        assert!(gh.has_flag("--auto"));
+        assert!(gh.has_flag("--squash"));
+        assert!(!gh.has_flag("--admin"));
+
+        let stmt = &statements("gh pr review --approve=true 123")[0];
+        let gh = stmt.commands()[0].gh().unwrap();
+        assert_eq!(gh.subcommand, &["pr", "review"]);
+        assert!(gh.has_flag("--approve"));
+
+        assert!(statements("git merge main")[0].commands()[0].gh().is_none());
+    }
+
+    #[test]
+    fn test_command_package_install() {
+        for (script, manifest) in [
+            ("npm ci", Some("package.json")),
+            ("npm install --no-audit", Some("package.json")),
+            ("npm ci --ignore-scripts", None),
+            ("npm run build", None),
+            ("yarn", Some("package.json")),
+            ("yarn --frozen-lockfile", Some("package.json")),
+            ("yarn test", None),
+            (
+                "pip install -r requirements-dev.txt",
+                Some("requirements-dev.txt"),
+            ),
+            ("python3 -m pip install -e .", Some("pyproject.toml")),
+            ("pip install requests", None),
+            ("bundle install --jobs 4", Some("Gemfile")),
+            ("sudo composer install", Some("composer.json")),
+        ] {
+            let stmt = &statements(script)[0];
+            assert_eq!(stmt.commands()[0].package_install(), manifest, "{script}");
+        }
+    }
+
+    #[test]
+    fn test_command_is_dynamic() {
+        for dynamic in [
+            "$CMD --flag",
+            r#"eval "$BODY""#,
+            r#"bash -c "$BODY""#,
+            "sudo ${COMMAND}",
+            "Invoke-Expression $env:BODY",
+        ] {
+            let stmt = &statements(dynamic)[0];
+            assert!(stmt.commands()[0].is_dynamic(), "{dynamic}");
+        }
+
+        for fixed in [
+            r#"echo "$BODY""#,
+            "gh pr comment $PR",
+            r#"bash script.sh "$ARG""#,
+        ] {
+            let stmt = &statements(fixed)[0];
+            assert!(!stmt.commands()[0].is_dynamic(), "{fixed}");
+        }
+    }
+}
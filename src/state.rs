@@ -2,10 +2,16 @@
 //!
 //! Primarily for maintaining caches between audits and audit runs.
 
+use std::path::Path;
+
+use anyhow::Result;
 use moka::sync::Cache;
 
 use crate::{
-    github_api::{Branch, Client, ComparisonStatus, Tag},
+    auth,
+    cache::DiskCache,
+    config::Config,
+    github_api::{self, Branch, Client, ComparisonStatus, Repository, Runner, Tag},
     Args,
 };
 
@@ -13,16 +19,69 @@ use crate::{
 pub(crate) struct AuditConfig {
     pub(crate) pedantic: bool,
     pub(crate) offline: bool,
-    pub(crate) gh_token: Option<String>,
+    /// The only audits that may use GitHub's API, if they're restricted.
+    pub(crate) online_audits: Option<Vec<String>>,
+    /// The GitHub API token to use, if we're online and found one.
+    pub(crate) gh_token: Option<auth::Token>,
+    /// The base URL of the GitHub API to query, e.g. `https://api.github.com`.
+    pub(crate) github_api_url: String,
+    /// The most GitHub API requests to make, if they're limited.
+    pub(crate) github_api_budget: Option<u64>,
+    /// The loaded configuration file, or an empty one if none was given.
+    pub(crate) file: Config,
 }
 
-impl From<&Args> for AuditConfig {
-    fn from(value: &Args) -> Self {
-        Self {
+impl TryFrom<&Args> for AuditConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &Args) -> Result<Self> {
+        let file = match (&value.config, value.inputs.first()) {
+            (Some(path), _) => Config::load(path)?,
+            // Input from stdin is labeled with the path it'd have on disk.
+            (None, Some(input)) if input == Path::new("-") => {
+                Config::discover(&value.stdin_filename)?
+            }
+            (None, Some(input)) => Config::discover(input)?,
+            // Only `--org` was given, so there's no local input to
+            // discover from.
+            (None, None) => Config::discover(Path::new("."))?,
+        };
+
+        // Flags take precedence over the configuration file, and an
+        // explicit API URL over the hostname.
+        let gh_hostname = value
+            .gh_hostname
+            .as_deref()
+            .or(file.gh_hostname())
+            .unwrap_or("github.com");
+        let github_api_url = match &value.github_api_url {
+            Some(url) => url.clone(),
+            None => github_api::api_url(gh_hostname),
+        };
+
+        // There's no point in looking for a token (which can mean running
+        // `gh`) if we're staying offline.
+        let offline = value.offline || file.offline();
+        let gh_token = if offline {
+            None
+        } else {
+            auth::discover(value.gh_token.as_deref(), gh_hostname)
+        };
+
+        let online_audits = match &value.online_audits {
+            Some(online_audits) => Some(online_audits.clone()),
+            None => file.online_audits().map(<[String]>::to_vec),
+        };
+
+        Ok(Self {
             pedantic: value.pedantic,
-            offline: value.offline,
-            gh_token: value.gh_token.clone(),
-        }
+            offline,
+            online_audits,
+            gh_token,
+            github_api_url,
+            github_api_budget: value.github_api_budget,
+            file,
+        })
     }
 }
 
@@ -30,24 +89,69 @@ impl From<&Args> for AuditConfig {
 pub(crate) struct AuditState {
     /// The current config.
     pub(crate) config: AuditConfig,
-    pub(crate) caches: Caches,
+    /// A GitHub API client shared between audits, if we're online.
+    client: Option<Client>,
 }
 
 impl AuditState {
     pub(crate) fn new(config: AuditConfig) -> Self {
+        let client = config.gh_token.as_ref().map(|token| {
+            Client::new(
+                &config.github_api_url,
+                &token.secret,
+                Caches::new(),
+                config.github_api_budget,
+            )
+        });
+
+        Self { config, client }
+    }
+
+    /// Return a cache-configured GitHub API client, if a GitHub API token
+    /// is present and we haven't been asked to stay offline.
+    pub(crate) fn github_client(&self) -> Option<Client> {
+        self.client.clone()
+    }
+
+    /// Returns whether the audit `ident` may use GitHub's API, i.e. whether
+    /// we're online and it's allowed by `online-audits` (if that's set).
+    pub(crate) fn is_online(&self, ident: &str) -> bool {
+        self.client.is_some()
+            && self
+                .config
+                .online_audits
+                .as_ref()
+                .map_or(true, |online| online.iter().any(|audit| audit == ident))
+    }
+
+    /// Returns a copy of this state for the audit `ident`, whose GitHub API
+    /// requests are attributed to it.
+    pub(crate) fn for_audit(&self, ident: &'static str) -> Self {
         Self {
-            config,
-            caches: Caches::new(),
+            client: self.client.as_ref().map(|client| client.for_audit(ident)),
+            ..self.clone()
         }
     }
 
-    /// Return a cache-configured GitHub API client, if
-    /// a GitHub API token is present.
-    pub(crate) fn github_client(&self) -> Option<Client> {
-        self.config
-            .gh_token
-            .as_ref()
-            .map(|token| Client::new(token, self.caches.clone()))
+    /// Returns a copy of this state without GitHub API access, for audits
+    /// that aren't allowed to use it.
+    pub(crate) fn offline(&self) -> Self {
+        Self {
+            client: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns why audits that require GitHub API access can't use it,
+    /// when they can't; see [`AuditState::is_online`].
+    pub(crate) fn offline_reason(&self) -> &'static str {
+        if self.config.offline {
+            "zizmor is running offline"
+        } else if self.client.is_none() {
+            "no GitHub API token was found (see --gh-token)"
+        } else {
+            "they aren't in online-audits"
+        }
     }
 }
 
@@ -67,6 +171,33 @@ pub(crate) struct Caches {
     /// This is not technically true of Git SHAs due to SHAttered, but is
     /// effectively true for SHAs on GitHub due to GitHub's collision detection.
     pub(crate) ref_comparison_cache: Cache<(String, String), Option<ComparisonStatus>>,
+
+    /// A cache of `(owner, repo) => self-hosted runners`.
+    pub(crate) runner_cache: Cache<(String, String), Option<Vec<Runner>>>,
+
+    /// A cache of `(org, group) => self-hosted runners`.
+    pub(crate) runner_group_cache: Cache<(String, String), Option<Vec<Runner>>>,
+
+    /// A cache of `owner/repo/subpath@ref => action definition`.
+    pub(crate) action_metadata_cache: Cache<String, Option<String>>,
+
+    /// An on-disk cache of action definitions at commit refs.
+    pub(crate) action_metadata_disk: DiskCache,
+
+    /// A cache of `(owner, repo) => repository metadata`.
+    pub(crate) repository_cache: Cache<(String, String), Option<Repository>>,
+
+    /// An on-disk cache of `owner/repo => repository metadata`, for reuse
+    /// across runs.
+    pub(crate) repository_disk: DiskCache,
+
+    /// An on-disk cache of API responses and their `ETag`s, for
+    /// revalidating with conditional requests.
+    pub(crate) api_response_disk: DiskCache,
+
+    /// An on-disk cache of data derived from API responses (e.g. which
+    /// commit a tag points to), reused across runs until it expires.
+    pub(crate) api_data_disk: DiskCache,
 }
 
 impl Caches {
@@ -77,6 +208,14 @@ impl Caches {
             branch_cache: Cache::new(1000),
             tag_cache: Cache::new(1000),
             ref_comparison_cache: Cache::new(10000),
+            runner_cache: Cache::new(100),
+            runner_group_cache: Cache::new(100),
+            action_metadata_cache: Cache::new(1000),
+            action_metadata_disk: DiskCache::new("action-metadata"),
+            repository_cache: Cache::new(1000),
+            repository_disk: DiskCache::new("repository-metadata"),
+            api_response_disk: DiskCache::new("api-responses"),
+            api_data_disk: DiskCache::new("api-data"),
         }
     }
 }
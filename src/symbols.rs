@@ -0,0 +1,333 @@
+//! Scoped resolution of the `env`, `vars`, and `inputs` contexts.
+//!
+//! A [`SymbolTable`] answers what a context like `env.RUNNER_LABEL` contains
+//! at a given scope within a workflow: the workflow itself, one of its jobs,
+//! or a step within a job. As on GitHub, a step's `env:` shadows its job's,
+//! which in turn shadows the workflow's.
+//!
+//! Values are resolved statically: literals directly, and templates by
+//! evaluating them against the symbols of their enclosing scopes. Anything
+//! that depends on the state of the run (e.g. `github.*`, or a variable that
+//! an earlier step may overwrite via `$GITHUB_ENV`) is unknown.
+//!
+//! `vars.*` resolve to the `vars` in zizmor's configuration file, and
+//! `inputs.*` to the `with:` of the job that calls a reusable workflow
+//! (see [`SymbolTable::callee`]).
+
+use std::{collections::HashMap, ops::Deref};
+
+use github_actions_models::{
+    common::EnvValue,
+    workflow::{self, job::StepBody},
+};
+
+use crate::{
+    expr::{Contexts, Expr, Value},
+    models::{EnvVar, Job, Step, Workflow},
+    shell::statements,
+};
+
+/// A variable from an `env:` block, along with its value at the scope
+/// that it was looked up in, if that's statically known.
+pub(crate) struct Symbol<'w> {
+    pub(crate) var: EnvVar<'w>,
+    pub(crate) value: Option<Value>,
+}
+
+/// Resolves the `env`, `vars`, and `inputs` contexts within a workflow.
+#[derive(Clone)]
+pub(crate) struct SymbolTable<'w> {
+    workflow: &'w Workflow,
+    /// The configured `vars`, as an object.
+    vars: Value,
+    /// The symbols of the workflow that calls this one (if it's a reusable
+    /// workflow), along with the ID of the calling job.
+    caller: Option<(Box<SymbolTable<'w>>, &'w str)>,
+}
+
+impl<'w> SymbolTable<'w> {
+    pub(crate) fn new(workflow: &'w Workflow, vars: &HashMap<String, String>) -> Self {
+        let mut vars = vars
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+            .collect::<Vec<_>>();
+        vars.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+
+        Self {
+            workflow,
+            vars: Value::Object(vars),
+            caller: None,
+        }
+    }
+
+    /// Returns a [`SymbolTable`] for `callee`, a reusable workflow called by
+    /// `job`, in which the callee's `inputs` resolve to the values that
+    /// `job` passes to it.
+    pub(crate) fn callee(&self, job: &Job<'w>, callee: &'w Workflow) -> Self {
+        Self {
+            workflow: callee,
+            vars: self.vars.clone(),
+            caller: Some((Box::new(self.clone()), job.id)),
+        }
+    }
+
+    /// Returns the `env:` variables that are visible at the given scope,
+    /// i.e. to the given step, or to every step of the given job, with
+    /// inner variables shadowing outer ones. Variables are sorted by name.
+    ///
+    /// Variables that an earlier step may overwrite via `$GITHUB_ENV` have
+    /// no known value. Without a `step`, every step of the job counts
+    /// as earlier.
+    pub(crate) fn env(&self, job: Option<&Job<'w>>, step: Option<&Step<'w>>) -> Vec<Symbol<'w>> {
+        // Workflow and job `env:` blocks can't reference `env` themselves.
+        let contexts = self.contexts(None, None);
+
+        let mut symbols = vec![];
+        shadow(&mut symbols, self.workflow.env_vars(), &contexts);
+
+        let Some(job) = job else {
+            return symbols;
+        };
+        shadow(&mut symbols, job.env_vars(), &contexts);
+
+        for earlier in job.steps().take(step.map_or(usize::MAX, |step| step.index)) {
+            let StepBody::Run { run, .. } = &earlier.body else {
+                continue;
+            };
+
+            for stmt in statements(run) {
+                if !stmt.writes_to("GITHUB_ENV") {
+                    continue;
+                }
+
+                for symbol in &mut symbols {
+                    if stmt.may_assign(symbol.var.name) {
+                        symbol.value = None;
+                    }
+                }
+            }
+        }
+
+        if let Some(step) = step {
+            let contexts = contexts.with_partial("env", env_object(&symbols));
+            shadow(&mut symbols, step.env_vars(), &contexts);
+        }
+
+        symbols
+    }
+
+    /// Returns the contexts that are (partially) known at the given scope,
+    /// for evaluating the expressions there.
+    ///
+    /// As on GitHub, `env` is only available within steps: job-level keys
+    /// like `runs-on:` and `container:` can't reference it.
+    pub(crate) fn contexts(&self, job: Option<&Job<'w>>, step: Option<&Step<'w>>) -> Contexts {
+        let contexts = Contexts::default()
+            .with_partial("vars", self.vars.clone())
+            .with_partial("inputs", self.inputs());
+
+        match step {
+            Some(step) => contexts.with_partial("env", env_object(&self.env(job, Some(step)))),
+            None => contexts,
+        }
+    }
+
+    /// Returns the statically known value of `context` (e.g.
+    /// `env.RUNNER_LABEL`) at the given scope, if there is one.
+    ///
+    /// Unlike [`SymbolTable::contexts`], `env` is resolved at job scope too,
+    /// as it's visible to each of the job's steps.
+    pub(crate) fn resolve(
+        &self,
+        context: &str,
+        job: Option<&Job<'w>>,
+        step: Option<&Step<'w>>,
+    ) -> Option<Value> {
+        let contexts = self
+            .contexts(None, None)
+            .with_partial("env", env_object(&self.env(job, step)));
+
+        Expr::parse(context).ok()?.evaluate(&contexts)
+    }
+
+    /// Returns the `inputs` that the caller passes to this workflow,
+    /// as an object.
+    fn inputs(&self) -> Value {
+        let Some((caller, job_id)) = &self.caller else {
+            return Value::Object(vec![]);
+        };
+        let Some(job) = caller.workflow.job(job_id) else {
+            return Value::Object(vec![]);
+        };
+        let workflow::Job::ReusableWorkflowCallJob(reusable) = job.deref() else {
+            return Value::Object(vec![]);
+        };
+
+        let contexts = caller.contexts(Some(&job), None);
+        Value::Object(
+            reusable
+                .with
+                .iter()
+                .filter_map(|(name, value)| Some((name.clone(), resolve_value(value, &contexts)?)))
+                .collect(),
+        )
+    }
+}
+
+/// Adds `vars` to `symbols`, replacing the existing symbols with the same
+/// names and resolving their values against `contexts`.
+fn shadow<'w>(symbols: &mut Vec<Symbol<'w>>, vars: Vec<EnvVar<'w>>, contexts: &Contexts) {
+    for var in vars {
+        // Everything is a string once it's in the environment.
+        let value =
+            resolve_value(var.value, contexts).map(|value| Value::String(value.to_string()));
+
+        symbols.retain(|symbol| symbol.var.name != var.name);
+        symbols.push(Symbol { var, value });
+    }
+
+    symbols.sort_by_key(|symbol| symbol.var.name);
+}
+
+/// Returns the value of the given `env:` or `with:` entry, if it's known.
+fn resolve_value(value: &EnvValue, contexts: &Contexts) -> Option<Value> {
+    match value {
+        EnvValue::String(template) => contexts.expand(template),
+        EnvValue::Boolean(b) => Some(Value::Boolean(*b)),
+        other => Some(Value::String(other.to_string())),
+    }
+}
+
+/// Returns the known values among `symbols`, as an `env` object.
+fn env_object(symbols: &[Symbol<'_>]) -> Value {
+    Value::Object(
+        symbols
+            .iter()
+            .filter_map(|symbol| Some((symbol.var.name.to_string(), symbol.value.clone()?)))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::SymbolTable;
+    use crate::{expr::Value, models::test_workflow};
+
+    fn string(value: &str) -> Option<Value> {
+        Some(Value::String(value.into()))
+    }
+
+    #[test]
+    fn symbol_table_scopes() {
+        let workflow = test_workflow(
+            r#"
+on: push
+env:
+  REGISTRY: ghcr.io
+  TAG: latest
+jobs:
+  build:
+    runs-on: ${{ vars.RUNNER }}
+    env:
+      TAG: v1
+      IMAGE: ${{ vars.ORG }}/app
+    steps:
+      - run: echo "TAG=$(git describe)" >> $GITHUB_ENV
+      - run: echo "$FULL"
+        env:
+          FULL: ${{ env.REGISTRY }}/${{ env.IMAGE }}
+          REF: ${{ github.ref }}
+  deploy:
+    uses: ./.github/workflows/deploy.yml
+    with:
+      image: ${{ vars.ORG }}/app
+      dry_run: true
+"#,
+        );
+        let callee = test_workflow(
+            r#"
+on: workflow_call
+jobs:
+  deploy:
+    runs-on: ubuntu-latest
+    env:
+      TARGET: ${{ inputs.image }}
+    steps:
+      - run: echo "$TARGET"
+"#,
+        );
+
+        let vars = HashMap::from([
+            ("RUNNER".to_string(), "linux-large".to_string()),
+            ("ORG".to_string(), "example".to_string()),
+        ]);
+        let symbols = SymbolTable::new(&workflow, &vars);
+
+        assert_eq!(symbols.resolve("env.TAG", None, None), string("latest"));
+        assert_eq!(
+            symbols.resolve("vars.RUNNER", None, None),
+            string("linux-large")
+        );
+        assert_eq!(symbols.resolve("vars.MISSING", None, None), None);
+        assert_eq!(symbols.resolve("inputs.image", None, None), None);
+
+        let build = workflow.job("build").unwrap();
+        let steps = build.steps().collect::<Vec<_>>();
+
+        // The job's `TAG` shadows the workflow's, until a step
+        // overwrites it via `$GITHUB_ENV`.
+        assert_eq!(
+            symbols.resolve("env.TAG", Some(&build), Some(&steps[0])),
+            string("v1")
+        );
+        assert_eq!(
+            symbols.resolve("env.TAG", Some(&build), Some(&steps[1])),
+            None
+        );
+        assert_eq!(symbols.resolve("env.TAG", Some(&build), None), None);
+        assert_eq!(
+            symbols.resolve("env.IMAGE", Some(&build), None),
+            string("example/app")
+        );
+
+        assert_eq!(
+            symbols.resolve("env.FULL", Some(&build), Some(&steps[1])),
+            string("ghcr.io/example/app")
+        );
+        assert_eq!(
+            symbols.resolve("env.REF", Some(&build), Some(&steps[1])),
+            None
+        );
+        assert_eq!(
+            symbols
+                .env(Some(&build), Some(&steps[1]))
+                .iter()
+                .map(|symbol| symbol.var.name)
+                .collect::<Vec<_>>(),
+            ["FULL", "IMAGE", "REF", "REGISTRY", "TAG"]
+        );
+
+        // `env` isn't available to job-level keys.
+        let contexts = symbols.contexts(Some(&build), None);
+        assert_eq!(contexts.expand("${{ vars.RUNNER }}"), string("linux-large"));
+        assert_eq!(contexts.expand("${{ env.REGISTRY }}"), None);
+
+        let deploy = workflow.job("deploy").unwrap();
+        let callee_symbols = symbols.callee(&deploy, &callee);
+        let callee_job = callee.job("deploy").unwrap();
+        assert_eq!(
+            callee_symbols.resolve("inputs.image", None, None),
+            string("example/app")
+        );
+        assert_eq!(
+            callee_symbols.resolve("inputs.dry_run", None, None),
+            Some(Value::Boolean(true))
+        );
+        assert_eq!(
+            callee_symbols.resolve("env.TARGET", Some(&callee_job), None),
+            string("example/app")
+        );
+    }
+}
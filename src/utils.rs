@@ -1,5 +1,7 @@
 //! Helper routines.
 
+use std::{ops::Range, path::Path};
+
 use github_actions_models::common::expr::ExplicitExpr;
 
 /// Splits the given `patterns` string into one or more patterns, using
@@ -24,7 +26,7 @@ pub(crate) fn split_patterns(patterns: &str) -> impl Iterator<Item = &str> {
 ///
 /// Adapted roughly from GitHub's `parseScalar`:
 /// See: <https://github.com/actions/languageservices/blob/3a8c29c2d/workflow-parser/src/templates/template-reader.ts#L448>
-fn extract_expression(text: &str) -> Option<(ExplicitExpr, usize)> {
+pub(crate) fn extract_expression(text: &str) -> Option<(ExplicitExpr, usize)> {
     let start = text.find("${{")?;
 
     let mut end = None;
@@ -49,25 +51,86 @@ fn extract_expression(text: &str) -> Option<(ExplicitExpr, usize)> {
 
 /// Extract zero or more expressions from the given free-form text.
 pub(crate) fn extract_expressions(text: &str) -> Vec<ExplicitExpr> {
+    extract_expression_spans(text)
+        .into_iter()
+        .map(|(expr, _)| expr)
+        .collect()
+}
+
+/// Like [`extract_expressions`], but also returns the byte span of each
+/// expression within `text`, including its `${{ }}` delimiters.
+pub(crate) fn extract_expression_spans(text: &str) -> Vec<(ExplicitExpr, Range<usize>)> {
     let mut exprs = vec![];
-    let mut view = text;
+    let mut offset = 0;
 
-    while let Some((expr, next)) = extract_expression(view) {
-        exprs.push(expr);
+    while let Some((expr, next)) = extract_expression(&text[offset..]) {
+        let end = offset + next;
+        let start = end - expr.as_curly().len();
+        exprs.push((expr, start..end));
 
-        if next >= text.len() {
+        if end >= text.len() {
             break;
         } else {
-            view = &view[next..];
+            offset = end;
         }
     }
 
     exprs
 }
 
+/// Returns the GitHub `(owner, repo)` that the given path belongs to,
+/// based on the `origin` remote of its enclosing Git repository.
+///
+/// Returns `None` if the path isn't in a Git repository, or if the
+/// repository's `origin` isn't on GitHub.
+pub(crate) fn github_repo_for_path(path: &Path) -> Option<(String, String)> {
+    let path = std::fs::canonicalize(path).ok()?;
+    let config = path
+        .ancestors()
+        .map(|dir| dir.join(".git").join("config"))
+        .find(|config| config.is_file())?;
+    let config = std::fs::read_to_string(config).ok()?;
+
+    // Git's config format is INI-like; we only need the `url` key
+    // from the `[remote "origin"]` section.
+    let mut in_origin = false;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_origin = line == r#"[remote "origin"]"#;
+        } else if in_origin {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "url" {
+                    return parse_github_remote(value.trim());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Parses a GitHub remote URL (HTTPS or SSH) into its `(owner, repo)`.
+fn parse_github_remote(url: &str) -> Option<(String, String)> {
+    let slug = url
+        .strip_prefix("https://github.com/")
+        .or_else(|| url.strip_prefix("git@github.com:"))
+        .or_else(|| url.strip_prefix("ssh://git@github.com/"))?;
+    let slug = slug.trim_end_matches('/');
+    let slug = slug.strip_suffix(".git").unwrap_or(slug);
+
+    match slug.split_once('/') {
+        Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() && !repo.contains('/') => {
+            Some((owner.into(), repo.into()))
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::utils::{extract_expression, extract_expressions};
+    use crate::utils::{
+        extract_expression, extract_expression_spans, extract_expressions, parse_github_remote,
+    };
 
     #[test]
     fn split_patterns() {
@@ -139,4 +202,53 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_parse_expression_spans() {
+        let text = "echo ${{ github.ref }} && echo '${{ inputs.a }}${{ inputs.b }}'";
+        let spans = extract_expression_spans(text)
+            .into_iter()
+            .map(|(expr, span)| (expr.as_bare().to_string(), &text[span]))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spans,
+            &[
+                ("github.ref".into(), "${{ github.ref }}"),
+                ("inputs.a".into(), "${{ inputs.a }}"),
+                ("inputs.b".into(), "${{ inputs.b }}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_github_remote() {
+        let cases = &[
+            (
+                "https://github.com/woodruffw/zizmor",
+                Some(("woodruffw", "zizmor")),
+            ),
+            (
+                "https://github.com/woodruffw/zizmor.git",
+                Some(("woodruffw", "zizmor")),
+            ),
+            (
+                "git@github.com:woodruffw/zizmor.git",
+                Some(("woodruffw", "zizmor")),
+            ),
+            (
+                "ssh://git@github.com/woodruffw/zizmor",
+                Some(("woodruffw", "zizmor")),
+            ),
+            ("https://gitlab.com/woodruffw/zizmor", None),
+            ("https://github.com/woodruffw", None),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(
+                parse_github_remote(url),
+                expected.map(|(o, r)| (o.to_string(), r.to_string()))
+            );
+        }
+    }
 }
@@ -0,0 +1,200 @@
+//! `--watch`, which re-audits inputs whenever they change.
+//!
+//! Inputs are polled for changes, rather than watched via OS-specific
+//! notification APIs: the inputs are few and small, and polling behaves the
+//! same everywhere (including on network filesystems and in containers).
+//! After each re-audit, only the findings that are new or resolved since
+//! the previous audit are rendered. Findings are matched across audits by
+//! their fingerprints, so that a finding doesn't appear to be resolved (and
+//! new) just because the lines around it changed.
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+
+use crate::{collect, finding::Finding, registry::WorkflowRegistry};
+
+/// How often inputs are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A finding, as tracked across audits.
+pub(crate) struct Entry {
+    ident: &'static str,
+    desc: &'static str,
+    /// The finding's primary location, as `path:line`.
+    location: String,
+}
+
+/// The (unsuppressed) findings of an audit, by fingerprint.
+#[derive(Default)]
+pub(crate) struct Findings(BTreeMap<String, Entry>);
+
+impl Findings {
+    pub(crate) fn record(&mut self, registry: &WorkflowRegistry, finding: &Finding<'_>) {
+        let location = finding
+            .locations
+            .first()
+            .map_or_else(String::new, |location| {
+                format!(
+                    "{path}:{line}",
+                    path = registry.display_path(location.symbolic.name),
+                    line = location.concrete.location.start_point.row + 1
+                )
+            });
+
+        self.0.insert(
+            finding.fingerprint.clone(),
+            Entry {
+                ident: finding.ident,
+                desc: finding.desc,
+                location,
+            },
+        );
+    }
+
+    /// Returns the findings that are new in `self`, and the ones that have
+    /// been resolved, since `previous`.
+    fn diff<'a>(&'a self, previous: &'a Findings) -> (Vec<&'a Entry>, Vec<&'a Entry>) {
+        let new = self
+            .0
+            .iter()
+            .filter(|(fingerprint, _)| !previous.0.contains_key(*fingerprint))
+            .map(|(_, entry)| entry)
+            .collect();
+        let resolved = previous
+            .0
+            .iter()
+            .filter(|(fingerprint, _)| !self.0.contains_key(*fingerprint))
+            .map(|(_, entry)| entry)
+            .collect();
+
+        (new, resolved)
+    }
+}
+
+/// Renders the findings that are new or resolved in `current`, since
+/// `previous`.
+pub(crate) fn render_diff(previous: &Findings, current: &Findings) -> Result<()> {
+    let mut stdout = anstream::stdout();
+    let (new, resolved) = current.diff(previous);
+
+    for entry in &new {
+        writeln!(
+            stdout,
+            "{} {location}: {ident}: {desc}",
+            "+".red(),
+            location = entry.location,
+            ident = entry.ident.bold(),
+            desc = entry.desc
+        )?;
+    }
+    for entry in &resolved {
+        writeln!(
+            stdout,
+            "{} {location}: {ident}: {desc}",
+            "-".green(),
+            location = entry.location,
+            ident = entry.ident.bold(),
+            desc = entry.desc
+        )?;
+    }
+
+    writeln!(
+        stdout,
+        "{nnew} new, {nresolved} resolved, {nfindings} findings in total",
+        nnew = new.len().red(),
+        nresolved = resolved.len().green(),
+        nfindings = current.0.len()
+    )?;
+
+    Ok(())
+}
+
+/// The modification times of every input collected from some paths.
+#[derive(PartialEq)]
+pub(crate) struct Snapshot(BTreeMap<PathBuf, Option<SystemTime>>);
+
+/// Returns the modification times of every input collected from `inputs`.
+pub(crate) fn snapshot(inputs: &[PathBuf]) -> Result<Snapshot> {
+    Ok(Snapshot(
+        collect::collect(inputs)?
+            .iter()
+            .flat_map(|repository| &repository.inputs)
+            .map(|pending| {
+                let path = pending.path();
+                let modified = path.metadata().and_then(|m| m.modified()).ok();
+                (path.to_path_buf(), modified)
+            })
+            .collect(),
+    ))
+}
+
+/// Blocks until an input collected from `inputs` changes since `before`,
+/// or until one is added or removed.
+pub(crate) fn wait(inputs: &[PathBuf], before: &Snapshot) -> Result<()> {
+    log::info!("watching for changes");
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        // Inputs can be briefly missing while an editor saves them, in
+        // which case we just try again.
+        let Ok(after) = snapshot(inputs) else {
+            continue;
+        };
+        if after != *before {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{Entry, Findings};
+
+    #[test]
+    fn test_diff() {
+        let findings = |fingerprints: &[&str]| {
+            Findings(
+                fingerprints
+                    .iter()
+                    .map(|fingerprint| {
+                        let entry = Entry {
+                            ident: "example",
+                            desc: "an example",
+                            location: format!("ci.yml:{fingerprint}"),
+                        };
+                        (fingerprint.to_string(), entry)
+                    })
+                    .collect::<BTreeMap<_, _>>(),
+            )
+        };
+
+        let previous = findings(&["1", "2"]);
+        let current = findings(&["2", "3"]);
+        let (new, resolved) = current.diff(&previous);
+        assert_eq!(
+            new.iter().map(|e| e.location.as_str()).collect::<Vec<_>>(),
+            ["ci.yml:3"]
+        );
+        assert_eq!(
+            resolved
+                .iter()
+                .map(|e| e.location.as_str())
+                .collect::<Vec<_>>(),
+            ["ci.yml:1"]
+        );
+
+        let (new, resolved) = current.diff(&current);
+        assert!(new.is_empty() && resolved.is_empty());
+    }
+}